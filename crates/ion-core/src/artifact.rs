@@ -0,0 +1,190 @@
+//! Full tool outputs kept outside the model context (DESIGN.md §16.4).
+//!
+//! A tool result longer than [`ARTIFACT_THRESHOLD_BYTES`] is saved whole
+//! as an artifact. The model-visible result - what the session entry
+//! records and the context carries - is a head and tail excerpt naming
+//! the artifact, which the model pages through with `read_artifact`.
+//! Artifacts are content-addressed: the store keeps each body once in
+//! its blob table and reattaches it on load, and the session's
+//! [`ArtifactTable`] serves `read_artifact` and frontends.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
+
+use crate::tool::{LineRange, Tool, ToolOutcome, ToolSpec, render_lines};
+
+/// Results longer than this are saved as artifacts.
+pub(crate) const ARTIFACT_THRESHOLD_BYTES: usize = 64 * 1024;
+/// The model-visible excerpt of an artifact: this much of its head and
+/// as much of its tail.
+const EXCERPT_HALF_BYTES: usize = 8 * 1024;
+
+/// A tool output saved in full. Serializes as its id only; the session
+/// store keeps the text in its blob table.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Artifact {
+    /// Lowercase hex SHA-256 of `text`.
+    pub id: String,
+    #[serde(skip)]
+    pub text: Arc<str>,
+}
+
+impl Artifact {
+    #[must_use]
+    pub fn new(text: impl Into<Arc<str>>) -> Self {
+        let text = text.into();
+        Self {
+            id: sha256_hex(text.as_bytes()),
+            text,
+        }
+    }
+
+    /// The model-visible stand-in for the full text: its head and tail
+    /// around a notice naming the artifact. Cuts fall on line breaks
+    /// where one is near, so excerpts do not start or end mid-line. A
+    /// text too short to cut is its own excerpt.
+    #[must_use]
+    pub fn excerpt(&self) -> String {
+        let text = &*self.text;
+        if text.len() <= 2 * EXCERPT_HALF_BYTES {
+            return text.to_owned();
+        }
+        let mut head_end = floor_char_boundary(text, EXCERPT_HALF_BYTES);
+        if let Some(newline) = text[..head_end].rfind('\n') {
+            head_end = newline + 1;
+        }
+        let mut tail_start = ceil_char_boundary(text, text.len() - EXCERPT_HALF_BYTES);
+        if let Some(newline) = text[tail_start..].find('\n')
+            && tail_start + newline + 1 < text.len()
+        {
+            tail_start += newline + 1;
+        }
+        format!(
+            "{}\n… {} bytes omitted; the full output ({} bytes, {} lines) is artifact {}. \
+             Page through it with read_artifact …\n{}",
+            &text[..head_end],
+            tail_start - head_end,
+            text.len(),
+            text.lines().count(),
+            self.id,
+            &text[tail_start..]
+        )
+    }
+}
+
+/// Lowercase hex SHA-256 of `data`: the content address of blobs.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// The artifacts of one session, by id.
+#[derive(Default)]
+pub(crate) struct ArtifactTable {
+    artifacts: Mutex<HashMap<String, Arc<str>>>,
+}
+
+impl ArtifactTable {
+    /// Make `artifact` readable; loading a session re-adds the ones its
+    /// transcript names.
+    pub(crate) fn insert(&self, artifact: &Artifact) {
+        self.artifacts
+            .lock()
+            .expect("artifact table poisoned")
+            .entry(artifact.id.clone())
+            .or_insert_with(|| Arc::clone(&artifact.text));
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<Arc<str>> {
+        self.artifacts
+            .lock()
+            .expect("artifact table poisoned")
+            .get(id)
+            .cloned()
+    }
+
+    /// Save an oversized outcome as an artifact, leaving the excerpt in
+    /// its place.
+    pub(crate) fn offload(&self, outcome: &mut ToolOutcome) {
+        if outcome.output.len() <= ARTIFACT_THRESHOLD_BYTES {
+            return;
+        }
+        let artifact = Artifact::new(std::mem::take(&mut outcome.output));
+        self.insert(&artifact);
+        outcome.output = artifact.excerpt();
+        outcome.artifact = Some(artifact);
+    }
+}
+
+/// `read_artifact`: numbered lines of a saved output, paged like `read`.
+pub(crate) struct ReadArtifactTool {
+    pub(crate) artifacts: Arc<ArtifactTable>,
+}
+
+impl Tool for ReadArtifactTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "read_artifact".to_owned(),
+            description: format!(
+                "Read a saved tool output with numbered lines. Tool results over {} KiB are \
+                 shown as an excerpt naming an artifact id; page through the full output \
+                 with offset and limit.",
+                ARTIFACT_THRESHOLD_BYTES / 1024
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "The artifact id from the excerpt." },
+                    "offset": { "type": "integer", "minimum": 1, "description": "First line to read (1-based)." },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of lines to read." }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        _cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let Some(id) = arguments.get("id").and_then(Value::as_str) else {
+                return ToolOutcome::error("missing argument: id");
+            };
+            let range = match LineRange::from_arguments(&arguments) {
+                Ok(range) => range,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let Some(text) = self.artifacts.get(id) else {
+                return ToolOutcome::error(format!("no artifact {id} in this session"));
+            };
+            match render_lines(&text, range) {
+                Ok(out) => ToolOutcome::text(out),
+                Err(err) => ToolOutcome::error(err),
+            }
+        })
+    }
+}
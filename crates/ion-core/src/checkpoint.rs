@@ -0,0 +1,325 @@
+//! Workspace checkpoints: undo for the files an operation changed.
+//!
+//! Admitting a `write`, `edit`, or `apply_patch` records each target
+//! file's preimage with the effect intent - the bytes content-addressed
+//! in the store's blob table, the row tied to the effect id. Undo puts
+//! an operation's files back as they were before its first change to
+//! each, but only while every file still holds what the operation
+//! left: a file edited since, by the user or a later operation, is a
+//! conflict, and a conflicted undo restores nothing. The preimages are
+//! staged beside their files and renamed into place together, so an
+//! undo that fails part way leaves the files as they were. A restore is
+//! noted in the transcript for the model. An undone operation's
+//! snapshots are retired, so repeated undos walk back through the
+//! session. A user-facing safety net, not a durability
+//! mechanism; recovery keeps using the effect's reconciliation
+//! evidence (DESIGN.md §12.3).
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use tokio::fs;
+
+use crate::artifact::sha256_hex;
+use crate::error::CommandError;
+use crate::ids::{EffectId, OperationId, SessionId};
+use crate::session::{OperationState, SessionEntry};
+use crate::store::{EntryRecord, FileSnapshot, SessionStore};
+
+/// What one undo did.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum UndoOutcome {
+    /// These files are back to their state before the operation.
+    Restored {
+        operation_id: OperationId,
+        files: Vec<PathBuf>,
+    },
+    /// These files changed after the operation wrote them; nothing was
+    /// restored.
+    Conflicted {
+        operation_id: OperationId,
+        files: Vec<PathBuf>,
+    },
+    /// No operation has changes left to undo.
+    Nothing,
+}
+
+impl fmt::Display for UndoOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |files: &[PathBuf]| {
+            files
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Self::Restored {
+                operation_id,
+                files,
+            } => write!(f, "undid {operation_id}: restored {}", list(files)),
+            Self::Conflicted {
+                operation_id,
+                files,
+            } => write!(
+                f,
+                "cannot undo {operation_id}: changed since it wrote them: {}",
+                list(files)
+            ),
+            Self::Nothing => f.write_str("nothing to undo"),
+        }
+    }
+}
+
+/// The preimages of the files an admitted effect is about to change,
+/// read now. `evidence` is the effect's reconciliation evidence, which
+/// names them. Only a missing file has no preimage; a file that exists
+/// but cannot be read fails the capture, since undo could not restore it.
+pub(crate) async fn capture(
+    effect_id: EffectId,
+    evidence: &Value,
+) -> Result<Vec<FileSnapshot>, String> {
+    let files = match evidence.get("files").and_then(Value::as_array) {
+        Some(files) => files.iter().collect(),
+        None => vec![evidence],
+    };
+    let mut snapshots = Vec::with_capacity(files.len());
+    for file in files {
+        let Some(path) = file.get("path").and_then(Value::as_str) else {
+            continue;
+        };
+        let preimage = match fs::read(path).await {
+            Ok(bytes) => Some(bytes.into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(format!("{path}: cannot snapshot for undo: {err}")),
+        };
+        snapshots.push(FileSnapshot {
+            effect_id,
+            path: PathBuf::from(path),
+            preimage,
+            postimage_hash: file
+                .get("postimage_hash")
+                .and_then(Value::as_str)
+                .map(str::to_owned),
+        });
+    }
+    Ok(snapshots)
+}
+
+/// One file an operation changed: its state before the first change,
+/// and every state the operation meant to leave it in.
+struct Changed {
+    path: PathBuf,
+    before: Option<String>,
+    after: Vec<Option<String>>,
+}
+
+/// Undo `operation`, or with `None` the most recent operation of the
+/// session not undone yet, straight against the store. Refused while
+/// the store shows an operation of the session still open, since it
+/// may be mid-write on the same files; a hosted session undoes through
+/// [`SessionHandle::undo`](crate::SessionHandle::undo) instead.
+pub async fn undo(
+    store: &SessionStore,
+    session_id: SessionId,
+    operation: Option<OperationId>,
+) -> Result<UndoOutcome, CommandError> {
+    let loaded = store
+        .load(session_id)
+        .await
+        .map_err(|err| CommandError::Persistence(err.to_string()))?;
+    if let Some(open) = loaded
+        .operations
+        .iter()
+        .find(|operation| !matches!(operation.latest.1.state, OperationState::Finished(_)))
+    {
+        return Err(CommandError::Busy {
+            operation_id: open.id,
+        });
+    }
+    let outcome = restore(store, session_id, operation).await?;
+    if let Some(entry) = note(&outcome) {
+        let seq = loaded.entries.last().map_or(1, |(seq, _)| seq + 1);
+        store
+            .append_entry(session_id, EntryRecord { seq, entry })
+            .await
+            .map_err(|err| CommandError::Persistence(err.to_string()))?;
+    }
+    Ok(outcome)
+}
+
+/// The transcript entry a restoring undo leaves.
+pub(crate) fn note(outcome: &UndoOutcome) -> Option<SessionEntry> {
+    match outcome {
+        UndoOutcome::Restored {
+            operation_id,
+            files,
+        } => Some(SessionEntry::Undone {
+            operation_id: *operation_id,
+            files: files.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// [`undo`] for the session runtime, which already knows no operation
+/// of its own is running.
+pub(crate) async fn restore(
+    store: &SessionStore,
+    session_id: SessionId,
+    operation: Option<OperationId>,
+) -> Result<UndoOutcome, CommandError> {
+    let persistence = |err: crate::store::StoreError| CommandError::Persistence(err.to_string());
+    let mut operations: Vec<(OperationId, Vec<Changed>)> = Vec::new();
+    for row in store
+        .file_snapshots(session_id)
+        .await
+        .map_err(persistence)?
+    {
+        if operations
+            .last()
+            .is_none_or(|(id, _)| *id != row.operation_id)
+        {
+            operations.push((row.operation_id, Vec::new()));
+        }
+        let (_, files) = operations.last_mut().expect("pushed above");
+        match files.iter_mut().find(|file| file.path == row.path) {
+            Some(file) => file.after.push(row.postimage_hash),
+            None => files.push(Changed {
+                path: row.path,
+                before: row.preimage_hash,
+                after: vec![row.postimage_hash],
+            }),
+        }
+    }
+    let candidates: Vec<_> = match operation {
+        Some(wanted) => operations
+            .into_iter()
+            .filter(|(id, _)| *id == wanted)
+            .collect(),
+        None => operations.into_iter().rev().collect(),
+    };
+    for (operation_id, files) in candidates {
+        let mut restore = Vec::new();
+        let mut conflicts = Vec::new();
+        for file in files {
+            let current = fs::read(&file.path)
+                .await
+                .ok()
+                .map(|bytes| sha256_hex(&bytes));
+            if current == file.before {
+                continue;
+            }
+            if file.after.contains(&current) {
+                restore.push(file);
+            } else {
+                conflicts.push(file.path);
+            }
+        }
+        if !conflicts.is_empty() {
+            return Ok(UndoOutcome::Conflicted {
+                operation_id,
+                files: conflicts,
+            });
+        }
+        put_back(store, &restore).await?;
+        let restored: Vec<PathBuf> = restore.into_iter().map(|file| file.path).collect();
+        store.mark_undone(operation_id).await.map_err(persistence)?;
+        if restored.is_empty() {
+            // Its changes never reached the disk, or were reverted by
+            // hand; nothing of it to undo.
+            continue;
+        }
+        return Ok(UndoOutcome::Restored {
+            operation_id,
+            files: restored,
+        });
+    }
+    Ok(UndoOutcome::Nothing)
+}
+
+/// Write the files' preimages back, and remove the files the operation
+/// created. Every preimage is staged in a temporary file beside its
+/// target first; only when all are staged are they renamed into place.
+async fn put_back(store: &SessionStore, files: &[Changed]) -> Result<(), CommandError> {
+    let mut staged: Vec<(PathBuf, &Changed)> = Vec::new();
+    for file in files {
+        match stage(store, file).await {
+            Ok(Some(temp)) => staged.push((temp, file)),
+            Ok(None) => {}
+            Err(err) => {
+                for (temp, _) in &staged {
+                    let _ = fs::remove_file(temp).await;
+                }
+                return Err(err);
+            }
+        }
+    }
+    for (index, (temp, file)) in staged.iter().enumerate() {
+        if let Err(err) = fs::rename(temp, &file.path).await {
+            for (temp, _) in &staged[index..] {
+                let _ = fs::remove_file(temp).await;
+            }
+            return Err(failed(file, err));
+        }
+    }
+    for file in files.iter().filter(|file| file.before.is_none()) {
+        match fs::remove_file(&file.path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(failed(file, err));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write a file's preimage to a temporary file beside it, with the
+/// file's current permissions; `None` for a file the operation created.
+async fn stage(store: &SessionStore, file: &Changed) -> Result<Option<PathBuf>, CommandError> {
+    let Some(hash) = &file.before else {
+        return Ok(None);
+    };
+    let bytes = store
+        .blob(hash.clone())
+        .await
+        .map_err(|err| CommandError::Persistence(err.to_string()))?
+        .ok_or_else(|| {
+            CommandError::UndoFailed(format!(
+                "{}: preimage {hash} is missing",
+                file.path.display()
+            ))
+        })?;
+    if let Some(parent) = file.path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|err| failed(file, err))?;
+    }
+    let name = file
+        .path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let temp = file
+        .path
+        .with_file_name(format!(".{name}.undo-{}", uuid::Uuid::now_v7()));
+    let written = async {
+        fs::write(&temp, &bytes).await?;
+        if let Ok(meta) = fs::metadata(&file.path).await {
+            fs::set_permissions(&temp, meta.permissions()).await?;
+        }
+        Ok(())
+    }
+    .await;
+    match written {
+        Ok(()) => Ok(Some(temp)),
+        Err(err) => {
+            let _ = fs::remove_file(&temp).await;
+            Err(failed(file, err))
+        }
+    }
+}
+
+fn failed(file: &Changed, err: std::io::Error) -> CommandError {
+    CommandError::UndoFailed(format!("{}: {err}", file.path.display()))
+}
@@ -6,6 +6,9 @@
 //! [`ContextPlan`]. This module is the v0 projector; the
 //! ContextManifest and compaction machinery extend it in later slices.
 
+use std::sync::Arc;
+
+use crate::artifact::sha256_hex;
 use crate::session::SessionEntry;
 use crate::tool::ToolCall;
 
@@ -16,11 +19,38 @@ You work inside the user's project directory. \
 Use the provided tools to read, write, edit, search, and run commands. \
 Prefer tools over guessing; report failures plainly.";
 
+/// An image content part. Serializes as its media type and content
+/// hash only: the session store keeps the bytes once, in a
+/// content-addressed blob table, and reattaches them on load.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Image {
+    /// `image/png`, `image/jpeg`, `image/gif`, or `image/webp`.
+    pub media_type: String,
+    /// Lowercase hex SHA-256 of `data`.
+    pub sha256: String,
+    #[serde(skip)]
+    pub data: Arc<[u8]>,
+}
+
+impl Image {
+    #[must_use]
+    pub fn new(media_type: impl Into<String>, data: impl Into<Arc<[u8]>>) -> Self {
+        let data = data.into();
+        Self {
+            media_type: media_type.into(),
+            sha256: sha256_hex(&data),
+            data,
+        }
+    }
+}
+
 /// One model-facing message in the projected conversation.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ContextMessage {
     User {
         content: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        images: Vec<Image>,
     },
     Assistant {
         content: String,
@@ -30,6 +60,8 @@ pub enum ContextMessage {
     Tool {
         call_id: u64,
         content: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        images: Vec<Image>,
     },
 }
 
@@ -47,11 +79,29 @@ pub struct ContextPlan {
 }
 
 impl ContextMessage {
+    /// A text-only user message.
+    #[must_use]
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::User {
+            content: content.into(),
+            images: Vec::new(),
+        }
+    }
+
+    /// The images this message carries.
+    #[must_use]
+    pub fn images(&self) -> &[Image] {
+        match self {
+            Self::User { images, .. } | Self::Tool { images, .. } => images,
+            Self::Assistant { .. } => &[],
+        }
+    }
+
     /// The readable text content of this message, whatever its role.
     #[must_use]
     pub fn prompt_text(&self) -> &str {
         match self {
-            Self::User { content } | Self::Tool { content, .. } => content,
+            Self::User { content, .. } | Self::Tool { content, .. } => content,
             Self::Assistant { content, .. } => content,
         }
     }
@@ -75,19 +125,31 @@ pub fn project(entries: &[SessionEntry], first_seq: u64) -> ContextPlan {
                 if (*covers_through_seq + 1) >= first_seq + index as u64 {
                     messages.clear();
                 }
-                messages.push(ContextMessage::User {
-                    content: format!("[Context summary of the earlier conversation]\n{summary}"),
-                });
+                messages.push(ContextMessage::user(format!(
+                    "[Context summary of the earlier conversation]\n{summary}"
+                )));
             }
             SessionEntry::UserMessage { text } => {
-                messages.push(ContextMessage::User {
-                    content: text.clone(),
-                });
+                messages.push(ContextMessage::user(text.clone()));
             }
             SessionEntry::ModelChanged { .. } => {
                 // Configuration lineage is canonical session state, not
                 // a conversational message.
             }
+            SessionEntry::Undone {
+                operation_id,
+                files,
+            } => {
+                let files: Vec<String> = files
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                messages.push(ContextMessage::user(format!(
+                    "[The user undid {operation_id}: these files are back as they were \
+                     before it: {}]",
+                    files.join(", ")
+                )));
+            }
             SessionEntry::AssistantMessage { text } => {
                 messages.push(ContextMessage::Assistant {
                     content: text.clone(),
@@ -110,11 +172,22 @@ pub fn project(entries: &[SessionEntry], first_seq: u64) -> ContextPlan {
                 }
             }
             SessionEntry::ToolResult { result } => {
-                let (call_id, content) = match result {
-                    crate::tool::ToolResult::Ok { call_id, output } => (*call_id, output.clone()),
-                    crate::tool::ToolResult::Err { call_id, error } => (*call_id, error.clone()),
+                let (call_id, content, images) = match result {
+                    crate::tool::ToolResult::Ok {
+                        call_id,
+                        output,
+                        images,
+                        ..
+                    } => (*call_id, output.clone(), images.clone()),
+                    crate::tool::ToolResult::Err { call_id, error, .. } => {
+                        (*call_id, error.clone(), Vec::new())
+                    }
                 };
-                messages.push(ContextMessage::Tool { call_id, content });
+                messages.push(ContextMessage::Tool {
+                    call_id,
+                    content,
+                    images,
+                });
             }
         }
     }
@@ -184,7 +257,7 @@ pub fn usage_hint(
 /// plan (trailing edge so prefix-cache reuse of earlier content is
 /// preserved).
 pub fn push_hint(plan: &mut ContextPlan, hint: String) {
-    plan.messages.push(ContextMessage::User { content: hint });
+    plan.messages.push(ContextMessage::user(hint));
 }
 
 /// The hidden recovery turn's prompt after a compaction with
@@ -3,27 +3,59 @@
 //! A child is the same primitive as a root session - a full
 //! `SessionRuntime` with its own durable store record - never separate
 //! runtime code. [`DelegateTool`] is the model-facing surface: it
-//! spawns read-only children with an explicit objective, a runtime
-//! budget, and durable lineage, then returns each child's compact
-//! result. The full child transcript stays in its own session for
-//! inspection; nothing is injected into the parent automatically.
+//! spawns children with an explicit objective, a runtime budget, and
+//! durable lineage, then returns each child's compact result. The full
+//! child transcript stays in its own session for inspection; nothing
+//! is injected into the parent automatically.
+//!
+//! Children are read-only unless the model asks for a worktree one: it
+//! edits files in its own git worktree (worktree.rs), and its result
+//! carries the diff, which [`MergeChildTool`] applies to the parent's
+//! workspace on request.
+//!
+//! While a call runs, each child's events reach the parent's frontends
+//! as [`ChildUpdate`]s through the call's live output: a bounded,
+//! display-only summary that is never persisted in the parent.
+//!
+//! A call may name an [`AgentProfile`] from host settings per child:
+//! a system prompt addendum, a model, a narrower tool set, and a
+//! budget. The profile is recorded on the child's session row.
+//!
+//! Each child's row also records the call that started it. [`children`]
+//! and [`child_transcript`] read a parent's children back for hosts, and
+//! a `delegate` call re-executed after process loss resumes the
+//! children its lost attempt started instead of starting them again.
+//!
+//! A running child can be cancelled alone, by the user through the
+//! parent session or when it outlives its wall-clock timeout. The
+//! result tallies how the children ended and keeps each unfinished
+//! child's partial text.
 //!
 //! Delegation is a structural capability like `compact`: the gate does
 //! not require a grant, because every effect a child can produce is
 //! individually gated inside the child (§20.4). Nesting is disabled
-//! structurally: child catalogs are read-only and never contain a
-//! delegate tool.
+//! structurally: child catalogs never contain a delegate tool.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use serde_json::{Value, json};
 use tokio_util::sync::CancellationToken;
 
+use crate::error::CommandError;
 use crate::ids::SessionId;
-use crate::provider::Provider;
-use crate::runtime::{Runtime, RuntimeBudget};
-use crate::store::SessionStore;
-use crate::tool::{Tool, ToolOutcome, ToolSpec};
+use crate::provider::{Provider, SwitchingProvider, TokenUsage};
+use crate::runtime::{ChildLineage, OperationStatus, Runtime, RuntimeBudget, SessionHandle};
+use crate::session::{OperationOutcome, OperationState, SessionEntry};
+use crate::store::{LoadedSession, ParentCall, SessionStore, StoreError};
+use crate::tool::{
+    CanonicalTarget, RecoveryClass, Tool, ToolOutcome, ToolProgress, ToolRegistry, ToolSpec,
+    file_evidence,
+};
+use crate::worktree::{self, Changes, Worktree};
 
 /// Conservative default bounds for children (§20.5): exact numbers are
 /// host configuration; these exist so hosts that do not tune budgets
@@ -43,17 +75,169 @@ pub struct ChildSpec {
     /// Explicit context seed appended after the objective (§20.3):
     /// never an implicit copy of parent state.
     pub context_seed: Option<String>,
+    pub workspace: ChildWorkspace,
+    /// Name of the [`AgentProfile`] the child runs under.
+    pub profile: Option<String>,
+    /// Wall-clock limit the call asked for; the child is cancelled
+    /// when it runs out.
+    pub timeout: Option<Duration>,
+}
+
+impl ChildSpec {
+    /// The child's first prompt: the objective, then the context seed.
+    fn prompt(&self) -> String {
+        match &self.context_seed {
+            Some(seed) => format!("{}\n\nContext:\n{seed}", self.objective),
+            None => self.objective.clone(),
+        }
+    }
+}
+
+/// A named child configuration defined in host settings (§20.2).
+/// Unset fields keep the host's child defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AgentProfile {
+    pub name: String,
+    /// What the profile is for; shown to the model in the `delegate`
+    /// schema.
+    pub description: Option<String>,
+    /// Appended to the system section of every child model step.
+    pub system_prompt: Option<String>,
+    /// Exact model id the child runs on instead of the host's.
+    pub model: Option<String>,
+    /// Tool allowlist. It only narrows the child's base tool set;
+    /// names outside that set are ignored, never granted.
+    pub tools: Option<Vec<String>>,
+    pub max_model_steps: Option<u32>,
+    pub max_tool_calls: Option<u32>,
+    /// Wall-clock seconds a child may run; a call may ask for less.
+    pub timeout_secs: Option<u64>,
+}
+
+impl AgentProfile {
+    /// The child budget under this profile: its own bounds where set,
+    /// the host's otherwise.
+    #[must_use]
+    pub fn budget(&self, default: RuntimeBudget) -> RuntimeBudget {
+        RuntimeBudget {
+            max_model_steps: self.max_model_steps.or(default.max_model_steps),
+            max_tool_calls: self.max_tool_calls.or(default.max_tool_calls),
+        }
+    }
+}
+
+/// Longest objective or text line a [`ChildUpdate`] carries, in chars.
+const CHILD_LINE_CHARS: usize = 120;
+/// Assistant text a child streams between two `Text` updates, unless a
+/// line ends first.
+const CHILD_TEXT_BYTES: usize = 80;
+
+/// A bounded summary of one child's live events, forwarded to the
+/// parent's frontends while the `delegate` call runs (§20.7).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ChildUpdate {
+    /// The child session exists and is working on its objective.
+    Started {
+        objective: String,
+        profile: Option<String>,
+    },
+    ToolStarted {
+        tool: String,
+        target: Option<String>,
+    },
+    ToolSettled {
+        tool: String,
+        is_error: bool,
+    },
+    /// The latest line of the child's assistant text.
+    Text {
+        line: String,
+    },
+    Finished,
+    Failed {
+        message: String,
+    },
+    /// The child ran out of wall-clock time and was cancelled.
+    TimedOut,
+    Cancelled,
+}
+
+/// Where a child works and what it may change there (§20.4).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChildWorkspace {
+    /// Research in the parent's workspace with read-only tools.
+    #[default]
+    ReadOnly,
+    /// Edit files in a private git worktree at the parent repository's
+    /// `HEAD`; the parent gets the diff back.
+    Worktree,
+}
+
+/// Changes worktree children made, by child session, until merged.
+/// The store keeps them across restarts; this is what this process has
+/// seen, for gating a merge synchronously.
+type PendingMerges = Arc<Mutex<HashMap<SessionId, Changes>>>;
+
+/// The children a session's `delegate` calls are running, by child
+/// session, so one can be cancelled without its siblings (§20.6).
+#[derive(Clone, Default)]
+pub(crate) struct RunningChildren(Arc<Mutex<HashMap<SessionId, CancellationToken>>>);
+
+impl RunningChildren {
+    pub(crate) fn insert(&self, child: SessionId, cancel: CancellationToken) {
+        self.0
+            .lock()
+            .expect("running children poisoned")
+            .insert(child, cancel);
+    }
+
+    pub(crate) fn remove(&self, child: SessionId) {
+        self.0
+            .lock()
+            .expect("running children poisoned")
+            .remove(&child);
+    }
+
+    /// Cancel one running child; false when none runs under `child`.
+    pub(crate) fn cancel(&self, child: SessionId) -> bool {
+        match self
+            .0
+            .lock()
+            .expect("running children poisoned")
+            .get(&child)
+        {
+            Some(cancel) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 /// Configuration and bounds for children spawned by one delegate tool.
 pub struct DelegateConfig<P> {
+    /// The parent's working directory: read-only children resolve
+    /// paths under it, and worktrees check out its repository.
+    pub cwd: PathBuf,
     pub store: SessionStore,
     pub make_provider: Arc<dyn Fn() -> P + Send + Sync>,
+    /// Builds a provider for an exact model id, for profiles that name
+    /// one; `None` when the host cannot select models.
+    pub make_model_provider: Option<Arc<dyn Fn(String) -> P + Send + Sync>>,
     /// Maximum concurrently running children (§20.5); further children
     /// wait for a permit.
     pub max_active_children: usize,
-    /// Budget applied to every child.
+    /// Budget applied to every child without a profile budget.
     pub child_budget: RuntimeBudget,
+    /// Profiles a call may name.
+    pub profiles: Vec<AgentProfile>,
+}
+
+impl<P> DelegateConfig<P> {
+    fn profile(&self, name: &str) -> Option<&AgentProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
 }
 
 /// The model-facing delegation tool. Registered under a dedicated
@@ -61,6 +245,7 @@ pub struct DelegateConfig<P> {
 pub struct DelegateTool<P> {
     config: Arc<DelegateConfig<P>>,
     parent_id: SessionId,
+    merges: PendingMerges,
 }
 
 impl<P> DelegateTool<P> {
@@ -69,34 +254,99 @@ impl<P> DelegateTool<P> {
         Self {
             config: Arc::new(config),
             parent_id,
+            merges: PendingMerges::default(),
         }
     }
+
+    /// The `merge_child` tool for this delegate tool's worktree
+    /// children; register it alongside.
+    #[must_use]
+    pub fn merge_tool(&self) -> MergeChildTool {
+        MergeChildTool {
+            store: self.config.store.clone(),
+            parent_id: self.parent_id,
+            merges: Arc::clone(&self.merges),
+        }
+    }
+
+    /// The children an earlier attempt of `call` started, oldest first.
+    async fn earlier_children(&self, call: ParentCall) -> Result<Vec<LoadedSession>, StoreError> {
+        let mut found = Vec::new();
+        for id in self
+            .config
+            .store
+            .call_children(self.parent_id, call)
+            .await?
+        {
+            found.push(self.config.store.load(id).await?);
+        }
+        Ok(found)
+    }
 }
 
 impl<P: Provider + 'static> Tool for DelegateTool<P> {
     fn spec(&self) -> ToolSpec {
+        let mut child = json!({
+            "type": "object",
+            "properties": {
+                "objective": { "type": "string" },
+                "context": {
+                    "type": "string",
+                    "description": "optional context seed"
+                },
+                "workspace": {
+                    "type": "string",
+                    "enum": ["read_only", "worktree"],
+                    "description": "read_only (default) or worktree: \
+        edit files in a private git worktree at HEAD"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "optional wall-clock limit; a child that runs out is \
+        cancelled and reports what it had"
+                }
+            },
+            "required": ["objective"]
+        });
+        if !self.config.profiles.is_empty() {
+            let names: Vec<&str> = self
+                .config
+                .profiles
+                .iter()
+                .map(|profile| profile.name.as_str())
+                .collect();
+            let described: Vec<String> = self
+                .config
+                .profiles
+                .iter()
+                .map(|profile| match &profile.description {
+                    Some(description) => format!("{}: {description}", profile.name),
+                    None => profile.name.clone(),
+                })
+                .collect();
+            child["properties"]["profile"] = json!({
+                "type": "string",
+                "enum": names,
+                "description": format!("optional named configuration; {}", described.join("; "))
+            });
+        }
         ToolSpec {
             name: "delegate".to_owned(),
-            description: "Run bounded research children with read-only tools. \
-Each child gets an explicit objective and cannot widen capabilities; \
-their results return as text. Use for parallel investigation."
+            description: "Run bounded children concurrently. Each child gets an explicit \
+objective and cannot widen capabilities; their results return as text. Children \
+research with read-only tools by default; a child with workspace \"worktree\" can \
+also write, edit, and patch files in a private git worktree of this repository, \
+and its result includes the diff, which merge_child applies here. The result \
+reports which children completed, failed, timed out, or were cancelled, with each \
+unfinished child's partial result."
                 .to_owned(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "children": {
                         "type": "array",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "objective": { "type": "string" },
-                                "context": {
-                                    "type": "string",
-                                    "description": "optional context seed"
-                                }
-                            },
-                            "required": ["objective"]
-                        },
+                        "items": child,
                         "minItems": 1,
                         "description": "children to run concurrently"
                     }
@@ -110,45 +360,97 @@ their results return as text. Use for parallel investigation."
         &'a self,
         arguments: Value,
         cancel: CancellationToken,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ToolOutcome> + Send + 'a>> {
+        self.call_with_progress(arguments, cancel, ToolProgress::default())
+    }
+
+    fn call_with_progress<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+        progress: ToolProgress,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ToolOutcome> + Send + 'a>> {
         Box::pin(async move {
             let Some(children) = parse_children(&arguments) else {
                 return ToolOutcome::error(
                     "malformed arguments: `children` must be a non-empty array of \
-                     {objective, context?} objects",
+                     {objective, context?, workspace?, profile?, timeout_secs?} objects",
                 );
             };
+            // Admission validates names against the schema enum; this
+            // covers hosts that skip schema validation.
+            if let Some(name) = children
+                .iter()
+                .filter_map(|spec| spec.profile.as_deref())
+                .find(|name| self.config.profile(name).is_none())
+            {
+                return ToolOutcome::error(format!("unknown profile `{name}`"));
+            }
+            // A call re-executed after process loss finds what its lost
+            // attempt started; each child with the same prompt is
+            // resumed instead of started again (§20.8).
+            let mut earlier = match progress.call().filter(|_| progress.is_retry()) {
+                Some(call) => match self.earlier_children(call).await {
+                    Ok(earlier) => earlier,
+                    Err(err) => {
+                        return ToolOutcome::error(format!(
+                            "could not look up this call's earlier children: {err}"
+                        ));
+                    }
+                },
+                None => Vec::new(),
+            };
             let semaphore = Arc::new(tokio::sync::Semaphore::new(self.config.max_active_children));
             let mut handles = Vec::with_capacity(children.len());
             for spec in children {
+                let prompt = spec.prompt();
+                let resumed = earlier
+                    .iter()
+                    .position(|loaded| first_prompt(loaded) == Some(prompt.as_str()))
+                    .map(|index| earlier.remove(index));
                 let semaphore = Arc::clone(&semaphore);
                 let config = Arc::clone(&self.config);
-                let parent_id = self.parent_id;
+                let lineage = ChildLineage {
+                    parent: self.parent_id,
+                    call: progress.call(),
+                    profile: None,
+                };
+                let merges = Arc::clone(&self.merges);
                 let cancel = cancel.child_token();
+                let progress = progress.clone();
                 handles.push(tokio::spawn(async move {
                     let _permit = semaphore.acquire().await;
-                    run_child(config, parent_id, spec, cancel).await
+                    match resumed {
+                        Some(loaded) => {
+                            resume_child(config, lineage.parent, spec, loaded, progress, cancel)
+                                .await
+                        }
+                        None => run_child(config, lineage, spec, merges, progress, cancel).await,
+                    }
                 }));
             }
             // Parent cancellation cancels descendants (§20.6): the
             // child token above fires, each child's operation cancels,
             // and the results report it - the parent turn continues.
-            let mut output = String::new();
+            let mut statuses = Vec::with_capacity(handles.len());
+            let mut results = Vec::with_capacity(handles.len());
             for handle in handles {
-                let result = handle
-                    .await
-                    .unwrap_or_else(|err| format!("child task failed: {err}"));
-                if !output.is_empty() {
-                    output.push_str("\n\n");
-                }
-                output.push_str(&result);
+                let (status, result) = handle.await.unwrap_or_else(|err| {
+                    (ChildStatus::Failed, format!("child task failed: {err}"))
+                });
+                statuses.push(status);
+                results.push(result);
             }
             if cancel.is_cancelled() {
                 return ToolOutcome::error("cancelled");
             }
-            ToolOutcome::text(output)
+            ToolOutcome::text(format!("{}\n\n{}", tally(&statuses), results.join("\n\n")))
         })
     }
+
+    fn recovery_class(&self) -> RecoveryClass {
+        RecoveryClass::Resume
+    }
 }
 
 fn parse_children(arguments: &Value) -> Option<Vec<ChildSpec>> {
@@ -162,135 +464,867 @@ fn parse_children(arguments: &Value) -> Option<Vec<ChildSpec>> {
         if objective.is_empty() {
             return None;
         }
+        let workspace = match entry.get("workspace").map(Value::as_str) {
+            None | Some(Some("read_only")) => ChildWorkspace::ReadOnly,
+            Some(Some("worktree")) => ChildWorkspace::Worktree,
+            Some(_) => return None,
+        };
         specs.push(ChildSpec {
             objective: objective.to_owned(),
             context_seed: entry
                 .get("context")
                 .and_then(|v| v.as_str())
                 .map(str::to_owned),
+            workspace,
+            profile: match entry.get("profile") {
+                None => None,
+                Some(profile) => Some(profile.as_str()?.to_owned()),
+            },
+            timeout: match entry.get("timeout_secs") {
+                None => None,
+                Some(secs) => Some(Duration::from_secs(secs.as_u64().filter(|secs| *secs > 0)?)),
+            },
         });
     }
     Some(specs)
 }
 
 /// Run one child to its terminal outcome and render the compact
-/// result: final assistant text plus the child session reference.
+/// result: final assistant text plus the child session reference, and
+/// for a worktree child what it changed.
 async fn run_child<P>(
     config: Arc<DelegateConfig<P>>,
-    parent_id: SessionId,
+    mut lineage: ChildLineage,
     spec: ChildSpec,
+    merges: PendingMerges,
+    progress: ToolProgress,
     cancel: CancellationToken,
-) -> String
+) -> (ChildStatus, String)
 where
     P: Provider,
 {
-    // Absolute root: relative cwd would make every child path
-    // resolution depend on the host process's working directory.
-    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let catalog = crate::tool::ToolCatalog::read_only(cwd);
-    let runtime = Runtime::start_child(
-        (config.make_provider)(),
-        catalog,
-        config.store.clone(),
-        Arc::new(crate::policy::DefaultPolicy),
-        config.child_budget,
-        parent_id,
-    );
+    let parent_id = lineage.parent;
+    let worktree = match spec.workspace {
+        ChildWorkspace::ReadOnly => None,
+        ChildWorkspace::Worktree => {
+            let cwd = config.cwd.clone();
+            let name = uuid::Uuid::now_v7().to_string();
+            match tokio::task::spawn_blocking(move || Worktree::create(&cwd, &name)).await {
+                Ok(Ok(worktree)) => Some(worktree),
+                Ok(Err(message)) => return unstarted(&message),
+                Err(err) => return unstarted(&err.to_string()),
+            }
+        }
+    };
+    lineage.profile = spec
+        .profile
+        .as_deref()
+        .and_then(|name| config.profile(name))
+        .cloned();
+    let deadline = child_deadline(&spec, lineage.profile.as_ref());
+    let registry = match &worktree {
+        Some(worktree) => ToolRegistry::file_editing(worktree.cwd()),
+        None => ToolRegistry::read_only(&config.cwd),
+    };
+    let (registry, budget) = child_tools(&config, registry, lineage.profile.as_ref());
+    let model = lineage
+        .profile
+        .as_ref()
+        .and_then(|profile| profile.model.clone());
+    let runtime = match (model, &config.make_model_provider) {
+        (None, _) => Runtime::start_child(
+            (config.make_provider)(),
+            registry,
+            config.store.clone(),
+            Arc::new(crate::policy::DefaultPolicy),
+            budget,
+            lineage,
+        ),
+        (Some(model), Some(make)) => Runtime::start_child(
+            SwitchingProvider::new(model.clone(), make(model)),
+            registry,
+            config.store.clone(),
+            Arc::new(crate::policy::DefaultPolicy),
+            budget,
+            lineage,
+        ),
+        (Some(model), None) => {
+            return unstarted(&format!("this host cannot run profile model `{model}`"));
+        }
+    };
     let child_id = runtime.session_id();
     let session = runtime.session();
 
     // Subscribe before submit: live events predate subscribers.
     let Ok((_snapshot, mut events)) = session.subscribe().await else {
-        return format!("child failed: could not subscribe ({child_id})");
+        return unstarted(&format!("could not subscribe ({child_id})"));
     };
-    let prompt = match &spec.context_seed {
-        Some(seed) => format!("{}\n\nContext:\n{seed}", spec.objective),
-        None => spec.objective.clone(),
+    let Ok(operation_id) = session.submit(spec.prompt()).await else {
+        return unstarted(&format!("submit rejected ({child_id})"));
     };
-    let Ok(operation_id) = session.submit(prompt).await else {
-        return format!("child failed: submit rejected ({child_id})");
+    progress.child(
+        child_id,
+        ChildUpdate::Started {
+            objective: clip(&spec.objective),
+            profile: spec.profile.clone(),
+        },
+    );
+
+    let mut pump = ChildPump {
+        child_id,
+        progress: &progress,
+        tools: HashMap::new(),
+        draft: String::new(),
+        reported: 0,
     };
+    progress.child_running(child_id, &cancel);
+    let terminal = pump
+        .drive(&session, &mut events, operation_id, &cancel, deadline)
+        .await;
+    progress.child_done(child_id);
+    progress.child(child_id, terminal.update());
+
+    let _ = session.close().await;
+    let status = terminal.status();
+    let mut result = terminal.render(child_id);
+    if let Some(worktree) = worktree {
+        // The worktree goes with the child session; only its diff
+        // outlives it.
+        let changes = tokio::task::spawn_blocking(move || worktree.changes())
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+        result.push_str(&match changes {
+            Ok(None) => "\n(no file changes)".to_owned(),
+            Ok(Some(changes)) => {
+                let rendered = render_changes(child_id, &changes);
+                if let Err(err) = config
+                    .store
+                    .save_merge(parent_id, child_id, changes.clone())
+                    .await
+                {
+                    tracing::warn!(child = %child_id, "could not persist pending merge: {err}");
+                }
+                merges
+                    .lock()
+                    .expect("pending merges poisoned")
+                    .insert(child_id, changes);
+                rendered
+            }
+            Err(message) => format!("\ncould not collect its changes: {message}"),
+        });
+    }
+    (status, result)
+}
+
+/// What a worktree child changed, as its result shows it.
+fn render_changes(child_id: SessionId, changes: &Changes) -> String {
+    format!(
+        "\nchanged {} (merge with merge_child {{\"session\": \"{child_id}\"}}):\n{}",
+        changes.files.join(", "),
+        changes.diff.trim_end()
+    )
+}
 
-    let terminal = tokio::select! {
-        outcome = pump_child(&mut events, operation_id) => outcome,
-        () = cancel.cancelled() => {
-            // §20.6: cancelling the parent cancels descendants; the
-            // child settles durably as cancelled on its own.
-            let _ = session.cancel(operation_id).await;
-            pump_child(&mut events, operation_id).await
+/// A child that never got to run.
+fn unstarted(message: &str) -> (ChildStatus, String) {
+    (ChildStatus::Failed, format!("child failed: {message}"))
+}
+
+/// Reattach to a child an earlier attempt of the call started: report
+/// a finished child's recorded outcome, or reopen an unfinished one
+/// and drive its recovered operation to the end (§20.8). A finished
+/// worktree child's unmerged changes come back from the store; an
+/// unfinished one cannot resume, because its checkout went with the
+/// process, and what is left of the checkout is pruned.
+async fn resume_child<P>(
+    config: Arc<DelegateConfig<P>>,
+    parent: SessionId,
+    spec: ChildSpec,
+    loaded: LoadedSession,
+    progress: ToolProgress,
+    cancel: CancellationToken,
+) -> (ChildStatus, String)
+where
+    P: Provider,
+{
+    let child_id = loaded.session.id;
+    progress.child(
+        child_id,
+        ChildUpdate::Started {
+            objective: clip(&spec.objective),
+            profile: spec.profile.clone(),
+        },
+    );
+    let (terminal, settled) = match finished(&loaded) {
+        Some(outcome) => (ChildTerminal::settled(outcome, &loaded.entries), true),
+        None if spec.workspace == ChildWorkspace::Worktree => {
+            let cwd = config.cwd.clone();
+            match tokio::task::spawn_blocking(move || worktree::prune_orphans(&cwd)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(message)) => tracing::warn!("could not prune orphaned worktrees: {message}"),
+                Err(err) => tracing::warn!("could not prune orphaned worktrees: {err}"),
+            }
+            (
+                ChildTerminal::failed("interrupted, and its worktree does not survive a restart"),
+                false,
+            )
         }
+        None => (
+            reopen_child(&config, &spec, loaded, &progress, &cancel).await,
+            false,
+        ),
     };
+    progress.child(child_id, terminal.update());
+    let status = terminal.status();
+    let mut result = terminal.render(child_id);
+    if settled && spec.workspace == ChildWorkspace::Worktree {
+        // The lost attempt reported these changes before the restart;
+        // report them again, since this result replaces that one.
+        result.push_str(&match config.store.pending_merge(parent, child_id).await {
+            Ok(Some(changes)) => render_changes(child_id, &changes),
+            Ok(None) => "\n(no unmerged file changes)".to_owned(),
+            Err(err) => format!("\ncould not look up its changes: {err}"),
+        });
+    }
+    (status, result)
+}
 
+/// Reopen an unfinished read-only child under the profile recorded on
+/// its row, and wait for the operation its own recovery continues.
+async fn reopen_child<P>(
+    config: &DelegateConfig<P>,
+    spec: &ChildSpec,
+    loaded: LoadedSession,
+    progress: &ToolProgress,
+    cancel: &CancellationToken,
+) -> ChildTerminal
+where
+    P: Provider,
+{
+    let child_id = loaded.session.id;
+    let profile = loaded.session.profile.clone();
+    let (registry, budget) = child_tools(
+        config,
+        ToolRegistry::read_only(&config.cwd),
+        profile.as_ref(),
+    );
+    let deadline = child_deadline(spec, profile.as_ref());
+    let model = profile.and_then(|profile| profile.model);
+    let opened = match (model, &config.make_model_provider) {
+        (None, _) => {
+            Runtime::open_child(
+                (config.make_provider)(),
+                registry,
+                config.store.clone(),
+                Arc::new(crate::policy::DefaultPolicy),
+                budget,
+                child_id,
+            )
+            .await
+        }
+        (Some(model), Some(make)) => {
+            Runtime::open_child(
+                SwitchingProvider::new(model.clone(), make(model)),
+                registry,
+                config.store.clone(),
+                Arc::new(crate::policy::DefaultPolicy),
+                budget,
+                child_id,
+            )
+            .await
+        }
+        (Some(model), None) => {
+            return ChildTerminal::failed(format!("this host cannot run profile model `{model}`"));
+        }
+    };
+    let runtime = match opened {
+        Ok(runtime) => runtime,
+        Err(err) => return ChildTerminal::failed(format!("could not reopen: {err}")),
+    };
+    let session = runtime.session();
+    let Ok((snapshot, mut events)) = session.subscribe().await else {
+        return ChildTerminal::failed("could not subscribe");
+    };
+    let live = match snapshot.operation {
+        OperationStatus::Active { operation_id, .. } => {
+            let mut pump = ChildPump {
+                child_id,
+                progress,
+                tools: HashMap::new(),
+                draft: String::new(),
+                reported: 0,
+            };
+            progress.child_running(child_id, cancel);
+            let terminal = pump
+                .drive(&session, &mut events, operation_id, cancel, deadline)
+                .await;
+            progress.child_done(child_id);
+            Some(terminal)
+        }
+        // Recovery settled the operation before the subscription.
+        OperationStatus::Idle => None,
+    };
     let _ = session.close().await;
-    match terminal {
-        ChildTerminal::Completed(text) => {
-            format!("{text}\n\n[child session: {child_id}]")
+    // The operation's text spans both processes; the transcript has
+    // all of it. Only the live side knows a cancellation was a timeout.
+    let recorded = config.store.load(child_id).await.ok().and_then(|loaded| {
+        finished(&loaded).map(|outcome| ChildTerminal::settled(outcome, &loaded.entries))
+    });
+    match (recorded, live) {
+        (
+            Some(ChildTerminal::Cancelled { partial }),
+            Some(ChildTerminal::TimedOut { after, .. }),
+        ) => ChildTerminal::TimedOut { after, partial },
+        (recorded, live) => recorded
+            .or(live)
+            .unwrap_or_else(|| ChildTerminal::failed("did not resume")),
+    }
+}
+
+/// A child's tool set and budget under its profile, if any.
+fn child_tools<P>(
+    config: &DelegateConfig<P>,
+    mut registry: ToolRegistry,
+    profile: Option<&AgentProfile>,
+) -> (ToolRegistry, RuntimeBudget) {
+    let mut budget = config.child_budget;
+    if let Some(profile) = profile {
+        if let Some(tools) = &profile.tools {
+            registry = registry.only(tools);
         }
-        ChildTerminal::Failed(message) => {
-            format!("child failed: {message} [child session: {child_id}]")
+        budget = profile.budget(budget);
+    }
+    (registry, budget)
+}
+
+/// How long a child may run: the shorter of the call's and its
+/// profile's limits.
+fn child_deadline(spec: &ChildSpec, profile: Option<&AgentProfile>) -> Option<Duration> {
+    let profile = profile
+        .and_then(|profile| profile.timeout_secs)
+        .map(Duration::from_secs);
+    match (spec.timeout, profile) {
+        (Some(asked), Some(limit)) => Some(asked.min(limit)),
+        (asked, limit) => asked.or(limit),
+    }
+}
+
+/// One child session as hosts list it under its parent (§20.8).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChildSummary {
+    pub session_id: SessionId,
+    /// The first line of the child's prompt.
+    pub objective: String,
+    pub profile: Option<String>,
+    /// How the child's operation ended; `None` while it has not:
+    /// running, or interrupted and resumable.
+    pub outcome: Option<OperationOutcome>,
+    /// Tokens over all of the child's model steps.
+    pub usage: TokenUsage,
+}
+
+impl fmt::Display for ChildSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match &self.outcome {
+            None => "unfinished".to_owned(),
+            Some(OperationOutcome::Completed) => "completed".to_owned(),
+            Some(OperationOutcome::Failed(message)) => format!("failed ({})", clip(message)),
+            Some(OperationOutcome::Cancelled) => "cancelled".to_owned(),
+            Some(OperationOutcome::Indeterminate) => "indeterminate".to_owned(),
+            Some(OperationOutcome::ApprovalRequired { tool }) => {
+                format!("needs approval for `{tool}`")
+            }
+        };
+        write!(
+            f,
+            "{}  {outcome}  {} in / {} out  {}",
+            self.session_id, self.usage.input, self.usage.output, self.objective
+        )?;
+        if let Some(profile) = &self.profile {
+            write!(f, " [{profile}]")?;
         }
-        ChildTerminal::Cancelled => {
-            format!("child cancelled [child session: {child_id}]")
+        Ok(())
+    }
+}
+
+/// The children of `parent`, oldest first, with their objective,
+/// outcome, and usage.
+pub async fn children(
+    store: &SessionStore,
+    parent: SessionId,
+) -> Result<Vec<ChildSummary>, CommandError> {
+    let persistence = |err: StoreError| CommandError::Persistence(err.to_string());
+    let mut summaries = Vec::new();
+    for id in store.children(parent).await.map_err(persistence)? {
+        let loaded = store.load(id).await.map_err(persistence)?;
+        let usage = store.usage(id).await.map_err(persistence)?.iter().fold(
+            TokenUsage {
+                input: 0,
+                output: 0,
+                cache_read: 0,
+                cache_write: 0,
+            },
+            |total, row| TokenUsage {
+                input: total.input + row.input_tokens,
+                output: total.output + row.output_tokens,
+                cache_read: total.cache_read + row.cache_read_tokens,
+                cache_write: total.cache_write + row.cache_write_tokens,
+            },
+        );
+        summaries.push(ChildSummary {
+            session_id: id,
+            objective: clip(first_prompt(&loaded).unwrap_or_default()),
+            profile: loaded.session.profile.as_ref().map(|p| p.name.clone()),
+            outcome: finished(&loaded).cloned(),
+            usage,
+        });
+    }
+    Ok(summaries)
+}
+
+/// The full transcript of one of `parent`'s children, for reading.
+pub async fn child_transcript(
+    store: &SessionStore,
+    parent: SessionId,
+    child: SessionId,
+) -> Result<Vec<SessionEntry>, CommandError> {
+    let loaded = store.load(child).await.map_err(|err| match err {
+        StoreError::NotFound(_) => CommandError::NotAChild(child),
+        other => CommandError::Persistence(other.to_string()),
+    })?;
+    if loaded.session.parent_session_id != Some(parent) {
+        return Err(CommandError::NotAChild(child));
+    }
+    Ok(loaded.entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// The prompt a child was started with: its first user message.
+fn first_prompt(loaded: &LoadedSession) -> Option<&str> {
+    loaded.entries.iter().find_map(|(_, entry)| match entry {
+        SessionEntry::UserMessage { text } => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+/// How a child's latest operation ended, if it has.
+fn finished(loaded: &LoadedSession) -> Option<&OperationOutcome> {
+    match &loaded.operations.last()?.latest.1.state {
+        OperationState::Finished(outcome) => Some(outcome),
+        _ => None,
+    }
+}
+
+/// `merge_child`: apply a worktree child's changes to the parent's
+/// working tree. Gated like a patch to the files it changes.
+pub struct MergeChildTool {
+    store: SessionStore,
+    parent_id: SessionId,
+    merges: PendingMerges,
+}
+
+impl MergeChildTool {
+    fn child(arguments: &Value) -> Option<SessionId> {
+        let raw = arguments.get("session")?.as_str()?.trim();
+        SessionId::parse(raw.strip_prefix("session-").unwrap_or(raw))
+    }
+
+    fn cached(&self, id: SessionId) -> Option<Changes> {
+        self.merges
+            .lock()
+            .expect("pending merges poisoned")
+            .get(&id)
+            .cloned()
+    }
+
+    /// A child's unmerged changes, from this process or the store.
+    async fn changes(&self, id: SessionId) -> Result<Changes, String> {
+        let changes = match self.cached(id) {
+            Some(changes) => Some(changes),
+            None => self
+                .store
+                .pending_merge(self.parent_id, id)
+                .await
+                .map_err(|err| format!("could not look up its changes: {err}"))?,
+        };
+        changes.ok_or_else(|| "no unmerged changes from that child session".to_owned())
+    }
+}
+
+impl Tool for MergeChildTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "merge_child".to_owned(),
+            description: "Apply the file changes a worktree child made to this workspace. \
+All or nothing: if any change no longer applies cleanly, no file is touched."
+                .to_owned(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "session": {
+                        "type": "string",
+                        "description": "the child session id from its delegate result"
+                    }
+                },
+                "required": ["session"]
+            }),
         }
     }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        _cancel: CancellationToken,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let Some(id) = Self::child(&arguments) else {
+                return ToolOutcome::error("malformed arguments: `session` must be a session id");
+            };
+            let changes = match self.changes(id).await {
+                Ok(changes) => changes,
+                Err(message) => return ToolOutcome::error(message),
+            };
+            let files = changes.files.clone();
+            match tokio::task::spawn_blocking(move || worktree::apply(&changes)).await {
+                Ok(Ok(())) => {
+                    self.merges
+                        .lock()
+                        .expect("pending merges poisoned")
+                        .remove(&id);
+                    if let Err(err) = self.store.remove_merge(id).await {
+                        tracing::warn!(child = %id, "could not forget merged changes: {err}");
+                    }
+                    ToolOutcome::text(format!("merged {id}: {}", files.join(", ")))
+                }
+                Ok(Err(message)) => ToolOutcome::error(format!("merge failed: {message}")),
+                Err(err) => ToolOutcome::error(format!("merge failed: {err}")),
+            }
+        })
+    }
+
+    /// A merge rewrites files like a patch does, so it recovers the
+    /// same way and `undo` can revert it.
+    fn recovery_class(&self) -> RecoveryClass {
+        RecoveryClass::Reconcile
+    }
+
+    /// Each changed file's preimage and the postimage the diff leaves,
+    /// in the shape `apply_patch` records.
+    fn reconciliation_evidence<'a>(
+        &'a self,
+        arguments: &'a Value,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Value, String>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            let id = Self::child(arguments)
+                .ok_or_else(|| "malformed arguments: `session` must be a session id".to_owned())?;
+            let changes = self.changes(id).await?;
+            let previews = tokio::task::spawn_blocking(move || worktree::preview(&changes))
+                .await
+                .map_err(|err| format!("could not preview the merge: {err}"))??;
+            let files: Vec<Value> = previews
+                .iter()
+                .map(|file| {
+                    file_evidence(&file.path, file.before.as_deref(), file.after.as_deref())
+                })
+                .collect();
+            Ok(json!({ "files": files }))
+        })
+    }
+
+    /// The files the merge changes, when this process recorded them.
+    /// Changes only the store knows, from before a restart, are gated
+    /// like a remote effect: the paths cannot be read here without
+    /// blocking.
+    fn local_target(&self, arguments: &Value) -> Option<CanonicalTarget> {
+        let Some(id) = Self::child(arguments) else {
+            return Some(CanonicalTarget::Paths { paths: Vec::new() });
+        };
+        let changes = self.cached(id)?;
+        Some(CanonicalTarget::Paths {
+            paths: changes
+                .files
+                .iter()
+                .map(|file| changes.repo.join(file))
+                .collect(),
+        })
+    }
 }
 
+/// How one child of a call ended, for the result's tally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChildStatus {
+    Completed,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+/// The result's first line: how many children ended which way.
+fn tally(statuses: &[ChildStatus]) -> String {
+    let parts: Vec<String> = [
+        (ChildStatus::Completed, "completed"),
+        (ChildStatus::Failed, "failed"),
+        (ChildStatus::TimedOut, "timed out"),
+        (ChildStatus::Cancelled, "cancelled"),
+    ]
+    .into_iter()
+    .filter_map(|(wanted, label)| {
+        let count = statuses.iter().filter(|status| **status == wanted).count();
+        (count > 0).then(|| format!("{count} {label}"))
+    })
+    .collect();
+    let noun = if statuses.len() == 1 {
+        "child"
+    } else {
+        "children"
+    };
+    format!("{} {noun}: {}", statuses.len(), parts.join(", "))
+}
+
+/// How a child ended. The unfinished ends keep the text the child had
+/// produced, so the parent still gets what it was worth.
 enum ChildTerminal {
     Completed(String),
-    Failed(String),
-    Cancelled,
+    Failed { message: String, partial: String },
+    TimedOut { after: Duration, partial: String },
+    Cancelled { partial: String },
 }
 
-/// Drain child events until the operation terminates, keeping the last
-/// assistant draft as the compact result.
-async fn pump_child(
-    events: &mut crate::runtime::EventSubscription,
-    operation_id: crate::ids::OperationId,
-) -> ChildTerminal {
-    let mut draft = String::new();
-    loop {
-        let event = match events.recv().await {
-            Ok(event) => event,
-            Err(crate::RuntimeError::SubscriptionLagged) => {
-                // The compact result must not present silently
-                // incomplete deltas as the child's answer (§21.4).
-                return ChildTerminal::Failed("child event stream lagged".to_owned());
+impl ChildTerminal {
+    fn failed(message: impl Into<String>) -> Self {
+        Self::Failed {
+            message: message.into(),
+            partial: String::new(),
+        }
+    }
+
+    /// A finished child's outcome as recorded, with the text its last
+    /// operation answered.
+    fn settled(outcome: &OperationOutcome, entries: &[(u64, SessionEntry)]) -> Self {
+        let start = entries
+            .iter()
+            .rposition(|(_, entry)| matches!(entry, SessionEntry::UserMessage { .. }))
+            .map_or(0, |index| index + 1);
+        let answered: Vec<&str> = entries[start..]
+            .iter()
+            .filter_map(|(_, entry)| match entry {
+                SessionEntry::AssistantMessage { text } if !text.is_empty() => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let partial = answered.join("\n");
+        match outcome {
+            OperationOutcome::Completed if partial.is_empty() => {
+                Self::Completed("(no output)".to_owned())
             }
-            Err(_) => return ChildTerminal::Failed("event stream closed".to_owned()),
+            OperationOutcome::Completed => Self::Completed(partial),
+            OperationOutcome::Failed(message) => Self::Failed {
+                message: message.clone(),
+                partial,
+            },
+            OperationOutcome::Cancelled => Self::Cancelled { partial },
+            OperationOutcome::Indeterminate => Self::Failed {
+                message: "an interrupted effect is indeterminate".to_owned(),
+                partial,
+            },
+            OperationOutcome::ApprovalRequired { tool } => Self::Failed {
+                message: format!("approval required for `{tool}` (read-only child)"),
+                partial,
+            },
+        }
+    }
+
+    fn status(&self) -> ChildStatus {
+        match self {
+            Self::Completed(_) => ChildStatus::Completed,
+            Self::Failed { .. } => ChildStatus::Failed,
+            Self::TimedOut { .. } => ChildStatus::TimedOut,
+            Self::Cancelled { .. } => ChildStatus::Cancelled,
+        }
+    }
+
+    fn update(&self) -> ChildUpdate {
+        match self {
+            Self::Completed(_) => ChildUpdate::Finished,
+            Self::Failed { message, .. } => ChildUpdate::Failed {
+                message: clip(message),
+            },
+            Self::TimedOut { .. } => ChildUpdate::TimedOut,
+            Self::Cancelled { .. } => ChildUpdate::Cancelled,
+        }
+    }
+
+    /// The child's part of the compact result.
+    fn render(self, child_id: SessionId) -> String {
+        let (head, partial) = match self {
+            Self::Completed(text) => return format!("{text}\n\n[child session: {child_id}]"),
+            Self::Failed { message, partial } => (format!("child failed: {message}"), partial),
+            Self::TimedOut { after, partial } => (
+                format!("child timed out after {}s", after.as_secs()),
+                partial,
+            ),
+            Self::Cancelled { partial } => ("child cancelled".to_owned(), partial),
         };
-        if event.operation_id() != Some(operation_id) {
-            continue;
+        match partial.trim() {
+            "" => format!("{head} [child session: {child_id}]"),
+            partial => format!("{head} [child session: {child_id}]\npartial result:\n{partial}"),
         }
-        match event {
-            crate::RuntimeEvent::AssistantTextDelta { text, .. } => {
-                draft.push_str(&text);
+    }
+}
+
+/// One child's event stream, drained into its compact result and
+/// summarized for the parent's frontends as it goes.
+struct ChildPump<'a> {
+    child_id: SessionId,
+    progress: &'a ToolProgress,
+    /// Running tools by call id, for naming their settlement.
+    tools: HashMap<u64, String>,
+    draft: String,
+    /// Length of `draft` at the last `Text` update.
+    reported: usize,
+}
+
+impl ChildPump<'_> {
+    /// Run the child's operation to its end. Cancelling the parent or
+    /// this child alone cancels it (§20.6), as does running past
+    /// `deadline`; the child settles durably as cancelled on its own.
+    async fn drive(
+        &mut self,
+        session: &SessionHandle,
+        events: &mut crate::runtime::EventSubscription,
+        operation_id: crate::ids::OperationId,
+        cancel: &CancellationToken,
+        deadline: Option<Duration>,
+    ) -> ChildTerminal {
+        let expired = async {
+            match deadline {
+                Some(after) => {
+                    tokio::time::sleep(after).await;
+                    after
+                }
+                None => std::future::pending().await,
             }
-            // Thinking and tool previews are parent-display-only; a
-            // child's terminal draft is its final assistant text.
-            crate::RuntimeEvent::ThinkingDelta { .. } => {}
-            crate::RuntimeEvent::OperationFinished { .. } => {
-                let result = if draft.is_empty() {
-                    "(no output)".to_owned()
-                } else {
-                    draft
-                };
-                return ChildTerminal::Completed(result);
+        };
+        tokio::select! {
+            outcome = self.run(events, operation_id) => outcome,
+            () = cancel.cancelled() => {
+                let _ = session.cancel(operation_id).await;
+                self.run(events, operation_id).await
             }
-            crate::RuntimeEvent::OperationCancelled { .. } => {
-                return ChildTerminal::Cancelled;
+            after = expired => {
+                let _ = session.cancel(operation_id).await;
+                match self.run(events, operation_id).await {
+                    ChildTerminal::Cancelled { partial } => ChildTerminal::TimedOut { after, partial },
+                    other => other,
+                }
             }
-            crate::RuntimeEvent::OperationFailed { message, .. } => {
-                return ChildTerminal::Failed(message);
+        }
+    }
+
+    /// Drain child events until the operation terminates, keeping the
+    /// last assistant draft as the compact result.
+    async fn run(
+        &mut self,
+        events: &mut crate::runtime::EventSubscription,
+        operation_id: crate::ids::OperationId,
+    ) -> ChildTerminal {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(crate::RuntimeError::SubscriptionLagged) => {
+                    // The compact result must not present silently
+                    // incomplete deltas as the child's answer (§21.4).
+                    return ChildTerminal::failed("child event stream lagged");
+                }
+                Err(_) => return ChildTerminal::failed("event stream closed"),
+            };
+            if event.operation_id() != Some(operation_id) {
+                continue;
             }
-            crate::RuntimeEvent::OperationApprovalRequired { tool, .. } => {
-                return ChildTerminal::Failed(format!(
-                    "approval required for `{tool}` (read-only child)"
-                ));
+            match event {
+                crate::RuntimeEvent::AssistantTextDelta { text, .. } => {
+                    self.draft.push_str(&text);
+                    self.report_text(text.contains('\n'));
+                }
+                crate::RuntimeEvent::ToolStarted {
+                    call_id,
+                    tool,
+                    target,
+                    ..
+                } => {
+                    self.tools.insert(call_id, tool.clone());
+                    self.progress
+                        .child(self.child_id, ChildUpdate::ToolStarted { tool, target });
+                }
+                crate::RuntimeEvent::ToolSettled {
+                    call_id, is_error, ..
+                } => {
+                    if let Some(tool) = self.tools.remove(&call_id) {
+                        self.progress
+                            .child(self.child_id, ChildUpdate::ToolSettled { tool, is_error });
+                    }
+                }
+                // Thinking and tool output stay in the child; a
+                // child's terminal draft is its final assistant text.
+                crate::RuntimeEvent::ThinkingDelta { .. }
+                | crate::RuntimeEvent::ToolOutput { .. }
+                | crate::RuntimeEvent::ChildProgress { .. } => {}
+                crate::RuntimeEvent::OperationFinished { .. } => {
+                    let result = if self.draft.is_empty() {
+                        "(no output)".to_owned()
+                    } else {
+                        std::mem::take(&mut self.draft)
+                    };
+                    return ChildTerminal::Completed(result);
+                }
+                crate::RuntimeEvent::OperationCancelled { .. } => {
+                    return ChildTerminal::Cancelled {
+                        partial: std::mem::take(&mut self.draft),
+                    };
+                }
+                crate::RuntimeEvent::OperationFailed { message, .. } => {
+                    return ChildTerminal::Failed {
+                        message,
+                        partial: std::mem::take(&mut self.draft),
+                    };
+                }
+                crate::RuntimeEvent::OperationApprovalRequired { tool, .. } => {
+                    return ChildTerminal::Failed {
+                        message: format!("approval required for `{tool}` (read-only child)"),
+                        partial: std::mem::take(&mut self.draft),
+                    };
+                }
+                crate::RuntimeEvent::OperationStarted { .. }
+                | crate::RuntimeEvent::SessionClosed { .. } => {}
             }
-            crate::RuntimeEvent::ToolStarted { .. }
-            | crate::RuntimeEvent::ToolSettled { .. }
-            | crate::RuntimeEvent::OperationStarted { .. }
-            | crate::RuntimeEvent::SessionClosed { .. } => {}
         }
     }
+
+    /// Report the draft's latest line once a line ends or enough new
+    /// text arrived, so a streaming child costs the parent a bounded
+    /// number of updates.
+    fn report_text(&mut self, line_ended: bool) {
+        if !line_ended && self.draft.len() - self.reported < CHILD_TEXT_BYTES {
+            return;
+        }
+        self.reported = self.draft.len();
+        if let Some(line) = self
+            .draft
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+        {
+            self.progress
+                .child(self.child_id, ChildUpdate::Text { line: clip(line) });
+        }
+    }
+}
+
+/// The first line of `text`, cut to [`CHILD_LINE_CHARS`].
+fn clip(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(CHILD_LINE_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_owned(),
+    }
 }
@@ -2,9 +2,9 @@
 
 use thiserror::Error;
 
-use crate::ids::OperationId;
+use crate::ids::{OperationId, RuntimeCursor, SessionId};
 
-#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[derive(Debug, Error, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum CommandError {
     #[error("session command queue is saturated")]
     QueueSaturated,
@@ -22,6 +22,18 @@ pub enum CommandError {
     UnsupportedModel(String),
     #[error("durable write failed: {0}")]
     Persistence(String),
+    #[error("this handle observes the session; commands need the controller")]
+    ReadOnly,
+    #[error("another client controls the session")]
+    ControllerHeld,
+    #[error("events after {cursor} are no longer buffered; resubscribe for a fresh snapshot")]
+    CursorUnavailable { cursor: RuntimeCursor },
+    #[error("could not restore {0}")]
+    UndoFailed(String),
+    #[error("{0} is not a child of this session")]
+    NotAChild(SessionId),
+    #[error("{0} is not a running child of this session")]
+    ChildNotRunning(SessionId),
 }
 
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
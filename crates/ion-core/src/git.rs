@@ -0,0 +1,680 @@
+//! Read-only git inspection: `git_status`, `git_diff`, `git_log`, and
+//! `git_blame`.
+//!
+//! Each tool runs one fixed `git` invocation in the project root: no
+//! shell, and no model-supplied options, since revisions that look
+//! like flags are refused. So an invocation only reads, and it
+//! canonicalizes to a path like `read` (DESIGN.md §17.3): ReplaySafe,
+//! and allowed by `DefaultPolicy` with no bash grant. `git_status`
+//! runs with optional locks off so it never rewrites the index under a
+//! concurrent writer, and no run starts an fsmonitor, a hook, a
+//! textconv driver, or a clean or smudge filter that config names:
+//! every configured filter is overridden to do nothing before the run.
+//! Output is compact and bounded.
+
+use std::ffi::OsString;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+
+use crate::tool::{LineRange, RecoveryClass, Tool, ToolOutcome, ToolSpec, resolve_under};
+
+/// Model-visible output of any git tool; longer output is cut on a line.
+const GIT_MAX_OUTPUT_BYTES: usize = 50 * 1024;
+/// Raw `git` output read before the process is killed.
+const GIT_CAPTURE_BYTES: usize = 8 * 1024 * 1024;
+/// Entries `git_status` lists before counting the rest.
+const STATUS_MAX_ENTRIES: usize = 256;
+const LOG_DEFAULT_COUNT: u64 = 20;
+const LOG_MAX_COUNT: u64 = 200;
+/// Context lines `git_diff` shows around a change, at most.
+const DIFF_MAX_CONTEXT: u64 = 20;
+/// Lines one `git_blame` call covers.
+const BLAME_MAX_LINES: u64 = 500;
+
+pub(crate) fn git_tools(cwd: &Arc<Path>) -> Vec<(Arc<dyn Tool>, RecoveryClass)> {
+    let root = || Arc::clone(cwd);
+    vec![
+        (
+            Arc::new(GitStatusTool { cwd: root() }),
+            RecoveryClass::ReplaySafe,
+        ),
+        (
+            Arc::new(GitDiffTool { cwd: root() }),
+            RecoveryClass::ReplaySafe,
+        ),
+        (
+            Arc::new(GitLogTool { cwd: root() }),
+            RecoveryClass::ReplaySafe,
+        ),
+        (
+            Arc::new(GitBlameTool { cwd: root() }),
+            RecoveryClass::ReplaySafe,
+        ),
+    ]
+}
+
+/// What one `git` run wrote to stdout.
+struct Captured {
+    stdout: String,
+    /// Output passed [`GIT_CAPTURE_BYTES`] and the process was killed.
+    cut: bool,
+}
+
+/// Run `git <args>` in `cwd`. A failed run is an error carrying git's
+/// own message.
+async fn run_git(
+    cwd: &Path,
+    args: Vec<OsString>,
+    cancel: &CancellationToken,
+) -> Result<Captured, String> {
+    let filters = tokio::select! {
+        () = cancel.cancelled() => return Err("cancelled".to_owned()),
+        filters = filter_overrides(cwd) => filters?,
+    };
+    let mut cmd = hardened_git(cwd);
+    cmd.args(filters)
+        .args(["--no-pager", "--literal-pathspecs"])
+        .args(["-c", "core.quotepath=false", "-c", "color.ui=false"])
+        .args(&args);
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("git failed to start: {err}"))?;
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let run = async {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let read_out = async {
+            let _ = (&mut stdout)
+                .take(GIT_CAPTURE_BYTES as u64 + 1)
+                .read_to_end(&mut out)
+                .await;
+            if out.len() > GIT_CAPTURE_BYTES {
+                let _ = child.start_kill();
+            }
+        };
+        let read_err = async {
+            let _ = stderr.read_to_end(&mut err).await;
+        };
+        tokio::join!(read_out, read_err);
+        let status = child.wait().await;
+        (out, err, status)
+    };
+    let (mut out, err, status) = tokio::select! {
+        () = cancel.cancelled() => return Err("cancelled".to_owned()),
+        finished = run => finished,
+    };
+    let cut = out.len() > GIT_CAPTURE_BYTES;
+    let success = status.is_ok_and(|status| status.success());
+    if !success && !cut {
+        let err = String::from_utf8_lossy(&err);
+        let subcommand = args
+            .first()
+            .map_or_else(String::new, |arg| format!(" {}", arg.to_string_lossy()));
+        return Err(format!("git{subcommand} failed: {}", err.trim()));
+    }
+    out.truncate(GIT_CAPTURE_BYTES);
+    Ok(Captured {
+        stdout: String::from_utf8_lossy(&out).into_owned(),
+        cut,
+    })
+}
+
+/// `git` in `cwd`, kept from running anything repository config names
+/// through an fsmonitor or a hook.
+fn hardened_git(cwd: &Path) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.args([
+        "-c",
+        "core.fsmonitor=false",
+        "-c",
+        "core.hooksPath=/dev/null",
+    ])
+    .current_dir(cwd)
+    .env("GIT_OPTIONAL_LOCKS", "0")
+    .stdin(Stdio::null());
+    cmd
+}
+
+/// `-c` options that turn every filter driver config defines into a
+/// no-op: attributes, which the workspace can change, select drivers,
+/// and a driver runs its command on files git reads.
+async fn filter_overrides(cwd: &Path) -> Result<Vec<String>, String> {
+    let output = hardened_git(cwd)
+        .args([
+            "config",
+            "--null",
+            "--name-only",
+            "--get-regexp",
+            r"^filter\.",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|err| format!("git failed to start: {err}"))?;
+    // Exit status 1: no filter is configured.
+    let names = String::from_utf8_lossy(&output.stdout);
+    let mut drivers: Vec<&str> = names
+        .split('\0')
+        .filter_map(|key| key.strip_prefix("filter.")?.rsplit_once('.'))
+        .map(|(driver, _)| driver)
+        .collect();
+    drivers.sort_unstable();
+    drivers.dedup();
+    Ok(drivers
+        .into_iter()
+        .flat_map(|driver| {
+            ["clean=", "smudge=", "process=", "required=false"]
+                .map(|setting| ["-c".to_owned(), format!("filter.{driver}.{setting}")])
+        })
+        .flatten()
+        .collect())
+}
+
+/// `text` within [`GIT_MAX_OUTPUT_BYTES`], cut on a line with a notice
+/// ending in `hint`.
+fn bounded(text: &str, cut: bool, hint: &str) -> String {
+    if text.len() <= GIT_MAX_OUTPUT_BYTES && !cut {
+        return text.trim_end().to_owned();
+    }
+    let mut end = GIT_MAX_OUTPUT_BYTES.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if let Some(newline) = text[..end].rfind('\n') {
+        end = newline;
+    }
+    format!(
+        "{}\n… output truncated at {} KiB; {hint}",
+        &text[..end],
+        GIT_MAX_OUTPUT_BYTES / 1024
+    )
+}
+
+/// The optional `path` argument as a pathspec under the project root.
+fn pathspec(cwd: &Path, arguments: &Value) -> Result<Option<OsString>, ToolOutcome> {
+    match arguments.get("path").and_then(Value::as_str) {
+        Some(raw) => resolve_under(cwd, raw).map(|path| Some(path.into_os_string())),
+        None => Ok(None),
+    }
+}
+
+/// A revision argument; anything git could parse as an option is
+/// refused.
+fn revision<'a>(arguments: &'a Value, key: &str) -> Result<Option<&'a str>, ToolOutcome> {
+    match arguments.get(key).and_then(Value::as_str) {
+        None => Ok(None),
+        Some(rev)
+            if rev.is_empty() || rev.starts_with('-') || rev.contains(char::is_whitespace) =>
+        {
+            Err(ToolOutcome::error(format!(
+                "{key} must be a revision such as HEAD~1 or main, not {rev:?}"
+            )))
+        }
+        Some(rev) => Ok(Some(rev)),
+    }
+}
+
+fn args<const N: usize>(fixed: [&str; N]) -> Vec<OsString> {
+    fixed.into_iter().map(OsString::from).collect()
+}
+
+/// Append `-- <path>` when the call names one.
+fn push_path(args: &mut Vec<OsString>, path: Option<OsString>) {
+    if let Some(path) = path {
+        args.push("--".into());
+        args.push(path);
+    }
+}
+
+// ---- git_status ----
+
+pub(crate) struct GitStatusTool {
+    cwd: Arc<Path>,
+}
+
+impl Tool for GitStatusTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "git_status".to_owned(),
+            description: format!(
+                "Show the git branch and its upstream, then staged, unstaged, untracked, and \
+                 conflicted files. Lists at most {STATUS_MAX_ENTRIES} files and counts the rest."
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Only report files under this path; defaults to the whole repository." }
+                }
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let path = match pathspec(&self.cwd, &arguments) {
+                Ok(path) => path,
+                Err(err) => return err,
+            };
+            let mut argv = args([
+                "status",
+                "--porcelain=v1",
+                "-z",
+                "--branch",
+                "--untracked-files=all",
+            ]);
+            push_path(&mut argv, path);
+            match run_git(&self.cwd, argv, &cancel).await {
+                Ok(captured) => ToolOutcome::text(render_status(&captured.stdout)),
+                Err(err) => ToolOutcome::error(err),
+            }
+        })
+    }
+}
+
+/// Group porcelain v1 `-z` records under headings, one file per line.
+fn render_status(porcelain: &str) -> String {
+    let mut branch = None;
+    let mut groups: [(&str, Vec<String>); 4] = [
+        ("conflicted", Vec::new()),
+        ("staged", Vec::new()),
+        ("unstaged", Vec::new()),
+        ("untracked", Vec::new()),
+    ];
+    let mut records = porcelain.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("## ") {
+            branch = Some(header.to_owned());
+            continue;
+        }
+        let (Some(code), Some(path)) = (record.get(..2), record.get(3..)) else {
+            continue;
+        };
+        let mut codes = code.chars();
+        let (index, worktree) = (codes.next().unwrap_or(' '), codes.next().unwrap_or(' '));
+        // A rename or copy is followed by its source path.
+        let path = if matches!(index, 'R' | 'C') {
+            let from = records.next().unwrap_or_default();
+            format!("{from} -> {path}")
+        } else {
+            path.to_owned()
+        };
+        let conflicted = index == 'U' || worktree == 'U' || code == "AA" || code == "DD";
+        if conflicted {
+            groups[0].1.push(format!("{code} {path}"));
+        } else if code == "??" {
+            groups[3].1.push(path);
+        } else {
+            if index != ' ' {
+                groups[1].1.push(format!("{}: {path}", change_name(index)));
+            }
+            if worktree != ' ' {
+                groups[2]
+                    .1
+                    .push(format!("{}: {path}", change_name(worktree)));
+            }
+        }
+    }
+    let mut out = vec![format!(
+        "branch: {}",
+        branch.as_deref().unwrap_or("(unknown)")
+    )];
+    let total: usize = groups.iter().map(|(_, files)| files.len()).sum();
+    if total == 0 {
+        out.push("nothing to commit, working tree clean".to_owned());
+        return out.join("\n");
+    }
+    let mut shown = 0;
+    for (heading, files) in &groups {
+        if files.is_empty() || shown == STATUS_MAX_ENTRIES {
+            continue;
+        }
+        out.push(format!("{heading} ({}):", files.len()));
+        for file in files.iter().take(STATUS_MAX_ENTRIES - shown) {
+            out.push(format!("  {file}"));
+            shown += 1;
+        }
+    }
+    if total > shown {
+        out.push(format!(
+            "… {} more files not shown (limit {STATUS_MAX_ENTRIES}); narrow with path",
+            total - shown
+        ));
+    }
+    out.join("\n")
+}
+
+fn change_name(code: char) -> &'static str {
+    match code {
+        'M' => "modified",
+        'A' => "added",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        'T' => "type changed",
+        _ => "changed",
+    }
+}
+
+// ---- git_diff ----
+
+pub(crate) struct GitDiffTool {
+    cwd: Arc<Path>,
+}
+
+impl Tool for GitDiffTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "git_diff".to_owned(),
+            description: format!(
+                "Show a git diff: a per-file summary, then the unified patch. By default the \
+                 unstaged changes; staged=true for the index, or base to compare the working \
+                 tree against a revision. Output is cut at {} KiB.",
+                GIT_MAX_OUTPUT_BYTES / 1024
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Only diff files under this path." },
+                    "staged": { "type": "boolean", "description": "Diff the index instead of the working tree." },
+                    "base": { "type": "string", "description": "Revision to compare against, e.g. HEAD~1 or main." },
+                    "context": { "type": "integer", "minimum": 0, "description": format!("Lines of context around each change; defaults to 3, at most {DIFF_MAX_CONTEXT}.") }
+                }
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let (path, base) = match (
+                pathspec(&self.cwd, &arguments),
+                revision(&arguments, "base"),
+            ) {
+                (Ok(path), Ok(base)) => (path, base),
+                (Err(err), _) | (_, Err(err)) => return err,
+            };
+            let mut argv = args([
+                "diff",
+                "--no-ext-diff",
+                "--no-textconv",
+                "--stat",
+                "--patch",
+            ]);
+            if arguments.get("staged").and_then(Value::as_bool) == Some(true) {
+                argv.push("--cached".into());
+            }
+            if let Some(context) = arguments.get("context").and_then(Value::as_u64) {
+                argv.push(format!("--unified={}", context.min(DIFF_MAX_CONTEXT)).into());
+            }
+            if let Some(base) = base {
+                argv.push(base.into());
+            }
+            push_path(&mut argv, path);
+            match run_git(&self.cwd, argv, &cancel).await {
+                Ok(captured) if captured.stdout.trim().is_empty() => {
+                    ToolOutcome::text("no changes")
+                }
+                Ok(captured) => ToolOutcome::text(bounded(
+                    &captured.stdout,
+                    captured.cut,
+                    "diff fewer files with path",
+                )),
+                Err(err) => ToolOutcome::error(err),
+            }
+        })
+    }
+}
+
+// ---- git_log ----
+
+pub(crate) struct GitLogTool {
+    cwd: Arc<Path>,
+}
+
+impl Tool for GitLogTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "git_log".to_owned(),
+            description: format!(
+                "List commits newest first, one per line: short hash, date, author, and \
+                 subject. Shows {LOG_DEFAULT_COUNT} commits unless max_count says otherwise."
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Only commits that touched this path." },
+                    "revision": { "type": "string", "description": "Where to start; defaults to HEAD. A range such as main..HEAD works too." },
+                    "max_count": { "type": "integer", "minimum": 1, "description": format!("Commits to show, at most {LOG_MAX_COUNT}.") }
+                }
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let (path, rev) = match (
+                pathspec(&self.cwd, &arguments),
+                revision(&arguments, "revision"),
+            ) {
+                (Ok(path), Ok(rev)) => (path, rev),
+                (Err(err), _) | (_, Err(err)) => return err,
+            };
+            let limit = arguments
+                .get("max_count")
+                .and_then(Value::as_u64)
+                .map_or(LOG_DEFAULT_COUNT, |n| n.min(LOG_MAX_COUNT));
+            // One extra commit says whether more exist.
+            let mut argv = args(["log", "--date=short", "--format=%h%x00%ad%x00%an%x00%s"]);
+            argv.push(format!("--max-count={}", limit + 1).into());
+            if let Some(rev) = rev {
+                argv.push(rev.into());
+            }
+            push_path(&mut argv, path);
+            let captured = match run_git(&self.cwd, argv, &cancel).await {
+                Ok(captured) => captured,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let commits: Vec<String> = captured
+                .stdout
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.splitn(4, '\0');
+                    let (hash, date, author, subject) = (
+                        fields.next()?,
+                        fields.next()?,
+                        fields.next()?,
+                        fields.next()?,
+                    );
+                    Some(format!("{hash} {date} {author}: {subject}"))
+                })
+                .collect();
+            if commits.is_empty() {
+                return ToolOutcome::text("no commits");
+            }
+            let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+            let mut out = commits[..commits.len().min(limit)].join("\n");
+            if commits.len() > limit {
+                out.push_str(&format!(
+                    "\n… more commits not shown (max_count {limit}); raise max_count or narrow with path"
+                ));
+            }
+            ToolOutcome::text(bounded(&out, false, "narrow with path or max_count"))
+        })
+    }
+}
+
+// ---- git_blame ----
+
+pub(crate) struct GitBlameTool {
+    cwd: Arc<Path>,
+}
+
+impl Tool for GitBlameTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "git_blame".to_owned(),
+            description: format!(
+                "Show who last changed each line of a file: short hash, author, date, line \
+                 number, and text. Covers at most {BLAME_MAX_LINES} lines per call; page with \
+                 offset and limit."
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "offset": { "type": "integer", "minimum": 1, "description": "First line to blame (1-based)." },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of lines to blame." },
+                    "revision": { "type": "string", "description": "Blame the file as of this revision; defaults to the working tree." }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let path = match pathspec(&self.cwd, &arguments) {
+                Ok(Some(path)) => path,
+                Ok(None) => return ToolOutcome::error("missing argument: path"),
+                Err(err) => return err,
+            };
+            let rev = match revision(&arguments, "revision") {
+                Ok(rev) => rev,
+                Err(err) => return err,
+            };
+            let range = match LineRange::from_arguments(&arguments) {
+                Ok(range) => range,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let offset = range.map_or(1, |range| range.offset);
+            let limit = range
+                .and_then(|range| range.limit)
+                .map_or(BLAME_MAX_LINES, |limit| limit.min(BLAME_MAX_LINES));
+            // One extra line says whether the file continues.
+            let mut argv = args(["blame", "--no-textconv", "--line-porcelain"]);
+            argv.push(format!("-L{offset},+{}", limit + 1).into());
+            if let Some(rev) = rev {
+                argv.push(rev.into());
+            }
+            push_path(&mut argv, Some(path));
+            let captured = match run_git(&self.cwd, argv, &cancel).await {
+                Ok(captured) => captured,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let mut lines = parse_blame(&captured.stdout);
+            let more = lines.len() as u64 > limit;
+            lines.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+            let Some(last) = lines.last().map(|line| line.number) else {
+                return ToolOutcome::text("no lines");
+            };
+            let mut out = lines
+                .iter()
+                .map(|line| {
+                    format!(
+                        "{} {} {} {:>6}\t{}",
+                        line.hash, line.author, line.date, line.number, line.text
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            if more {
+                out.push_str(&format!(
+                    "\n[truncated: showing lines {offset}-{last}; continue with offset={}]",
+                    last + 1
+                ));
+            }
+            ToolOutcome::text(bounded(&out, false, "page with offset and limit"))
+        })
+    }
+}
+
+/// One blamed line.
+struct BlameLine {
+    hash: String,
+    author: String,
+    date: String,
+    number: u64,
+    text: String,
+}
+
+/// Parse `--line-porcelain`: every line carries its commit's headers.
+fn parse_blame(porcelain: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let (mut hash, mut author, mut date, mut number) =
+        (String::new(), String::new(), String::new(), 0);
+    for line in porcelain.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            lines.push(BlameLine {
+                hash: hash.clone(),
+                author: author.clone(),
+                date: date.clone(),
+                number,
+                text: text.to_owned(),
+            });
+        } else if let Some(name) = line.strip_prefix("author ") {
+            name.clone_into(&mut author);
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            date = time.parse().map_or_else(|_| time.to_owned(), utc_date);
+        } else {
+            let mut fields = line.split(' ');
+            if let (Some(sha), Some(_), Some(final_line)) =
+                (fields.next(), fields.next(), fields.next())
+                && sha.len() >= 40
+                && sha.bytes().all(|b| b.is_ascii_hexdigit())
+            {
+                hash = sha[..8].to_owned();
+                number = final_line.parse().unwrap_or(0);
+            }
+        }
+    }
+    lines
+}
+
+/// `YYYY-MM-DD` of a Unix timestamp, in UTC.
+fn utc_date(seconds: i64) -> String {
+    // Howard Hinnant's days-to-civil.
+    let days = seconds.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -67,6 +67,17 @@ impl fmt::Display for OperationId {
     }
 }
 
+impl std::str::FromStr for OperationId {
+    type Err = String;
+
+    /// The display form (`op-<uuid>`) or the bare UUID.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(text.strip_prefix("op-").unwrap_or(text))
+            .map(Self)
+            .map_err(|_| format!("not an operation id: {text}"))
+    }
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
 )]
@@ -112,7 +123,19 @@ impl InboxId {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub struct RuntimeCursor(u64);
 
 impl RuntimeCursor {
@@ -132,3 +155,22 @@ impl fmt::Display for RuntimeCursor {
         write!(f, "cursor-{}", self.0)
     }
 }
+
+/// Identity of one loaded `SessionRuntime` incarnation (§21.2). Cursors
+/// restart with every incarnation, so a cursor only means something
+/// next to the instance that issued it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct RuntimeInstanceId(Uuid);
+
+impl RuntimeInstanceId {
+    #[must_use]
+    pub fn generate() -> Self {
+        Self(Uuid::now_v7())
+    }
+}
+
+impl fmt::Display for RuntimeInstanceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instance-{}", self.0)
+    }
+}
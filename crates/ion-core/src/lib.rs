@@ -8,49 +8,67 @@
 //! [`SessionHandle`] and subscribe to [`RuntimeEvent`]s. Persistence,
 //! tools, and TUI state are out of scope until their owning slices.
 
+mod artifact;
+mod checkpoint;
 mod context;
 mod delegate;
 mod error;
 mod extensions;
+mod git;
 mod ids;
 mod mcp;
+mod patch;
 mod policy;
+mod process;
 mod provider;
+mod remote;
 mod rpc;
 mod runtime;
+mod sandbox;
+mod schema;
 mod session;
+mod shell;
 mod store;
 mod tool;
+mod worktree;
 
-pub use context::{ContextMessage, ContextPlan, SYSTEM_SECTION, project};
-pub use delegate::{ChildSpec, DelegateConfig, DelegateTool, child_budget_default};
+pub use artifact::Artifact;
+pub use checkpoint::{UndoOutcome, undo};
+pub use context::{ContextMessage, ContextPlan, Image, SYSTEM_SECTION, project};
+pub use delegate::{
+    AgentProfile, ChildSpec, ChildSummary, ChildUpdate, ChildWorkspace, DelegateConfig,
+    DelegateTool, MergeChildTool, child_budget_default, child_transcript, children,
+};
 pub use error::{CommandError, RuntimeError};
 pub use extensions::{ExtensionDef, ExtensionService};
-pub use ids::{OperationId, RuntimeCursor, SessionId};
+pub use ids::{OperationId, RuntimeCursor, RuntimeInstanceId, SessionId};
 pub use mcp::{McpService, ServerDef};
 pub use policy::{AllowlistPolicy, DefaultPolicy, PolicyDecision, PolicyEngine};
 pub use provider::{
     EngineSignal, ModelConfig, Provider, ProviderRequest, ScriptedMessage, ScriptedProvider,
     SwitchingProvider, TokenUsage,
 };
+pub use remote::{BackendFuture, EventFeed, SessionBackend};
 pub use runtime::{
-    EventSubscription, LiveOperationState, OperationStatus, PendingTool, Runtime, RuntimeBudget,
-    RuntimeEvent, RuntimeHandle, SessionHandle, SessionSnapshot,
+    ChildLineage, EventSubscription, LiveOperationState, OperationStatus, PendingTool, Runtime,
+    RuntimeBudget, RuntimeEvent, RuntimeHandle, SessionHandle, SessionSetup, SessionSnapshot,
 };
+pub use sandbox::Sandbox;
 pub use session::{
     Applied, EffectIntent, InboxItem, InboxKind, OperationMachine, OperationOutcome,
     OperationState, SessionEntry, Transition, TransitionError,
 };
 pub use store::{
-    CheckpointPayload, CheckpointRecord, CommitRequest, EffectRecord, EntryRecord, InboxRecord,
-    InboxStatus, LoadedOperation, LoadedSession, SessionRecord, SessionStore, StoreError,
-    default_db_path,
+    CheckpointPayload, CheckpointRecord, CommitRequest, EffectRecord, EntryRecord, FileSnapshot,
+    InboxRecord, InboxStatus, LoadedOperation, LoadedSession, ParentCall, SessionRecord,
+    SessionStore, SnapshotRow, StoreError, default_db_path,
 };
 pub use tool::{
-    BashTool, CanonicalTarget, EditTool, FindTool, ReadTool, RecoveryClass, SearchTool, Tool,
-    ToolCall, ToolCallId, ToolOutcome, ToolRegistry, ToolResult, ToolSpec, WriteTool,
+    ApplyPatchTool, BashLimits, BashTool, CanonicalTarget, EditTool, FileDiff, FindTool, LineRange,
+    ReadTool, RecoveryClass, SearchTool, Tool, ToolCall, ToolCallId, ToolOutcome, ToolProgress,
+    ToolRegistry, ToolResult, ToolSpec, WriteTool,
 };
-pub use tool::{ToolCatalog, target_from_arguments};
+pub use tool::{ToolCatalog, inline_attachments, short_artifact_id, target_from_arguments};
 
 #[cfg(test)]
 mod tests;
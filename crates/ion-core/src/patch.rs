@@ -0,0 +1,349 @@
+//! Unified-diff parsing and application for the `apply_patch` tool,
+//! and rendering of the diffs file-mutating tools report.
+//!
+//! Pure text transforms. Parsing never touches the filesystem, so
+//! canonicalization can name every file a patch targets before the
+//! policy decides (DESIGN.md §17.3); applying a file's hunks is a
+//! function of its current contents alone, so admission computes the
+//! same postimage the executor later writes (§12.3).
+
+/// One file section of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FilePatch {
+    /// Path before the change; `None` when the patch creates the file.
+    pub(crate) old_path: Option<String>,
+    /// Path after the change; `None` when the patch deletes the file.
+    pub(crate) new_path: Option<String>,
+    pub(crate) hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path this section is reported under.
+    pub(crate) fn display_path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// One `@@` hunk. Lines carry no terminator; the flag records a
+/// `\ No newline at end of file` marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Hunk {
+    old_start: usize,
+    old: Vec<(String, bool)>,
+    new: Vec<(String, bool)>,
+}
+
+/// Parse a unified diff (plain or `git diff` output) into its file
+/// sections. Headers other than `---`/`+++`/`@@` are ignored.
+pub(crate) fn parse(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = patch.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(old) = line.strip_prefix("--- ") else {
+            continue;
+        };
+        let new = lines
+            .next()
+            .and_then(|line| line.strip_prefix("+++ "))
+            .ok_or_else(|| format!("expected a +++ line after `{line}`"))?;
+        let old_path = header_path(old, "a/");
+        let new_path = header_path(new, "b/");
+        if old_path.is_none() && new_path.is_none() {
+            return Err("a file section names /dev/null on both sides".to_owned());
+        }
+        let mut hunks = Vec::new();
+        while let Some(header) = lines.next_if(|line| line.starts_with("@@")) {
+            let (old_start, old_count, new_count) = hunk_header(header)?;
+            let mut hunk = Hunk {
+                old_start,
+                old: Vec::new(),
+                new: Vec::new(),
+            };
+            let mut last_tag = "";
+            while hunk.old.len() < old_count || hunk.new.len() < new_count {
+                let Some(line) = lines.next() else {
+                    return Err(format!("hunk `{header}` ends early"));
+                };
+                // Some editors strip the lone space of an empty
+                // context line.
+                let (tag, text) = line.split_at(line.len().min(1));
+                let text = text.to_owned();
+                match tag {
+                    " " | "" => {
+                        hunk.old.push((text.clone(), true));
+                        hunk.new.push((text, true));
+                    }
+                    "-" => hunk.old.push((text, true)),
+                    "+" => hunk.new.push((text, true)),
+                    "\\" => mark_no_newline(&mut hunk, last_tag),
+                    _ => return Err(format!("unexpected line in hunk `{header}`: {line}")),
+                }
+                if tag != "\\" {
+                    last_tag = tag;
+                }
+                if hunk.old.len() > old_count || hunk.new.len() > new_count {
+                    return Err(format!("hunk `{header}` has more lines than its header"));
+                }
+            }
+            if lines.next_if(|line| line.starts_with('\\')).is_some() {
+                mark_no_newline(&mut hunk, last_tag);
+            }
+            hunks.push(hunk);
+        }
+        if hunks.is_empty() {
+            return Err(format!(
+                "no hunks for {}",
+                new_path
+                    .as_deref()
+                    .or(old_path.as_deref())
+                    .unwrap_or_default()
+            ));
+        }
+        let patch = FilePatch {
+            old_path,
+            new_path,
+            hunks,
+        };
+        if files
+            .iter()
+            .any(|file| file.display_path() == patch.display_path())
+        {
+            return Err(format!("{} is patched twice", patch.display_path()));
+        }
+        files.push(patch);
+    }
+    if files.is_empty() {
+        return Err("patch touches no files".to_owned());
+    }
+    Ok(files)
+}
+
+/// A `---`/`+++` path: timestamps dropped, git's `a/`/`b/` prefix
+/// stripped, `/dev/null` meaning no file.
+fn header_path(raw: &str, prefix: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or(raw).trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_owned())
+}
+
+/// `@@ -start,count +start,count @@`; an omitted count is 1.
+fn hunk_header(header: &str) -> Result<(usize, usize, usize), String> {
+    let malformed = || format!("malformed hunk header: {header}");
+    let mut ranges = header
+        .trim_start_matches('@')
+        .split_whitespace()
+        .take_while(|part| !part.starts_with('@'));
+    let mut range = |sign: char| -> Result<(usize, usize), String> {
+        let part = ranges
+            .next()
+            .and_then(|part| part.strip_prefix(sign))
+            .ok_or_else(malformed)?;
+        let (start, count) = part.split_once(',').unwrap_or((part, "1"));
+        Ok((
+            start.parse().map_err(|_| malformed())?,
+            count.parse().map_err(|_| malformed())?,
+        ))
+    };
+    let (old_start, old_count) = range('-')?;
+    let (_, new_count) = range('+')?;
+    Ok((old_start, old_count, new_count))
+}
+
+/// Apply a `\ No newline at end of file` marker to the line before it,
+/// on the side(s) that line's `tag` belongs to.
+fn mark_no_newline(hunk: &mut Hunk, tag: &str) {
+    if matches!(tag, " " | "" | "-")
+        && let Some(line) = hunk.old.last_mut()
+    {
+        line.1 = false;
+    }
+    if matches!(tag, " " | "" | "+")
+        && let Some(line) = hunk.new.last_mut()
+    {
+        line.1 = false;
+    }
+}
+
+/// Apply `patch`'s hunks to `original`. Every hunk's old side must
+/// match exactly; its position may drift from the header when earlier
+/// edits moved lines, and the nearest match wins.
+pub(crate) fn apply(original: &str, patch: &FilePatch) -> Result<String, String> {
+    let lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(original.len());
+    let mut cursor = 0;
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        let expected = if hunk.old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let at = find_hunk(&lines, cursor, expected, &hunk.old).ok_or_else(|| {
+            format!(
+                "hunk {} (at line {}) does not match {}",
+                index + 1,
+                hunk.old_start,
+                patch.display_path()
+            )
+        })?;
+        out.extend(lines[cursor..at].iter().copied());
+        for (text, newline) in &hunk.new {
+            out.push_str(text);
+            if *newline {
+                out.push('\n');
+            }
+        }
+        cursor = at + hunk.old.len();
+    }
+    out.extend(lines[cursor..].iter().copied());
+    Ok(out)
+}
+
+/// The match for `old` at or after `cursor` closest to `expected`.
+fn find_hunk(
+    lines: &[&str],
+    cursor: usize,
+    expected: usize,
+    old: &[(String, bool)],
+) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    if cursor > last {
+        return None;
+    }
+    let matches_at = |at: usize| {
+        old.iter()
+            .zip(&lines[at..])
+            .all(|((text, _), line)| line.strip_suffix('\n').unwrap_or(line) == text)
+    };
+    let expected = expected.clamp(cursor, last);
+    (0..=last - cursor).find_map(|distance| {
+        [
+            expected.checked_add(distance),
+            expected.checked_sub(distance),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|&at| at >= cursor && at <= last && matches_at(at))
+    })
+}
+
+/// Lines of unchanged context around each rendered change.
+const CONTEXT_LINES: usize = 3;
+/// Largest changed region (old lines × new lines) diffed line by line;
+/// beyond it the region renders as one replacement.
+const LCS_MAX_CELLS: usize = 1 << 22;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Render the change from `old` to `new` as unified-diff hunks without
+/// file headers. Empty when the texts have the same lines.
+pub(crate) fn diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // Each op with the old/new line index it starts at.
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for op in edit_script(&old, &new) {
+        ops.push((op, i, j));
+        match op {
+            Op::Equal => (i, j) = (i + 1, j + 1),
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+    let mut out = String::new();
+    let mut at = 0;
+    while let Some(offset) = ops[at..].iter().position(|(op, ..)| *op != Op::Equal) {
+        let first = at + offset;
+        // Extend over later changes whose gap is small enough that
+        // their context would overlap.
+        let mut end = first;
+        loop {
+            while end < ops.len() && ops[end].0 != Op::Equal {
+                end += 1;
+            }
+            match ops[end..].iter().position(|(op, ..)| *op != Op::Equal) {
+                Some(gap) if gap <= 2 * CONTEXT_LINES => end += gap,
+                _ => break,
+            }
+        }
+        let start = first.saturating_sub(CONTEXT_LINES);
+        let stop = (end + CONTEXT_LINES).min(ops.len());
+        let hunk = &ops[start..stop];
+        let old_count = hunk.iter().filter(|(op, ..)| *op != Op::Insert).count();
+        let new_count = hunk.iter().filter(|(op, ..)| *op != Op::Delete).count();
+        // An empty side names the line before it, as `diff -u` does.
+        let old_line = hunk[0].1 + usize::from(old_count > 0);
+        let new_line = hunk[0].2 + usize::from(new_count > 0);
+        out.push_str(&format!(
+            "@@ -{old_line},{old_count} +{new_line},{new_count} @@\n"
+        ));
+        for &(op, i, j) in hunk {
+            let line = match op {
+                Op::Equal => format!(" {}\n", old[i]),
+                Op::Delete => format!("-{}\n", old[i]),
+                Op::Insert => format!("+{}\n", new[j]),
+            };
+            out.push_str(&line);
+        }
+        at = stop;
+    }
+    out.pop();
+    out
+}
+
+/// A shortest-enough edit script: the common prefix and suffix are
+/// kept, and the region between them is diffed by longest common
+/// subsequence while it stays small.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let mut ops = vec![Op::Equal; prefix];
+    let (mut i, mut j) = (0, 0);
+    if a.len().saturating_mul(b.len()) <= LCS_MAX_CELLS {
+        // table[i * width + j]: LCS length of a[i..] and b[j..].
+        let width = b.len() + 1;
+        let mut table = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                table[i * width + j] = if a[i] == b[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                ops.push(Op::Equal);
+                (i, j) = (i + 1, j + 1);
+            } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+                ops.push(Op::Delete);
+                i += 1;
+            } else {
+                ops.push(Op::Insert);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Delete, a.len() - i));
+    ops.extend(std::iter::repeat_n(Op::Insert, b.len() - j));
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
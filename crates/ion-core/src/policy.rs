@@ -29,16 +29,28 @@ pub trait PolicyEngine: Send + Sync + 'static {
     fn decide(&self, tool: &str, target: &CanonicalTarget) -> PolicyDecision;
 }
 
-/// v0 default: local reads and file mutations run; `bash` requires an
-/// explicit grant because its side effects are unbounded and its
-/// recovery class is NeverReplay (§12.4).
+/// v0 default: local reads and file mutations run; `bash` and
+/// `process_start` require an explicit grant because their side effects
+/// are unbounded and their recovery class is NeverReplay (§12.4).
+/// Reading or stopping a process the session started needs none, nor
+/// does reading one of its saved outputs, nor a command sandboxed
+/// without network on a kernel that also keeps it from the user's other
+/// processes: its writes stay inside the workspace like a file
+/// mutation's.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct DefaultPolicy;
 
 impl PolicyEngine for DefaultPolicy {
     fn decide(&self, _tool: &str, target: &CanonicalTarget) -> PolicyDecision {
         match target {
-            CanonicalTarget::Path { .. } => PolicyDecision::Allow,
+            CanonicalTarget::Path { .. }
+            | CanonicalTarget::Paths { .. }
+            | CanonicalTarget::Process { .. }
+            | CanonicalTarget::Artifact { .. } => PolicyDecision::Allow,
+            CanonicalTarget::Command {
+                sandbox: Some(sandbox),
+                ..
+            } if !sandbox.network && crate::sandbox::isolates_processes() => PolicyDecision::Allow,
             // Unbounded side effects: local shell and remote MCP/extension
             // effects both require an explicit grant (§12.4, §19.2).
             CanonicalTarget::Command { .. } | CanonicalTarget::Remote { .. } => {
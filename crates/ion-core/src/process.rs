@@ -0,0 +1,518 @@
+//! Background processes for long-running servers and watchers.
+//!
+//! `bash` runs a command to completion; `process_start` leaves one
+//! running and returns at once. Processes belong to the session's tool
+//! registry: each [`ToolCatalog::fork`](crate::ToolCatalog::fork) gets
+//! an empty table, and a dropped table kills whatever is still alive.
+//! A process is also killed when the operation that started it is
+//! cancelled. Output is kept in a bounded ring buffer; `process_output`
+//! returns what arrived since the last read.
+//!
+//! Starting a process is a NeverReplay effect (DESIGN.md §12.4): after a
+//! crash nothing restarts it, and a start that was in flight settles as
+//! indeterminate. A process the transcript shows as started and never
+//! stopped is listed again on recovery with an indeterminate status, and
+//! its id is not reused: it may still run, but nothing here owns it.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::tool::{
+    BASH_CHUNK_BYTES, RecoveryClass, Tool, ToolOutcome, ToolSpec, kill_process_group,
+};
+
+/// Output each process keeps; older bytes are dropped and counted.
+const PROCESS_OUTPUT_BYTES: usize = 64 * 1024;
+/// Most processes one session may have running at once.
+const MAX_RUNNING: usize = 16;
+/// Longest `process_output` may wait for a process to finish.
+const MAX_WAIT_SECS: u64 = 30;
+/// How long `process_stop` waits for a killed process to be reaped.
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// The background processes of one session.
+pub(crate) struct ProcessTable {
+    cwd: Arc<Path>,
+    next_id: AtomicU64,
+    processes: Mutex<BTreeMap<u64, Arc<Managed>>>,
+    /// Cancelled when the table drops; every process's stop token is a
+    /// child of it.
+    closed: CancellationToken,
+}
+
+struct Managed {
+    command: String,
+    pid: i32,
+    output: Arc<Mutex<Ring>>,
+    stop: CancellationToken,
+    /// `Some` once the process itself exited or was killed.
+    exit: watch::Receiver<Option<Exit>>,
+    /// Cancelled once the process is reaped and its output drained.
+    finished: CancellationToken,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Exit {
+    Code(i32),
+    Killed,
+    /// Started by an earlier incarnation of the session; whether it
+    /// still runs is unknown.
+    Orphaned,
+}
+
+/// Bounded output with a read cursor, in absolute byte offsets.
+#[derive(Default)]
+struct Ring {
+    bytes: VecDeque<u8>,
+    /// Offset of `bytes[0]`: how many bytes were dropped so far.
+    start: u64,
+    /// Offset up to which output was already returned.
+    read: u64,
+}
+
+impl Ring {
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend(chunk);
+        let excess = self.bytes.len().saturating_sub(PROCESS_OUTPUT_BYTES);
+        if excess > 0 {
+            self.bytes.drain(..excess);
+            self.start += excess as u64;
+        }
+    }
+
+    /// Output not returned yet, and how many unread bytes were dropped
+    /// before it could be.
+    fn take_unread(&mut self) -> (u64, String) {
+        let from = self.read.max(self.start);
+        let dropped = from - self.read;
+        let skip = usize::try_from(from - self.start).unwrap_or(usize::MAX);
+        let unread: Vec<u8> = self.bytes.iter().skip(skip).copied().collect();
+        self.read = self.start + self.bytes.len() as u64;
+        (dropped, String::from_utf8_lossy(&unread).into_owned())
+    }
+}
+
+impl Managed {
+    fn status(&self) -> String {
+        match *self.exit.borrow() {
+            None => "running".to_owned(),
+            Some(Exit::Code(code)) => format!("exited with code {code}"),
+            Some(Exit::Killed) => "killed".to_owned(),
+            Some(Exit::Orphaned) => {
+                "indeterminate (started before the session was restored)".to_owned()
+            }
+        }
+    }
+
+    /// A header line and the output since the last read.
+    fn report(&self, id: u64) -> String {
+        let (dropped, unread) = self
+            .output
+            .lock()
+            .expect("process output poisoned")
+            .take_unread();
+        let mut out = format!("process {id} {}: {}\n", self.status(), self.command);
+        if dropped > 0 {
+            out.push_str(&format!(
+                "… {dropped} bytes dropped; only the last {} KiB are kept …\n",
+                PROCESS_OUTPUT_BYTES / 1024
+            ));
+        }
+        if unread.is_empty() {
+            out.push_str("(no new output)");
+        } else {
+            out.push_str(&unread);
+        }
+        out
+    }
+}
+
+impl ProcessTable {
+    pub(crate) fn new(cwd: Arc<Path>) -> Self {
+        Self {
+            cwd,
+            next_id: AtomicU64::new(1),
+            processes: Mutex::new(BTreeMap::new()),
+            closed: CancellationToken::new(),
+        }
+    }
+
+    /// Record the processes a loaded transcript shows as started and
+    /// not stopped. Ids this table still runs (a hibernated session
+    /// keeps its table) are left alone; the rest were lost with the
+    /// process that owned them and are listed as orphaned, with no pid
+    /// to signal since it may have been reused.
+    pub(crate) fn reconcile(&self, started: impl IntoIterator<Item = (u64, String)>) {
+        let mut processes = self.processes.lock().expect("process table poisoned");
+        for (id, command) in started {
+            self.next_id.fetch_max(id + 1, Ordering::Relaxed);
+            processes.entry(id).or_insert_with(|| {
+                let finished = CancellationToken::new();
+                finished.cancel();
+                Arc::new(Managed {
+                    command,
+                    pid: 0,
+                    output: Arc::default(),
+                    stop: CancellationToken::new(),
+                    exit: watch::channel(Some(Exit::Orphaned)).1,
+                    finished,
+                })
+            });
+        }
+    }
+
+    fn get(&self, id: u64) -> Result<Arc<Managed>, String> {
+        self.processes
+            .lock()
+            .expect("process table poisoned")
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| format!("no background process {id}"))
+    }
+
+    /// Start `command` with `sh -c` in the registry's directory. It is
+    /// killed when `cancel` (the starting operation's) fires, when it
+    /// is stopped, or when the table drops.
+    fn start(&self, command: &str, cancel: CancellationToken) -> Result<u64, String> {
+        let mut processes = self.processes.lock().expect("process table poisoned");
+        let running = processes
+            .values()
+            .filter(|process| process.exit.borrow().is_none())
+            .count();
+        if running >= MAX_RUNNING {
+            return Err(format!(
+                "{running} background processes are running (limit {MAX_RUNNING}); \
+                 stop one with process_stop first"
+            ));
+        }
+        let mut cmd = Command::new("sh");
+        // One stream for both, so output interleaves as written.
+        cmd.arg("-c")
+            .arg(format!("exec 2>&1\n{command}"))
+            .current_dir(&self.cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
+        let mut child = cmd.spawn().map_err(|err| format!("spawn failed: {err}"))?;
+        let pid = child.id().map_or(0, |pid| pid as i32);
+        let output = Arc::new(Mutex::new(Ring::default()));
+        let reader = child.stdout.take().map(|mut stdout| {
+            let output = Arc::clone(&output);
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; BASH_CHUNK_BYTES];
+                while let Ok(n) = stdout.read(&mut buf).await {
+                    if n == 0 {
+                        break;
+                    }
+                    output
+                        .lock()
+                        .expect("process output poisoned")
+                        .push(&buf[..n]);
+                }
+            })
+        });
+        let (exit_tx, exit) = watch::channel(None);
+        let stop = self.closed.child_token();
+        let finished = CancellationToken::new();
+        tokio::spawn(supervise(
+            child,
+            pid,
+            reader,
+            stop.clone(),
+            cancel,
+            exit_tx,
+            finished.clone(),
+        ));
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        processes.insert(
+            id,
+            Arc::new(Managed {
+                command: command.to_owned(),
+                pid,
+                output,
+                stop,
+                exit,
+                finished,
+            }),
+        );
+        Ok(id)
+    }
+}
+
+impl Drop for ProcessTable {
+    /// The session is gone: nothing it started outlives it, even when
+    /// no runtime is left to run the supervisors.
+    fn drop(&mut self) {
+        let processes = self.processes.get_mut().expect("process table poisoned");
+        for process in processes.values() {
+            if !process.finished.is_cancelled() {
+                kill_process_group(process.pid);
+            }
+        }
+        self.closed.cancel();
+    }
+}
+
+/// Own one process until it exited and its output is drained, or until
+/// it is stopped; a stop kills the whole group, including children that
+/// outlived the leader and still hold the pipe.
+async fn supervise(
+    mut child: Child,
+    pid: i32,
+    reader: Option<JoinHandle<()>>,
+    stop: CancellationToken,
+    cancel: CancellationToken,
+    exit_tx: watch::Sender<Option<Exit>>,
+    finished: CancellationToken,
+) {
+    let mut reader = reader.unwrap_or_else(|| tokio::spawn(async {}));
+    let mut running = true;
+    let mut reading = true;
+    while running || reading {
+        tokio::select! {
+            status = child.wait(), if running => {
+                running = false;
+                let exit = status
+                    .ok()
+                    .and_then(|status| status.code())
+                    .map_or(Exit::Killed, Exit::Code);
+                let _ = exit_tx.send(Some(exit));
+            }
+            _ = &mut reader, if reading => reading = false,
+            () = stop.cancelled() => break,
+            () = cancel.cancelled() => break,
+        }
+    }
+    if running || reading {
+        kill_process_group(pid);
+        if running {
+            // Reap the killed leader so it does not linger as a zombie.
+            let _ = child.wait().await;
+            let _ = exit_tx.send(Some(Exit::Killed));
+        }
+        if reading {
+            let _ = reader.await;
+        }
+    }
+    finished.cancel();
+}
+
+/// How a `process_start` result begins, before `{id}: {command}`.
+const STARTED: &str = "started process ";
+
+/// The id and command a successful `process_start` output reports.
+pub(crate) fn started_process(output: &str) -> Option<(u64, String)> {
+    let (id, command) = output.strip_prefix(STARTED)?.split_once(": ")?;
+    Some((id.parse().ok()?, command.to_owned()))
+}
+
+pub(crate) fn process_id(arguments: &Value) -> Result<u64, String> {
+    arguments
+        .get("id")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| "missing integer argument: id".to_owned())
+}
+
+/// The core process tools over one table, with their recovery classes.
+pub(crate) fn process_tools(table: &Arc<ProcessTable>) -> Vec<(Arc<dyn Tool>, RecoveryClass)> {
+    vec![
+        (
+            Arc::new(ProcessStartTool {
+                table: Arc::clone(table),
+            }),
+            RecoveryClass::NeverReplay,
+        ),
+        // Reading only moves this session's cursor; after process loss
+        // the table is empty and a replay reports that.
+        (
+            Arc::new(ProcessOutputTool {
+                table: Arc::clone(table),
+            }),
+            RecoveryClass::ReplaySafe,
+        ),
+        (
+            Arc::new(ProcessStopTool {
+                table: Arc::clone(table),
+            }),
+            RecoveryClass::NeverReplay,
+        ),
+    ]
+}
+
+pub(crate) struct ProcessStartTool {
+    table: Arc<ProcessTable>,
+}
+
+impl Tool for ProcessStartTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "process_start".to_owned(),
+            description: format!(
+                "Start a long-running shell command (a dev server, a watcher) in the background \
+                 and return its id at once. Read its output with process_output and stop it \
+                 with process_stop; it is killed when the turn is cancelled or the session \
+                 ends. At most {MAX_RUNNING} run at once."
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "Shell command to run with sh -c." }
+                },
+                "required": ["command"]
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let Some(command) = arguments.get("command").and_then(|v| v.as_str()) else {
+                return ToolOutcome::error("missing string argument: command");
+            };
+            match self.table.start(command, cancel) {
+                Ok(id) => ToolOutcome::text(format!("{STARTED}{id}: {command}")),
+                Err(err) => ToolOutcome::error(err),
+            }
+        })
+    }
+}
+
+pub(crate) struct ProcessOutputTool {
+    table: Arc<ProcessTable>,
+}
+
+impl Tool for ProcessOutputTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "process_output".to_owned(),
+            description: format!(
+                "Show a background process's status and the output it produced since the last \
+                 call; only the last {} KiB are kept. Without an id, list the session's \
+                 processes.",
+                PROCESS_OUTPUT_BYTES / 1024
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "description": "Process id from process_start." },
+                    "wait_secs": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": format!("Wait up to this many seconds, at most {MAX_WAIT_SECS}, for the process to finish first.")
+                    }
+                },
+                "required": []
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            if arguments.get("id").is_none_or(Value::is_null) {
+                let processes = self.table.processes.lock().expect("process table poisoned");
+                if processes.is_empty() {
+                    return ToolOutcome::text("no background processes");
+                }
+                let lines: Vec<String> = processes
+                    .iter()
+                    .map(|(id, process)| format!("{id} {}: {}", process.status(), process.command))
+                    .collect();
+                return ToolOutcome::text(lines.join("\n"));
+            }
+            let id = match process_id(&arguments) {
+                Ok(id) => id,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let process = match self.table.get(id) {
+                Ok(process) => process,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let wait = match arguments.get("wait_secs") {
+                None | Some(Value::Null) => None,
+                Some(value) => match value.as_u64() {
+                    Some(secs) if secs > 0 => Some(Duration::from_secs(secs.min(MAX_WAIT_SECS))),
+                    _ => return ToolOutcome::error("wait_secs must be a positive integer"),
+                },
+            };
+            if let Some(wait) = wait {
+                tokio::select! {
+                    () = process.finished.cancelled() => {}
+                    () = tokio::time::sleep(wait) => {}
+                    () = cancel.cancelled() => return ToolOutcome::error("cancelled"),
+                }
+            }
+            ToolOutcome::text(process.report(id))
+        })
+    }
+}
+
+pub(crate) struct ProcessStopTool {
+    table: Arc<ProcessTable>,
+}
+
+impl Tool for ProcessStopTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "process_stop".to_owned(),
+            description: "Kill a background process and everything it started, and return its \
+                          remaining output. The process is forgotten afterwards."
+                .to_owned(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "description": "Process id from process_start." }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        _cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let id = match process_id(&arguments) {
+                Ok(id) => id,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            let process = match self.table.get(id) {
+                Ok(process) => process,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            process.stop.cancel();
+            // SIGKILL is prompt; the bound only guards a wedged reap.
+            let _ = tokio::time::timeout(STOP_GRACE, process.finished.cancelled()).await;
+            self.table
+                .processes
+                .lock()
+                .expect("process table poisoned")
+                .remove(&id);
+            ToolOutcome::text(process.report(id))
+        })
+    }
+}
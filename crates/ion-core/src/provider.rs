@@ -282,6 +282,10 @@ pub enum ScriptedMessage {
         name: String,
         arguments: serde_json::Value,
     },
+    /// Emit several complete tool calls in one step, then complete it.
+    ToolCalls {
+        calls: Vec<(String, serde_json::Value)>,
+    },
     /// Emit a usage update, then continue with the next message.
     Usage(TokenUsage),
     /// Fail the step with the given message.
@@ -312,6 +316,16 @@ impl ScriptedMessage {
             arguments,
         }
     }
+
+    #[must_use]
+    pub fn tools<N: Into<String>>(calls: impl IntoIterator<Item = (N, serde_json::Value)>) -> Self {
+        Self::ToolCalls {
+            calls: calls
+                .into_iter()
+                .map(|(name, arguments)| (name.into(), arguments))
+                .collect(),
+        }
+    }
 }
 
 /// A provider adapter that plays a scripted transcript across successive
@@ -352,6 +366,30 @@ impl ScriptedProvider {
     pub fn echo() -> Self {
         Self::new(vec![ScriptedMessage::text("ok")])
     }
+
+    /// Emit one completed tool call; false once the runtime is gone.
+    async fn send_call(
+        &self,
+        out: &mpsc::Sender<EngineSignal>,
+        operation_id: OperationId,
+        step: u64,
+        name: String,
+        arguments: serde_json::Value,
+    ) -> bool {
+        let call_id = self.call_ids.fetch_add(1, Ordering::Relaxed);
+        out.send(EngineSignal::ToolCallCompleted {
+            operation_id,
+            step,
+            call: ToolCall {
+                operation_id,
+                call_id,
+                name,
+                arguments,
+            },
+        })
+        .await
+        .is_ok()
+    }
 }
 
 impl Provider for ScriptedProvider {
@@ -457,20 +495,9 @@ impl Provider for ScriptedProvider {
                         }
                     }
                     ScriptedMessage::ToolCall { name, arguments } => {
-                        let call_id = self.call_ids.fetch_add(1, Ordering::Relaxed);
-                        if out
-                            .send(EngineSignal::ToolCallCompleted {
-                                operation_id,
-                                step,
-                                call: ToolCall {
-                                    operation_id,
-                                    call_id,
-                                    name,
-                                    arguments,
-                                },
-                            })
+                        if !self
+                            .send_call(&out, operation_id, step, name, arguments)
                             .await
-                            .is_err()
                         {
                             return;
                         }
@@ -479,6 +506,20 @@ impl Provider for ScriptedProvider {
                             .await;
                         return;
                     }
+                    ScriptedMessage::ToolCalls { calls } => {
+                        for (name, arguments) in calls {
+                            if !self
+                                .send_call(&out, operation_id, step, name, arguments)
+                                .await
+                            {
+                                return;
+                            }
+                        }
+                        let _ = out
+                            .send(EngineSignal::Completed { operation_id, step })
+                            .await;
+                        return;
+                    }
                 }
             }
         }
@@ -0,0 +1,214 @@
+//! Remote session handles (DESIGN.md §21.1, Step 10).
+//!
+//! A daemon hosts `SessionRuntime`s in its own process; its clients
+//! still speak the one [`SessionHandle`] contract every frontend uses.
+//! [`SessionHandle::remote`] is that bridge: commands are forwarded in
+//! submission order to a [`SessionBackend`] (the wire adapter), and the
+//! events the adapter receives are pushed through an [`EventFeed`] into
+//! an ordinary [`EventSubscription`]. The bridge owns no agent truth -
+//! the hosting process's `SessionRuntime` stays the single writer.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use tokio::sync::mpsc;
+
+use crate::checkpoint::UndoOutcome;
+use crate::delegate::ChildSummary;
+use crate::error::{CommandError, RuntimeError};
+use crate::ids::{OperationId, RuntimeCursor, RuntimeInstanceId, SessionId};
+use crate::runtime::{
+    Authority, COMMAND_CAPACITY, EventSubscription, RuntimeEvent, SUBSCRIBER_CAPACITY,
+    SessionCommand, SessionHandle, SessionSnapshot,
+};
+use crate::session::SessionEntry;
+
+/// The future every [`SessionBackend`] method returns.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, CommandError>> + Send + 'a>>;
+
+/// One session's command surface as served by another process. Each
+/// method answers exactly like its [`SessionHandle`] counterpart; a lost
+/// transport answers [`CommandError::Closed`].
+pub trait SessionBackend: Send + Sync + 'static {
+    fn submit(&self, prompt: String) -> BackendFuture<'_, OperationId>;
+    fn steer(&self, text: String) -> BackendFuture<'_, ()>;
+    fn follow_up(&self, text: String) -> BackendFuture<'_, ()>;
+    fn compact(&self, instructions: Option<String>) -> BackendFuture<'_, bool>;
+    fn switch_model(&self, model_ref: String) -> BackendFuture<'_, String>;
+    fn cancel(&self, operation_id: OperationId) -> BackendFuture<'_, ()>;
+    fn cancel_child(&self, child: SessionId) -> BackendFuture<'_, ()>;
+    fn undo(&self, operation: Option<OperationId>) -> BackendFuture<'_, UndoOutcome>;
+    fn snapshot(&self) -> BackendFuture<'_, SessionSnapshot>;
+    fn artifact(&self, id: String) -> BackendFuture<'_, Option<String>>;
+    fn children(&self) -> BackendFuture<'_, Vec<ChildSummary>>;
+    fn child_transcript(&self, child: SessionId) -> BackendFuture<'_, Vec<SessionEntry>>;
+    /// Snapshot plus a live subscription. Implementations pair the
+    /// remote subscription with [`EventFeed::channel`] so no event
+    /// between the snapshot and the first delta is lost.
+    fn subscribe(&self) -> BackendFuture<'_, (SessionSnapshot, EventSubscription)>;
+    /// Replay after a cursor, then live; paired with
+    /// [`EventFeed::channel`] like `subscribe`.
+    fn resume(
+        &self,
+        instance: RuntimeInstanceId,
+        after: RuntimeCursor,
+    ) -> BackendFuture<'_, EventSubscription>;
+    /// Take the controller lease for this attachment. The lease lives
+    /// with the serving side; the remote handle only forwards.
+    fn claim_control(&self) -> BackendFuture<'_, ()>;
+    fn release_control(&self) -> BackendFuture<'_, ()>;
+    fn close(&self) -> BackendFuture<'_, ()>;
+}
+
+/// Producer side of a remote [`EventSubscription`].
+///
+/// Bounded like the local broadcast ring (§21.4), with one slot held in
+/// reserve: when the subscriber falls behind, the reserved slot carries
+/// [`RuntimeError::SubscriptionLagged`] and the feed ends, so lag is
+/// always delivered instead of silently dropping deltas.
+pub struct EventFeed {
+    tx: mpsc::Sender<Result<RuntimeEvent, RuntimeError>>,
+    ended: bool,
+}
+
+impl EventFeed {
+    #[must_use]
+    pub fn channel() -> (Self, EventSubscription) {
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_CAPACITY + 1);
+        (Self { tx, ended: false }, EventSubscription::remote(rx))
+    }
+
+    /// Deliver one event. Returns false once the feed has ended - the
+    /// subscriber dropped it or lagged - and the caller should release
+    /// the upstream subscription.
+    pub fn push(&mut self, event: RuntimeEvent) -> bool {
+        if self.ended || self.tx.is_closed() {
+            self.ended = true;
+            return false;
+        }
+        if self.tx.capacity() > 1 {
+            return self.tx.try_send(Ok(event)).is_ok();
+        }
+        self.lagged();
+        false
+    }
+
+    /// The upstream subscription lagged (or this feed overflowed):
+    /// surface it to the subscriber and end the feed.
+    pub fn lagged(&mut self) {
+        if !self.ended {
+            self.ended = true;
+            let _ = self.tx.try_send(Err(RuntimeError::SubscriptionLagged));
+        }
+    }
+
+    /// True once no further events will be delivered.
+    #[must_use]
+    pub fn is_ended(&self) -> bool {
+        self.ended || self.tx.is_closed()
+    }
+}
+
+impl SessionHandle {
+    /// A handle whose commands are served by `backend` instead of an
+    /// in-process `SessionRuntime`. Commands are forwarded one at a
+    /// time, preserving the order callers issued them in; after
+    /// `close` the handle reports [`CommandError::Closed`]. The serving
+    /// side arbitrates control; an [`SessionHandle::observer`] derived
+    /// from this handle is refused locally.
+    pub fn remote(backend: impl SessionBackend) -> Self {
+        let (tx, mut rx) = mpsc::channel(COMMAND_CAPACITY);
+        tokio::spawn(async move {
+            while let Some(command) = rx.recv().await {
+                if matches!(
+                    command,
+                    SessionCommand::ReleaseControl {
+                        authority: Authority::Observer,
+                        ..
+                    }
+                ) || command.authority() == Some(Authority::Observer)
+                {
+                    command.reject(CommandError::ReadOnly);
+                    continue;
+                }
+                match command {
+                    SessionCommand::Submit { prompt, reply, .. } => {
+                        let _ = reply.send(backend.submit(prompt).await);
+                    }
+                    SessionCommand::Steer { text, reply, .. } => {
+                        let _ = reply.send(backend.steer(text).await);
+                    }
+                    SessionCommand::FollowUp { text, reply, .. } => {
+                        let _ = reply.send(backend.follow_up(text).await);
+                    }
+                    SessionCommand::Cancel {
+                        operation_id,
+                        reply,
+                        ..
+                    } => {
+                        let _ = reply.send(backend.cancel(operation_id).await);
+                    }
+                    SessionCommand::CancelChild { child, reply, .. } => {
+                        let _ = reply.send(backend.cancel_child(child).await);
+                    }
+                    SessionCommand::Compact {
+                        instructions,
+                        reply,
+                        ..
+                    } => {
+                        let _ = reply.send(backend.compact(instructions).await);
+                    }
+                    SessionCommand::SwitchModel {
+                        model_ref, reply, ..
+                    } => {
+                        let _ = reply.send(backend.switch_model(model_ref).await);
+                    }
+                    SessionCommand::Undo {
+                        operation, reply, ..
+                    } => {
+                        let _ = reply.send(backend.undo(operation).await);
+                    }
+                    SessionCommand::Snapshot { reply } => {
+                        let _ = reply.send(backend.snapshot().await);
+                    }
+                    SessionCommand::Artifact { id, reply } => {
+                        let _ = reply.send(backend.artifact(id).await);
+                    }
+                    SessionCommand::Children { reply } => {
+                        let _ = reply.send(backend.children().await);
+                    }
+                    SessionCommand::ChildTranscript { child, reply } => {
+                        let _ = reply.send(backend.child_transcript(child).await);
+                    }
+                    SessionCommand::Subscribe { reply } => {
+                        let _ = reply.send(backend.subscribe().await);
+                    }
+                    SessionCommand::Resume {
+                        instance,
+                        after,
+                        reply,
+                    } => {
+                        let _ = reply.send(backend.resume(instance, after).await);
+                    }
+                    SessionCommand::ClaimControl { reply } => {
+                        // The lease number is nominal here: the serving
+                        // side checks its own.
+                        let _ = reply.send(backend.claim_control().await.map(|()| 0));
+                    }
+                    SessionCommand::ReleaseControl { reply, .. } => {
+                        let _ = reply.send(backend.release_control().await);
+                    }
+                    SessionCommand::Close { reply, .. } => {
+                        let closed = backend.close().await;
+                        let done = closed.is_ok();
+                        let _ = reply.send(closed);
+                        if done {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        Self::from_sender(tx)
+    }
+}
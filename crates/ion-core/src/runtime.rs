@@ -10,8 +10,11 @@
 //! session at its last durable checkpoint. Provider/tool I/O stays off
 //! the mutation line; only bounded local persistence is awaited (§4.3).
 
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::task::JoinHandle;
@@ -19,10 +22,13 @@ use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 use tracing::{debug, error, info, warn};
 
-use crate::context::{ContextPlan, project};
+use crate::checkpoint::UndoOutcome;
+use crate::context::{ContextMessage, ContextPlan, Image, project};
+use crate::delegate::{AgentProfile, ChildSummary, ChildUpdate, RunningChildren};
 use crate::error::{CommandError, RuntimeError};
-use crate::ids::{EffectId, InboxId, OperationId, RuntimeCursor, SessionId};
+use crate::ids::{EffectId, InboxId, OperationId, RuntimeCursor, RuntimeInstanceId, SessionId};
 use crate::policy::{DefaultPolicy, PolicyDecision, PolicyEngine};
+use crate::process::{process_id, started_process};
 use crate::provider::{EngineSignal, ModelConfig, Provider, ProviderRequest, TokenUsage};
 use crate::session::{
     EffectIntent, InboxItem, InboxKind, OperationMachine, OperationOutcome, OperationState,
@@ -30,18 +36,32 @@ use crate::session::{
 };
 use crate::store::{
     CheckpointPayload, CheckpointRecord, CommitRequest, EffectRecord, EntryRecord, InboxRecord,
-    InboxStatus, LoadedSession, SessionRecord, SessionStore, SettledEffect, StoreError,
+    InboxStatus, LoadedSession, ParentCall, SessionRecord, SessionStore, SettledEffect, StoreError,
     UsageRecord,
 };
-use crate::tool::{RecoveryClass, ToolCall, ToolCatalog, ToolResult, ToolSpec};
+use crate::tool::{
+    CanonicalTarget, FileDiff, RecoveryClass, ToolCall, ToolCatalog, ToolProgress, ToolResult,
+    ToolSpec,
+};
 
-const COMMAND_CAPACITY: usize = 32;
+pub(crate) const COMMAND_CAPACITY: usize = 32;
 const ENGINE_CAPACITY: usize = 64;
 /// Broadcast buffer per subscriber (§21.4): a slow UI never blocks or
 /// grows the runtime; overflow surfaces as a reliable lag error.
-const SUBSCRIBER_CAPACITY: usize = 64;
-type SubscribeReply = Result<(SessionSnapshot, EventSubscription), CommandError>;
-type ToolSettlement = (EffectId, ToolResult);
+pub(crate) const SUBSCRIBER_CAPACITY: usize = 64;
+/// Recent events kept for cursor resume. Larger than the subscriber
+/// ring, so a lagged subscriber usually catches up without a snapshot.
+const REPLAY_CAPACITY: usize = 512;
+pub(crate) type SubscribeReply = Result<(SessionSnapshot, EventSubscription), CommandError>;
+type ToolSettlement = (EffectId, ToolResult, Vec<FileDiff>);
+/// Live output from a running tool call.
+type ToolChunk = (OperationId, u64, LiveOutput);
+
+/// What a running tool call reports while it runs; display-only.
+enum LiveOutput {
+    Chunk(String),
+    Child(SessionId, ChildUpdate),
+}
 
 /// One-line display summary of a call's canonical target (best
 /// effort; None when canonicalization fails — the denial surfaces
@@ -52,19 +72,41 @@ fn target_summary(
     arguments: &serde_json::Value,
 ) -> Option<String> {
     match tools.canonicalize(name, arguments) {
-        Ok(crate::tool::CanonicalTarget::Path { path }) => Some(path.file_name().map_or_else(
-            || path.display().to_string(),
-            |n| n.to_string_lossy().into_owned(),
-        )),
-        Ok(crate::tool::CanonicalTarget::Command { command }) => Some(command),
+        Ok(crate::tool::CanonicalTarget::Path { path, lines }) => {
+            let file = path.file_name().map_or_else(
+                || path.display().to_string(),
+                |n| n.to_string_lossy().into_owned(),
+            );
+            Some(match lines {
+                Some(lines) => format!("{file}:{lines}"),
+                None => file,
+            })
+        }
+        Ok(crate::tool::CanonicalTarget::Paths { paths }) => Some(
+            paths
+                .iter()
+                .map(|path| {
+                    path.file_name().map_or_else(
+                        || path.display().to_string(),
+                        |n| n.to_string_lossy().into_owned(),
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        Ok(crate::tool::CanonicalTarget::Command { command, .. }) => Some(command),
         Ok(crate::tool::CanonicalTarget::Remote { tool }) => Some(tool),
+        Ok(crate::tool::CanonicalTarget::Process { id }) => id.map(|id| format!("process {id}")),
+        Ok(crate::tool::CanonicalTarget::Artifact { id }) => {
+            Some(format!("artifact {}", crate::tool::short_artifact_id(&id)))
+        }
         Err(_) => None,
     }
 }
 
 /// Live presentation events (DESIGN.md §21.3). Durable semantic state
 /// lives in session entries and operation state, never here.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum RuntimeEvent {
     OperationStarted {
         cursor: RuntimeCursor,
@@ -91,6 +133,24 @@ pub enum RuntimeEvent {
         /// Short canonical-target summary for display (path or command).
         target: Option<String>,
     },
+    /// Output a running tool produced, as it was produced. Display-only
+    /// and never persisted: the settled result is what the model sees.
+    ToolOutput {
+        cursor: RuntimeCursor,
+        operation_id: OperationId,
+        call_id: u64,
+        chunk: String,
+    },
+    /// What a child of a running `delegate` call is doing, keyed by
+    /// the child's session (§20.7). A bounded summary of the child's
+    /// own events: display-only and never persisted in the parent.
+    ChildProgress {
+        cursor: RuntimeCursor,
+        operation_id: OperationId,
+        call_id: u64,
+        child_session_id: SessionId,
+        update: ChildUpdate,
+    },
     /// A started tool effect settled durably. Emitted after the
     /// settlement checkpoint commits, so subscribers see completion
     /// exactly when it is durable.
@@ -101,6 +161,13 @@ pub enum RuntimeEvent {
         is_error: bool,
         /// Bounded tail of the settled output for frontend rendering.
         preview: Option<String>,
+        /// Files the call changed, as bounded diffs.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        diffs: Vec<FileDiff>,
+        /// The id of the full output, when the model saw an excerpt;
+        /// [`SessionHandle::artifact`] returns its text.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        artifact: Option<String>,
     },
     OperationFinished {
         cursor: RuntimeCursor,
@@ -137,6 +204,8 @@ impl RuntimeEvent {
             | Self::AssistantTextDelta { operation_id, .. }
             | Self::ThinkingDelta { operation_id, .. }
             | Self::ToolStarted { operation_id, .. }
+            | Self::ToolOutput { operation_id, .. }
+            | Self::ChildProgress { operation_id, .. }
             | Self::ToolSettled { operation_id, .. }
             | Self::OperationFinished { operation_id, .. }
             | Self::OperationFailed { operation_id, .. }
@@ -153,6 +222,8 @@ impl RuntimeEvent {
             | Self::AssistantTextDelta { cursor, .. }
             | Self::ThinkingDelta { cursor, .. }
             | Self::ToolStarted { cursor, .. }
+            | Self::ToolOutput { cursor, .. }
+            | Self::ChildProgress { cursor, .. }
             | Self::ToolSettled { cursor, .. }
             | Self::OperationFinished { cursor, .. }
             | Self::OperationFailed { cursor, .. }
@@ -164,7 +235,7 @@ impl RuntimeEvent {
 }
 
 /// Live status of the session's active operation.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum OperationStatus {
     Idle,
     Active {
@@ -177,9 +248,11 @@ pub enum OperationStatus {
 /// Snapshot-plus-cursor view of one session (DESIGN.md §21.2). The
 /// durable semantic view is the session entry log; live events are
 /// never persisted.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct SessionSnapshot {
     pub cursor: RuntimeCursor,
+    /// The incarnation `cursor` belongs to; resume needs both.
+    pub instance: RuntimeInstanceId,
     pub operation: OperationStatus,
     pub entries: Vec<SessionEntry>,
     /// The session's durable model selection; authoritative across
@@ -192,7 +265,7 @@ pub struct SessionSnapshot {
 }
 
 /// One started-but-unsettled tool call of the live operation.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct PendingTool {
     pub call_id: u64,
     pub tool: String,
@@ -200,7 +273,7 @@ pub struct PendingTool {
 }
 
 /// Live, never-durable draft state of the active operation (§21.4).
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct LiveOperationState {
     /// Accumulated assistant text of the in-flight step.
     pub draft_text: String,
@@ -211,12 +284,41 @@ pub struct LiveOperationState {
 }
 
 pub struct EventSubscription {
-    rx: broadcast::Receiver<RuntimeEvent>,
+    source: EventSource,
+    /// Replayed events delivered before the live source (resume).
+    backlog: VecDeque<RuntimeEvent>,
+}
+
+/// Where a subscription's events come from: the in-process broadcast
+/// ring, or a remote feed whose producer already resolved lag
+/// (see [`crate::EventFeed`]).
+enum EventSource {
+    Local(broadcast::Receiver<RuntimeEvent>),
+    Remote(mpsc::Receiver<Result<RuntimeEvent, RuntimeError>>),
 }
 
 impl EventSubscription {
+    pub(crate) const fn remote(rx: mpsc::Receiver<Result<RuntimeEvent, RuntimeError>>) -> Self {
+        Self {
+            source: EventSource::Remote(rx),
+            backlog: VecDeque::new(),
+        }
+    }
+
     pub async fn recv(&mut self) -> Result<RuntimeEvent, RuntimeError> {
-        match self.rx.recv().await {
+        if let Some(event) = self.backlog.pop_front() {
+            return Ok(event);
+        }
+        let rx = match &mut self.source {
+            EventSource::Local(rx) => rx,
+            EventSource::Remote(rx) => {
+                return rx
+                    .recv()
+                    .await
+                    .unwrap_or(Err(RuntimeError::SubscriptionClosed));
+            }
+        };
+        match rx.recv().await {
             Ok(event) => Ok(event),
             Err(broadcast::error::RecvError::Lagged(_skipped)) => {
                 // Reliable by construction: the receiver detects the
@@ -228,48 +330,189 @@ impl EventSubscription {
     }
 }
 
-enum SessionCommand {
+/// Who may mutate the session through a handle. Reads (snapshot,
+/// subscribe, resume) need no authority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Authority {
+    /// The composing host: embedded frontends and the daemon itself.
+    Host,
+    /// A client holding the session's controller lease.
+    Lease(u64),
+    /// A read-only attached client.
+    Observer,
+}
+
+pub(crate) enum SessionCommand {
     Submit {
+        authority: Authority,
         prompt: String,
         reply: oneshot::Sender<Result<OperationId, CommandError>>,
     },
     Steer {
+        authority: Authority,
         text: String,
         reply: oneshot::Sender<Result<(), CommandError>>,
     },
     FollowUp {
+        authority: Authority,
         text: String,
         reply: oneshot::Sender<Result<(), CommandError>>,
     },
     Cancel {
+        authority: Authority,
         operation_id: OperationId,
         reply: oneshot::Sender<Result<(), CommandError>>,
     },
+    /// Cancel one running delegated child, leaving its siblings and
+    /// the parent operation running (§20.6).
+    CancelChild {
+        authority: Authority,
+        child: SessionId,
+        reply: oneshot::Sender<Result<(), CommandError>>,
+    },
     /// User-requested compaction: honored at the next continuation
     /// boundary of the active operation. Ok(false) = idle, nothing to
     /// compact (compaction runs within an operation, §14.7).
     Compact {
+        authority: Authority,
         instructions: Option<String>,
         reply: oneshot::Sender<Result<bool, CommandError>>,
     },
     SwitchModel {
+        authority: Authority,
         model_ref: String,
         reply: oneshot::Sender<Result<String, CommandError>>,
     },
+    /// Restore the files an operation changed; `None` = the latest one
+    /// with changes left (DESIGN.md §12.3).
+    Undo {
+        authority: Authority,
+        operation: Option<OperationId>,
+        reply: oneshot::Sender<Result<UndoOutcome, CommandError>>,
+    },
+    Snapshot {
+        reply: oneshot::Sender<Result<SessionSnapshot, CommandError>>,
+    },
+    Artifact {
+        id: String,
+        reply: oneshot::Sender<Result<Option<String>, CommandError>>,
+    },
+    /// This session's delegated children (§20.8).
+    Children {
+        reply: oneshot::Sender<Result<Vec<ChildSummary>, CommandError>>,
+    },
+    ChildTranscript {
+        child: SessionId,
+        reply: oneshot::Sender<Result<Vec<SessionEntry>, CommandError>>,
+    },
     Subscribe {
         reply: oneshot::Sender<SubscribeReply>,
     },
+    /// Live events after `after`, replayed from the recent-event
+    /// buffer, then the live stream.
+    Resume {
+        instance: RuntimeInstanceId,
+        after: RuntimeCursor,
+        reply: oneshot::Sender<Result<EventSubscription, CommandError>>,
+    },
+    ClaimControl {
+        reply: oneshot::Sender<Result<u64, CommandError>>,
+    },
+    ReleaseControl {
+        authority: Authority,
+        reply: oneshot::Sender<Result<(), CommandError>>,
+    },
     Close {
+        authority: Authority,
         reply: oneshot::Sender<Result<(), CommandError>>,
     },
 }
 
-/// Command sender for the process runtime (DESIGN.md §8.1). Session
-/// commands live on [`SessionHandle`]; one-shot callers reach the sole
-/// session through [`Runtime::session`].
+impl SessionCommand {
+    /// The authority a mutating command was issued under; None for
+    /// reads and for control arbitration itself.
+    pub(crate) const fn authority(&self) -> Option<Authority> {
+        match self {
+            Self::Submit { authority, .. }
+            | Self::Steer { authority, .. }
+            | Self::FollowUp { authority, .. }
+            | Self::Cancel { authority, .. }
+            | Self::CancelChild { authority, .. }
+            | Self::Compact { authority, .. }
+            | Self::SwitchModel { authority, .. }
+            | Self::Undo { authority, .. }
+            | Self::Close { authority, .. } => Some(*authority),
+            Self::Snapshot { .. }
+            | Self::Artifact { .. }
+            | Self::Children { .. }
+            | Self::ChildTranscript { .. }
+            | Self::Subscribe { .. }
+            | Self::Resume { .. }
+            | Self::ClaimControl { .. }
+            | Self::ReleaseControl { .. } => None,
+        }
+    }
+
+    /// Answer the command with `err` without running it.
+    pub(crate) fn reject(self, err: CommandError) {
+        match self {
+            Self::Submit { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Steer { reply, .. }
+            | Self::FollowUp { reply, .. }
+            | Self::Cancel { reply, .. }
+            | Self::CancelChild { reply, .. }
+            | Self::ReleaseControl { reply, .. }
+            | Self::Close { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Compact { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::SwitchModel { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Undo { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Snapshot { reply } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Artifact { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Children { reply } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::ChildTranscript { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Subscribe { reply } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::Resume { reply, .. } => {
+                let _ = reply.send(Err(err));
+            }
+            Self::ClaimControl { reply } => {
+                let _ = reply.send(Err(err));
+            }
+        }
+    }
+}
+
+/// Per-session tool setup the registry runs for every session it
+/// creates or opens after the first: the hook that binds
+/// session-scoped tools (e.g. `delegate`) to the new session's id.
+pub type SessionSetup = Arc<dyn Fn(&ToolCatalog, SessionId) + Send + Sync>;
+
+/// Handle on the process runtime's session registry (DESIGN.md §4.2,
+/// §8.1). Every loaded session keeps its own single-writer task; the
+/// registry only composes, tracks, and drains them. Session commands
+/// live on [`SessionHandle`].
 #[derive(Clone)]
 pub struct RuntimeHandle {
-    tx: mpsc::Sender<SessionCommand>,
+    registry: Arc<Registry>,
 }
 
 impl fmt::Debug for RuntimeHandle {
@@ -279,29 +522,291 @@ impl fmt::Debug for RuntimeHandle {
 }
 
 impl RuntimeHandle {
-    /// Close the runtime's sessions and shut down (DESIGN.md §25.2).
+    /// A registry with no session loaded yet, sharing one provider,
+    /// store, policy, and base tool catalog across its sessions.
+    #[must_use]
+    pub fn new(
+        provider: impl Provider,
+        tools: impl Into<ToolCatalog>,
+        store: SessionStore,
+        policy: Arc<dyn PolicyEngine>,
+    ) -> Self {
+        let mut composition = Composition::new(provider, tools, store);
+        composition.policy = policy;
+        Self {
+            registry: Arc::new(Registry::new(composition)),
+        }
+    }
+
+    /// Run `setup` on the tool catalog of every session created or
+    /// opened from now on.
+    pub fn set_session_setup(&self, setup: SessionSetup) {
+        *self.registry.setup.lock().expect("registry poisoned") = Some(setup);
+    }
+
+    /// Hibernate sessions spawned from now on after `timeout` without
+    /// activity (DESIGN.md §4.2). Off by default.
+    pub fn set_idle_timeout(&self, timeout: Option<Duration>) {
+        *self
+            .registry
+            .idle_timeout
+            .lock()
+            .expect("registry poisoned") = timeout;
+    }
+
+    /// Start a new durable session on its own task.
+    pub fn create_session(&self) -> Result<(SessionId, SessionHandle), CommandError> {
+        let session_id = SessionId::generate();
+        let handle = self.registry.spawn(session_id, None, true)?;
+        Ok((session_id, handle))
+    }
+
+    /// Load a persisted session, or return the loaded one: a session
+    /// never gets two writers.
+    pub async fn open_session(&self, session_id: SessionId) -> Result<SessionHandle, RuntimeError> {
+        if let Some(handle) = self.session(session_id) {
+            return Ok(handle);
+        }
+        let loaded = self
+            .registry
+            .composition
+            .store()
+            .load(session_id)
+            .await
+            .map_err(|err| RuntimeError::OperationFailed(err.to_string()))?;
+        Ok(self.registry.spawn(session_id, Some(loaded), true)?)
+    }
+
+    /// The host handle of a loaded session.
+    #[must_use]
+    pub fn session(&self, session_id: SessionId) -> Option<SessionHandle> {
+        self.registry
+            .live()
+            .into_iter()
+            .find_map(|(id, handle)| (id == session_id).then_some(handle))
+    }
+
+    /// Whether a loaded session is hibernated: its runtime state is
+    /// released and the next command rehydrates it from the store.
+    #[must_use]
+    pub fn is_hibernated(&self, session_id: SessionId) -> bool {
+        self.registry
+            .state
+            .lock()
+            .expect("registry poisoned")
+            .sessions
+            .iter()
+            .any(|entry| entry.session_id == session_id && entry.hibernated.load(Ordering::Acquire))
+    }
+
+    /// Loaded sessions in load order. Sessions whose task ended (closed
+    /// or crashed) are no longer listed.
+    #[must_use]
+    pub fn list_sessions(&self) -> Vec<SessionId> {
+        self.registry.live().into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Close one session and wait for its task to drain (§9.5).
+    pub async fn close_session(&self, session_id: SessionId) -> Result<(), CommandError> {
+        let entry = self.registry.take(session_id).ok_or(CommandError::Closed)?;
+        self.registry.drain(entry).await.map_err(|(err, entry)| {
+            self.registry.restore(entry);
+            err
+        })
+    }
+
+    /// Close every loaded session, one at a time in load order, and
+    /// refuse new ones (DESIGN.md §25.2). A session that cannot take
+    /// the close stays loaded and the first such error is returned.
     pub async fn shutdown(&self) -> Result<(), CommandError> {
-        self.request(|reply| SessionCommand::Close { reply }).await
+        let entries = {
+            let mut state = self.registry.state.lock().expect("registry poisoned");
+            state.shut_down = true;
+            std::mem::take(&mut state.sessions)
+        };
+        let mut first_error = None;
+        for entry in entries {
+            if let Err((err, entry)) = self.registry.drain(entry).await {
+                warn!(session = %entry.session_id, %err, "session did not close");
+                first_error.get_or_insert(err);
+                // Still loaded: the caller may retry the shutdown.
+                self.registry.restore(entry);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+/// One loaded session: its host handle and its task.
+struct Entry {
+    session_id: SessionId,
+    handle: SessionHandle,
+    join: Option<JoinHandle<()>>,
+    /// Set while the task is parked without a [`SessionRuntime`].
+    hibernated: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct RegistryState {
+    /// Load order; shutdown drains front to back.
+    sessions: Vec<Entry>,
+    shut_down: bool,
+}
+
+struct Registry {
+    composition: Box<dyn Spawn>,
+    setup: std::sync::Mutex<Option<SessionSetup>>,
+    idle_timeout: std::sync::Mutex<Option<Duration>>,
+    state: std::sync::Mutex<RegistryState>,
+}
+
+impl Registry {
+    fn new<P: Provider>(composition: Composition<P>) -> Self {
+        Self {
+            composition: Box::new(SharedComposition::from(composition)),
+            setup: std::sync::Mutex::new(None),
+            idle_timeout: std::sync::Mutex::new(None),
+            state: std::sync::Mutex::new(RegistryState::default()),
+        }
     }
 
-    async fn request<T>(
+    /// Spawn a session task. `fork` gives it a private copy of the base
+    /// catalog (and runs the setup hook); the first session of an
+    /// embedded [`Runtime`] shares the host's catalog instead.
+    fn spawn(
         &self,
-        build: impl FnOnce(oneshot::Sender<Result<T, CommandError>>) -> SessionCommand,
-    ) -> Result<T, CommandError> {
-        let (reply, rx) = oneshot::channel();
-        self.tx.try_send(build(reply)).map_err(|err| match err {
-            mpsc::error::TrySendError::Full(_) => CommandError::QueueSaturated,
-            mpsc::error::TrySendError::Closed(_) => CommandError::Closed,
-        })?;
-        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+        session_id: SessionId,
+        loaded: Option<LoadedSession>,
+        fork: bool,
+    ) -> Result<SessionHandle, CommandError> {
+        let tools = if fork {
+            let tools = self.composition.tools().fork();
+            if let Some(setup) = self.setup.lock().expect("registry poisoned").clone() {
+                setup(&tools, session_id);
+            }
+            tools
+        } else {
+            self.composition.tools().clone()
+        };
+        let idle_timeout = *self.idle_timeout.lock().expect("registry poisoned");
+        let mut state = self.state.lock().expect("registry poisoned");
+        if state.shut_down {
+            return Err(CommandError::Closed);
+        }
+        // A concurrent open may have loaded it while the store read
+        // was in flight.
+        if let Some(entry) = state
+            .sessions
+            .iter()
+            .find(|entry| entry.session_id == session_id && !entry.is_finished())
+        {
+            return Ok(entry.handle.clone());
+        }
+        let entry = self
+            .composition
+            .spawn(session_id, tools, loaded, idle_timeout);
+        let handle = entry.handle.clone();
+        state.sessions.push(entry);
+        Ok(handle)
+    }
+
+    /// Loaded sessions, after reaping tasks that already ended. A task
+    /// that panicked is logged and forgotten; its siblings keep running.
+    fn live(&self) -> Vec<(SessionId, SessionHandle)> {
+        let mut state = self.state.lock().expect("registry poisoned");
+        state.sessions.retain(|entry| {
+            let finished = entry.is_finished();
+            if finished {
+                info!(session = %entry.session_id, "session task ended; unloaded");
+            }
+            !finished
+        });
+        state
+            .sessions
+            .iter()
+            .map(|entry| (entry.session_id, entry.handle.clone()))
+            .collect()
+    }
+
+    fn restore(&self, entry: Entry) {
+        self.state
+            .lock()
+            .expect("registry poisoned")
+            .sessions
+            .push(entry);
+    }
+
+    fn take(&self, session_id: SessionId) -> Option<Entry> {
+        let mut state = self.state.lock().expect("registry poisoned");
+        let index = state
+            .sessions
+            .iter()
+            .position(|entry| entry.session_id == session_id)?;
+        Some(state.sessions.remove(index))
+    }
+
+    /// Close one session and await its task. A session that is already
+    /// gone (closed elsewhere, or crashed) counts as drained; one that
+    /// refuses the close is handed back.
+    async fn drain(&self, mut entry: Entry) -> Result<(), (CommandError, Entry)> {
+        match entry.handle.close().await {
+            Ok(()) | Err(CommandError::Closed | CommandError::RuntimeDropped) => {}
+            Err(err) => return Err((err, entry)),
+        }
+        if let Some(join) = entry.join.take()
+            && let Err(err) = join.await
+            && err.is_panic()
+        {
+            error!(session = %entry.session_id, "session task panicked");
+        }
+        Ok(())
+    }
+
+    /// Hand a session's task to the embedded [`Runtime`] that awaits it.
+    fn take_join(&self, session_id: SessionId) -> Option<JoinHandle<()>> {
+        self.state
+            .lock()
+            .expect("registry poisoned")
+            .sessions
+            .iter_mut()
+            .find(|entry| entry.session_id == session_id)
+            .and_then(|entry| entry.join.take())
+    }
+
+    #[cfg(test)]
+    fn abort(&self, session_id: SessionId) {
+        if let Some(entry) = self
+            .state
+            .lock()
+            .expect("registry poisoned")
+            .sessions
+            .iter()
+            .find(|entry| entry.session_id == session_id)
+            && let Some(join) = &entry.join
+        {
+            join.abort();
+        }
+    }
+}
+
+impl Entry {
+    fn is_finished(&self) -> bool {
+        self.join.as_ref().is_some_and(JoinHandle::is_finished)
     }
 }
 
 /// Command sender for one loaded session (DESIGN.md §8.1). Success means
 /// the transition authority accepted the command durably (P4).
+///
+/// Several frontends may watch one session. The host's handle always
+/// commands; attached clients either hold the single controller lease
+/// ([`SessionHandle::claim_control`]) or observe read-only
+/// ([`SessionHandle::observer`]), and their mutating commands fail with
+/// [`CommandError::ReadOnly`].
 #[derive(Clone)]
 pub struct SessionHandle {
     tx: mpsc::Sender<SessionCommand>,
+    authority: Authority,
 }
 
 impl fmt::Debug for SessionHandle {
@@ -311,11 +816,62 @@ impl fmt::Debug for SessionHandle {
 }
 
 impl SessionHandle {
+    pub(crate) const fn from_sender(tx: mpsc::Sender<SessionCommand>) -> Self {
+        Self {
+            tx,
+            authority: Authority::Host,
+        }
+    }
+
+    /// A read-only handle on the same session: snapshots and events,
+    /// no commands.
+    #[must_use]
+    pub fn observer(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            authority: Authority::Observer,
+        }
+    }
+
+    #[must_use]
+    pub fn is_observer(&self) -> bool {
+        self.authority == Authority::Observer
+    }
+
+    /// Take the session's controller lease. Only one attached client
+    /// controls at a time; while another holds it this fails with
+    /// [`CommandError::ControllerHeld`].
+    pub async fn claim_control(&self) -> Result<Self, CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::ClaimControl { reply })
+            .map_err(command_send_error)?;
+        let lease = rx.await.map_err(|_| CommandError::RuntimeDropped)??;
+        Ok(Self {
+            tx: self.tx.clone(),
+            authority: Authority::Lease(lease),
+        })
+    }
+
+    /// Give the controller lease back. The host's handle revokes
+    /// whichever lease is held; afterwards this handle is read-only.
+    pub async fn release_control(&self) -> Result<(), CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::ReleaseControl {
+                authority: self.authority,
+                reply,
+            })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
     /// Accept a prompt durably and open a new operation when idle.
     pub async fn submit(&self, prompt: impl Into<String>) -> Result<OperationId, CommandError> {
         let (reply, rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::Submit {
+                authority: self.authority,
                 prompt: prompt.into(),
                 reply,
             })
@@ -329,6 +885,7 @@ impl SessionHandle {
         let (reply, rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::Steer {
+                authority: self.authority,
                 text: text.into(),
                 reply,
             })
@@ -343,6 +900,7 @@ impl SessionHandle {
         let (reply, rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::FollowUp {
+                authority: self.authority,
                 text: text.into(),
                 reply,
             })
@@ -360,6 +918,7 @@ impl SessionHandle {
         let (tx, rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::Compact {
+                authority: self.authority,
                 instructions,
                 reply: tx,
             })
@@ -373,6 +932,7 @@ impl SessionHandle {
         let (reply, rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::SwitchModel {
+                authority: self.authority,
                 model_ref: model_ref.into(),
                 reply,
             })
@@ -380,10 +940,26 @@ impl SessionHandle {
         rx.await.map_err(|_| CommandError::RuntimeDropped)?
     }
 
+    /// Put back the files `operation` changed - by default the latest
+    /// operation with changes left - unless any was edited since.
+    /// Refused while an operation runs.
+    pub async fn undo(&self, operation: Option<OperationId>) -> Result<UndoOutcome, CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::Undo {
+                authority: self.authority,
+                operation,
+                reply,
+            })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
     pub async fn cancel(&self, operation_id: OperationId) -> Result<(), CommandError> {
         let (reply, rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::Cancel {
+                authority: self.authority,
                 operation_id,
                 reply,
             })
@@ -391,18 +967,67 @@ impl SessionHandle {
         rx.await.map_err(|_| CommandError::RuntimeDropped)?
     }
 
+    /// Cancel one child a running `delegate` call started; the call
+    /// reports it cancelled with what it had produced.
+    pub async fn cancel_child(&self, child: SessionId) -> Result<(), CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::CancelChild {
+                authority: self.authority,
+                child,
+                reply,
+            })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
     pub async fn snapshot(&self) -> Result<SessionSnapshot, CommandError> {
         let (reply, rx) = oneshot::channel();
         self.tx
-            .try_send(SessionCommand::Subscribe { reply })
+            .try_send(SessionCommand::Snapshot { reply })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
+    /// The full text of a tool output the model saw only an excerpt of
+    /// (DESIGN.md §16.4); `None` for an id this session never saved.
+    pub async fn artifact(&self, id: impl Into<String>) -> Result<Option<String>, CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::Artifact {
+                id: id.into(),
+                reply,
+            })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
+    /// The children this session delegated to, oldest first, with
+    /// their objective, outcome, and usage (§20.8).
+    pub async fn children(&self) -> Result<Vec<ChildSummary>, CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::Children { reply })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
+    /// The full transcript of one of this session's children, for
+    /// reading; the child is not loaded.
+    pub async fn child_transcript(
+        &self,
+        child: SessionId,
+    ) -> Result<Vec<SessionEntry>, CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::ChildTranscript { child, reply })
             .map_err(command_send_error)?;
-        let (snapshot, _events) = rx.await.map_err(|_| CommandError::RuntimeDropped)??;
-        Ok(snapshot)
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
     }
 
     /// Snapshot plus bounded live events (DESIGN.md §21.2). A consumer
-    /// that falls behind resynchronizes from a fresh snapshot; past
-    /// events are never replayed.
+    /// that falls behind first tries [`SessionHandle::resume`], then
+    /// resynchronizes from a fresh snapshot.
     pub async fn subscribe(&self) -> Result<(SessionSnapshot, EventSubscription), CommandError> {
         let (reply, rx) = oneshot::channel();
         self.tx
@@ -412,13 +1037,36 @@ impl SessionHandle {
         Ok((snapshot, events))
     }
 
+    /// Live events after `after` without a new snapshot: the runtime
+    /// replays what it still buffers, then continues live. A cursor
+    /// from another incarnation, or older than the buffer, fails with
+    /// [`CommandError::CursorUnavailable`]; subscribe afresh then.
+    pub async fn resume(
+        &self,
+        instance: RuntimeInstanceId,
+        after: RuntimeCursor,
+    ) -> Result<EventSubscription, CommandError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .try_send(SessionCommand::Resume {
+                instance,
+                after,
+                reply,
+            })
+            .map_err(command_send_error)?;
+        rx.await.map_err(|_| CommandError::RuntimeDropped)?
+    }
+
     /// Close the session (DESIGN.md §9.5): lifecycle shutdown, never a
     /// user cancellation. The reply arrives after the suspension commit
     /// and task drainage complete. An open operation stays recoverable.
     pub async fn close(&self) -> Result<(), CommandError> {
         let (reply, rx) = oneshot::channel();
         self.tx
-            .try_send(SessionCommand::Close { reply })
+            .try_send(SessionCommand::Close {
+                authority: self.authority,
+                reply,
+            })
             .map_err(command_send_error)?;
         rx.await.map_err(|_| CommandError::RuntimeDropped)?
     }
@@ -439,6 +1087,8 @@ struct Composition<P> {
     policy: Arc<dyn PolicyEngine>,
     budget: RuntimeBudget,
     parent: Option<SessionId>,
+    parent_call: Option<ParentCall>,
+    profile: Option<AgentProfile>,
 }
 
 impl<P: Provider> Composition<P> {
@@ -450,56 +1100,221 @@ impl<P: Provider> Composition<P> {
             policy: Arc::new(DefaultPolicy),
             budget: RuntimeBudget::unbounded(),
             parent: None,
+            parent_call: None,
+            profile: None,
         }
     }
 
+    /// Compose the registry and load its first session on the host's
+    /// own catalog, so scopes the host registers afterwards reach it.
     fn spawn(self, session_id: SessionId, loaded: Option<LoadedSession>) -> Runtime {
+        let registry = Arc::new(Registry::new(self));
+        let session = registry
+            .spawn(session_id, loaded, false)
+            .expect("a fresh registry accepts its first session");
+        Runtime {
+            handle: RuntimeHandle { registry },
+            session,
+            session_id,
+        }
+    }
+}
+
+/// The composition every session of one registry shares.
+struct SharedComposition<P> {
+    provider: Arc<P>,
+    tools: ToolCatalog,
+    store: SessionStore,
+    policy: Arc<dyn PolicyEngine>,
+    budget: RuntimeBudget,
+    parent: Option<SessionId>,
+    parent_call: Option<ParentCall>,
+    profile: Option<AgentProfile>,
+}
+
+impl<P> From<Composition<P>> for SharedComposition<P> {
+    fn from(composition: Composition<P>) -> Self {
+        Self {
+            provider: Arc::new(composition.provider),
+            tools: composition.tools,
+            store: composition.store,
+            policy: composition.policy,
+            budget: composition.budget,
+            parent: composition.parent,
+            parent_call: composition.parent_call,
+            profile: composition.profile,
+        }
+    }
+}
+
+/// Provider-erased session spawning, so [`RuntimeHandle`] stays
+/// non-generic.
+trait Spawn: Send + Sync {
+    fn tools(&self) -> &ToolCatalog;
+    fn store(&self) -> &SessionStore;
+    fn spawn(
+        &self,
+        session_id: SessionId,
+        tools: ToolCatalog,
+        loaded: Option<LoadedSession>,
+        idle_timeout: Option<Duration>,
+    ) -> Entry;
+}
+
+impl<P: Provider> Spawn for SharedComposition<P> {
+    fn tools(&self) -> &ToolCatalog {
+        &self.tools
+    }
+
+    fn store(&self) -> &SessionStore {
+        &self.store
+    }
+
+    fn spawn(
+        &self,
+        session_id: SessionId,
+        tools: ToolCatalog,
+        loaded: Option<LoadedSession>,
+        idle_timeout: Option<Duration>,
+    ) -> Entry {
         let initial_model_ref = self.provider.initial_model_ref();
         let (tx, rx) = mpsc::channel(COMMAND_CAPACITY);
-        let handle = RuntimeHandle { tx: tx.clone() };
-        let session = SessionHandle { tx };
-        let provider = Arc::new(self.provider);
-        let tools = Arc::new(self.tools);
+        let deps = SessionDeps {
+            provider: Arc::clone(&self.provider),
+            initial_model_ref,
+            tools: Arc::new(tools),
+            store: self.store.clone(),
+            policy: Arc::clone(&self.policy),
+            budget: self.budget,
+            parent: self.parent,
+            parent_call: self.parent_call,
+            profile: self.profile.clone(),
+            idle_timeout,
+        };
         let cwd = std::env::current_dir()
             .map(|p| p.to_string_lossy().into_owned())
             .unwrap_or_default();
-        let join = tokio::spawn(async move {
-            SessionRuntime::new(
-                session_id,
-                cwd,
-                SessionDeps {
-                    provider,
-                    initial_model_ref,
-                    tools,
-                    store: self.store,
-                    policy: self.policy,
-                    budget: self.budget,
-                    parent: self.parent,
-                },
-                rx,
-                loaded,
-            )
-            .run()
-            .await;
-        });
-        Runtime {
-            handle,
-            session,
+        let hibernated = Arc::new(AtomicBool::new(false));
+        let join = tokio::spawn(host_session(
+            session_id,
+            cwd,
+            deps,
+            rx,
+            loaded,
+            Arc::clone(&hibernated),
+        ));
+        Entry {
             session_id,
-            join,
+            handle: SessionHandle::from_sender(tx),
+            join: Some(join),
+            hibernated,
         }
     }
 }
 
-/// Process-level runtime: composition and the session registry. v0 keeps
-/// exactly one loaded session (DESIGN.md §32 Step 1).
+/// A session task for its whole load (DESIGN.md §4.2). An idle runtime
+/// hibernates: everything semantic is already durable (P4), so it drops
+/// its state and the task parks on the command queue with only the
+/// [`Residue`]. The next command rehydrates it through `restore_from`
+/// and is then handled as if the runtime had never left.
+async fn host_session<P: Provider>(
+    session_id: SessionId,
+    cwd: String,
+    deps: SessionDeps<P>,
+    commands: mpsc::Receiver<SessionCommand>,
+    loaded: Option<LoadedSession>,
+    hibernated: Arc<AtomicBool>,
+) {
+    let mut runtime = SessionRuntime::new(session_id, cwd.clone(), deps.clone(), commands, loaded);
+    let mut wake = None;
+    loop {
+        let Exit::Hibernated {
+            mut commands,
+            residue,
+        } = runtime.run(wake.take()).await
+        else {
+            return;
+        };
+        hibernated.store(true, Ordering::Release);
+        let loaded = loop {
+            // Every handle dropped: nothing can wake the session again.
+            let Some(command) = commands.recv().await else {
+                return;
+            };
+            if let SessionCommand::Close {
+                authority: Authority::Host,
+                reply,
+            } = command
+            {
+                // Nothing live to settle; the durable session is closed.
+                info!(session = %session_id, "hibernated session closed");
+                let _ = reply.send(Ok(()));
+                return;
+            }
+            match deps.store.load(session_id).await {
+                Ok(loaded) => {
+                    wake = Some(command);
+                    break loaded;
+                }
+                Err(err) => {
+                    warn!(session = %session_id, %err, "hibernated session did not rehydrate");
+                    command.reject(CommandError::Persistence(err.to_string()));
+                }
+            }
+        };
+        runtime = SessionRuntime::new(
+            session_id,
+            cwd.clone(),
+            deps.clone(),
+            commands,
+            Some(loaded),
+        );
+        runtime.apply_residue(residue);
+        hibernated.store(false, Ordering::Release);
+        info!(session = %session_id, "session rehydrated");
+    }
+}
+
+/// Resolves after `timeout`; never without one.
+async fn idle(timeout: Option<Duration>) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// How a session runtime's loop ended.
+enum Exit {
+    Closed,
+    /// Idle with nothing live; the command queue goes back to the
+    /// session task.
+    Hibernated {
+        commands: mpsc::Receiver<SessionCommand>,
+        residue: Residue,
+    },
+}
+
+/// In-memory state that outlives a hibernation. Snapshots keep the
+/// same instance and cursor across it; leases keep counting, so one
+/// issued before never matches one issued after; the usage anchors keep
+/// hints from repeating.
+struct Residue {
+    instance: RuntimeInstanceId,
+    cursor: RuntimeCursor,
+    next_lease: u64,
+    last_context_tokens: Option<u64>,
+    last_hint_tokens: Option<u64>,
+    context_window: Option<u64>,
+}
+
+/// Process-level runtime: composition and the session registry
+/// (DESIGN.md §4.2). An embedded host composes it around one session;
+/// more load through [`RuntimeHandle`].
 pub struct Runtime {
     handle: RuntimeHandle,
     session: SessionHandle,
     session_id: SessionId,
-    join: JoinHandle<()>,
 }
-
 impl Runtime {
     /// Compose the runtime with one durable session in the default data
     /// root. Panics if the store cannot be opened; hosts that need
@@ -538,7 +1353,8 @@ impl Runtime {
 
     /// Compose a bounded child session with durable lineage (§20.1,
     /// §20.3): the same primitive as a root session - own machine, own
-    /// store record - plus a persisted parent reference.
+    /// store record - plus a persisted parent reference and the agent
+    /// profile it runs under, if any.
     #[must_use]
     pub fn start_child(
         provider: impl Provider,
@@ -546,15 +1362,38 @@ impl Runtime {
         store: SessionStore,
         policy: Arc<dyn PolicyEngine>,
         budget: RuntimeBudget,
-        parent: SessionId,
+        lineage: ChildLineage,
     ) -> Self {
         let mut composition = Composition::new(provider, tools, store);
         composition.policy = policy;
         composition.budget = budget;
-        composition.parent = Some(parent);
+        composition.parent = Some(lineage.parent);
+        composition.parent_call = lineage.call;
+        composition.profile = lineage.profile;
         composition.spawn(SessionId::generate(), None)
     }
 
+    /// Reopen a persisted child session under the child bounds, so a
+    /// recovered parent call can reattach to it (§20.8). Its own open
+    /// operation recovers like any reopened session's.
+    pub async fn open_child(
+        provider: impl Provider,
+        tools: impl Into<ToolCatalog>,
+        store: SessionStore,
+        policy: Arc<dyn PolicyEngine>,
+        budget: RuntimeBudget,
+        session_id: SessionId,
+    ) -> Result<Self, RuntimeError> {
+        let loaded = store
+            .load(session_id)
+            .await
+            .map_err(|err| RuntimeError::OperationFailed(err.to_string()))?;
+        let mut composition = Composition::new(provider, tools, store);
+        composition.policy = policy;
+        composition.budget = budget;
+        Ok(composition.spawn(session_id, Some(loaded)))
+    }
+
     /// Compose the runtime with an explicit approval policy and a
     /// runtime-enforced budget (§20.5). Used for bounded child
     /// sessions; hosts may also budget the root session.
@@ -596,7 +1435,7 @@ impl Runtime {
         self.handle.clone()
     }
 
-    /// The sole loaded session.
+    /// The session the runtime was composed with.
     #[must_use]
     pub fn session(&self) -> SessionHandle {
         self.session.clone()
@@ -607,10 +1446,16 @@ impl Runtime {
         self.session_id
     }
 
+    /// Wait for the composed session's task to end.
     pub async fn join(self) -> Result<(), RuntimeError> {
-        self.join
+        let Some(join) = self.handle.registry.take_join(self.session_id) else {
+            return Ok(());
+        };
+        let result = join
             .await
-            .map_err(|_| RuntimeError::OperationFailed("runtime task panicked".to_owned()))
+            .map_err(|_| RuntimeError::OperationFailed("runtime task panicked".to_owned()));
+        self.handle.registry.take(self.session_id);
+        result
     }
 
     /// Test hook (DESIGN.md §30.2): abort the session task at its current
@@ -618,7 +1463,7 @@ impl Runtime {
     /// window. Durable state stays at the last committed checkpoint.
     #[cfg(test)]
     pub(crate) fn crash(&self) {
-        self.join.abort();
+        self.handle.registry.abort(self.session_id);
     }
 }
 
@@ -678,6 +1523,11 @@ struct ActiveOperation {
     state_seq: u64,
     /// The one in-flight effect intent, if any.
     open_effect: Option<EffectRecord>,
+    /// The in-flight tool batch's intents, in call order.
+    open_batch: Vec<EffectRecord>,
+    /// Settlements of `open_batch` so far, by position; never durable
+    /// until the whole batch commits.
+    batch_settled: Vec<Option<(ToolResult, Vec<FileDiff>)>>,
     /// Inbox items durably accepted but not yet applied.
     pending_steers: Vec<InboxId>,
     pending_followups: Vec<InboxId>,
@@ -708,6 +1558,16 @@ impl RuntimeBudget {
     }
 }
 
+/// Where a child session comes from (§20.3), recorded on its session
+/// row before it runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildLineage {
+    pub parent: SessionId,
+    /// The parent's tool call that starts the child, when there is one.
+    pub call: Option<ParentCall>,
+    pub profile: Option<AgentProfile>,
+}
+
 struct SessionDeps<P> {
     provider: Arc<P>,
     initial_model_ref: String,
@@ -717,6 +1577,27 @@ struct SessionDeps<P> {
     budget: RuntimeBudget,
     /// Durable lineage for bounded child sessions (§20.3).
     parent: Option<SessionId>,
+    parent_call: Option<ParentCall>,
+    profile: Option<AgentProfile>,
+    /// Hibernate after this long without activity (§4.2).
+    idle_timeout: Option<Duration>,
+}
+
+impl<P> Clone for SessionDeps<P> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: Arc::clone(&self.provider),
+            initial_model_ref: self.initial_model_ref.clone(),
+            tools: Arc::clone(&self.tools),
+            store: self.store.clone(),
+            policy: Arc::clone(&self.policy),
+            budget: self.budget,
+            parent: self.parent,
+            parent_call: self.parent_call,
+            profile: self.profile.clone(),
+            idle_timeout: self.idle_timeout,
+        }
+    }
 }
 
 struct SessionRuntime<P> {
@@ -731,6 +1612,11 @@ struct SessionRuntime<P> {
     policy: Arc<dyn PolicyEngine>,
     budget: RuntimeBudget,
     parent_session_id: Option<SessionId>,
+    parent_call: Option<ParentCall>,
+    /// The agent profile a child runs under (§20.2); its system prompt
+    /// addendum joins every projection.
+    profile: Option<AgentProfile>,
+    idle_timeout: Option<Duration>,
     /// Tool effects admitted by the active operation (budget counter).
     operation_tool_calls: u32,
     commands: mpsc::Receiver<SessionCommand>,
@@ -738,7 +1624,12 @@ struct SessionRuntime<P> {
     engine_rx: mpsc::Receiver<EngineSignal>,
     tool_tx: mpsc::Sender<ToolSettlement>,
     tool_rx: mpsc::Receiver<ToolSettlement>,
+    /// Live tool output; tools drop chunks rather than wait when full.
+    output_tx: mpsc::Sender<ToolChunk>,
+    output_rx: mpsc::Receiver<ToolChunk>,
     cancel_root: CancellationToken,
+    /// Children running under this session's `delegate` calls.
+    running_children: RunningChildren,
     tracker: TaskTracker,
     cursor: RuntimeCursor,
     /// Canonical semantic session view, mirroring the durable store.
@@ -788,6 +1679,13 @@ struct SessionRuntime<P> {
     /// are dropped.
     model_step: u64,
     events: broadcast::Sender<RuntimeEvent>,
+    /// This incarnation; snapshots pair it with the cursor.
+    instance: RuntimeInstanceId,
+    /// The most recent emitted events, oldest first, for resume.
+    replay: VecDeque<RuntimeEvent>,
+    /// The attached client holding the controller lease, if any.
+    controller: Option<u64>,
+    next_lease: u64,
     /// Started-but-unsettled tool calls of the active operation;
     /// mirrors emitted ToolStarted/ToolSettled for snapshot
     /// reconstruction (§21.4).
@@ -813,9 +1711,13 @@ impl<P: Provider> SessionRuntime<P> {
             policy,
             budget,
             parent,
+            parent_call,
+            profile,
+            idle_timeout,
         } = deps;
         let (engine_tx, engine_rx) = mpsc::channel(ENGINE_CAPACITY);
         let (tool_tx, tool_rx) = mpsc::channel(ENGINE_CAPACITY);
+        let (output_tx, output_rx) = mpsc::channel(ENGINE_CAPACITY);
         let (events, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
         let mut runtime = Self {
             session_id,
@@ -827,13 +1729,19 @@ impl<P: Provider> SessionRuntime<P> {
             policy,
             budget,
             parent_session_id: parent,
+            parent_call,
+            profile,
+            idle_timeout,
             operation_tool_calls: 0,
             commands,
             engine_tx,
             engine_rx,
             tool_tx,
             tool_rx,
+            output_tx,
+            output_rx,
             cancel_root: CancellationToken::new(),
+            running_children: RunningChildren::default(),
             tracker: TaskTracker::new(),
             cursor: RuntimeCursor::default(),
             entries: Vec::new(),
@@ -854,6 +1762,10 @@ impl<P: Provider> SessionRuntime<P> {
             last_step_was_compaction: false,
             model_step: 0,
             events,
+            instance: RuntimeInstanceId::generate(),
+            replay: VecDeque::with_capacity(REPLAY_CAPACITY),
+            controller: None,
+            next_lease: 0,
             live_tools: Vec::new(),
             closed: false,
             resumed: false,
@@ -870,15 +1782,44 @@ impl<P: Provider> SessionRuntime<P> {
     /// machine, its pending inbox, and its pending effect intent.
     fn restore_from(&mut self, loaded: LoadedSession) {
         self.selected_model_ref = loaded.session.initial_model_ref.clone();
+        self.profile.clone_from(&loaded.session.profile);
         let mut max_seq = 0;
+        // Background processes the transcript started and never
+        // stopped, keyed by their table id. Call ids restart with each
+        // operation, and a result follows its call's operation.
+        let mut process_calls = HashMap::new();
+        let mut started = BTreeMap::new();
+        let mut operation = None;
         for (seq, entry) in loaded.entries {
             max_seq = max_seq.max(seq);
             if let SessionEntry::ModelChanged { model_ref } = &entry {
                 self.selected_model_ref.clone_from(model_ref);
             }
+            if let SessionEntry::ToolCall { call } = &entry {
+                operation = Some(call.operation_id);
+                if matches!(call.name.as_str(), "process_start" | "process_stop") {
+                    process_calls.insert((call.operation_id, call.call_id), call.clone());
+                }
+            }
+            if let SessionEntry::ToolResult { result } = &entry {
+                self.tools.restore_artifacts(result.artifact());
+                if let ToolResult::Ok {
+                    call_id, output, ..
+                } = result
+                    && let Some(operation) = operation
+                    && let Some(call) = process_calls.remove(&(operation, *call_id))
+                {
+                    if call.name == "process_start" {
+                        started.extend(started_process(output));
+                    } else if let Ok(id) = process_id(&call.arguments) {
+                        started.remove(&id);
+                    }
+                }
+            }
             self.entries.push(entry);
         }
         self.next_entry_seq = max_seq + 1;
+        self.tools.restore_processes(started);
         for operation in loaded.operations {
             let (state_seq, payload) = operation.latest;
             if matches!(payload.state, OperationState::Finished(_)) {
@@ -943,6 +1884,8 @@ impl<P: Provider> SessionRuntime<P> {
                 cancel: self.cancel_root.child_token(),
                 state_seq,
                 open_effect: payload.open_effect.clone(),
+                open_batch: payload.open_batch.clone(),
+                batch_settled: Vec::new(),
                 pending_steers: loaded
                     .pending_inbox
                     .iter()
@@ -959,7 +1902,30 @@ impl<P: Provider> SessionRuntime<P> {
         }
     }
 
-    async fn run(mut self) {
+    /// Carry a hibernated incarnation's residue into this one.
+    fn apply_residue(&mut self, residue: Residue) {
+        self.instance = residue.instance;
+        self.cursor = residue.cursor;
+        self.next_lease = residue.next_lease;
+        self.last_context_tokens = residue.last_context_tokens;
+        self.last_hint_tokens = residue.last_hint_tokens;
+        self.context_window = residue.context_window;
+    }
+
+    /// Nothing live would be lost by hibernating: no operation, no
+    /// in-flight effect, no subscriber, no controller lease.
+    fn can_hibernate(&self) -> bool {
+        !self.closed
+            && self.operation.is_none()
+            && self.suspended_operations.is_empty()
+            && self.controller.is_none()
+            && self.events.receiver_count() == 0
+            && self.tracker.is_empty()
+    }
+
+    /// Run the session loop; `wake` is the command that rehydrated a
+    /// hibernated session, handled before anything else.
+    async fn run(mut self, wake: Option<SessionCommand>) -> Exit {
         if !self.resumed && !self.closed {
             let record = SessionRecord {
                 id: self.session_id,
@@ -967,6 +1933,8 @@ impl<P: Provider> SessionRuntime<P> {
                 title: String::new(),
                 initial_model_ref: self.selected_model_ref.clone(),
                 parent_session_id: self.parent_session_id,
+                parent_call: self.parent_call,
+                profile: self.profile.clone(),
             };
             if let Err(err) = self.store.create_session(record).await {
                 error!(
@@ -975,22 +1943,24 @@ impl<P: Provider> SessionRuntime<P> {
                     "session row not durable; session will not start"
                 );
                 self.closed = true;
-                return;
+                return Exit::Closed;
             }
         }
         info!(session = %self.session_id, "session opened");
         if self.operation.is_some() {
             self.recover_open_operation().await;
         }
-        loop {
+        let mut exiting = match wake {
+            Some(command) => self.handle_command(command).await,
+            None => false,
+        };
+        while !exiting {
             tokio::select! {
                 command = self.commands.recv() => {
                     let Some(command) = command else {
                         break;
                     };
-                    if self.handle_command(command).await {
-                        break;
-                    }
+                    exiting = self.handle_command(command).await;
                 }
                 signal = self.engine_rx.recv() => {
                     if let Some(signal) = signal {
@@ -1002,59 +1972,151 @@ impl<P: Provider> SessionRuntime<P> {
                         self.handle_tool_result(result).await;
                     }
                 }
+                Some(chunk) = self.output_rx.recv() => self.handle_tool_output(chunk),
+                () = idle(self.idle_timeout) => {
+                    if self.can_hibernate() {
+                        info!(session = %self.session_id, "session idle; hibernating");
+                        let residue = Residue {
+                            instance: self.instance,
+                            cursor: self.cursor,
+                            next_lease: self.next_lease,
+                            last_context_tokens: self.last_context_tokens,
+                            last_hint_tokens: self.last_hint_tokens,
+                            context_window: self.context_window,
+                        };
+                        return Exit::Hibernated {
+                            commands: self.commands,
+                            residue,
+                        };
+                    }
+                }
             }
         }
         // The session task is ending; the close result has no caller.
         let _ = self.close_internal().await;
+        Exit::Closed
     }
 
     /// Returns true when the session loop must exit.
     async fn handle_command(&mut self, command: SessionCommand) -> bool {
+        if let Some(authority) = command.authority()
+            && let Err(err) = self.authorize(authority)
+        {
+            command.reject(err);
+            return false;
+        }
         match command {
-            SessionCommand::Submit { prompt, reply } => {
+            SessionCommand::Submit { prompt, reply, .. } => {
                 let _ = reply.send(self.submit(prompt).await);
                 false
             }
-            SessionCommand::Steer { text, reply } => {
+            SessionCommand::Steer { text, reply, .. } => {
                 let _ = reply.send(self.enqueue_inbox(InboxKind::Steer, text).await);
                 false
             }
-            SessionCommand::FollowUp { text, reply } => {
-                let _ = reply.send(self.enqueue_inbox(InboxKind::FollowUp, text).await);
+            SessionCommand::FollowUp { text, reply, .. } => {
+                let _ = reply.send(self.enqueue_inbox(InboxKind::FollowUp, text).await);
+                false
+            }
+            SessionCommand::Cancel {
+                operation_id,
+                reply,
+                ..
+            } => {
+                let _ = reply.send(self.cancel(operation_id).await);
+                false
+            }
+            SessionCommand::CancelChild { child, reply, .. } => {
+                let result = if self.running_children.cancel(child) {
+                    Ok(())
+                } else {
+                    Err(CommandError::ChildNotRunning(child))
+                };
+                let _ = reply.send(result);
+                false
+            }
+            SessionCommand::Compact {
+                instructions,
+                reply,
+                ..
+            } => {
+                let requested = self.operation.is_some();
+                if requested {
+                    // Consumed at the next continuation boundary by the
+                    // same path the model's own compact tool uses.
+                    self.pending_compact = Some(PendingCompact {
+                        instructions,
+                        continue_after: false,
+                    });
+                }
+                let _ = reply.send(Ok(requested));
+                false
+            }
+            SessionCommand::SwitchModel {
+                model_ref, reply, ..
+            } => {
+                let _ = reply.send(self.switch_model(model_ref).await);
+                false
+            }
+            SessionCommand::Undo {
+                operation, reply, ..
+            } => {
+                let _ = reply.send(self.undo(operation).await);
+                false
+            }
+            SessionCommand::Snapshot { reply } => {
+                let _ = reply.send(if self.closed {
+                    Err(CommandError::Closed)
+                } else {
+                    Ok(self.snapshot())
+                });
+                false
+            }
+            SessionCommand::Artifact { id, reply } => {
+                let _ = reply.send(if self.closed {
+                    Err(CommandError::Closed)
+                } else {
+                    Ok(self.tools.artifact(&id).map(|text| text.to_string()))
+                });
+                false
+            }
+            SessionCommand::Children { reply } => {
+                let _ = reply.send(if self.closed {
+                    Err(CommandError::Closed)
+                } else {
+                    crate::delegate::children(&self.store, self.session_id).await
+                });
+                false
+            }
+            SessionCommand::ChildTranscript { child, reply } => {
+                let _ = reply.send(if self.closed {
+                    Err(CommandError::Closed)
+                } else {
+                    crate::delegate::child_transcript(&self.store, self.session_id, child).await
+                });
                 false
             }
-            SessionCommand::Cancel {
-                operation_id,
-                reply,
-            } => {
-                let _ = reply.send(self.cancel(operation_id).await);
+            SessionCommand::Subscribe { reply } => {
+                let _ = reply.send(self.subscribe());
                 false
             }
-            SessionCommand::Compact {
-                instructions,
+            SessionCommand::Resume {
+                instance,
+                after,
                 reply,
             } => {
-                let requested = self.operation.is_some();
-                if requested {
-                    // Consumed at the next continuation boundary by the
-                    // same path the model's own compact tool uses.
-                    self.pending_compact = Some(PendingCompact {
-                        instructions,
-                        continue_after: false,
-                    });
-                }
-                let _ = reply.send(Ok(requested));
+                let _ = reply.send(self.resume(instance, after));
                 false
             }
-            SessionCommand::SwitchModel { model_ref, reply } => {
-                let _ = reply.send(self.switch_model(model_ref).await);
+            SessionCommand::ClaimControl { reply } => {
+                let _ = reply.send(self.claim_control());
                 false
             }
-            SessionCommand::Subscribe { reply } => {
-                let _ = reply.send(self.subscribe());
+            SessionCommand::ReleaseControl { authority, reply } => {
+                let _ = reply.send(self.release_control(authority));
                 false
             }
-            SessionCommand::Close { reply } => {
+            SessionCommand::Close { reply, .. } => {
                 // The reply arrives after suspension and drainage, not
                 // before (DESIGN.md §25.2).
                 let result = self.close_internal().await;
@@ -1064,6 +2126,29 @@ impl<P: Provider> SessionRuntime<P> {
         }
     }
 
+    async fn undo(&mut self, operation: Option<OperationId>) -> Result<UndoOutcome, CommandError> {
+        if self.closed {
+            return Err(CommandError::Closed);
+        }
+        // A running operation may be mid-write on the same files.
+        if let Some(active) = &self.operation {
+            return Err(CommandError::Busy {
+                operation_id: active.machine.operation_id(),
+            });
+        }
+        let outcome = crate::checkpoint::restore(&self.store, self.session_id, operation).await?;
+        if let Some(entry) = crate::checkpoint::note(&outcome) {
+            let record = self.stage_entry(&entry);
+            self.store
+                .append_entry(self.session_id, record)
+                .await
+                .map_err(persistence_command_error)?;
+            self.next_entry_seq += 1;
+            self.entries.push(entry);
+        }
+        Ok(outcome)
+    }
+
     async fn submit(&mut self, prompt: String) -> Result<OperationId, CommandError> {
         if self.closed {
             return Err(CommandError::Closed);
@@ -1091,6 +2176,7 @@ impl<P: Provider> SessionRuntime<P> {
                 prompt: machine.prompt().to_owned(),
                 tools: machine_snapshot_tools(&machine),
                 open_effect: None,
+                open_batch: Vec::new(),
             },
         };
         // Accepted intent is durable before acknowledgment (P4, §9.1).
@@ -1104,6 +2190,8 @@ impl<P: Provider> SessionRuntime<P> {
             cancel: self.cancel_root.child_token(),
             state_seq: 1,
             open_effect: None,
+            open_batch: Vec::new(),
+            batch_settled: Vec::new(),
             pending_steers: Vec::new(),
             pending_followups: Vec::new(),
         };
@@ -1289,6 +2377,7 @@ impl<P: Provider> SessionRuntime<P> {
                         prompt: payload.prompt.clone(),
                         tools: payload.tools.clone(),
                         open_effect: None,
+                        open_batch: Vec::new(),
                     },
                 },
                 entries: Vec::new(),
@@ -1298,6 +2387,7 @@ impl<P: Provider> SessionRuntime<P> {
                 inbox: Vec::new(),
                 inbox_applied: Vec::new(),
                 usage: Vec::new(),
+                snapshots: Vec::new(),
             };
             if let Err(err) = self.store.commit(request).await {
                 error!(session = %self.session_id, error = %err, "could not settle a suspended operation");
@@ -1320,13 +2410,18 @@ impl<P: Provider> SessionRuntime<P> {
                     self.closed = true;
                     return;
                 };
-                let Some((step, model, plan, persisted_tools)) =
+                let Some((step, model, mut plan, persisted_tools)) =
                     model_step_from_input(&open.effective_input)
                 else {
                     error!(session = %self.session_id, "pending model step lacks an exact model snapshot; fencing");
                     self.closed = true;
                     return;
                 };
+                if !self.rehydrate_images(&mut plan) {
+                    error!(session = %self.session_id, "pending model step names an image the transcript lacks; fencing");
+                    self.closed = true;
+                    return;
+                }
                 let mut staged = self.operation.clone().expect("operation present");
                 let applied = staged
                     .machine
@@ -1386,11 +2481,17 @@ impl<P: Provider> SessionRuntime<P> {
                     self.closed = true;
                     return;
                 };
-                let Some((step, model, plan)) = compaction_from_input(&open.effective_input) else {
+                let Some((step, model, mut plan)) = compaction_from_input(&open.effective_input)
+                else {
                     error!(session = %self.session_id, "pending compaction lacks an exact model snapshot; fencing");
                     self.closed = true;
                     return;
                 };
+                if !self.rehydrate_images(&mut plan) {
+                    error!(session = %self.session_id, "pending compaction names an image the transcript lacks; fencing");
+                    self.closed = true;
+                    return;
+                }
                 let mut staged = self.operation.clone().expect("operation present");
                 staged
                     .machine
@@ -1440,8 +2541,10 @@ impl<P: Provider> SessionRuntime<P> {
                     return;
                 };
                 match open.recovery_class {
-                    RecoveryClass::ReplaySafe => {
-                        // Re-execute with the exact effective input.
+                    RecoveryClass::ReplaySafe | RecoveryClass::Resume => {
+                        // Re-execute with the exact effective input; a
+                        // resumable tool picks up the work the lost
+                        // attempt recorded under the same call.
                         let call =
                             tool_call_from_input(&open.effective_input).unwrap_or_else(|| {
                                 panic!("replay-safe tool effect without a usable input")
@@ -1491,8 +2594,8 @@ impl<P: Provider> SessionRuntime<P> {
                             &call.name,
                             target_summary(&self.tools, &call.name, &call.arguments),
                         );
-                        warn!(%operation_id, tool = %call.name, attempt = open.attempt + 1, "recovered a pending replay-safe tool by re-execution");
-                        self.spawn_tool_effect(Some(effect_id), call);
+                        warn!(%operation_id, tool = %call.name, attempt = open.attempt + 1, class = ?open.recovery_class, "recovered a pending tool by re-execution");
+                        self.spawn_tool_effect(Some(effect_id), call, open.attempt + 1);
                     }
                     RecoveryClass::NeverReplay => {
                         // Side effects cannot be classified (§12.4); an
@@ -1546,15 +2649,7 @@ impl<P: Provider> SessionRuntime<P> {
                             .unwrap_or(serde_json::Value::Null);
                         let verdict = match &evidence {
                             serde_json::Value::Null => crate::tool::ReconcileVerdict::Unknown,
-                            evidence => {
-                                let current = match evidence.get("path").and_then(|v| v.as_str()) {
-                                    Some(path) => {
-                                        crate::tool::file_hash(std::path::Path::new(path)).await
-                                    }
-                                    None => None,
-                                };
-                                crate::tool::classify_reconciliation(evidence, current)
-                            }
+                            evidence => crate::tool::reconcile(evidence).await,
                         };
                         match verdict {
                             crate::tool::ReconcileVerdict::SafeToExecute => {
@@ -1612,7 +2707,7 @@ impl<P: Provider> SessionRuntime<P> {
                                     &call.name,
                                     target_summary(&self.tools, &call.name, &call.arguments),
                                 );
-                                self.spawn_tool_effect(Some(effect_id), call);
+                                self.spawn_tool_effect(Some(effect_id), call, open.attempt + 1);
                                 info!(%operation_id, "reconciled a pending file mutation by preimage match");
                             }
                             crate::tool::ReconcileVerdict::AlreadyApplied => {
@@ -1631,6 +2726,8 @@ impl<P: Provider> SessionRuntime<P> {
                                         result: ToolResult::Ok {
                                             call_id,
                                             output: "recovered: already applied".to_owned(),
+                                            images: Vec::new(),
+                                            artifact: None,
                                         },
                                     })
                                     .expect("settle an already-applied reconcilable effect");
@@ -1708,6 +2805,7 @@ impl<P: Provider> SessionRuntime<P> {
                     }
                 }
             }
+            OperationState::ToolBatchPending { .. } => self.recover_tool_batch().await,
             OperationState::Accepted
             | OperationState::NeedAssistant
             | OperationState::NeedContinuation
@@ -1730,6 +2828,38 @@ impl<P: Provider> SessionRuntime<P> {
         self.next_entry_seq - self.entries.len() as u64
     }
 
+    /// Reattach image bytes to a plan read back from an effect input,
+    /// which records images by hash only. Every image in a plan came
+    /// from a transcript entry, so the loaded entries hold the bytes.
+    fn rehydrate_images(&self, plan: &mut ContextPlan) -> bool {
+        let known: HashMap<&str, &Image> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                SessionEntry::ToolResult {
+                    result: ToolResult::Ok { images, .. },
+                } => Some(images),
+                _ => None,
+            })
+            .flatten()
+            .map(|image| (image.sha256.as_str(), image))
+            .collect();
+        for message in &mut plan.messages {
+            let (ContextMessage::User { images, .. } | ContextMessage::Tool { images, .. }) =
+                message
+            else {
+                continue;
+            };
+            for image in images {
+                let Some(stored) = known.get(image.sha256.as_str()) else {
+                    return false;
+                };
+                image.data = stored.data.clone();
+            }
+        }
+        true
+    }
+
     async fn advance(&mut self) {
         loop {
             let Some(state) = self
@@ -1871,9 +3001,24 @@ impl<P: Provider> SessionRuntime<P> {
         }
     }
 
+    /// The projected context plus the profile's system prompt
+    /// addendum, which is configuration rather than a session entry.
+    fn project(&self) -> ContextPlan {
+        let mut plan = project(&self.entries, self.first_entry_seq());
+        if let Some(addendum) = self
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.system_prompt.as_deref())
+        {
+            plan.system.push_str("\n\n");
+            plan.system.push_str(addendum);
+        }
+        plan
+    }
+
     async fn project_model_step_plan(&mut self) -> crate::context::ContextPlan {
         let _ = self.current_model_config().await;
-        let mut plan = project(&self.entries, self.first_entry_seq());
+        let mut plan = self.project();
         let hint = crate::context::usage_hint(
             self.last_context_tokens.unwrap_or(0),
             self.context_window,
@@ -1910,14 +3055,13 @@ impl<P: Provider> SessionRuntime<P> {
     /// persistence failed.
     async fn start_compaction(&mut self, instructions: Option<String>) -> bool {
         let model = self.current_model_config().await;
-        let mut plan = project(&self.entries, self.first_entry_seq());
+        let mut plan = self.project();
         let mut content = crate::context::SUMMARIZE_INSTRUCTION.to_owned();
         if let Some(instructions) = instructions {
             content.push_str("\n\nPreservation instructions from the caller: ");
             content.push_str(&instructions);
         }
-        plan.messages
-            .push(crate::context::ContextMessage::User { content });
+        plan.messages.push(ContextMessage::user(content));
         let mut staged = self
             .operation
             .clone()
@@ -2088,6 +3232,10 @@ impl<P: Provider> SessionRuntime<P> {
     /// settle a validation denial through the normal path). Returns
     /// false when persistence failed.
     async fn admit_next_tool(&mut self) -> bool {
+        let batch = self.plan_tool_batch();
+        if batch.len() > 1 {
+            return self.admit_tool_batch(batch).await;
+        }
         // The policy gate runs before any effect intent is committed
         // (§17.3): peek the next call, canonicalize it, and decide.
         let Some(call) = self
@@ -2192,18 +3340,30 @@ impl<P: Provider> SessionRuntime<P> {
             PolicyDecision::ApprovalRequired => unreachable!("handled above"),
         };
         // §12.3: file-mutating effects persist reconciliation evidence
-        // with the intent, before execution. An evidence failure means
-        // the invocation could not be classified, so it is denied
-        // model-visibly instead of admitted blind.
-        let evidence = if denial.is_none() && matches!(call.name.as_str(), "write" | "edit") {
-            match crate::tool::reconciliation_evidence(
-                self.tools.cwd(),
-                &call.name,
-                &call.arguments,
-            )
-            .await
+        // with the intent, before execution, and the preimages `undo`
+        // restores. A failure of either means the invocation could not
+        // be classified or undone, so it is denied model-visibly instead
+        // of admitted blind.
+        let effect_id = EffectId::generate();
+        let mut snapshots = Vec::new();
+        let evidence = if denial.is_none()
+            && self.tools.recovery_class(&call.name) == RecoveryClass::Reconcile
+        {
+            match self
+                .tools
+                .reconciliation_evidence(&call.name, &call.arguments)
+                .await
             {
-                Ok(evidence) => Some(evidence),
+                Ok(evidence) => match crate::checkpoint::capture(effect_id, &evidence).await {
+                    Ok(captured) => {
+                        snapshots = captured;
+                        Some(evidence)
+                    }
+                    Err(message) => {
+                        denial = Some(message);
+                        None
+                    }
+                },
                 Err(message) => {
                     denial = Some(message);
                     None
@@ -2224,7 +3384,7 @@ impl<P: Provider> SessionRuntime<P> {
         // durable intent (§17.3: never approve one string and execute
         // a materially different one).
         let effect = EffectRecord {
-            id: EffectId::generate(),
+            id: effect_id,
             kind: format!("tool:{}", call.name),
             recovery_class: self.tools.recovery_class(&call.name),
             effective_input: serde_json::json!({
@@ -2238,9 +3398,8 @@ impl<P: Provider> SessionRuntime<P> {
         };
         // The pending effect is part of the checkpoint: it must be on the
         // staged operation before the commit is built.
-        let effect_id = effect.id;
         staged.open_effect = Some(effect.clone());
-        let (request, new_entry_seq) = build_commit_request(
+        let (mut request, new_entry_seq) = build_commit_request(
             self.session_id,
             &staged,
             staged.state_seq + 1,
@@ -2253,6 +3412,9 @@ impl<P: Provider> SessionRuntime<P> {
             Vec::new(),
             Vec::new(),
         );
+        // Preimages for `undo` ride the same commit as the intent, so a
+        // file is never changed without its snapshot on record.
+        request.snapshots = snapshots;
         if let Err(err) = self.store.commit(request).await {
             self.fail_operation_on_persistence(err).await;
             return false;
@@ -2268,17 +3430,175 @@ impl<P: Provider> SessionRuntime<P> {
                 ToolResult::Err {
                     call_id: call.call_id,
                     error: message,
+                    artifact: None,
                 },
+                Vec::new(),
             ));
         } else {
             self.operation_tool_calls += 1;
             let target = target_summary(&self.tools, &call.name, &call.arguments);
             self.emit_tool_started(call.operation_id, call.call_id, &call.name, target);
-            self.spawn_tool_effect(effect_id_of(self.operation.as_ref()), call);
+            self.spawn_tool_effect(effect_id_of(self.operation.as_ref()), call, 1);
+        }
+        true
+    }
+
+    /// The leading run of planned calls that may run concurrently
+    /// (§16.3): replay-safe tools the policy allows outright, with valid
+    /// arguments, within the tool-call budget. Anything else - a
+    /// mutation, an approval, a denial - ends the run and is admitted
+    /// alone, so effects with ordering hazards stay sequential.
+    fn plan_tool_batch(&self) -> Vec<(ToolCall, CanonicalTarget)> {
+        let Some(active) = &self.operation else {
+            return Vec::new();
+        };
+        let budget_left = self
+            .budget
+            .max_tool_calls
+            .map(|max| max.saturating_sub(self.operation_tool_calls) as usize);
+        let mut batch = Vec::new();
+        for call in active.machine.planned_calls() {
+            if budget_left.is_some_and(|left| batch.len() >= left)
+                || matches!(call.name.as_str(), "compact" | "delegate")
+                || self.tools.recovery_class(&call.name) != RecoveryClass::ReplaySafe
+            {
+                break;
+            }
+            let Ok(target) = self.tools.canonicalize(&call.name, &call.arguments) else {
+                break;
+            };
+            if self.policy.decide(&call.name, &target) != PolicyDecision::Allow
+                || self.tools.validate(&call.name, &call.arguments).is_err()
+            {
+                break;
+            }
+            batch.push((call.clone(), target));
+        }
+        batch
+    }
+
+    /// Commit the intents of a whole batch at once, then spawn every
+    /// call. Returns false when persistence failed.
+    async fn admit_tool_batch(&mut self, batch: Vec<(ToolCall, CanonicalTarget)>) -> bool {
+        let mut staged = self.operation.clone().expect("admit needs an operation");
+        staged
+            .machine
+            .apply(Transition::AdmitToolBatch { count: batch.len() })
+            .expect("admit a tool batch from ToolsPlanned");
+        let effects: Vec<EffectRecord> = batch
+            .iter()
+            .map(|(call, canonical)| EffectRecord {
+                id: EffectId::generate(),
+                kind: format!("tool:{}", call.name),
+                recovery_class: RecoveryClass::ReplaySafe,
+                effective_input: serde_json::json!({
+                    "tool": call.name,
+                    "arguments": call.arguments,
+                    "call_id": call.call_id,
+                    "canonical": canonical,
+                }),
+                attempt: 1,
+            })
+            .collect();
+        staged.open_batch = effects.clone();
+        staged.batch_settled = vec![None; effects.len()];
+        let (request, new_entry_seq) = build_commit_request(
+            self.session_id,
+            &staged,
+            staged.state_seq + 1,
+            self.next_entry_seq,
+            Vec::new(),
+            effects.clone(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        );
+        if let Err(err) = self.store.commit(request).await {
+            self.fail_operation_on_persistence(err).await;
+            return false;
+        }
+        self.next_entry_seq = new_entry_seq;
+        staged.state_seq += 1;
+        self.operation = Some(staged);
+        self.operation_tool_calls += batch.len() as u32;
+        debug!(calls = batch.len(), "admitted a concurrent tool batch");
+        for ((call, _), effect) in batch.into_iter().zip(&effects) {
+            let target = target_summary(&self.tools, &call.name, &call.arguments);
+            self.emit_tool_started(call.operation_id, call.call_id, &call.name, target);
+            self.spawn_tool_effect(Some(effect.id), call, effect.attempt);
         }
         true
     }
 
+    /// Re-run a tool batch found pending after process loss. Every call
+    /// in a batch is ReplaySafe and none of its results committed, so
+    /// the whole batch replays with the next attempt count (§12.2).
+    async fn recover_tool_batch(&mut self) {
+        let mut staged = self.operation.clone().expect("operation present");
+        let applied = staged
+            .machine
+            .apply(Transition::RecoverToolBatch)
+            .expect("recover a pending tool batch");
+        if staged.open_batch.len() != applied.intents.len() {
+            error!(session = %self.session_id, "pending tool batch without its effect intents; fencing");
+            self.closed = true;
+            return;
+        }
+        let settled = staged
+            .open_batch
+            .iter()
+            .map(|open| SettledEffect {
+                id: open.id,
+                settlement: serde_json::json!({ "recovered": "process_loss" }),
+            })
+            .collect();
+        let effects: Vec<EffectRecord> = staged
+            .open_batch
+            .iter()
+            .map(|open| EffectRecord {
+                id: EffectId::generate(),
+                kind: open.kind.clone(),
+                recovery_class: open.recovery_class,
+                effective_input: open.effective_input.clone(),
+                attempt: open.attempt + 1,
+            })
+            .collect();
+        staged.open_batch = effects.clone();
+        staged.batch_settled = vec![None; effects.len()];
+        let (request, new_entry_seq) = build_commit_request(
+            self.session_id,
+            &staged,
+            staged.state_seq + 1,
+            self.next_entry_seq,
+            Vec::new(),
+            effects.clone(),
+            settled,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        );
+        if let Err(err) = self.store.commit(request).await {
+            self.fail_operation_on_persistence(err).await;
+            return;
+        }
+        let operation_id = staged.machine.operation_id();
+        self.next_entry_seq = new_entry_seq;
+        staged.state_seq += 1;
+        self.operation = Some(staged);
+        warn!(%operation_id, calls = effects.len(), "recovered a pending tool batch by re-execution");
+        for (intent, effect) in applied.intents.into_iter().zip(&effects) {
+            let EffectIntent::Tool { call } = intent else {
+                panic!("RecoverToolBatch must yield tool intents");
+            };
+            let target = target_summary(&self.tools, &call.name, &call.arguments);
+            self.emit_tool_started(operation_id, call.call_id, &call.name, target);
+            self.spawn_tool_effect(Some(effect.id), call, effect.attempt);
+        }
+    }
+
     fn spawn_model_step(
         &mut self,
         operation_id: OperationId,
@@ -2312,7 +3632,9 @@ impl<P: Provider> SessionRuntime<P> {
         });
     }
 
-    fn spawn_tool_effect(&mut self, effect_id: Option<EffectId>, call: ToolCall) {
+    /// Run one admitted call; `attempt` is its effect's, above 1 when
+    /// recovery re-executes it.
+    fn spawn_tool_effect(&mut self, effect_id: Option<EffectId>, call: ToolCall, attempt: u64) {
         let Some(effect_id) = effect_id else {
             return;
         };
@@ -2323,27 +3645,47 @@ impl<P: Provider> SessionRuntime<P> {
             .map(|active| active.cancel.child_token())
             .unwrap_or_else(|| self.cancel_root.child_token());
         let tool_tx = self.tool_tx.clone();
+        let output_tx = self.output_tx.clone();
         let ToolCall {
+            operation_id,
             call_id,
             name,
             arguments,
-            ..
         } = call;
+        let children = output_tx.clone();
+        let progress = ToolProgress::new(move |chunk| {
+            let _ = output_tx.try_send((operation_id, call_id, LiveOutput::Chunk(chunk)));
+        })
+        .with_children(move |child, update| {
+            let _ = children.try_send((operation_id, call_id, LiveOutput::Child(child, update)));
+        })
+        .for_call(operation_id, call_id)
+        .with_running(self.running_children.clone());
+        let progress = if attempt > 1 {
+            progress.retried()
+        } else {
+            progress
+        };
         debug!(tool = %name, %call_id, "dispatching tool effect");
         self.tracker.spawn(async move {
-            let outcome = tools.execute(&name, &arguments, cancel).await;
+            let outcome = tools
+                .execute_with_progress(&name, &arguments, cancel, progress)
+                .await;
             let result = if outcome.is_error {
                 ToolResult::Err {
                     call_id,
                     error: outcome.output,
+                    artifact: outcome.artifact,
                 }
             } else {
                 ToolResult::Ok {
                     call_id,
                     output: outcome.output,
+                    images: outcome.images,
+                    artifact: outcome.artifact,
                 }
             };
-            let _ = tool_tx.send((effect_id, result)).await;
+            let _ = tool_tx.send((effect_id, result, outcome.diffs)).await;
         });
     }
 
@@ -2516,10 +3858,9 @@ impl<P: Provider> SessionRuntime<P> {
         self.overflow_retry_used = true;
         let mut staged = self.operation.clone().expect("settle needs an operation");
         let model = self.current_model_config().await;
-        let mut plan = project(&self.entries, self.first_entry_seq());
-        plan.messages.push(crate::context::ContextMessage::User {
-            content: crate::context::SUMMARIZE_INSTRUCTION.to_owned(),
-        });
+        let mut plan = self.project();
+        plan.messages
+            .push(ContextMessage::user(crate::context::SUMMARIZE_INSTRUCTION));
         let applied = staged
             .machine
             .apply(Transition::OverflowCompaction { plan: plan.clone() })
@@ -2692,11 +4033,55 @@ impl<P: Provider> SessionRuntime<P> {
         self.advance().await;
     }
 
+    /// Forward a running call's live output to subscribers. Output
+    /// for a call no longer running is dropped, so it never follows
+    /// `ToolSettled`.
+    fn handle_tool_output(&mut self, (operation_id, call_id, output): ToolChunk) {
+        let running = self
+            .operation
+            .as_ref()
+            .is_some_and(|active| active.machine.operation_id() == operation_id)
+            && self.live_tools.iter().any(|tool| tool.call_id == call_id);
+        if !running {
+            return;
+        }
+        self.emit(match output {
+            LiveOutput::Chunk(chunk) => RuntimeEvent::ToolOutput {
+                cursor: RuntimeCursor::default(),
+                operation_id,
+                call_id,
+                chunk,
+            },
+            LiveOutput::Child(child_session_id, update) => RuntimeEvent::ChildProgress {
+                cursor: RuntimeCursor::default(),
+                operation_id,
+                call_id,
+                child_session_id,
+                update,
+            },
+        });
+    }
+
     async fn handle_tool_result(&mut self, settlement: ToolSettlement) {
-        let (effect_id, result) = settlement;
+        // A call's chunks were sent before its settlement; deliver them
+        // first so output precedes `ToolSettled`.
+        while let Ok(chunk) = self.output_rx.try_recv() {
+            self.handle_tool_output(chunk);
+        }
+        if self.operation.as_ref().is_some_and(|active| {
+            matches!(
+                active.machine.state(),
+                OperationState::ToolBatchPending { .. }
+            )
+        }) {
+            self.settle_batch_member(settlement).await;
+            return;
+        }
+        let (effect_id, result, diffs) = settlement;
         let call_id = result.call_id();
         let is_error = matches!(&result, ToolResult::Err { .. });
         let preview = result.display_preview();
+        let artifact = result.artifact().map(|artifact| artifact.id.clone());
         let expected = self
             .operation
             .as_ref()
@@ -2746,12 +4131,89 @@ impl<P: Provider> SessionRuntime<P> {
             call_id,
             is_error,
             preview,
+            diffs,
+            artifact,
         });
         self.emit_terminal_state(&applied.state.clone());
         self.operation = Some(staged);
         self.advance().await;
     }
 
+    /// Hold one batch member's settlement until the whole batch has
+    /// settled, then commit every result in call order at once.
+    async fn settle_batch_member(&mut self, settlement: ToolSettlement) {
+        let (effect_id, result, diffs) = settlement;
+        let active = self.operation.as_mut().expect("batch needs an operation");
+        let Some(slot) = active
+            .open_batch
+            .iter()
+            .position(|effect| effect.id == effect_id)
+        else {
+            debug!(?effect_id, "dropped stale tool settlement");
+            return;
+        };
+        active.batch_settled[slot] = Some((result, diffs));
+        if active.batch_settled.iter().any(Option::is_none) {
+            return;
+        }
+        let mut staged = active.clone();
+        let settled: Vec<(ToolResult, Vec<FileDiff>)> = std::mem::take(&mut staged.batch_settled)
+            .into_iter()
+            .flatten()
+            .collect();
+        let applied = staged
+            .machine
+            .apply(Transition::ToolBatchSettled {
+                results: settled.iter().map(|(result, _)| result.clone()).collect(),
+            })
+            .expect("batch settlement while ToolBatchPending");
+        let settled_effects = std::mem::take(&mut staged.open_batch)
+            .into_iter()
+            .zip(&settled)
+            .map(|(effect, (result, _))| SettledEffect {
+                id: effect.id,
+                settlement: serde_json::json!({ "output": result.clone().into_text() }),
+            })
+            .collect();
+        let (request, new_entry_seq) = build_commit_request(
+            self.session_id,
+            &staged,
+            staged.state_seq + 1,
+            self.next_entry_seq,
+            applied.entries.clone(),
+            Vec::new(),
+            settled_effects,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        );
+        if let Err(err) = self.store.commit(request).await {
+            self.fail_operation_on_persistence(err).await;
+            return;
+        }
+        self.next_entry_seq = new_entry_seq;
+        staged.state_seq += 1;
+        self.entries.extend(applied.entries);
+        // Settlements surface in call order, after the batch is durable.
+        for (result, diffs) in settled {
+            let call_id = result.call_id();
+            self.live_tools.retain(|pending| pending.call_id != call_id);
+            self.emit(RuntimeEvent::ToolSettled {
+                cursor: RuntimeCursor::default(),
+                operation_id: staged.machine.operation_id(),
+                call_id,
+                is_error: !result.is_ok(),
+                preview: result.display_preview(),
+                diffs,
+                artifact: result.artifact().map(|artifact| artifact.id.clone()),
+            });
+        }
+        self.emit_terminal_state(&applied.state.clone());
+        self.operation = Some(staged);
+        self.advance().await;
+    }
+
     /// A required commit failed: the staged clone is discarded and live
     /// state stays at its last durable checkpoint. Fail the operation
     /// visibly from that checkpoint; if even the failure commit fails,
@@ -2788,6 +4250,7 @@ impl<P: Provider> SessionRuntime<P> {
             })
             .expect("fail the operation from an open state");
         staged.open_effect = None;
+        staged.open_batch.clear();
         let (request, _new_entry_seq) = build_commit_request(
             self.session_id,
             &staged,
@@ -2891,12 +4354,79 @@ impl<P: Provider> SessionRuntime<P> {
         }
         let snapshot = self.snapshot();
         let rx = self.events.subscribe();
-        Ok((snapshot, EventSubscription { rx }))
+        Ok((
+            snapshot,
+            EventSubscription {
+                source: EventSource::Local(rx),
+                backlog: VecDeque::new(),
+            },
+        ))
+    }
+
+    /// Replay buffered events after `after`, then continue live. Both
+    /// halves are taken on the writer, so nothing falls in between.
+    fn resume(
+        &mut self,
+        instance: RuntimeInstanceId,
+        after: RuntimeCursor,
+    ) -> Result<EventSubscription, CommandError> {
+        if self.closed {
+            return Err(CommandError::Closed);
+        }
+        let oldest = self
+            .replay
+            .front()
+            .map_or_else(|| self.cursor.next(), RuntimeEvent::cursor);
+        if instance != self.instance || after > self.cursor || after.next() < oldest {
+            return Err(CommandError::CursorUnavailable { cursor: after });
+        }
+        let backlog = self
+            .replay
+            .iter()
+            .filter(|event| event.cursor() > after)
+            .cloned()
+            .collect();
+        Ok(EventSubscription {
+            source: EventSource::Local(self.events.subscribe()),
+            backlog,
+        })
+    }
+
+    /// Mutations from the host always pass; an attached client needs
+    /// the current controller lease.
+    fn authorize(&self, authority: Authority) -> Result<(), CommandError> {
+        match authority {
+            Authority::Host => Ok(()),
+            Authority::Lease(lease) if self.controller == Some(lease) => Ok(()),
+            Authority::Lease(_) | Authority::Observer => Err(CommandError::ReadOnly),
+        }
+    }
+
+    fn claim_control(&mut self) -> Result<u64, CommandError> {
+        if self.closed {
+            return Err(CommandError::Closed);
+        }
+        if self.controller.is_some() {
+            return Err(CommandError::ControllerHeld);
+        }
+        self.next_lease += 1;
+        self.controller = Some(self.next_lease);
+        info!(session = %self.session_id, lease = self.next_lease, "controller attached");
+        Ok(self.next_lease)
+    }
+
+    fn release_control(&mut self, authority: Authority) -> Result<(), CommandError> {
+        self.authorize(authority)?;
+        if let Some(lease) = self.controller.take() {
+            info!(session = %self.session_id, lease, "controller released");
+        }
+        Ok(())
     }
 
     fn snapshot(&self) -> SessionSnapshot {
         SessionSnapshot {
             cursor: self.cursor,
+            instance: self.instance,
             operation: match &self.operation {
                 None => OperationStatus::Idle,
                 Some(active) => OperationStatus::Active {
@@ -2955,6 +4485,7 @@ impl<P: Provider> SessionRuntime<P> {
                 .apply(Transition::Suspend)
                 .expect("suspend from an open operation");
             staged.open_effect = None;
+            staged.open_batch.clear();
             let (request, new_entry_seq) = build_commit_request(
                 self.session_id,
                 &staged,
@@ -3023,8 +4554,10 @@ impl<P: Provider> SessionRuntime<P> {
         self.cursor = self.cursor.next();
         set_cursor(&mut event, self.cursor);
         match &event {
-            RuntimeEvent::AssistantTextDelta { .. } => {
-                debug!(cursor = %self.cursor, "assistant text delta");
+            RuntimeEvent::AssistantTextDelta { .. }
+            | RuntimeEvent::ToolOutput { .. }
+            | RuntimeEvent::ChildProgress { .. } => {
+                debug!(cursor = %self.cursor, event = event_kind(&event), "streamed chunk");
             }
             other => {
                 info!(
@@ -3035,6 +4568,10 @@ impl<P: Provider> SessionRuntime<P> {
                 );
             }
         }
+        if self.replay.len() == REPLAY_CAPACITY {
+            self.replay.pop_front();
+        }
+        self.replay.push_back(event.clone());
         // A full ring drops the oldest buffered events for that
         // receiver; the receiver detects the gap and reports lag
         // reliably (broadcast semantics, §21.4). No receivers is the
@@ -3136,6 +4673,7 @@ fn build_commit_request(
                 prompt: staged.machine.prompt().to_owned(),
                 tools: staged.machine.frozen_tools().to_vec(),
                 open_effect: staged.open_effect.clone(),
+                open_batch: staged.open_batch.clone(),
             },
         },
         entries,
@@ -3145,6 +4683,7 @@ fn build_commit_request(
         inbox,
         inbox_applied,
         usage,
+        snapshots: Vec::new(),
     };
     (request, seq)
 }
@@ -3185,6 +4724,8 @@ fn set_cursor(event: &mut RuntimeEvent, cursor: RuntimeCursor) {
         | RuntimeEvent::AssistantTextDelta { cursor: slot, .. }
         | RuntimeEvent::ThinkingDelta { cursor: slot, .. }
         | RuntimeEvent::ToolStarted { cursor: slot, .. }
+        | RuntimeEvent::ToolOutput { cursor: slot, .. }
+        | RuntimeEvent::ChildProgress { cursor: slot, .. }
         | RuntimeEvent::ToolSettled { cursor: slot, .. }
         | RuntimeEvent::OperationFinished { cursor: slot, .. }
         | RuntimeEvent::OperationFailed { cursor: slot, .. }
@@ -3200,6 +4741,8 @@ fn event_kind(event: &RuntimeEvent) -> &'static str {
         RuntimeEvent::AssistantTextDelta { .. } => "assistant_text_delta",
         RuntimeEvent::ThinkingDelta { .. } => "thinking_delta",
         RuntimeEvent::ToolStarted { .. } => "tool_started",
+        RuntimeEvent::ToolOutput { .. } => "tool_output",
+        RuntimeEvent::ChildProgress { .. } => "child_progress",
         RuntimeEvent::ToolSettled { .. } => "tool_settled",
         RuntimeEvent::OperationFinished { .. } => "operation_finished",
         RuntimeEvent::OperationFailed { .. } => "operation_failed",
@@ -3219,11 +4762,27 @@ pub(crate) struct SaturatedHandle {
 impl SaturatedHandle {
     pub(crate) fn new() -> Self {
         let (tx, rx) = mpsc::channel(1);
-        let handle = RuntimeHandle { tx };
-        handle
+        let session = SessionHandle::from_sender(tx);
+        session
             .fill_queue()
             .expect("first fill occupies the bounded command queue");
-        Self { handle, _rx: rx }
+        let registry = Registry::new(Composition::new(
+            crate::provider::ScriptedProvider::echo(),
+            ToolCatalog::default(),
+            SessionStore::open_in_memory().expect("in-memory store"),
+        ));
+        registry.restore(Entry {
+            session_id: SessionId::generate(),
+            handle: session,
+            join: None,
+            hibernated: Arc::default(),
+        });
+        Self {
+            handle: RuntimeHandle {
+                registry: Arc::new(registry),
+            },
+            _rx: rx,
+        }
     }
 
     pub(crate) fn handle(&self) -> &RuntimeHandle {
@@ -3232,11 +4791,12 @@ impl SaturatedHandle {
 }
 
 #[cfg(test)]
-impl RuntimeHandle {
+impl SessionHandle {
     fn fill_queue(&self) -> Result<(), CommandError> {
         let (reply, _rx) = oneshot::channel();
         self.tx
             .try_send(SessionCommand::Submit {
+                authority: Authority::Host,
                 prompt: String::from("fill"),
                 reply,
             })
@@ -0,0 +1,468 @@
+//! Optional OS-level sandbox for `bash` (Linux).
+//!
+//! Ion never claims isolation it does not have (DESIGN.md §2): a
+//! sandboxed command either runs under kernel-enforced confinement or
+//! does not run at all. The confinement is applied between fork and
+//! exec:
+//!
+//! - Landlock: the whole filesystem is readable, but only the workspace
+//!   (and `/dev/null`) is writable. From Landlock ABI 6 on, signals and
+//!   abstract Unix sockets are also scoped to the sandbox.
+//! - The workspace's `.git` stays read-only: a command that could write
+//!   repository config or hooks would get code run unsandboxed by the
+//!   git tools or the user's next `git`. Landlock grants whole subtrees,
+//!   so this is a read-only bind mount in a mount namespace of the
+//!   command's own, which Landlock then keeps it from unmounting.
+//! - A fresh user namespace, and a fresh network namespace unless
+//!   network access is granted: the command then sees only a downed
+//!   loopback interface. The caller's uid and gid map to themselves, so
+//!   file ownership looks the same inside.
+//! - `RLIMIT_NPROC` bounds the processes the command may run; in its
+//!   own user namespace the count starts at zero.
+//! - A seccomp filter refuses new Unix sockets (the way to a session
+//!   bus, an agent, or a container daemon) and `ptrace`.
+//! - `no_new_privs`, so nothing regains privileges through setuid.
+//!
+//! Only with signal scoping can a command not reach the user's other
+//! processes; [`isolates_processes`] says whether this kernel has it,
+//! and the default policy auto-allows sandboxed commands only then.
+
+use std::path::Path;
+
+/// How `bash` commands are confined. Part of the canonical target, so
+/// the policy decides with the sandbox in view (§17.3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Sandbox {
+    /// Keep the host network; otherwise commands get an empty network
+    /// namespace.
+    pub network: bool,
+    /// Most processes a command may run at once.
+    pub max_processes: u64,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            network: false,
+            max_processes: 256,
+        }
+    }
+}
+
+/// Whether a sandboxed command is kept from the user's other processes:
+/// no signals, traces, or Unix sockets reach outside the sandbox.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+#[must_use]
+pub(crate) fn isolates_processes() -> bool {
+    static SCOPED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *SCOPED.get_or_init(|| linux::abi() >= linux::SCOPE_ABI)
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+#[must_use]
+pub(crate) fn isolates_processes() -> bool {
+    false
+}
+
+impl Sandbox {
+    /// A one-line account of the confinement for tool descriptions.
+    pub(crate) fn describe(&self) -> String {
+        let network = if self.network { "allowed" } else { "off" };
+        format!(
+            "Commands run sandboxed: the filesystem is read-only outside the workspace \
+             and in its .git directory, network access is {network}, Unix sockets cannot be opened, and at most {} \
+             processes run at once.",
+            self.max_processes
+        )
+    }
+
+    /// Arrange for `cmd` to run confined to `workspace`. Fails, rather
+    /// than running the command unconfined, when this kernel cannot
+    /// enforce the sandbox.
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    pub(crate) fn confine(
+        &self,
+        cmd: &mut tokio::process::Command,
+        workspace: &Path,
+    ) -> Result<(), String> {
+        linux::confine(*self, cmd, workspace).map_err(|err| format!("sandbox unavailable: {err}"))
+    }
+
+    #[cfg(not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    pub(crate) fn confine(
+        &self,
+        _cmd: &mut tokio::process::Command,
+        _workspace: &Path,
+    ) -> Result<(), String> {
+        Err(
+            "sandbox unavailable: the bash sandbox is only supported on x86-64 and arm64 Linux"
+                .to_owned(),
+        )
+    }
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod linux {
+    use std::ffi::CString;
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::Sandbox;
+
+    // Landlock ABI (linux/landlock.h); libc does not carry it.
+    const CREATE_RULESET_VERSION: u32 = 1;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+    const ACCESS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_READ_DIR: u64 = 1 << 3;
+    /// Every filesystem right of ABI v1: `EXECUTE` through `MAKE_SYM`.
+    const ACCESS_ABI_V1: u64 = (1 << 13) - 1;
+    const ACCESS_REFER: u64 = 1 << 13;
+    const ACCESS_TRUNCATE: u64 = 1 << 14;
+    const ACCESS_IOCTL_DEV: u64 = 1 << 15;
+    /// The first ABI that scopes signals and abstract Unix sockets.
+    pub(super) const SCOPE_ABI: i64 = 6;
+    const SCOPE_ABSTRACT_UNIX_SOCKET: u64 = 1 << 0;
+    const SCOPE_SIGNAL: u64 = 1 << 1;
+
+    // Seccomp filter data (linux/audit.h, linux/seccomp.h).
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+    /// x32 system calls share the x86-64 arch with this bit set.
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+    const DATA_NR: u32 = 0;
+    const DATA_ARCH: u32 = 4;
+    /// The low half of the first argument, on a little-endian machine.
+    const DATA_ARG0: u32 = 16;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+        handled_access_net: u64,
+        scoped: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// Everything the child needs, prepared before fork: between fork
+    /// and exec only async-signal-safe syscalls may run.
+    struct Prepared {
+        ruleset: OwnedFd,
+        /// The workspace's `.git`, if any, and the flags of the mount it
+        /// is on, which a remount inside a user namespace must keep.
+        git: Option<(CString, libc::c_ulong)>,
+        filter: Vec<libc::sock_filter>,
+        network: bool,
+        max_processes: u64,
+        setgroups: CString,
+        uid_map: CString,
+        gid_map: CString,
+        uid_line: Vec<u8>,
+        gid_line: Vec<u8>,
+    }
+
+    pub(super) fn confine(
+        sandbox: Sandbox,
+        cmd: &mut tokio::process::Command,
+        workspace: &Path,
+    ) -> io::Result<()> {
+        let ruleset = ruleset(workspace)?;
+        let git = workspace.join(".git");
+        let git = if git.symlink_metadata().is_ok() {
+            Some(read_only_target(&git)?)
+        } else {
+            None
+        };
+        #[allow(unsafe_code)] // getuid/getgid cannot fail
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let prepared = Arc::new(Prepared {
+            ruleset,
+            git,
+            filter: filter(),
+            network: sandbox.network,
+            max_processes: sandbox.max_processes,
+            setgroups: CString::new("/proc/self/setgroups").expect("no nul"),
+            uid_map: CString::new("/proc/self/uid_map").expect("no nul"),
+            gid_map: CString::new("/proc/self/gid_map").expect("no nul"),
+            uid_line: format!("{uid} {uid} 1").into_bytes(),
+            gid_line: format!("{gid} {gid} 1").into_bytes(),
+        });
+        #[allow(unsafe_code)] // pre_exec runs between fork and exec
+        unsafe {
+            cmd.pre_exec(move || enter(&prepared));
+        }
+        Ok(())
+    }
+
+    /// `path` as the child mounts it read-only, with the flags of the
+    /// mount it is on.
+    fn read_only_target(path: &Path) -> io::Result<(CString, libc::c_ulong)> {
+        let path = CString::new(path.as_os_str().as_encoded_bytes())
+            .map_err(|_| io::Error::other("workspace path contains a nul byte"))?;
+        #[allow(unsafe_code)] // statvfs fills the zeroed struct it is given
+        let stat = unsafe {
+            let mut stat: libc::statvfs = std::mem::zeroed();
+            if libc::statvfs(path.as_ptr(), &raw mut stat) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            stat
+        };
+        let kept = [
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+        ]
+        .into_iter()
+        .filter(|(stat_flag, _)| stat.f_flag & stat_flag != 0)
+        .fold(0, |flags, (_, mount_flag)| flags | mount_flag);
+        Ok((path, kept))
+    }
+
+    /// The Landlock ABI this kernel speaks; below 1 without Landlock.
+    pub(super) fn abi() -> i64 {
+        #[allow(unsafe_code)] // a version query passes no pointers
+        unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        }
+    }
+
+    /// The Landlock ruleset: read and execute anywhere, everything
+    /// beneath the workspace, read and write `/dev/null`; signals and
+    /// abstract sockets stay inside where the kernel can scope them.
+    fn ruleset(workspace: &Path) -> io::Result<OwnedFd> {
+        let abi = abi();
+        if abi < 1 {
+            return Err(io::Error::other(
+                "this kernel does not enforce Landlock filesystem rules",
+            ));
+        }
+        let mut handled = ACCESS_ABI_V1;
+        if abi >= 2 {
+            handled |= ACCESS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_TRUNCATE;
+        }
+        if abi >= 5 {
+            handled |= ACCESS_IOCTL_DEV;
+        }
+        let scoped = if abi >= SCOPE_ABI {
+            SCOPE_ABSTRACT_UNIX_SOCKET | SCOPE_SIGNAL
+        } else {
+            0
+        };
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+            handled_access_net: 0,
+            scoped,
+        };
+        #[allow(unsafe_code)] // attr outlives the call
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &raw const attr,
+                size_of::<RulesetAttr>(),
+                0u32,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        #[allow(unsafe_code)] // a fresh descriptor we now own
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+        let read = ACCESS_EXECUTE | ACCESS_READ_FILE | ACCESS_READ_DIR;
+        allow(&ruleset, Path::new("/"), read)?;
+        allow(&ruleset, workspace, handled)?;
+        let device = ACCESS_READ_FILE | ACCESS_WRITE_FILE | (handled & ACCESS_TRUNCATE);
+        allow(&ruleset, Path::new("/dev/null"), device)?;
+        Ok(ruleset)
+    }
+
+    fn allow(ruleset: &OwnedFd, path: &Path, access: u64) -> io::Result<()> {
+        let target: File = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+            .open(path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+        let attr = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: target.as_raw_fd(),
+        };
+        #[allow(unsafe_code)] // attr and both descriptors outlive the call
+        let status = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &raw const attr,
+                0u32,
+            )
+        };
+        if status < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// The seccomp program: `socket(AF_UNIX, …)` fails with `EACCES`,
+    /// `ptrace` and the cross-process memory calls with `EPERM`, and a
+    /// foreign system-call ABI gets `EPERM` for everything.
+    fn filter() -> Vec<libc::sock_filter> {
+        const LOAD: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+        const JEQ: u16 = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+        const JGE: u16 = (libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K) as u16;
+        const RET: u16 = (libc::BPF_RET | libc::BPF_K) as u16;
+        let op = |code, jt, jf, k| libc::sock_filter { code, jt, jf, k };
+        let errno = |errno: libc::c_int| libc::SECCOMP_RET_ERRNO | errno as u32;
+        let nr = |call: libc::c_long| call as u32;
+        vec![
+            op(LOAD, 0, 0, DATA_ARCH),
+            op(JEQ, 1, 0, AUDIT_ARCH),
+            op(RET, 0, 0, errno(libc::EPERM)),
+            op(LOAD, 0, 0, DATA_NR),
+            op(JGE, 5, 0, X32_SYSCALL_BIT),
+            op(JEQ, 4, 0, nr(libc::SYS_ptrace)),
+            op(JEQ, 3, 0, nr(libc::SYS_process_vm_readv)),
+            op(JEQ, 2, 0, nr(libc::SYS_process_vm_writev)),
+            op(JEQ, 2, 0, nr(libc::SYS_socket)),
+            op(RET, 0, 0, libc::SECCOMP_RET_ALLOW),
+            op(RET, 0, 0, errno(libc::EPERM)),
+            // socket(): its domain decides.
+            op(LOAD, 0, 0, DATA_ARG0),
+            op(JEQ, 0, 1, libc::AF_UNIX as u32),
+            op(RET, 0, 0, errno(libc::EACCES)),
+            op(RET, 0, 0, libc::SECCOMP_RET_ALLOW),
+        ]
+    }
+
+    /// Runs in the forked child: no allocation, no locks.
+    fn enter(prepared: &Prepared) -> io::Result<()> {
+        #[allow(unsafe_code)] // async-signal-safe syscalls on prepared data
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Always a user namespace of its own, so the process limit
+            // below counts only what the command runs.
+            let mut namespaces = libc::CLONE_NEWUSER;
+            if !prepared.network {
+                namespaces |= libc::CLONE_NEWNET;
+            }
+            if prepared.git.is_some() {
+                namespaces |= libc::CLONE_NEWNS;
+            }
+            if libc::unshare(namespaces) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            write_proc(&prepared.setgroups, b"deny")?;
+            write_proc(&prepared.uid_map, &prepared.uid_line)?;
+            write_proc(&prepared.gid_map, &prepared.gid_line)?;
+            if let Some((git, kept)) = &prepared.git {
+                let none = std::ptr::null::<libc::c_char>();
+                // Private first, so the bind never shows outside.
+                if libc::mount(
+                    none,
+                    c"/".as_ptr(),
+                    none,
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ) != 0
+                    || libc::mount(
+                        git.as_ptr(),
+                        git.as_ptr(),
+                        none,
+                        libc::MS_BIND | libc::MS_REC,
+                        std::ptr::null(),
+                    ) != 0
+                    || libc::mount(
+                        none,
+                        git.as_ptr(),
+                        none,
+                        libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY | kept,
+                        std::ptr::null(),
+                    ) != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            let limit = libc::rlimit {
+                rlim_cur: prepared.max_processes,
+                rlim_max: prepared.max_processes,
+            };
+            if libc::setrlimit(libc::RLIMIT_NPROC, &raw const limit) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(
+                libc::SYS_landlock_restrict_self,
+                prepared.ruleset.as_raw_fd(),
+                0u32,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            let program = libc::sock_fprog {
+                len: prepared.filter.len() as u16,
+                filter: prepared.filter.as_ptr().cast_mut(),
+            };
+            if libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &raw const program,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(unsafe_code)] // raw open/write/close: async-signal-safe
+    unsafe fn write_proc(path: &CString, line: &[u8]) -> io::Result<()> {
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let written = libc::write(fd, line.as_ptr().cast(), line.len());
+            libc::close(fd);
+            if written < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
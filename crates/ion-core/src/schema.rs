@@ -0,0 +1,349 @@
+//! Tool-argument validation against `ToolSpec.input_schema`.
+//!
+//! A JSON Schema (draft 2020-12) subset, enough to hold native, MCP,
+//! and extension tools to the schemas they publish: `type`,
+//! `properties`, `required`, `dependentRequired`,
+//! `additionalProperties`, `items`, `enum`, `const`, and the numeric,
+//! length, and item-count bounds. Keywords
+//! outside the subset are ignored, so an unfamiliar schema construct
+//! never denies a call on its own. Violations carry the path of the
+//! offending value, so the model can fix exactly that argument.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+/// Report at most this many violations; the rest are counted.
+const MAX_REPORTED: usize = 8;
+
+/// One violation: where in the arguments, and what is wrong there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SchemaError {
+    /// JSONPath-style location, `$` for the arguments object itself.
+    pub(crate) path: String,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Check `instance` against `schema`, collecting every violation.
+pub(crate) fn validate(schema: &Value, instance: &Value) -> Result<(), Vec<SchemaError>> {
+    let mut errors = Vec::new();
+    check(schema, instance, "$", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// The violations as one model-readable line.
+pub(crate) fn describe(errors: &[SchemaError]) -> String {
+    let mut text = errors
+        .iter()
+        .take(MAX_REPORTED)
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    if errors.len() > MAX_REPORTED {
+        text.push_str(&format!("; and {} more", errors.len() - MAX_REPORTED));
+    }
+    text
+}
+
+fn check(schema: &Value, instance: &Value, path: &str, errors: &mut Vec<SchemaError>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => return fail(errors, path, "no value is allowed here".to_owned()),
+        Value::Object(schema) => schema,
+        // Not a schema; nothing to hold the value to.
+        _ => return,
+    };
+    if let Some(expected) = schema.get("type")
+        && !type_matches(expected, instance)
+    {
+        // Every other keyword presumes the right type.
+        return fail(
+            errors,
+            path,
+            format!(
+                "expected {}, found {}",
+                type_names(expected),
+                type_of(instance)
+            ),
+        );
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum")
+        && !allowed.iter().any(|value| json_eq(value, instance))
+    {
+        let allowed = allowed
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        fail(errors, path, format!("must be one of {allowed}"));
+    }
+    if let Some(constant) = schema.get("const")
+        && !json_eq(constant, instance)
+    {
+        fail(errors, path, format!("must equal {constant}"));
+    }
+    match instance {
+        Value::Object(object) => check_object(schema, object, path, errors),
+        Value::Array(items) => check_array(schema, items, path, errors),
+        Value::String(text) => check_string(schema, text, path, errors),
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                check_number(schema, number, path, errors);
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn check_object(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                fail(
+                    errors,
+                    &member(path, key),
+                    "required argument is missing".to_owned(),
+                );
+            }
+        }
+    }
+    if let Some(Value::Object(dependent)) = schema.get("dependentRequired") {
+        for (key, needed) in dependent
+            .iter()
+            .filter(|(key, _)| object.contains_key(*key))
+        {
+            for other in needed
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !object.contains_key(other) {
+                    fail(
+                        errors,
+                        &member(path, other),
+                        format!("required argument is missing (with {key})"),
+                    );
+                }
+            }
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, value) in object {
+        let path = member(path, key);
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(property) => check(property, value, &path, errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    fail(errors, &path, "unexpected argument".to_owned());
+                }
+                Some(additional) => check(additional, value, &path, errors),
+                None => {}
+            },
+        }
+    }
+}
+
+fn check_array(
+    schema: &Map<String, Value>,
+    items: &[Value],
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+        && (items.len() as u64) < min
+    {
+        fail(errors, path, format!("must have at least {min} items"));
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+        && (items.len() as u64) > max
+    {
+        fail(errors, path, format!("must have at most {max} items"));
+    }
+    match schema.get("items") {
+        // Draft-07 tuples, still common in published tool schemas.
+        Some(Value::Array(positional)) => {
+            for (index, (item, schema)) in items.iter().zip(positional).enumerate() {
+                check(schema, item, &format!("{path}[{index}]"), errors);
+            }
+        }
+        Some(each) => {
+            for (index, item) in items.iter().enumerate() {
+                check(each, item, &format!("{path}[{index}]"), errors);
+            }
+        }
+        None => {}
+    }
+}
+
+fn check_string(
+    schema: &Map<String, Value>,
+    text: &str,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let length = text.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+        && length < min
+    {
+        fail(errors, path, format!("must be at least {min} characters"));
+    }
+    if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+        && length > max
+    {
+        fail(errors, path, format!("must be at most {max} characters"));
+    }
+}
+
+fn check_number(
+    schema: &Map<String, Value>,
+    number: f64,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    if let Some(min) = bound("minimum")
+        && number < min
+    {
+        fail(
+            errors,
+            path,
+            format!("must be at least {}", schema["minimum"]),
+        );
+    }
+    if let Some(min) = bound("exclusiveMinimum")
+        && number <= min
+    {
+        fail(
+            errors,
+            path,
+            format!("must be greater than {}", schema["exclusiveMinimum"]),
+        );
+    }
+    if let Some(max) = bound("maximum")
+        && number > max
+    {
+        fail(
+            errors,
+            path,
+            format!("must be at most {}", schema["maximum"]),
+        );
+    }
+    if let Some(max) = bound("exclusiveMaximum")
+        && number >= max
+    {
+        fail(
+            errors,
+            path,
+            format!("must be less than {}", schema["exclusiveMaximum"]),
+        );
+    }
+}
+
+fn fail(errors: &mut Vec<SchemaError>, path: &str, message: String) {
+    errors.push(SchemaError {
+        path: path.to_owned(),
+        message,
+    });
+}
+
+/// `$.name` for identifier-like keys, `$["odd key"]` otherwise.
+fn member(path: &str, key: &str) -> String {
+    let plain = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}[{}]", Value::from(key))
+    }
+}
+
+fn type_matches(expected: &Value, instance: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, instance),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|name| is_type(name, instance)),
+        // A malformed `type` constrains nothing.
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, instance: &Value) -> bool {
+    match name {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        // 2020-12: any number with a zero fractional part, so 2.0 too.
+        "integer" => match instance {
+            Value::Number(number) => {
+                number.is_i64()
+                    || number.is_u64()
+                    || number.as_f64().is_some_and(|value| value.fract() == 0.0)
+            }
+            _ => false,
+        },
+        _ => true,
+    }
+}
+
+fn type_names(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("?").to_owned(),
+    }
+}
+
+fn type_of(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+    }
+}
+
+/// JSON equality with 1 and 1.0 equal, as the spec requires.
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_eq(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, value)| y.get(key).is_some_and(|other| json_eq(value, other)))
+        }
+        _ => a == b,
+    }
+}
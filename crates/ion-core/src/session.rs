@@ -61,6 +61,12 @@ pub enum SessionEntry {
     ToolResult {
         result: ToolResult,
     },
+    /// An undo put these files back as they were before the operation,
+    /// so the model does not go on believing its edits are there.
+    Undone {
+        operation_id: OperationId,
+        files: Vec<std::path::PathBuf>,
+    },
 }
 
 /// Total durable operation state (DESIGN.md §10.1). Only states with
@@ -82,6 +88,13 @@ pub enum OperationState {
     ToolEffectPending {
         pending: Vec<ToolCall>,
     },
+    /// Intents for a run of independent replay-safe calls committed
+    /// together; they execute concurrently and settle as one batch,
+    /// results in call order (DESIGN.md §16.3).
+    ToolBatchPending {
+        batch: Vec<ToolCall>,
+        pending: Vec<ToolCall>,
+    },
     /// Assistant completed without tool calls; accepted inbox items are
     /// pending, so the operation continues.
     NeedContinuation,
@@ -177,10 +190,21 @@ pub enum Transition {
     ProviderCancelled,
     /// Admit the next planned tool: canonicalize, validate, commit intent.
     AdmitNextTool,
+    /// Admit the next `count` planned tools as one concurrent batch.
+    /// The runtime only batches calls that are replay-safe and allowed
+    /// by policy without approval.
+    AdmitToolBatch {
+        count: usize,
+    },
     /// A tool effect settled.
     ToolSettled {
         result: ToolResult,
     },
+    /// Every effect of the pending batch settled; `results` are in
+    /// call order.
+    ToolBatchSettled {
+        results: Vec<ToolResult>,
+    },
     /// Semantic cancellation request (DESIGN.md §9.4).
     CancelRequested,
     /// Harness failure (lost persistence, impossible invariant) from any
@@ -201,6 +225,9 @@ pub enum Transition {
     RecoverTool {
         call: ToolCall,
     },
+    /// Recovery: re-execute a whole pending batch. Its calls are all
+    /// ReplaySafe and none settled durably, so all of them run again.
+    RecoverToolBatch,
     /// Recovery: an unresolved NeverReplay effect becomes indeterminate;
     /// the user/agent must inspect and decide (§12.2).
     SettleIndeterminate,
@@ -357,6 +384,15 @@ impl OperationMachine {
         }
     }
 
+    /// Every planned tool call not yet admitted, in call order.
+    #[must_use]
+    pub fn planned_calls(&self) -> &[ToolCall] {
+        match &self.state {
+            OperationState::ToolsPlanned { pending } => pending,
+            _ => &[],
+        }
+    }
+
     /// The capability snapshot frozen for this operation's model steps
     /// (DESIGN.md §18.2).
     #[must_use]
@@ -404,11 +440,14 @@ impl OperationMachine {
             Transition::ProviderFailed { message } => self.provider_failed(message),
             Transition::ProviderCancelled => self.provider_cancelled(),
             Transition::AdmitNextTool => self.admit_next_tool(),
+            Transition::AdmitToolBatch { count } => self.admit_tool_batch(count),
             Transition::ToolSettled { result } => self.tool_settled(result),
+            Transition::ToolBatchSettled { results } => self.tool_batch_settled(results),
             Transition::CancelRequested => self.cancel_requested_transition(),
             Transition::FailOperation { message } => self.fail_operation(message),
             Transition::RecoverModelStep { model, plan } => self.recover_model_step(model, plan),
             Transition::RecoverTool { call } => self.recover_tool(call),
+            Transition::RecoverToolBatch => self.recover_tool_batch(),
             Transition::SettleIndeterminate => self.settle_indeterminate(),
             Transition::ApprovalRequired { tool } => self.approval_required(tool),
             Transition::StartCompaction { plan } => self.start_compaction(plan),
@@ -460,6 +499,7 @@ impl OperationMachine {
                 | OperationState::AssistantEffectPending
                 | OperationState::ToolsPlanned { .. }
                 | OperationState::ToolEffectPending { .. }
+                | OperationState::ToolBatchPending { .. }
         ) {
             match item.kind {
                 InboxKind::Steer => self.steers.push(item),
@@ -599,6 +639,36 @@ impl OperationMachine {
         })
     }
 
+    fn admit_tool_batch(&mut self, count: usize) -> Result<Applied, TransitionError> {
+        let pending = match &self.state {
+            OperationState::ToolsPlanned { pending } if count <= pending.len() && count > 0 => {
+                pending.clone()
+            }
+            state => {
+                return Err(TransitionError {
+                    state: state_name(state),
+                    transition: "admit_tool_batch",
+                });
+            }
+        };
+        let batch = pending[..count].to_vec();
+        let rest = pending[count..].to_vec();
+        let intents = batch
+            .iter()
+            .map(|call| EffectIntent::Tool { call: call.clone() })
+            .collect();
+        self.state = OperationState::ToolBatchPending {
+            batch,
+            pending: rest,
+        };
+        Ok(Applied {
+            state: self.state.clone(),
+            entries: Vec::new(),
+            intents,
+            cancel_effects: false,
+        })
+    }
+
     fn tool_settled(&mut self, result: ToolResult) -> Result<Applied, TransitionError> {
         let pending = match &self.state {
             OperationState::ToolEffectPending { pending } => pending.clone(),
@@ -624,6 +694,42 @@ impl OperationMachine {
         })
     }
 
+    fn tool_batch_settled(&mut self, results: Vec<ToolResult>) -> Result<Applied, TransitionError> {
+        let pending = match &self.state {
+            OperationState::ToolBatchPending { batch, pending }
+                if batch.len() == results.len()
+                    && batch
+                        .iter()
+                        .zip(&results)
+                        .all(|(call, result)| call.call_id == result.call_id()) =>
+            {
+                pending.clone()
+            }
+            state => {
+                return Err(TransitionError {
+                    state: state_name(state),
+                    transition: "tool_batch_settled",
+                });
+            }
+        };
+        self.state = if self.cancel_requested {
+            OperationState::Finished(OperationOutcome::Cancelled)
+        } else if pending.is_empty() {
+            OperationState::NeedAssistant
+        } else {
+            OperationState::ToolsPlanned { pending }
+        };
+        Ok(Applied {
+            state: self.state.clone(),
+            entries: results
+                .into_iter()
+                .map(|result| SessionEntry::ToolResult { result })
+                .collect(),
+            intents: Vec::new(),
+            cancel_effects: false,
+        })
+    }
+
     fn cancel_requested_transition(&mut self) -> Result<Applied, TransitionError> {
         if matches!(self.state, OperationState::Finished(_)) {
             return Err(TransitionError {
@@ -701,6 +807,24 @@ impl OperationMachine {
         })
     }
 
+    fn recover_tool_batch(&mut self) -> Result<Applied, TransitionError> {
+        let OperationState::ToolBatchPending { batch, .. } = &self.state else {
+            return Err(TransitionError {
+                state: state_name(&self.state),
+                transition: "recover_tool_batch",
+            });
+        };
+        Ok(Applied {
+            state: self.state.clone(),
+            entries: Vec::new(),
+            intents: batch
+                .iter()
+                .map(|call| EffectIntent::Tool { call: call.clone() })
+                .collect(),
+            cancel_effects: false,
+        })
+    }
+
     fn settle_indeterminate(&mut self) -> Result<Applied, TransitionError> {
         if !matches!(
             self.state,
@@ -915,6 +1039,7 @@ fn state_name(state: &OperationState) -> &'static str {
         OperationState::AssistantEffectPending => "assistant_effect_pending",
         OperationState::ToolsPlanned { .. } => "tools_planned",
         OperationState::ToolEffectPending { .. } => "tool_effect_pending",
+        OperationState::ToolBatchPending { .. } => "tool_batch_pending",
         OperationState::NeedContinuation => "need_continuation",
         OperationState::CompactionPending => "compaction_pending",
         OperationState::Suspended => "suspended",
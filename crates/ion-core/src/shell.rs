@@ -0,0 +1,370 @@
+//! Persistent shell sessions for the `bash` tool.
+//!
+//! Opt-in per registry: one long-lived `bash` runs every command, so
+//! `cd`, exported variables, and activated environments carry over
+//! between calls. Each command is written to the shell's stdin as an
+//! `eval` (a syntax error cannot swallow the protocol that follows it),
+//! then a `printf` of a per-command sentinel carrying the exit status
+//! and `$PWD`. Output before the sentinel is the command's.
+//!
+//! The shell leads its own process group and runs with job control
+//! (`set -m`), so each command's jobs lead groups of their own.
+//! Cancelling or timing out a command kills only the groups it started;
+//! the shell reports its sentinel and keeps its directory, variables,
+//! and functions. A command the shell runs itself (a builtin loop) has
+//! no group to kill: when no sentinel follows within a grace period, the
+//! shell's whole group goes. A shell that died for any reason restarts
+//! on the next command in the last reported directory, with a fresh
+//! environment.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio_util::sync::CancellationToken;
+
+use crate::sandbox::Sandbox;
+use crate::tool::{BASH_CHUNK_BYTES, HeadTail, ToolOutcome, ToolProgress, kill_process_group};
+
+pub(crate) struct PersistentShell {
+    /// Where a restart starts when the last directory is gone.
+    root: PathBuf,
+    /// The directory the last command finished in: where the next one
+    /// runs. Read by canonicalization, so policy sees it.
+    cwd: std::sync::Mutex<PathBuf>,
+    /// The live shell; `None` until the first command and after death.
+    process: tokio::sync::Mutex<Option<ShellProcess>>,
+    /// Confinement every (re)started shell runs under.
+    sandbox: Option<Sandbox>,
+}
+
+struct ShellProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+}
+
+impl Drop for ShellProcess {
+    /// Background jobs the shell started die with it.
+    fn drop(&mut self) {
+        if let Some(pid) = self.child.id() {
+            for group in job_groups(pid) {
+                kill_process_group(group);
+            }
+            kill_process_group(pid as i32);
+        }
+    }
+}
+
+/// How long a killed command's shell gets to report its sentinel
+/// before the shell itself is killed.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// How reading one command's output ended.
+enum Ending {
+    /// The sentinel arrived: exit status and working directory.
+    Finished(i32, PathBuf),
+    /// The shell exited (the command ran `exit`, or it was killed).
+    ShellExited,
+    TimedOut,
+    Cancelled,
+}
+
+impl PersistentShell {
+    pub(crate) fn new(root: &Path, sandbox: Option<Sandbox>) -> Self {
+        let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        Self {
+            cwd: std::sync::Mutex::new(root.clone()),
+            root,
+            process: tokio::sync::Mutex::new(None),
+            sandbox,
+        }
+    }
+
+    /// The directory the next command runs in.
+    pub(crate) fn cwd(&self) -> PathBuf {
+        self.cwd.lock().expect("shell cwd poisoned").clone()
+    }
+
+    fn set_cwd(&self, cwd: PathBuf) {
+        *self.cwd.lock().expect("shell cwd poisoned") = cwd;
+    }
+
+    /// Start a shell in the last reported directory, or the root when
+    /// that directory no longer exists.
+    async fn spawn(&self) -> std::io::Result<ShellProcess> {
+        let mut dir = self.cwd();
+        if !dir.is_dir() {
+            dir = self.root.clone();
+            self.set_cwd(dir.clone());
+        }
+        let mut cmd = Command::new("bash");
+        cmd.current_dir(&dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
+        if let Some(sandbox) = self.sandbox {
+            sandbox
+                .confine(&mut cmd, &self.root)
+                .map_err(std::io::Error::other)?;
+        }
+        let mut child = cmd.spawn()?;
+        let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(std::io::Error::other("shell pipes unavailable"));
+        };
+        // One stream for both, so output interleaves as written; job
+        // control gives every command's jobs their own groups.
+        stdin.write_all(b"exec 2>&1\nset -m\n").await?;
+        Ok(ShellProcess {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Run `command` in the shell, streaming its output to `progress`.
+    /// Commands run one at a time; a second call waits for the first.
+    pub(crate) async fn run(
+        &self,
+        command: &str,
+        timeout: Duration,
+        cancel: CancellationToken,
+        progress: ToolProgress,
+    ) -> ToolOutcome {
+        let sentinel = format!("__ion_done_{}__", uuid::Uuid::now_v7().simple());
+        let script = format!(
+            "eval '{}' </dev/null\nprintf '%s %d %s\\n' {sentinel} \"$?\" \"$PWD\"\n",
+            command.replace('\'', r"'\''")
+        );
+        let mut slot = self.process.lock().await;
+        // A shell that died since the last command fails the write;
+        // one restart covers it.
+        let mut written = false;
+        let mut earlier = Vec::new();
+        for _ in 0..2 {
+            if slot.is_none() {
+                match self.spawn().await {
+                    Ok(process) => *slot = Some(process),
+                    Err(err) => return ToolOutcome::error(format!("spawn failed: {err}")),
+                }
+            }
+            let process = slot.as_mut().expect("spawned above");
+            // Jobs running before this command are not its to kill.
+            earlier = process.child.id().map(job_groups).unwrap_or_default();
+            if process.stdin.write_all(script.as_bytes()).await.is_ok() {
+                written = true;
+                break;
+            }
+            *slot = None;
+        }
+        if !written {
+            return ToolOutcome::error("shell unavailable: commands cannot be written");
+        }
+        let process = slot.as_mut().expect("written above");
+        let shell_pid = process.child.id();
+
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        let mut output = HeadTail::default();
+        let mut deliver = |bytes: &[u8]| {
+            if !bytes.is_empty() {
+                progress.emit(String::from_utf8_lossy(bytes).into_owned());
+                output.push(bytes);
+            }
+        };
+        // Bytes read but not yet known to precede the sentinel.
+        let mut pending: Vec<u8> = Vec::new();
+        let ending = tokio::select! {
+            report = read_to_sentinel(&mut process.stdout, &sentinel, &mut pending, &mut deliver) => {
+                match report {
+                    Some((status, cwd)) => Ending::Finished(status, cwd),
+                    None => Ending::ShellExited,
+                }
+            }
+            () = &mut deadline => Ending::TimedOut,
+            () = cancel.cancelled() => Ending::Cancelled,
+        };
+        // Kill only what this command started; the shell reports the
+        // sentinel once its foreground job is gone.
+        let ending = match ending {
+            Ending::TimedOut | Ending::Cancelled => {
+                let killed = match shell_pid {
+                    Some(pid) => job_groups(pid)
+                        .into_iter()
+                        .filter(|group| !earlier.contains(group))
+                        .inspect(|group| kill_process_group(*group))
+                        .count(),
+                    None => 0,
+                };
+                let report = if killed > 0 {
+                    tokio::time::timeout(
+                        KILL_GRACE,
+                        read_to_sentinel(
+                            &mut process.stdout,
+                            &sentinel,
+                            &mut pending,
+                            &mut deliver,
+                        ),
+                    )
+                    .await
+                    .ok()
+                    .flatten()
+                } else {
+                    None
+                };
+                match report {
+                    Some((_, cwd)) => {
+                        self.set_cwd(cwd);
+                        if matches!(ending, Ending::Cancelled) {
+                            return ToolOutcome::error("cancelled");
+                        }
+                        return ToolOutcome::error(format!(
+                            "command timed out after {}s and was killed\n{}",
+                            timeout.as_secs(),
+                            output.finish()
+                        ));
+                    }
+                    None => ending,
+                }
+            }
+            ending => ending,
+        };
+        match ending {
+            Ending::Finished(status, cwd) => {
+                self.set_cwd(cwd);
+                if status == 0 {
+                    ToolOutcome::text(output.finish())
+                } else {
+                    ToolOutcome::error(format!(
+                        "command exited with code {status}\n{}",
+                        output.finish()
+                    ))
+                }
+            }
+            Ending::ShellExited => {
+                // No sentinel is coming: whatever is held back is output.
+                deliver(&pending);
+                let code = process
+                    .child
+                    .wait()
+                    .await
+                    .ok()
+                    .and_then(|status| status.code())
+                    .unwrap_or(-1);
+                *slot = None;
+                ToolOutcome::error(format!(
+                    "the shell exited with code {code}; the next command starts a new shell in {}\n{}",
+                    self.cwd().display(),
+                    output.finish()
+                ))
+            }
+            Ending::TimedOut | Ending::Cancelled => {
+                deliver(&pending);
+                if let Some(pid) = process.child.id() {
+                    kill_process_group(pid as i32);
+                }
+                // Reap the killed shell so it does not linger as a zombie.
+                let _ = process.child.wait().await;
+                *slot = None;
+                if matches!(ending, Ending::Cancelled) {
+                    return ToolOutcome::error("cancelled");
+                }
+                ToolOutcome::error(format!(
+                    "command timed out after {}s and was killed with its shell; the next \
+                     command starts a new shell in {}\n{}",
+                    timeout.as_secs(),
+                    self.cwd().display(),
+                    output.finish()
+                ))
+            }
+        }
+    }
+}
+
+/// Read the shell's output up to `sentinel`, delivering what precedes
+/// it; the exit status and directory it reports, or `None` when the
+/// shell exited first. Cancel-safe: unread bytes stay in `pending`.
+async fn read_to_sentinel(
+    stdout: &mut ChildStdout,
+    sentinel: &str,
+    pending: &mut Vec<u8>,
+    deliver: &mut impl FnMut(&[u8]),
+) -> Option<(i32, PathBuf)> {
+    let mut buf = vec![0u8; BASH_CHUNK_BYTES];
+    loop {
+        match find(pending, sentinel.as_bytes()) {
+            Some(at) => {
+                let report = &pending[at + sentinel.len()..];
+                if let Some(end) = report.iter().position(|&b| b == b'\n') {
+                    let report = String::from_utf8_lossy(&report[..end]).into_owned();
+                    deliver(&pending[..at]);
+                    pending.clear();
+                    let (status, cwd) = report.trim_start().split_once(' ').unwrap_or(("-1", ""));
+                    return Some((status.parse().unwrap_or(-1), PathBuf::from(cwd)));
+                }
+            }
+            None => {
+                // Only a tail shorter than the sentinel can be the
+                // start of one.
+                let settled = pending.len().saturating_sub(sentinel.len() - 1);
+                deliver(&pending[..settled]);
+                pending.drain(..settled);
+            }
+        }
+        match stdout.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => pending.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// Process groups led by the shell's children other than its own: its
+/// jobs, under job control.
+#[cfg(target_os = "linux")]
+fn job_groups(shell: u32) -> Vec<i32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut groups = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // After the parenthesized command name: state, ppid, pgrp.
+        let Some((_, fields)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let mut fields = fields.split_whitespace().skip(1);
+        let (Some(ppid), Some(group)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if ppid.parse::<u32>() != Ok(shell) {
+            continue;
+        }
+        if let Ok(group) = group.parse::<i32>()
+            && u32::try_from(group) != Ok(shell)
+            && !groups.contains(&group)
+        {
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Without `/proc` no job is found, and a killed command takes its
+/// shell with it.
+#[cfg(not(target_os = "linux"))]
+fn job_groups(_shell: u32) -> Vec<i32> {
+    Vec::new()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
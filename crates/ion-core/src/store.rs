@@ -13,18 +13,25 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread::JoinHandle;
 
-use rusqlite::Connection;
 use rusqlite::types::Type;
+use rusqlite::{Connection, OptionalExtension};
 use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
+use crate::delegate::AgentProfile;
 use crate::ids::{EffectId, InboxId, OperationId, SessionId};
 use crate::session::{InboxKind, OperationState, SessionEntry};
-use crate::tool::RecoveryClass;
+use crate::tool::{RecoveryClass, ToolResult};
+use crate::worktree::Changes;
 
 const STORE_CAPACITY: usize = 64;
 
-const SCHEMA_VERSION: i64 = 6;
+const SCHEMA_VERSION: i64 = 12;
+
+/// Blob media type of saved tool outputs.
+const ARTIFACT_MEDIA_TYPE: &str = "text/plain; charset=utf-8";
+/// Blob media type of file preimages kept for undo.
+const SNAPSHOT_MEDIA_TYPE: &str = "application/octet-stream";
 
 /// Schema gating (DESIGN.md §11.1). Ion is v0 with no compatibility
 /// guarantees: a fresh database gets the current schema, and a database
@@ -74,9 +81,15 @@ CREATE TABLE IF NOT EXISTS sessions (
     cwd TEXT NOT NULL,
     title TEXT NOT NULL,
     parent_session_id TEXT,
-    initial_model_ref TEXT NOT NULL
+    parent_operation_id TEXT,
+    parent_call_id INTEGER,
+    initial_model_ref TEXT NOT NULL,
+    profile TEXT
 );
 
+CREATE INDEX IF NOT EXISTS sessions_parent_call
+    ON sessions(parent_session_id, parent_operation_id, parent_call_id);
+
 CREATE TABLE IF NOT EXISTS entries (
     session_id TEXT NOT NULL REFERENCES sessions(id),
     seq INTEGER NOT NULL,
@@ -87,6 +100,13 @@ CREATE TABLE IF NOT EXISTS entries (
     PRIMARY KEY (session_id, seq)
 );
 
+CREATE TABLE IF NOT EXISTS blobs (
+    sha256 TEXT PRIMARY KEY,
+    media_type TEXT NOT NULL,
+    data BLOB NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS operations (
     id TEXT PRIMARY KEY,
     session_id TEXT NOT NULL REFERENCES sessions(id),
@@ -126,6 +146,18 @@ CREATE TABLE IF NOT EXISTS effects (
     attempt INTEGER NOT NULL DEFAULT 1
 );
 
+CREATE TABLE IF NOT EXISTS file_snapshots (
+    effect_id TEXT NOT NULL REFERENCES effects(id),
+    operation_id TEXT NOT NULL REFERENCES operations(id),
+    session_id TEXT NOT NULL REFERENCES sessions(id),
+    path TEXT NOT NULL,
+    preimage_sha256 TEXT REFERENCES blobs(sha256),
+    postimage_sha256 TEXT,
+    created_at INTEGER NOT NULL,
+    undone_at INTEGER,
+    PRIMARY KEY (effect_id, path)
+);
+
 CREATE TABLE IF NOT EXISTS model_steps (
     effect_id TEXT PRIMARY KEY REFERENCES effects(id),
     operation_id TEXT NOT NULL REFERENCES operations(id),
@@ -134,6 +166,15 @@ CREATE TABLE IF NOT EXISTS model_steps (
     context_window INTEGER,
     created_at INTEGER NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS pending_merges (
+    child_session_id TEXT PRIMARY KEY REFERENCES sessions(id),
+    parent_session_id TEXT NOT NULL REFERENCES sessions(id),
+    repo TEXT NOT NULL,
+    files TEXT NOT NULL,
+    diff TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
 ";
 
 /// One durable session row.
@@ -148,6 +189,19 @@ pub struct SessionRecord {
     /// Present for bounded child sessions (§20.3): lineage is durable
     /// before the child runs.
     pub parent_session_id: Option<SessionId>,
+    /// The parent tool call that started a child; recovery of that
+    /// call finds its children by it (§20.8).
+    pub parent_call: Option<ParentCall>,
+    /// The agent profile a child was started with, as defined then
+    /// (§20.2), so its lineage explains how it was configured.
+    pub profile: Option<AgentProfile>,
+}
+
+/// The tool call in the parent session that started a child session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParentCall {
+    pub operation_id: OperationId,
+    pub call_id: u64,
 }
 
 /// One entry to append; `seq` is storage-assigned by the runtime's
@@ -161,7 +215,7 @@ pub struct EntryRecord {
 /// A total operation-state checkpoint (DESIGN.md §10.1). Carries
 /// everything needed to rebuild the live machine on reopen: the frozen
 /// capability snapshot, the operation prompt, and the pending effect
-/// intent, if any.
+/// intent (or batch of intents), if any.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct CheckpointPayload {
     pub state: OperationState,
@@ -169,6 +223,9 @@ pub struct CheckpointPayload {
     pub prompt: String,
     pub tools: Vec<crate::tool::ToolSpec>,
     pub open_effect: Option<EffectRecord>,
+    /// The intents of a pending tool batch, in call order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_batch: Vec<EffectRecord>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -232,6 +289,34 @@ pub struct CommitRequest {
     /// Token usage rows persisted atomically with this transition
     /// (DESIGN.md §27.2).
     pub usage: Vec<UsageRecord>,
+    /// Preimages of the files an opened effect is about to change,
+    /// kept for undo.
+    pub snapshots: Vec<FileSnapshot>,
+}
+
+/// One file as it was before an admitted write, edit, or patch: the
+/// workspace checkpoint `undo` restores. A user-facing safety net, not
+/// recovery evidence - that stays in the effect's reconciliation
+/// evidence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSnapshot {
+    pub effect_id: EffectId,
+    pub path: PathBuf,
+    /// The file's bytes before the effect; `None` when it did not exist.
+    pub preimage: Option<Arc<[u8]>>,
+    /// Hex SHA-256 the effect intends to leave; `None` when it deletes
+    /// the file.
+    pub postimage_hash: Option<String>,
+}
+
+/// One recorded snapshot as read back: hashes only, the preimage bytes
+/// stay in the blob table until [`SessionStore::blob`] fetches them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotRow {
+    pub operation_id: OperationId,
+    pub path: PathBuf,
+    pub preimage_hash: Option<String>,
+    pub postimage_hash: Option<String>,
 }
 
 /// One persisted token-usage row (DESIGN.md §27.2).
@@ -317,10 +402,46 @@ enum StoreCommand {
     LatestSession {
         reply: oneshot::Sender<Result<Option<SessionId>, StoreError>>,
     },
+    Children {
+        parent: SessionId,
+        reply: oneshot::Sender<Result<Vec<SessionId>, StoreError>>,
+    },
+    CallChildren {
+        parent: SessionId,
+        call: ParentCall,
+        reply: oneshot::Sender<Result<Vec<SessionId>, StoreError>>,
+    },
     Usage {
         session_id: SessionId,
         reply: oneshot::Sender<Result<Vec<UsageRow>, StoreError>>,
     },
+    FileSnapshots {
+        session_id: SessionId,
+        reply: oneshot::Sender<Result<Vec<SnapshotRow>, StoreError>>,
+    },
+    Blob {
+        sha256: String,
+        reply: oneshot::Sender<Result<Option<Arc<[u8]>>, StoreError>>,
+    },
+    MarkUndone {
+        operation_id: OperationId,
+        reply: oneshot::Sender<Result<(), StoreError>>,
+    },
+    SaveMerge {
+        parent: SessionId,
+        child: SessionId,
+        changes: Changes,
+        reply: oneshot::Sender<Result<(), StoreError>>,
+    },
+    PendingMerge {
+        parent: SessionId,
+        child: SessionId,
+        reply: oneshot::Sender<Result<Option<Changes>, StoreError>>,
+    },
+    RemoveMerge {
+        child: SessionId,
+        reply: oneshot::Sender<Result<(), StoreError>>,
+    },
 }
 
 /// Handle to the store thread. Cheap to clone.
@@ -438,12 +559,96 @@ impl SessionStore {
             .await
     }
 
-    /// The most recently updated session, for `--resume`.
+    /// The file snapshots of one session not yet undone, in admission
+    /// order.
+    pub async fn file_snapshots(
+        &self,
+        session_id: SessionId,
+    ) -> Result<Vec<SnapshotRow>, StoreError> {
+        self.request(|reply| StoreCommand::FileSnapshots { session_id, reply })
+            .await
+    }
+
+    /// Retire an operation's snapshots once undo has restored them.
+    pub async fn mark_undone(&self, operation_id: OperationId) -> Result<(), StoreError> {
+        self.request(|reply| StoreCommand::MarkUndone {
+            operation_id,
+            reply,
+        })
+        .await
+    }
+
+    /// The bytes stored under a content hash, if any.
+    pub async fn blob(&self, sha256: impl Into<String>) -> Result<Option<Arc<[u8]>>, StoreError> {
+        let sha256 = sha256.into();
+        self.request(|reply| StoreCommand::Blob { sha256, reply })
+            .await
+    }
+
+    /// The most recently updated root session, for `--resume`.
     pub async fn latest_session(&self) -> Result<Option<SessionId>, StoreError> {
         self.request(|reply| StoreCommand::LatestSession { reply })
             .await
     }
 
+    /// The child sessions started under `parent`, oldest first.
+    pub async fn children(&self, parent: SessionId) -> Result<Vec<SessionId>, StoreError> {
+        self.request(|reply| StoreCommand::Children { parent, reply })
+            .await
+    }
+
+    /// Keep a worktree child's changes until its parent merges them,
+    /// across restarts (§20.4).
+    pub(crate) async fn save_merge(
+        &self,
+        parent: SessionId,
+        child: SessionId,
+        changes: Changes,
+    ) -> Result<(), StoreError> {
+        self.request(|reply| StoreCommand::SaveMerge {
+            parent,
+            child,
+            changes,
+            reply,
+        })
+        .await
+    }
+
+    /// The unmerged changes `parent`'s child `child` made, if any.
+    pub(crate) async fn pending_merge(
+        &self,
+        parent: SessionId,
+        child: SessionId,
+    ) -> Result<Option<Changes>, StoreError> {
+        self.request(|reply| StoreCommand::PendingMerge {
+            parent,
+            child,
+            reply,
+        })
+        .await
+    }
+
+    /// Forget a child's changes once merged.
+    pub(crate) async fn remove_merge(&self, child: SessionId) -> Result<(), StoreError> {
+        self.request(|reply| StoreCommand::RemoveMerge { child, reply })
+            .await
+    }
+
+    /// The child sessions one call of `parent` started, oldest first
+    /// (§20.8).
+    pub async fn call_children(
+        &self,
+        parent: SessionId,
+        call: ParentCall,
+    ) -> Result<Vec<SessionId>, StoreError> {
+        self.request(|reply| StoreCommand::CallChildren {
+            parent,
+            call,
+            reply,
+        })
+        .await
+    }
+
     /// Test hook (DESIGN.md §30.5): the next mutating command fails
     /// visibly and nothing is written.
     pub fn fail_next_write(&self) {
@@ -518,13 +723,163 @@ fn handle_command(
         StoreCommand::LatestSession { reply } => {
             let _ = reply.send(latest_session(connection));
         }
+        StoreCommand::Children { parent, reply } => {
+            let _ = reply.send(children(connection, parent));
+        }
+        StoreCommand::CallChildren {
+            parent,
+            call,
+            reply,
+        } => {
+            let _ = reply.send(call_children(connection, parent, call));
+        }
+        StoreCommand::FileSnapshots { session_id, reply } => {
+            let _ = reply.send(file_snapshots(connection, session_id));
+        }
+        StoreCommand::Blob { sha256, reply } => {
+            let _ = reply.send(
+                connection
+                    .query_row(
+                        "SELECT data FROM blobs WHERE sha256 = ?1",
+                        rusqlite::params![sha256],
+                        |row| row.get::<_, Vec<u8>>(0),
+                    )
+                    .optional()
+                    .map(|data| data.map(Arc::from))
+                    .map_err(StoreError::from),
+            );
+        }
+        StoreCommand::MarkUndone {
+            operation_id,
+            reply,
+        } => {
+            let _ = reply.send(
+                connection
+                    .execute(
+                        "UPDATE file_snapshots SET undone_at = ?2
+                         WHERE operation_id = ?1 AND undone_at IS NULL",
+                        rusqlite::params![operation_id.as_uuid().to_string(), now_ms()],
+                    )
+                    .map(drop)
+                    .map_err(StoreError::from),
+            );
+        }
+        StoreCommand::SaveMerge {
+            parent,
+            child,
+            changes,
+            reply,
+        } => {
+            let _ = reply.send(check_injected(fail_next_write).and_then(|()| {
+                save_merge(connection, parent, child, &changes).map_err(StoreError::from)
+            }));
+        }
+        StoreCommand::PendingMerge {
+            parent,
+            child,
+            reply,
+        } => {
+            let _ = reply.send(pending_merge(connection, parent, child));
+        }
+        StoreCommand::RemoveMerge { child, reply } => {
+            let _ = reply.send(check_injected(fail_next_write).and_then(|()| {
+                connection
+                    .execute(
+                        "DELETE FROM pending_merges WHERE child_session_id = ?1",
+                        rusqlite::params![child.as_uuid().to_string()],
+                    )
+                    .map(drop)
+                    .map_err(StoreError::from)
+            }));
+        }
     }
 }
 
+fn save_merge(
+    connection: &mut Connection,
+    parent: SessionId,
+    child: SessionId,
+    changes: &Changes,
+) -> rusqlite::Result<()> {
+    let files = serde_json::to_string(&changes.files)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))?;
+    connection.execute(
+        "INSERT OR REPLACE INTO pending_merges
+             (child_session_id, parent_session_id, repo, files, diff, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            child.as_uuid().to_string(),
+            parent.as_uuid().to_string(),
+            changes.repo.to_string_lossy(),
+            files,
+            changes.diff,
+            now_ms(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn pending_merge(
+    connection: &mut Connection,
+    parent: SessionId,
+    child: SessionId,
+) -> Result<Option<Changes>, StoreError> {
+    let row = connection
+        .query_row(
+            "SELECT repo, files, diff FROM pending_merges
+             WHERE child_session_id = ?1 AND parent_session_id = ?2",
+            rusqlite::params![child.as_uuid().to_string(), parent.as_uuid().to_string()],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            },
+        )
+        .optional()?;
+    row.map(|(repo, files, diff)| {
+        Ok(Changes {
+            repo: PathBuf::from(repo),
+            files: serde_json::from_str(&files)
+                .map_err(|err| StoreError::Sqlite(format!("corrupt pending merge: {err}")))?,
+            diff,
+        })
+    })
+    .transpose()
+}
+
+fn file_snapshots(
+    connection: &mut Connection,
+    session_id: SessionId,
+) -> Result<Vec<SnapshotRow>, StoreError> {
+    let mut statement = connection.prepare(
+        "SELECT operation_id, path, preimage_sha256, postimage_sha256
+         FROM file_snapshots WHERE session_id = ?1 AND undone_at IS NULL
+         ORDER BY created_at, rowid",
+    )?;
+    let rows = statement
+        .query_map([session_id.as_uuid().to_string()], |row| {
+            Ok(SnapshotRow {
+                operation_id: OperationId::from_uuid(
+                    Uuid::parse_str(&row.get::<_, String>(0)?).map_err(|_| {
+                        rusqlite::Error::InvalidColumnType(0, "operation_id".into(), Type::Text)
+                    })?,
+                ),
+                path: PathBuf::from(row.get::<_, String>(1)?),
+                preimage_hash: row.get(2)?,
+                postimage_hash: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    Ok(rows)
+}
+
 fn latest_session(connection: &mut Connection) -> Result<Option<SessionId>, StoreError> {
     let row: Option<String> = connection
         .query_row(
-            "SELECT id FROM sessions ORDER BY updated_at DESC, created_at DESC LIMIT 1",
+            "SELECT id FROM sessions WHERE parent_session_id IS NULL
+             ORDER BY updated_at DESC, created_at DESC LIMIT 1",
             [],
             |row| row.get(0),
         )
@@ -541,6 +896,51 @@ fn latest_session(connection: &mut Connection) -> Result<Option<SessionId>, Stor
     .transpose()
 }
 
+fn children(connection: &mut Connection, parent: SessionId) -> Result<Vec<SessionId>, StoreError> {
+    let mut statement = connection.prepare(
+        "SELECT id FROM sessions WHERE parent_session_id = ?1 ORDER BY created_at, rowid",
+    )?;
+    let ids = statement
+        .query_map([parent.as_uuid().to_string()], |row| {
+            row.get::<_, String>(0)
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    ids.iter()
+        .map(|id| {
+            SessionId::parse(id)
+                .ok_or_else(|| StoreError::Sqlite("session id is not a uuid".into()))
+        })
+        .collect()
+}
+
+fn call_children(
+    connection: &mut Connection,
+    parent: SessionId,
+    call: ParentCall,
+) -> Result<Vec<SessionId>, StoreError> {
+    let mut statement = connection.prepare(
+        "SELECT id FROM sessions
+         WHERE parent_session_id = ?1 AND parent_operation_id = ?2 AND parent_call_id = ?3
+         ORDER BY created_at, rowid",
+    )?;
+    let ids = statement
+        .query_map(
+            rusqlite::params![
+                parent.as_uuid().to_string(),
+                call.operation_id.as_uuid().to_string(),
+                call.call_id as i64,
+            ],
+            |row| row.get::<_, String>(0),
+        )?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    ids.iter()
+        .map(|id| {
+            SessionId::parse(id)
+                .ok_or_else(|| StoreError::Sqlite("session id is not a uuid".into()))
+        })
+        .collect()
+}
+
 fn usage_rows(
     connection: &mut Connection,
     session_id: SessionId,
@@ -579,16 +979,28 @@ fn check_injected(flag: &AtomicBool) -> Result<(), StoreError> {
 
 fn create_session(connection: &Connection, record: &SessionRecord) -> Result<(), rusqlite::Error> {
     let now = now_ms();
+    let profile = record
+        .profile
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))?;
     connection.execute(
-        "INSERT INTO sessions (id, created_at, updated_at, cwd, title, parent_session_id, initial_model_ref)
-         VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO sessions (id, created_at, updated_at, cwd, title, parent_session_id,
+                               parent_operation_id, parent_call_id, initial_model_ref, profile)
+         VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         rusqlite::params![
             record.id.as_uuid().to_string(),
             now,
             record.cwd,
             record.title,
             record.parent_session_id.map(|id| id.as_uuid().to_string()),
+            record
+                .parent_call
+                .map(|call| call.operation_id.as_uuid().to_string()),
+            record.parent_call.map(|call| call.call_id as i64),
             record.initial_model_ref,
+            profile,
         ],
     )?;
     Ok(())
@@ -684,6 +1096,33 @@ fn commit(connection: &mut Connection, request: &CommitRequest) -> Result<(), ru
             )?;
         }
     }
+    for snapshot in &request.snapshots {
+        let preimage_hash = match &snapshot.preimage {
+            Some(bytes) => {
+                let sha256 = crate::artifact::sha256_hex(bytes);
+                tx.execute(
+                    "INSERT OR IGNORE INTO blobs (sha256, media_type, data, created_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![sha256, SNAPSHOT_MEDIA_TYPE, &bytes[..], now_ms()],
+                )?;
+                Some(sha256)
+            }
+            None => None,
+        };
+        tx.execute(
+            "INSERT INTO file_snapshots (effect_id, operation_id, session_id, path, preimage_sha256, postimage_sha256, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                snapshot.effect_id.as_uuid().to_string(),
+                request.operation_id.as_uuid().to_string(),
+                request.session_id.as_uuid().to_string(),
+                snapshot.path.to_string_lossy(),
+                preimage_hash,
+                snapshot.postimage_hash,
+                now_ms(),
+            ],
+        )?;
+    }
     for usage in &request.usage {
         tx.execute(
             "INSERT INTO usage (session_id, operation_id, step, input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, recorded_at)
@@ -800,6 +1239,25 @@ fn insert_entry(
     session_id: SessionId,
     entry: &EntryRecord,
 ) -> Result<(), rusqlite::Error> {
+    // Image bytes and artifact text live once in the content-addressed
+    // blob table; the entry payload carries only their hashes.
+    if let SessionEntry::ToolResult { result } = &entry.entry {
+        let insert_blob = |sha256: &str, media_type: &str, data: &[u8]| {
+            connection.execute(
+                "INSERT OR IGNORE INTO blobs (sha256, media_type, data, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![sha256, media_type, data, now_ms()],
+            )
+        };
+        if let ToolResult::Ok { images, .. } = result {
+            for image in images {
+                insert_blob(&image.sha256, &image.media_type, &image.data)?;
+            }
+        }
+        if let Some(artifact) = result.artifact() {
+            insert_blob(&artifact.id, ARTIFACT_MEDIA_TYPE, artifact.text.as_bytes())?;
+        }
+    }
     let payload = serde_json::to_string(&entry.entry)
         .map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))?;
     connection.execute(
@@ -829,24 +1287,45 @@ fn load(connection: &Connection, session_id: SessionId) -> Result<LoadedSession,
     let id = session_id.as_uuid().to_string();
     let session = connection
         .query_row(
-            "SELECT cwd, title, parent_session_id, initial_model_ref FROM sessions WHERE id = ?1",
+            "SELECT cwd, title, parent_session_id, initial_model_ref, profile,
+                    parent_operation_id, parent_call_id
+             FROM sessions WHERE id = ?1",
             rusqlite::params![id],
             |row| {
-                Ok(SessionRecord {
+                let parent_call = match (
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<i64>>(6)?,
+                ) {
+                    (Some(operation_id), Some(call_id)) => {
+                        Uuid::parse_str(&operation_id).ok().map(|uuid| ParentCall {
+                            operation_id: OperationId::from_uuid(uuid),
+                            call_id: call_id as u64,
+                        })
+                    }
+                    _ => None,
+                };
+                let record = SessionRecord {
                     id: session_id,
                     cwd: row.get(0)?,
                     title: row.get(1)?,
                     parent_session_id: row
                         .get::<_, Option<String>>(2)?
                         .and_then(|text| SessionId::parse(&text)),
+                    parent_call,
                     initial_model_ref: row.get(3)?,
-                })
+                    profile: None,
+                };
+                Ok((record, row.get::<_, Option<String>>(4)?))
             },
         )
         .map_err(|err| match err {
             rusqlite::Error::QueryReturnedNoRows => StoreError::NotFound(session_id),
             other => StoreError::from(other),
         })?;
+    let (mut session, profile) = session;
+    session.profile = profile
+        .map(|raw| decode("session profile", raw))
+        .transpose()?;
 
     let mut statement = connection
         .prepare("SELECT seq, payload FROM entries WHERE session_id = ?1 ORDER BY seq")?;
@@ -857,14 +1336,34 @@ fn load(connection: &Connection, session_id: SessionId) -> Result<LoadedSession,
         let seq = u64::try_from(seq)
             .map_err(|_| StoreError::Sqlite(format!("corrupt entry seq {seq}")))?;
         let payload: String = row.get(1)?;
-        entries.push((seq, decode("entry", payload)?));
+        let mut entry: SessionEntry = decode("entry", payload)?;
+        if let SessionEntry::ToolResult { result } = &mut entry {
+            let (images, artifact) = match result {
+                ToolResult::Ok {
+                    images, artifact, ..
+                } => (images.as_mut_slice(), artifact),
+                ToolResult::Err { artifact, .. } => (&mut [][..], artifact),
+            };
+            for image in images {
+                image.data = load_blob(connection, &image.sha256)?;
+            }
+            if let Some(artifact) = artifact {
+                let text = String::from_utf8(load_blob(connection, &artifact.id)?.to_vec())
+                    .map_err(|_| {
+                        StoreError::Sqlite(format!("corrupt artifact {}: not UTF-8", artifact.id))
+                    })?;
+                artifact.text = Arc::from(text);
+            }
+        }
+        entries.push((seq, entry));
     }
 
     let mut statement = connection.prepare(
         "SELECT o.id, s.state_seq, s.payload FROM operations o
          JOIN operation_states s ON s.operation_id = o.id
          WHERE o.session_id = ?1
-         AND s.state_seq = (SELECT MAX(state_seq) FROM operation_states WHERE operation_id = o.id)",
+         AND s.state_seq = (SELECT MAX(state_seq) FROM operation_states WHERE operation_id = o.id)
+         ORDER BY o.accepted_at, o.rowid",
     )?;
     let mut operations = Vec::new();
     let mut op_rows = statement.query(rusqlite::params![id])?;
@@ -908,6 +1407,18 @@ fn load(connection: &Connection, session_id: SessionId) -> Result<LoadedSession,
     })
 }
 
+fn load_blob(connection: &Connection, sha256: &str) -> Result<Arc<[u8]>, StoreError> {
+    let data: Option<Vec<u8>> = connection
+        .query_row(
+            "SELECT data FROM blobs WHERE sha256 = ?1",
+            rusqlite::params![sha256],
+            |row| row.get(0),
+        )
+        .optional()?;
+    data.map(Arc::from)
+        .ok_or_else(|| StoreError::Sqlite(format!("corrupt entry: missing blob {sha256}")))
+}
+
 fn state_kind(state: &OperationState) -> &'static str {
     match state {
         OperationState::Accepted => "accepted",
@@ -915,6 +1426,7 @@ fn state_kind(state: &OperationState) -> &'static str {
         OperationState::AssistantEffectPending => "assistant_effect_pending",
         OperationState::ToolsPlanned { .. } => "tools_planned",
         OperationState::ToolEffectPending { .. } => "tool_effect_pending",
+        OperationState::ToolBatchPending { .. } => "tool_batch_pending",
         OperationState::NeedContinuation => "need_continuation",
         OperationState::CompactionPending => "compaction_pending",
         OperationState::Suspended => "suspended",
@@ -930,6 +1442,7 @@ fn entry_kind(entry: &SessionEntry) -> &'static str {
         SessionEntry::ToolCall { .. } => "tool_call",
         SessionEntry::ToolResult { .. } => "tool_result",
         SessionEntry::Compaction { .. } => "compaction",
+        SessionEntry::Undone { .. } => "undone",
     }
 }
 
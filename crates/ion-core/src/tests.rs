@@ -21,7 +21,7 @@ use crate::store::{
     CheckpointPayload, CheckpointRecord, CommitRequest, EntryRecord, InboxRecord, SessionRecord,
     SessionStore,
 };
-use crate::tool::{RecoveryClass, ToolCall, ToolRegistry, ToolResult, ToolSpec};
+use crate::tool::{FileDiff, RecoveryClass, ToolCall, ToolRegistry, ToolResult, ToolSpec};
 
 const STEP: Duration = Duration::from_millis(50);
 
@@ -64,6 +64,8 @@ fn kinds(events: &[RuntimeEvent]) -> Vec<&'static str> {
             RuntimeEvent::AssistantTextDelta { .. } => "assistant_text_delta",
             RuntimeEvent::ThinkingDelta { .. } => "thinking_delta",
             RuntimeEvent::ToolStarted { .. } => "tool_started",
+            RuntimeEvent::ToolOutput { .. } => "tool_output",
+            RuntimeEvent::ChildProgress { .. } => "child_progress",
             RuntimeEvent::ToolSettled { .. } => "tool_settled",
             RuntimeEvent::OperationFinished { .. } => "operation_finished",
             RuntimeEvent::OperationFailed { .. } => "operation_failed",
@@ -88,7 +90,14 @@ fn texts(events: &[RuntimeEvent]) -> Vec<String> {
 /// core tool granted; the policy-gate tests construct their own.
 fn permissive_policy() -> Arc<dyn PolicyEngine> {
     Arc::new(AllowlistPolicy::new([
-        "read", "write", "edit", "bash", "search", "find",
+        "read",
+        "write",
+        "edit",
+        "apply_patch",
+        "bash",
+        "search",
+        "find",
+        "read_artifact",
     ]))
 }
 
@@ -158,6 +167,7 @@ fn start_model_step_commits_intent_with_frozen_tools() {
                 system: String::new(),
                 messages: vec![ContextMessage::User {
                     content: "user: goal".to_owned(),
+                    images: Vec::new(),
                 }],
             },
         })
@@ -172,6 +182,7 @@ fn start_model_step_commits_intent_with_frozen_tools() {
                 system: String::new(),
                 messages: vec![ContextMessage::User {
                     content: "user: goal".to_owned(),
+                    images: Vec::new(),
                 }],
             },
             tools: vec![spec("read")],
@@ -264,6 +275,8 @@ fn provider_completed_with_tools_plans_then_admits_sequentially() {
             result: ToolResult::Ok {
                 call_id: 1,
                 output: "contents".to_owned(),
+                images: Vec::new(),
+                artifact: None,
             },
         })
         .expect("settle 1");
@@ -278,7 +291,9 @@ fn provider_completed_with_tools_plans_then_admits_sequentially() {
         vec![SessionEntry::ToolResult {
             result: ToolResult::Ok {
                 call_id: 1,
-                output: "contents".to_owned()
+                output: "contents".to_owned(),
+                images: Vec::new(),
+                artifact: None,
             }
         }]
     );
@@ -293,6 +308,8 @@ fn provider_completed_with_tools_plans_then_admits_sequentially() {
             result: ToolResult::Ok {
                 call_id: 2,
                 output: "out".to_owned(),
+                images: Vec::new(),
+                artifact: None,
             },
         })
         .expect("settle 2");
@@ -300,6 +317,136 @@ fn provider_completed_with_tools_plans_then_admits_sequentially() {
     assert_eq!(applied.entries.len(), 1);
 }
 
+#[test]
+fn a_tool_batch_commits_its_intents_and_results_together_in_call_order() {
+    let (mut machine, _) = machine_with_tools("goal", vec![]);
+    machine
+        .apply(Transition::StartModelStep {
+            model: step_model(),
+            plan: ContextPlan {
+                system: String::new(),
+                messages: Vec::new(),
+            },
+        })
+        .expect("start");
+    machine
+        .apply(Transition::ProviderCompleted {
+            text: String::new(),
+            tool_calls: vec![call(1, "read"), call(2, "find"), call(3, "bash")],
+        })
+        .expect("complete");
+    assert_eq!(machine.planned_calls().len(), 3);
+
+    let applied = machine
+        .apply(Transition::AdmitToolBatch { count: 2 })
+        .expect("admit the batch");
+    assert_eq!(
+        applied.intents,
+        vec![
+            EffectIntent::Tool {
+                call: call(1, "read")
+            },
+            EffectIntent::Tool {
+                call: call(2, "find")
+            }
+        ]
+    );
+    assert_eq!(
+        machine.state(),
+        &OperationState::ToolBatchPending {
+            batch: vec![call(1, "read"), call(2, "find")],
+            pending: vec![call(3, "bash")]
+        }
+    );
+    assert!(machine.planned_calls().is_empty());
+    // A single settlement is not a batch settlement.
+    assert!(
+        machine
+            .apply(Transition::ToolSettled {
+                result: ToolResult::Ok {
+                    call_id: 1,
+                    output: "a".to_owned(),
+                    images: Vec::new(),
+                    artifact: None,
+                }
+            })
+            .is_err()
+    );
+    // Results must come back complete and in call order.
+    assert!(
+        machine
+            .apply(Transition::ToolBatchSettled {
+                results: vec![
+                    ToolResult::Ok {
+                        call_id: 2,
+                        output: "b".to_owned(),
+                        images: Vec::new(),
+                        artifact: None,
+                    },
+                    ToolResult::Ok {
+                        call_id: 1,
+                        output: "a".to_owned(),
+                        images: Vec::new(),
+                        artifact: None,
+                    }
+                ]
+            })
+            .is_err()
+    );
+
+    let applied = machine
+        .apply(Transition::ToolBatchSettled {
+            results: vec![
+                ToolResult::Ok {
+                    call_id: 1,
+                    output: "a".to_owned(),
+                    images: Vec::new(),
+                    artifact: None,
+                },
+                ToolResult::Ok {
+                    call_id: 2,
+                    output: "b".to_owned(),
+                    images: Vec::new(),
+                    artifact: None,
+                },
+            ],
+        })
+        .expect("settle the batch");
+    assert_eq!(
+        applied.entries,
+        vec![
+            SessionEntry::ToolResult {
+                result: ToolResult::Ok {
+                    call_id: 1,
+                    output: "a".to_owned(),
+                    images: Vec::new(),
+                    artifact: None,
+                }
+            },
+            SessionEntry::ToolResult {
+                result: ToolResult::Ok {
+                    call_id: 2,
+                    output: "b".to_owned(),
+                    images: Vec::new(),
+                    artifact: None,
+                }
+            }
+        ]
+    );
+    assert_eq!(
+        machine.state(),
+        &OperationState::ToolsPlanned {
+            pending: vec![call(3, "bash")]
+        }
+    );
+    assert!(
+        machine
+            .apply(Transition::AdmitToolBatch { count: 2 })
+            .is_err(),
+        "a batch cannot outrun the planned calls"
+    );
+}
+
 #[test]
 fn tool_error_is_a_model_visible_result_and_continues() {
     let (mut machine, _) = machine_with_tools("goal", vec![]);
@@ -324,6 +471,7 @@ fn tool_error_is_a_model_visible_result_and_continues() {
             result: ToolResult::Err {
                 call_id: 1,
                 error: "read failed".to_owned(),
+                artifact: None,
             },
         })
         .expect("settle");
@@ -333,7 +481,8 @@ fn tool_error_is_a_model_visible_result_and_continues() {
         vec![SessionEntry::ToolResult {
             result: ToolResult::Err {
                 call_id: 1,
-                error: "read failed".to_owned()
+                error: "read failed".to_owned(),
+                artifact: None,
             }
         }]
     );
@@ -349,6 +498,7 @@ fn steer_during_effect_queues_and_applies_at_the_boundary() {
                 system: String::new(),
                 messages: vec![ContextMessage::User {
                     content: "user: goal".to_owned(),
+                    images: Vec::new(),
                 }],
             },
         })
@@ -393,6 +543,7 @@ fn steer_during_effect_queues_and_applies_at_the_boundary() {
                 messages: vec![ContextMessage::User {
                     content: "user: goal\nassistant: partial\nuser: and also check tests"
                         .to_owned(),
+                    images: Vec::new(),
                 }],
             },
         })
@@ -407,6 +558,7 @@ fn steer_during_effect_queues_and_applies_at_the_boundary() {
                 messages: vec![ContextMessage::User {
                     content: "user: goal\nassistant: partial\nuser: and also check tests"
                         .to_owned(),
+                    images: Vec::new(),
                 }],
             },
             tools: vec![],
@@ -449,6 +601,7 @@ fn follow_up_continues_the_same_operation() {
                 system: String::new(),
                 messages: vec![ContextMessage::User {
                     content: "user: goal\nuser: now summarize".to_owned(),
+                    images: Vec::new(),
                 }],
             },
         })
@@ -514,6 +667,8 @@ fn cancel_request_sets_effects_updating_and_settles_cancelled() {
             result: ToolResult::Ok {
                 call_id: 1,
                 output: "raced".to_owned(),
+                images: Vec::new(),
+                artifact: None,
             },
         })
         .expect("settle");
@@ -617,6 +772,8 @@ fn invalid_state_transition_pairs_are_typed_errors() {
             result: ToolResult::Ok {
                 call_id: 1,
                 output: String::new(),
+                images: Vec::new(),
+                artifact: None,
             },
         },
     ] {
@@ -698,6 +855,8 @@ fn invalid_state_transition_pairs_are_typed_errors() {
             result: ToolResult::Ok {
                 call_id: 1,
                 output: String::new(),
+                images: Vec::new(),
+                artifact: None,
             },
         },
     ] {
@@ -980,14 +1139,107 @@ async fn registry_rejects_missing_required_args() {
     let err = registry
         .validate("read", &json!({}))
         .expect_err("missing path should fail");
-    assert!(err.contains("path"), "got: {err}");
+    assert_eq!(
+        err,
+        "invalid arguments for `read`: $.path: required argument is missing"
+    );
 
-    assert!(
-        registry.validate("read", &json!({"path": 5})).is_ok(),
-        "present key is structurally valid"
+    let err = registry
+        .validate("read", &json!({"path": 5}))
+        .expect_err("a number is not a path");
+    assert_eq!(
+        err,
+        "invalid arguments for `read`: $.path: expected string, found integer"
+    );
+}
+
+#[test]
+fn schema_violations_name_the_offending_path() {
+    use crate::schema::{describe, validate};
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "edits": {
+                "type": "array",
+                "minItems": 1,
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "old_text": { "type": "string" },
+                        "line": { "type": "integer", "exclusiveMinimum": 0, "maximum": 100 }
+                    },
+                    "required": ["old_text"]
+                }
+            },
+            "mode": { "type": ["string", "null"], "enum": ["fast", "slow", null] },
+            "meta": { "type": "object", "additionalProperties": { "type": "boolean" } },
+            "version": { "const": 2 },
+            "from": { "type": "integer" },
+            "to": { "type": "integer" },
+            "anything": true,
+            "nothing": false,
+            "pattern": { "type": "string", "pattern": "^never checked$" }
+        },
+        "dependentRequired": { "from": ["to"] }
+    });
+    let check = |instance| validate(&schema, &instance).map_err(|errors| describe(&errors));
+
+    check(json!({
+        "edits": [{ "old_text": "a", "line": 2.0 }],
+        "mode": null,
+        "meta": { "x-flag": true },
+        "version": 2.0,
+        "anything": [1, "two"],
+        "pattern": "unknown keywords constrain nothing"
+    }))
+    .expect("valid arguments pass");
+
+    assert_eq!(
+        check(json!({ "edits": [] })),
+        Err("$.edits: must have at least 1 items".to_owned())
+    );
+    assert_eq!(
+        check(json!({ "edits": [{ "old_text": 1, "line": 0 }, { "line": 101.5 }] })),
+        Err("$.edits[0].line: must be greater than 0; \
+             $.edits[0].old_text: expected string, found integer; \
+             $.edits[1].old_text: required argument is missing; \
+             $.edits[1].line: expected integer, found number"
+            .to_owned())
+    );
+    assert_eq!(
+        check(json!({ "mode": "medium", "meta": { "x-flag": "yes" } })),
+        Err("$.meta[\"x-flag\"]: expected boolean, found string; \
+             $.mode: must be one of \"fast\", \"slow\", null"
+            .to_owned())
+    );
+    assert_eq!(
+        check(json!({ "version": 3, "nothing": 0 })),
+        Err("$.nothing: no value is allowed here; $.version: must equal 2".to_owned())
+    );
+    assert_eq!(check(json!({ "to": 2 })), Ok(()));
+    assert_eq!(
+        check(json!({ "from": 1 })),
+        Err("$.to: required argument is missing (with from)".to_owned())
     );
 }
 
+#[test]
+fn oversized_limits_pass_validation_and_are_clamped_by_the_tool() {
+    let registry = ToolRegistry::default();
+    for (name, arguments) in [
+        (
+            "search",
+            json!({ "pattern": "x", "context": 50, "max_results": 100_000 }),
+        ),
+        ("find", json!({ "pattern": "*", "max_results": 100_000 })),
+        ("git_diff", json!({ "context": 500 })),
+        ("git_log", json!({ "max_count": 100_000 })),
+        ("process_output", json!({ "id": 1, "wait_secs": 100_000 })),
+    ] {
+        assert_eq!(registry.validate(name, &arguments), Ok(()), "{name}");
+    }
+}
+
 #[tokio::test]
 async fn registry_rejects_unknown_tool() {
     let registry = ToolRegistry::default();
@@ -1002,10 +1254,13 @@ fn tool_result_classifies_ok_and_err() {
     let ok = ToolResult::Ok {
         call_id: 1,
         output: "hi".into(),
+        images: Vec::new(),
+        artifact: None,
     };
     let err = ToolResult::Err {
         call_id: 2,
         error: "boom".into(),
+        artifact: None,
     };
     assert_eq!(ok.call_id(), 1);
     assert!(ok.is_ok());
@@ -1014,7 +1269,9 @@ fn tool_result_classifies_ok_and_err() {
     assert_eq!(
         ToolResult::Ok {
             call_id: 3,
-            output: "x".into()
+            output: "x".into(),
+            images: Vec::new(),
+            artifact: None,
         }
         .into_text(),
         "x"
@@ -1047,13 +1304,13 @@ async fn read_write_edit_search_find_roundtrip() {
         )
         .await;
     assert!(!out.is_error, "write failed: {out:?}");
-    assert_eq!(out.output, "written");
+    assert_eq!(out.output, "written\n@@ -0,0 +1,1 @@\n+hello world");
 
     let out = registry
         .execute("read", &json!({"path":"sub/note.txt"}), cancel.clone())
         .await;
     assert!(!out.is_error, "read failed: {out:?}");
-    assert_eq!(out.output, "hello world");
+    assert_eq!(out.output, "     1\thello world");
 
     let out = registry
         .execute(
@@ -1066,7 +1323,7 @@ async fn read_write_edit_search_find_roundtrip() {
     let out = registry
         .execute("read", &json!({"path":"sub/note.txt"}), cancel.clone())
         .await;
-    assert_eq!(out.output, "hello ion");
+    assert_eq!(out.output, "     1\thello ion");
 
     let out = registry
         .execute(
@@ -1100,81 +1357,1232 @@ async fn read_write_edit_search_find_roundtrip() {
 }
 
 #[tokio::test]
-async fn bash_runs_command_and_reports_nonzero_exit() {
-    let registry = ToolRegistry::default();
-    let cancel = tokio_util::sync::CancellationToken::new();
+async fn search_honors_ignore_files_globs_and_modes() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).expect("mkdir");
+    std::fs::create_dir_all(root.join("build")).expect("mkdir");
+    std::fs::create_dir_all(root.join("vendor")).expect("mkdir");
+    std::fs::write(root.join(".gitignore"), "build/\n").expect("seed");
+    std::fs::write(root.join(".ignore"), "*.log\n").expect("seed");
+    std::fs::write(
+        root.join("src/a.rs"),
+        "one\nTODO(a.b)\nthree\nfour\nTODO later\n",
+    )
+    .expect("seed");
+    std::fs::write(root.join("build/out.rs"), "TODO built\n").expect("seed");
+    std::fs::write(root.join("vendor/dep.rs"), "TODO vendored\n").expect("seed");
+    std::fs::write(root.join("run.log"), "TODO logged\n").expect("seed");
+    let registry = ToolRegistry::with_cwd(root);
+    let search = async |arguments: serde_json::Value| {
+        let out = registry
+            .execute("search", &arguments, CancellationToken::new())
+            .await;
+        assert!(!out.is_error, "{out:?}");
+        out.output
+    };
+
+    assert_eq!(
+        search(json!({"pattern": "TODO", "exclude": ["vendor"]})).await,
+        "src/a.rs:2:TODO(a.b)\nsrc/a.rs:5:TODO later"
+    );
+    assert_eq!(
+        search(json!({"pattern": "todo", "include": ["vendor/*"], "case_insensitive": true})).await,
+        "vendor/dep.rs:1:TODO vendored"
+    );
+    assert_eq!(
+        search(json!({"pattern": "(a.b)", "fixed_strings": true})).await,
+        "src/a.rs:2:TODO(a.b)"
+    );
+    assert_eq!(
+        search(
+            json!({"pattern": "TODO", "include": ["*.rs"], "exclude": ["vendor"], "context": 1})
+        )
+        .await,
+        "src/a.rs-1-one\nsrc/a.rs:2:TODO(a.b)\nsrc/a.rs-3-three\nsrc/a.rs-4-four\nsrc/a.rs:5:TODO later"
+    );
+}
+
+#[tokio::test]
+async fn search_and_find_count_what_the_cap_cuts() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    for n in 0..5 {
+        std::fs::write(dir.path().join(format!("f{n}.txt")), "hit\nhit\n").expect("seed");
+    }
+    let registry = ToolRegistry::with_cwd(dir.path());
     let out = registry
-        .execute("bash", &json!({"command":"echo hi"}), cancel.clone())
+        .execute(
+            "search",
+            &json!({"pattern": "hit", "max_results": 3}),
+            CancellationToken::new(),
+        )
         .await;
-    assert!(!out.is_error, "bash failed: {out:?}");
-    assert!(out.output.contains("hi"), "got: {out:?}");
+    assert_eq!(
+        out.output,
+        "f0.txt:1:hit\nf0.txt:2:hit\nf1.txt:1:hit\n\
+         … 7 more matches not shown (limit 3); narrow the pattern, path, or include globs"
+    );
+    let out = registry
+        .execute(
+            "find",
+            &json!({"pattern": "*.txt", "max_results": 2}),
+            CancellationToken::new(),
+        )
+        .await;
+    assert_eq!(
+        out.output,
+        "f0.txt\nf1.txt\n… 3 more paths not shown (limit 2); narrow the pattern or path"
+    );
 
+    // One file keeps only the capped matches and counts the rest; a
+    // file over the size bound is not searched at all.
+    std::fs::write(dir.path().join("a.txt"), "hit\n".repeat(10_000)).expect("seed");
+    std::fs::write(dir.path().join("huge.txt"), "hit\n".repeat(2_000_000)).expect("seed");
     let out = registry
-        .execute("bash", &json!({"command":"exit 3"}), cancel.clone())
+        .execute(
+            "search",
+            &json!({"pattern": "hit", "max_results": 2}),
+            CancellationToken::new(),
+        )
         .await;
-    assert!(out.is_error, "nonzero exit should error");
-    assert!(out.output.contains("3"), "got: {out:?}");
+    assert_eq!(
+        out.output,
+        "a.txt:1:hit\na.txt:2:hit\n\
+         … 10008 more matches not shown (limit 2); narrow the pattern, path, or include globs"
+    );
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let out = registry
+        .execute("find", &json!({"pattern": "*.txt"}), cancel)
+        .await;
+    assert!(out.is_error);
+    assert_eq!(out.output, "cancelled");
+}
+
+/// Run git in `dir` with a fixed identity and clock, returning stdout.
+fn git_in(dir: &std::path::Path, args: &[&str], author: &str, date: &str) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", author)
+        .env("GIT_AUTHOR_EMAIL", "dev@example.com")
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_NAME", author)
+        .env("GIT_COMMITTER_EMAIL", "dev@example.com")
+        .env("GIT_COMMITTER_DATE", date)
+        .output()
+        .expect("git runs");
+    assert!(output.status.success(), "git {args:?}: {output:?}");
+    String::from_utf8(output.stdout)
+        .expect("utf-8")
+        .trim()
+        .to_owned()
 }
 
 #[tokio::test]
-async fn bash_cancel_kills_long_running_command() {
-    let registry = ToolRegistry::default();
-    let cancel = tokio_util::sync::CancellationToken::new();
-    let cancel_clone = cancel.clone();
-    let handle = tokio::spawn(async move {
+async fn git_tools_inspect_a_repository_without_a_shell() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let root = dir.path();
+    let at = "2026-01-02T10:00:00Z";
+    git_in(root, &["init", "-q", "-b", "main"], "Alice", at);
+    std::fs::write(
+        root.join("lib.rs"),
+        "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\n",
+    )
+    .expect("seed");
+    git_in(root, &["add", "lib.rs"], "Alice", at);
+    git_in(root, &["commit", "-q", "-m", "add lib"], "Alice", at);
+    let at = "2026-03-04T10:00:00Z";
+    std::fs::write(
+        root.join("lib.rs"),
+        "fn a() {}\nfn b() { todo!() }\nfn c() {}\nfn d() {}\n",
+    )
+    .expect("edit");
+    git_in(root, &["commit", "-q", "-am", "stub b"], "Bob", at);
+    let head = git_in(root, &["rev-parse", "HEAD"], "Bob", at);
+    let short = git_in(root, &["rev-parse", "--short", "HEAD"], "Bob", at);
+    let first = git_in(root, &["rev-parse", "--short", "HEAD~1"], "Bob", at);
+
+    std::fs::write(
+        root.join("lib.rs"),
+        "fn a() {}\nfn b() { todo!() }\nfn c() { 3 }\nfn d() {}\n",
+    )
+    .expect("unstaged edit");
+    std::fs::write(root.join("new.rs"), "fn new() {}\n").expect("new");
+    git_in(root, &["add", "new.rs"], "Bob", at);
+    std::fs::write(root.join("notes.txt"), "todo\n").expect("untracked");
+
+    let registry = ToolRegistry::with_cwd(root);
+    let call = async |name: &str, arguments: serde_json::Value| {
         registry
-            .execute("bash", &json!({"command":"sleep 30"}), cancel_clone)
+            .execute(name, &arguments, CancellationToken::new())
             .await
-    });
-    sleep(STEP).await;
-    cancel.cancel();
-    let outcome = timeout(Duration::from_secs(3), handle)
-        .await
-        .expect("tool should be killed on cancel")
-        .expect("task join");
-    assert!(outcome.is_error);
-    assert_eq!(outcome.output, "cancelled");
-}
+    };
 
-// ---- Operation-level integration tests ----
+    let status = call("git_status", json!({})).await;
+    assert_eq!(
+        status.output,
+        "branch: main\nstaged (1):\n  added: new.rs\nunstaged (1):\n  modified: lib.rs\n\
+         untracked (1):\n  notes.txt"
+    );
+    let status = call("git_status", json!({"path": "new.rs"})).await;
+    assert_eq!(status.output, "branch: main\nstaged (1):\n  added: new.rs");
+
+    let diff = call("git_diff", json!({"context": 0})).await;
+    assert!(!diff.is_error, "{}", diff.output);
+    assert!(diff.output.starts_with(" lib.rs | 2 +-"), "{}", diff.output);
+    assert!(
+        diff.output.contains("\n-fn c() {}\n+fn c() { 3 }"),
+        "{}",
+        diff.output
+    );
+    assert!(!diff.output.contains("new.rs"), "{}", diff.output);
+    let staged = call("git_diff", json!({"staged": true})).await;
+    assert!(staged.output.contains("+fn new() {}"), "{}", staged.output);
+    let since = call("git_diff", json!({"base": "HEAD~1", "path": "lib.rs"})).await;
+    assert!(
+        since.output.contains("+fn b() { todo!() }"),
+        "{}",
+        since.output
+    );
+    assert!(since.output.contains("+fn c() { 3 }"), "{}", since.output);
+    let clean = call("git_diff", json!({"path": "notes.txt"})).await;
+    assert_eq!(clean.output, "no changes");
+    let option = call("git_diff", json!({"base": "--output=/tmp/x"})).await;
+    assert!(option.is_error);
+    assert_eq!(
+        option.output,
+        "base must be a revision such as HEAD~1 or main, not \"--output=/tmp/x\""
+    );
+
+    let log = call("git_log", json!({})).await;
+    assert_eq!(
+        log.output,
+        format!("{short} 2026-03-04 Bob: stub b\n{first} 2026-01-02 Alice: add lib")
+    );
+    let log = call("git_log", json!({"max_count": 1})).await;
+    assert_eq!(
+        log.output,
+        format!(
+            "{short} 2026-03-04 Bob: stub b\n\
+             … more commits not shown (max_count 1); raise max_count or narrow with path"
+        )
+    );
+
+    let parent = git_in(root, &["rev-parse", "HEAD~1"], "Bob", at);
+    let blame = call(
+        "git_blame",
+        json!({"path": "lib.rs", "offset": 2, "limit": 2, "revision": "HEAD"}),
+    )
+    .await;
+    assert_eq!(
+        blame.output,
+        format!(
+            "{} Bob 2026-03-04      2\tfn b() {{ todo!() }}\n\
+             {} Alice 2026-01-02      3\tfn c() {{}}\n\
+             [truncated: showing lines 2-3; continue with offset=4]",
+            &head[..8],
+            &parent[..8]
+        )
+    );
+    // Without a revision the working tree is blamed, edits included.
+    let blame = call("git_blame", json!({"path": "lib.rs", "offset": 3})).await;
+    let lines: Vec<&str> = blame.output.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", blame.output);
+    assert!(
+        lines[0].starts_with("00000000 Not Committed Yet ")
+            && lines[0].ends_with("      3\tfn c() { 3 }"),
+        "{}",
+        blame.output
+    );
+}
 
 #[tokio::test]
-async fn tool_loop_success_admits_tools_and_finishes() {
-    let provider = ScriptedProvider::new(vec![
-        ScriptedMessage::tool("bash", json!({"command":"echo tool-said-hello"})),
-        ScriptedMessage::text("final answer\n"),
-    ]);
-    let runtime = start_runtime(provider, ToolRegistry::default());
-    let session = runtime.session();
-    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
-    let operation_id = session.submit("go").await.expect("submit");
-    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+async fn repository_config_cannot_make_git_tools_run_code() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let root = dir.path();
+    let at = "2026-01-02T10:00:00Z";
+    git_in(root, &["init", "-q", "-b", "main"], "Alice", at);
+    std::fs::write(root.join("lib.rs"), "fn a() {}\n").expect("seed");
+    git_in(root, &["add", "lib.rs"], "Alice", at);
+    git_in(root, &["commit", "-q", "-m", "add lib"], "Alice", at);
+    std::fs::write(root.join("lib.rs"), "fn a() { 1 }\n").expect("edit");
+
+    // A repository that runs a script from its fsmonitor, its hooks,
+    // a textconv driver, and a filter, each leaving a marker behind.
+    let marker = root.join("ran");
+    let script = root.join("evil.sh");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\necho \"$0\" >> {}\ncat \"$1\"\n",
+            marker.display()
+        ),
+    )
+    .expect("script");
+    std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+        .expect("chmod");
+    let script = script.display().to_string();
+    git_in(root, &["config", "core.fsmonitor", &script], "Alice", at);
+    git_in(
+        root,
+        &["config", "diff.evil.textconv", &script],
+        "Alice",
+        at,
+    );
+    for driver in [
+        "filter.evil.clean",
+        "filter.evil.smudge",
+        "filter.evil.process",
+    ] {
+        git_in(root, &["config", driver, &script], "Alice", at);
+    }
+    git_in(
+        root,
+        &["config", "filter.evil.required", "true"],
+        "Alice",
+        at,
+    );
+    std::fs::write(root.join(".gitattributes"), "*.rs diff=evil filter=evil\n")
+        .expect("attributes");
+    std::fs::create_dir_all(root.join(".git/hooks")).expect("hooks");
+    for hook in ["pre-commit", "post-index-change", "reference-transaction"] {
+        std::fs::copy(&script, root.join(".git/hooks").join(hook)).expect("hook");
+    }
 
-    assert!(matches!(
-        recorded.last(),
-        Some(RuntimeEvent::OperationFinished { operation_id: id, .. }) if *id == operation_id
-    ));
-    // Tool execution is a live event; tool output is a semantic entry,
-    // never an assistant text delta (DESIGN.md §16.4).
-    assert!(recorded.iter().any(|event| matches!(
-        event,
-        RuntimeEvent::ToolStarted { tool, .. } if tool == "bash"
-    )));
-    assert_eq!(texts(&recorded), vec!["final answer\n".to_owned()]);
-    assert!(recorded.iter().all(|e| !matches!(
-        e,
-        RuntimeEvent::OperationFailed { .. } | RuntimeEvent::OperationCancelled { .. }
-    )));
-    let snapshot = session.snapshot().await.expect("snapshot");
-    assert!(snapshot.entries.iter().any(|entry| matches!(
-        entry,
-        SessionEntry::ToolResult {
-            result: ToolResult::Ok { output, .. },
-        } if output.contains("tool-said-hello")
-    )));
+    let registry = ToolRegistry::with_cwd(root);
+    let call = async |name: &str, arguments: serde_json::Value| {
+        registry
+            .execute(name, &arguments, CancellationToken::new())
+            .await
+    };
+    for (name, arguments) in [
+        ("git_status", json!({})),
+        ("git_diff", json!({})),
+        ("git_diff", json!({"base": "HEAD"})),
+        ("git_log", json!({"path": "lib.rs"})),
+        ("git_blame", json!({"path": "lib.rs"})),
+    ] {
+        let outcome = call(name, arguments).await;
+        assert!(!outcome.is_error, "{name}: {}", outcome.output);
+    }
+    assert!(
+        !marker.exists(),
+        "{}",
+        std::fs::read_to_string(&marker).unwrap_or_default()
+    );
 
-    session.close().await.expect("close");
-    runtime.join().await.expect("join");
+    // Nor can the file tools plant such config in the first place.
+    let config = std::fs::read_to_string(root.join(".git/config")).expect("config");
+    for (name, arguments) in [
+        ("write", json!({"path": ".git/config", "contents": ""})),
+        (
+            "write",
+            json!({"path": "sub/../.git/hooks/post-checkout", "contents": ""}),
+        ),
+        (
+            "write",
+            json!({"path": "vendor/.GIT/config", "contents": ""}),
+        ),
+        (
+            "edit",
+            json!({"path": ".git/config", "old_str": "[core]", "new_str": "[x]"}),
+        ),
+        (
+            "apply_patch",
+            json!({"patch": "--- /dev/null\n+++ b/.git/hooks/pre-push\n@@ -0,0 +1 @@\n+true\n"}),
+        ),
+    ] {
+        let outcome = call(name, arguments).await;
+        assert!(outcome.is_error, "{name}: {}", outcome.output);
+        assert!(
+            outcome.output.contains("refusing to modify git metadata"),
+            "{name}: {}",
+            outcome.output
+        );
+    }
+    assert_eq!(
+        std::fs::read_to_string(root.join(".git/config")).expect("config"),
+        config
+    );
+    assert!(!root.join(".git/hooks/pre-push").exists());
+    // A file merely named like git metadata is fine.
+    let outcome = call(
+        "write",
+        json!({"path": ".gitignore", "contents": "target\n"}),
+    )
+    .await;
+    assert!(!outcome.is_error, "{}", outcome.output);
+}
+
+#[tokio::test]
+async fn git_tools_are_read_only_paths_the_default_policy_allows() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let registry = ToolRegistry::with_cwd(dir.path());
+    for name in ["git_status", "git_diff", "git_log", "git_blame"] {
+        assert_eq!(registry.recovery_class(name), RecoveryClass::ReplaySafe);
+        assert!(ToolRegistry::read_only(dir.path()).get(name).is_some());
+    }
+    let target = registry
+        .canonicalize("git_blame", &json!({"path": "src/../lib.rs", "offset": 2}))
+        .expect("canonicalize");
+    assert_eq!(
+        target,
+        crate::tool::CanonicalTarget::Path {
+            path: dir.path().join("lib.rs"),
+            lines: Some(crate::LineRange {
+                offset: 2,
+                limit: None
+            }),
+        }
+    );
+    assert_eq!(
+        crate::DefaultPolicy.decide("git_blame", &target),
+        crate::PolicyDecision::Allow
+    );
+    let target = registry
+        .canonicalize("git_log", &json!({}))
+        .expect("canonicalize");
+    assert_eq!(
+        crate::DefaultPolicy.decide("git_log", &target),
+        crate::PolicyDecision::Allow
+    );
+
+    // Outside a repository git's own message comes back.
+    let outcome = registry
+        .execute("git_status", &json!({}), CancellationToken::new())
+        .await;
+    assert!(outcome.is_error);
+    assert!(
+        outcome
+            .output
+            .starts_with("git status failed: fatal: not a git repository"),
+        "{}",
+        outcome.output
+    );
+}
+
+#[tokio::test]
+async fn read_pages_large_files_and_reports_binaries() {
+    let tmp = std::env::temp_dir().join(format!("ion-tool-test-{}-read", std::process::id()));
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).expect("tmp");
+    let long: String = (1..=3000).map(|n| format!("line {n}\n")).collect();
+    std::fs::write(tmp.join("long.txt"), long).expect("write");
+    let wide: String = (1..=100)
+        .map(|_| format!("{}\n", "x".repeat(1000)))
+        .collect();
+    std::fs::write(tmp.join("wide.txt"), wide).expect("write");
+    std::fs::write(tmp.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").expect("write");
+    std::fs::write(tmp.join("clip.wav"), b"RIFF\x24\0\0\0WAVEfmt ").expect("write");
+    std::fs::write(tmp.join("latin1.txt"), b"caf\xe9").expect("write");
+    let registry = ToolRegistry::with_cwd(&tmp);
+    let read = async |arguments: serde_json::Value| {
+        registry
+            .execute("read", &arguments, CancellationToken::new())
+            .await
+    };
+
+    // Unranged reads stop at the line cap and say how to go on.
+    let out = read(json!({"path": "long.txt"})).await;
+    assert!(!out.is_error, "{out:?}");
+    assert!(out.output.starts_with("     1\tline 1\n"));
+    assert!(out.output.contains("  2000\tline 2000\n"));
+    assert!(!out.output.contains("line 2001"));
+    assert!(
+        out.output
+            .ends_with("[truncated: showing lines 1-2000 of 3000; continue with offset=2001]")
+    );
+    let out = read(json!({"path": "long.txt", "offset": 2001})).await;
+    assert!(out.output.starts_with("  2001\tline 2001\n"));
+    assert!(out.output.ends_with("  3000\tline 3000"));
+
+    let out = read(json!({"path": "long.txt", "offset": 10, "limit": 2})).await;
+    assert_eq!(
+        out.output,
+        "    10\tline 10\n    11\tline 11\n\
+         [truncated: showing lines 10-11 of 3000; continue with offset=12]"
+    );
+    let out = read(json!({"path": "long.txt", "offset": 3001})).await;
+    assert!(out.is_error);
+    assert!(out.output.contains("past the end"), "{out:?}");
+    let out = read(json!({"path": "long.txt", "limit": 0})).await;
+    assert!(out.is_error);
+
+    // The byte cap holds regardless of line count.
+    let out = read(json!({"path": "wide.txt"})).await;
+    let (body, notice) = out.output.rsplit_once('\n').expect("notice");
+    assert!(body.len() <= 50 * 1024);
+    assert!(notice.contains("continue with offset="), "{notice}");
+
+    // Images come back as attachments with a one-line description.
+    let out = read(json!({"path": "logo.png"})).await;
+    assert!(!out.is_error);
+    assert_eq!(out.output, "image: logo.png (image/png, 16 bytes)");
+    assert_eq!(out.images.len(), 1);
+    assert_eq!(out.images[0].media_type, "image/png");
+    assert_eq!(&out.images[0].data[..], b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+
+    // Other binary content is described, not an error.
+    let out = read(json!({"path": "clip.wav"})).await;
+    assert!(!out.is_error);
+    assert!(out.images.is_empty());
+    assert_eq!(
+        out.output,
+        "binary file not shown: clip.wav (RIFF media, 16 bytes)"
+    );
+    // Text in another encoding reads with the invalid bytes replaced;
+    // a NUL byte marks binary data.
+    let out = read(json!({"path": "latin1.txt"})).await;
+    assert!(!out.is_error);
+    assert_eq!(out.output, "     1\tcaf\u{fffd}");
+    std::fs::write(tmp.join("data.bin"), b"ab\0cd").expect("write");
+    let out = read(json!({"path": "data.bin"})).await;
+    assert_eq!(
+        out.output,
+        "binary file not shown: data.bin (binary data, 5 bytes)"
+    );
+
+    // Lines split like `str::lines`, wherever the read buffer ends.
+    let crlf = "a\r\nb\r\n\nc".repeat(5000);
+    std::fs::write(tmp.join("crlf.txt"), &crlf).expect("write");
+    let out = read(json!({"path": "crlf.txt", "offset": 14_990})).await;
+    assert_eq!(
+        out.output,
+        crate::tool::render_lines(
+            &crlf,
+            Some(crate::tool::LineRange {
+                offset: 14_990,
+                limit: None
+            })
+        )
+        .expect("render")
+    );
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn ranged_reads_canonicalize_with_their_lines() {
+    let registry = ToolRegistry::with_cwd("/tmp/project");
+    let target = registry
+        .canonicalize("read", &json!({ "path": "a.rs", "offset": 10, "limit": 5 }))
+        .expect("canonicalize");
+    let lines = crate::tool::LineRange {
+        offset: 10,
+        limit: Some(5),
+    };
+    assert_eq!(
+        target,
+        crate::tool::CanonicalTarget::Path {
+            path: "/tmp/project/a.rs".into(),
+            lines: Some(lines),
+        }
+    );
+    assert_eq!(lines.to_string(), "10-14");
+    // Model-supplied bounds near the integer limit saturate.
+    let huge = crate::tool::LineRange {
+        offset: u64::MAX - 1,
+        limit: Some(u64::MAX),
+    };
+    assert_eq!(huge.to_string(), format!("{}-{}", u64::MAX - 1, u64::MAX));
+    assert_eq!(
+        crate::tool::target_from_arguments("read", &json!({ "path": "src/a.rs", "offset": 3 })),
+        Some("a.rs:3-".to_owned())
+    );
+    assert!(
+        registry
+            .canonicalize("read", &json!({ "path": "a.rs", "offset": 0 }))
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn edit_requires_unique_matches_and_applies_batches_atomically() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("a.txt");
+    std::fs::write(&file, "let a = 1;\nlet b = 1;\n").expect("seed");
+    let registry = ToolRegistry::with_cwd(dir.path());
+    let edit = async |arguments: serde_json::Value| {
+        registry
+            .execute("edit", &arguments, CancellationToken::new())
+            .await
+    };
+
+    let out = edit(json!({"path": "a.txt", "old_str": "= 1", "new_str": "= 2"})).await;
+    assert!(out.is_error);
+    assert!(out.output.contains("matches 2 times"), "{out:?}");
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read"),
+        "let a = 1;\nlet b = 1;\n"
+    );
+
+    let out = edit(json!({
+        "path": "a.txt", "old_str": "= 1", "new_str": "= 2", "replace_all": true
+    }))
+    .await;
+    assert!(!out.is_error, "{out:?}");
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read"),
+        "let a = 2;\nlet b = 2;\n"
+    );
+
+    // Later edits see earlier ones; any failure leaves the file as it was.
+    let out = edit(json!({"path": "a.txt", "edits": [
+        { "old_str": "let a", "new_str": "let x" },
+        { "old_str": "let x = 2", "new_str": "let x = 3" },
+    ]}))
+    .await;
+    assert!(!out.is_error, "{out:?}");
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read"),
+        "let x = 3;\nlet b = 2;\n"
+    );
+    let out = edit(json!({"path": "a.txt", "edits": [
+        { "old_str": "let b", "new_str": "let y" },
+        { "old_str": "missing", "new_str": "" },
+    ]}))
+    .await;
+    assert!(out.is_error);
+    assert!(
+        out.output.starts_with("edit 2: old_str not found"),
+        "{out:?}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read"),
+        "let x = 3;\nlet b = 2;\n"
+    );
+
+    let out = edit(json!({"path": "a.txt", "old_str": "let b = 2", "new_str": "let b = 2"})).await;
+    assert_eq!(out.output, "edited (no changes)");
+
+    let out = edit(json!({"path": "a.txt", "edits": []})).await;
+    assert!(out.is_error);
+    let out = edit(json!({"path": "a.txt", "old_str": "", "new_str": "x"})).await;
+    assert!(out.is_error);
+
+    // Leaving out new_str is an error, not a deletion.
+    let out = edit(json!({"path": "a.txt", "old_str": "let b = 2;"})).await;
+    assert!(out.is_error);
+    assert!(out.output.contains("new_str"), "{out:?}");
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read"),
+        "let x = 3;\nlet b = 2;\n"
+    );
+
+    // Overlapping matches are ambiguous too.
+    std::fs::write(&file, "aaa\n").expect("seed");
+    let out = edit(json!({"path": "a.txt", "old_str": "aa", "new_str": "b"})).await;
+    assert!(out.output.contains("matches 2 times"), "{out:?}");
+    assert_eq!(std::fs::read_to_string(&file).expect("read"), "aaa\n");
+}
+
+#[tokio::test]
+async fn bash_runs_command_and_reports_nonzero_exit() {
+    let registry = ToolRegistry::default();
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let out = registry
+        .execute("bash", &json!({"command":"echo hi"}), cancel.clone())
+        .await;
+    assert!(!out.is_error, "bash failed: {out:?}");
+    assert!(out.output.contains("hi"), "got: {out:?}");
+
+    let out = registry
+        .execute("bash", &json!({"command":"exit 3"}), cancel.clone())
+        .await;
+    assert!(out.is_error, "nonzero exit should error");
+    assert!(out.output.contains("3"), "got: {out:?}");
+}
+
+#[tokio::test]
+async fn bash_cancel_kills_long_running_command() {
+    let registry = ToolRegistry::default();
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let cancel_clone = cancel.clone();
+    let handle = tokio::spawn(async move {
+        registry
+            .execute("bash", &json!({"command":"sleep 30"}), cancel_clone)
+            .await
+    });
+    sleep(STEP).await;
+    cancel.cancel();
+    let outcome = timeout(Duration::from_secs(3), handle)
+        .await
+        .expect("tool should be killed on cancel")
+        .expect("task join");
+    assert!(outcome.is_error);
+    assert_eq!(outcome.output, "cancelled");
+}
+
+#[tokio::test]
+async fn bash_returns_when_the_command_exits_though_a_background_job_holds_its_output() {
+    let registry = ToolRegistry::default();
+    let outcome = timeout(
+        Duration::from_secs(2),
+        registry.execute(
+            "bash",
+            &json!({"command": "sleep 3 & echo started", "timeout_secs": 10}),
+            CancellationToken::new(),
+        ),
+    )
+    .await
+    .expect("returns once the command exits");
+    assert!(!outcome.is_error, "{outcome:?}");
+    assert_eq!(outcome.output, "started\n");
+}
+
+#[tokio::test]
+async fn bash_times_out_and_keeps_the_head_and_tail_of_huge_output() {
+    let registry = ToolRegistry::default().with_bash_limits(crate::BashLimits {
+        default_timeout: Duration::from_secs(30),
+        max_timeout: Duration::from_secs(1),
+    });
+    let outcome = timeout(
+        Duration::from_secs(5),
+        registry.execute(
+            "bash",
+            &json!({"command": "echo started; sleep 30", "timeout_secs": 60}),
+            CancellationToken::new(),
+        ),
+    )
+    .await
+    .expect("the maximum timeout applies");
+    assert!(outcome.is_error);
+    assert_eq!(
+        outcome.output,
+        "command timed out after 1s and was killed\nstarted\n"
+    );
+
+    let chunks = Arc::new(Mutex::new(String::new()));
+    let seen = Arc::clone(&chunks);
+    let progress = crate::ToolProgress::new(move |chunk| seen.lock().unwrap().push_str(&chunk));
+    let outcome = registry
+        .execute_with_progress(
+            "bash",
+            &json!({"command": "echo first; seq 1 100000"}),
+            CancellationToken::new(),
+            progress,
+        )
+        .await;
+    assert!(!outcome.is_error, "{outcome:?}");
+    assert!(outcome.output.starts_with("first\n1\n2\n"), "{outcome:?}");
+    assert!(outcome.output.ends_with("99999\n100000\n"), "{outcome:?}");
+    assert!(outcome.output.len() < 20 * 1024);
+    // The model sees an excerpt; the whole output is an artifact.
+    let artifact = outcome.artifact.expect("oversized output is saved");
+    assert!(
+        outcome
+            .output
+            .contains(&format!("is artifact {}", artifact.id))
+    );
+    assert!(artifact.text.contains("\n50000\n"));
+    // Frontends saw every byte as it streamed.
+    let streamed = chunks.lock().unwrap().clone();
+    assert!(streamed.starts_with("first\n") && streamed.contains("\n50000\n"));
+
+    // Past the memory bound the middle of the stream is dropped.
+    let outcome = registry
+        .execute(
+            "bash",
+            &json!({"command": "seq 1 2000000"}),
+            CancellationToken::new(),
+        )
+        .await;
+    let artifact = outcome.artifact.expect("oversized output is saved");
+    assert!(artifact.text.contains(" bytes of output omitted …\n"));
+    assert!(artifact.text.ends_with("1999999\n2000000\n"));
+
+    // Stdout and stderr interleave in the order they were written.
+    let outcome = registry
+        .execute(
+            "bash",
+            &json!({"command": "echo out; sleep 0.2; echo err >&2; sleep 0.2; echo done"}),
+            CancellationToken::new(),
+        )
+        .await;
+    assert_eq!(outcome.output, "out\nerr\ndone\n");
+}
+
+#[tokio::test]
+async fn persistent_shell_keeps_state_and_restarts_where_it_left_off() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let root = std::fs::canonicalize(dir.path()).expect("canonical root");
+    std::fs::create_dir(root.join("sub")).expect("mkdir");
+    let registry = ToolRegistry::with_cwd(&root)
+        .with_bash_limits(crate::BashLimits {
+            default_timeout: Duration::from_secs(1),
+            max_timeout: Duration::from_secs(1),
+        })
+        .with_persistent_shell();
+    let bash = |command: &str| {
+        let registry = registry.clone();
+        let arguments = json!({ "command": command });
+        async move {
+            registry
+                .execute("bash", &arguments, CancellationToken::new())
+                .await
+        }
+    };
+
+    let outcome = bash("cd sub && export GREETING=hi").await;
+    assert!(!outcome.is_error, "{outcome:?}");
+    let outcome = bash("echo \"$GREETING from $(basename \"$PWD\")\"").await;
+    assert_eq!(outcome.output, "hi from sub\n");
+    // Policy sees where the next command will run.
+    let target = registry
+        .canonicalize("bash", &json!({ "command": "ls" }))
+        .expect("canonicalize");
+    assert_eq!(
+        target,
+        crate::tool::CanonicalTarget::Command {
+            command: "ls".into(),
+            cwd: root.join("sub"),
+            sandbox: None,
+        }
+    );
+
+    let outcome = bash("false").await;
+    assert!(outcome.is_error);
+    assert_eq!(outcome.output, "command exited with code 1\n");
+
+    // A timeout kills the command, not the shell: its state survives.
+    let outcome = timeout(Duration::from_secs(5), bash("echo slow; sleep 30"))
+        .await
+        .expect("timeout kills the command");
+    assert!(outcome.is_error);
+    assert!(
+        outcome
+            .output
+            .starts_with("command timed out after 1s and was killed\nslow\n"),
+        "{outcome:?}"
+    );
+    let sub = root.join("sub").display().to_string();
+    let outcome = bash("echo \"[$GREETING]\"; pwd").await;
+    assert_eq!(outcome.output, format!("[hi]\n{sub}\n"));
+
+    // A builtin loop has no job to kill: the shell goes, and the next
+    // one starts in the same directory with a fresh environment.
+    let outcome = timeout(Duration::from_secs(10), bash("while :; do :; done"))
+        .await
+        .expect("timeout kills the shell");
+    assert!(
+        outcome.output.starts_with(&format!(
+            "command timed out after 1s and was killed with its shell; the next command \
+             starts a new shell in {sub}"
+        )),
+        "{outcome:?}"
+    );
+    let outcome = bash("echo \"[$GREETING]\"; pwd").await;
+    assert_eq!(outcome.output, format!("[]\n{sub}\n"));
+
+    // `exit` ends the shell and is reported; the next call restarts it.
+    let outcome = bash("cd ..; exit 3").await;
+    assert!(outcome.is_error);
+    assert!(
+        outcome.output.starts_with("the shell exited with code 3;"),
+        "{outcome:?}"
+    );
+    let outcome = bash("echo back").await;
+    assert_eq!(outcome.output, "back\n");
+}
+
+#[tokio::test]
+async fn a_cancelled_command_leaves_the_persistent_shell_state() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let root = std::fs::canonicalize(dir.path()).expect("canonical root");
+    std::fs::create_dir(root.join("sub")).expect("mkdir");
+    let registry = ToolRegistry::with_cwd(&root).with_persistent_shell();
+    let outcome = registry
+        .execute(
+            "bash",
+            &json!({ "command": "cd sub; export KEPT=yes; greet() { echo hello; }" }),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(!outcome.is_error, "{outcome:?}");
+
+    let cancel = CancellationToken::new();
+    let arguments = json!({ "command": "sleep 30" });
+    let running = registry.execute("bash", &arguments, cancel.clone());
+    let canceller = async {
+        sleep(Duration::from_millis(300)).await;
+        cancel.cancel();
+    };
+    let (outcome, ()) = timeout(Duration::from_secs(5), async {
+        tokio::join!(running, canceller)
+    })
+    .await
+    .expect("cancellation ends the command");
+    assert_eq!(outcome.output, "cancelled");
+
+    let outcome = registry
+        .execute(
+            "bash",
+            &json!({ "command": "greet; echo \"$KEPT\"; basename \"$PWD\"" }),
+            CancellationToken::new(),
+        )
+        .await;
+    assert_eq!(outcome.output, "hello\nyes\nsub\n", "{outcome:?}");
+}
+
+// Needs Landlock and unprivileged user namespaces, as the sandbox does.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+#[tokio::test]
+async fn sandboxed_bash_writes_only_the_workspace_and_has_no_network() {
+    let workspace = tempfile::tempdir().expect("workspace");
+    let outside = tempfile::tempdir().expect("outside");
+    std::fs::write(outside.path().join("notes"), "readable").expect("write");
+    let registry = ToolRegistry::with_cwd(workspace.path()).with_sandbox(crate::Sandbox::default());
+    let bash = |registry: &ToolRegistry, command: String| {
+        let registry = registry.clone();
+        async move {
+            registry
+                .execute(
+                    "bash",
+                    &json!({ "command": command }),
+                    CancellationToken::new(),
+                )
+                .await
+        }
+    };
+
+    let outcome = bash(&registry, "echo kept > inside && cat inside".to_owned()).await;
+    assert_eq!(outcome.output, "kept\n", "{outcome:?}");
+    // The repository's own metadata is not part of the workspace: no
+    // config or hook the git tools would run can be planted.
+    std::fs::create_dir_all(workspace.path().join(".git/hooks")).expect("git dir");
+    std::fs::write(workspace.path().join(".git/config"), "").expect("config");
+    for command in [
+        "echo '[filter \"x\"] clean = touch ran' >> .git/config",
+        "echo true > .git/hooks/pre-commit",
+        "mv .git moved",
+        "umount .git",
+    ] {
+        let outcome = bash(&registry, command.to_owned()).await;
+        assert!(outcome.is_error, "{command}: {outcome:?}");
+    }
+    assert_eq!(
+        std::fs::read_to_string(workspace.path().join(".git/config")).expect("config"),
+        ""
+    );
+    assert!(!workspace.path().join(".git/hooks/pre-commit").exists());
+    let outcome = bash(
+        &registry,
+        "mkdir -p target && echo built > target/out".to_owned(),
+    )
+    .await;
+    assert!(!outcome.is_error, "{outcome:?}");
+    let outside_path = outside.path().display();
+    let outcome = bash(&registry, format!("cat {outside_path}/notes")).await;
+    assert_eq!(outcome.output, "readable");
+    let outcome = bash(&registry, format!("touch {outside_path}/escaped")).await;
+    assert!(outcome.is_error, "{outcome:?}");
+    assert!(!outside.path().join("escaped").exists());
+    // Only the loopback interface exists, and ownership is unchanged.
+    let outcome = bash(
+        &registry,
+        "grep -c : /proc/net/dev; echo > /dev/null; id -u".to_owned(),
+    )
+    .await;
+    #[allow(unsafe_code)] // getuid cannot fail
+    let uid = unsafe { libc::getuid() };
+    assert_eq!(outcome.output, format!("1\n{uid}\n"));
+    // The seccomp filter is in place, and where the kernel scopes
+    // signals the user's other processes are out of reach.
+    let outcome = bash(&registry, "grep '^Seccomp:' /proc/self/status".to_owned()).await;
+    assert_eq!(outcome.output, "Seccomp:\t2\n");
+    let mut neighbour = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .expect("spawn");
+    let outcome = bash(&registry, format!("kill {}", neighbour.id())).await;
+    assert_eq!(outcome.is_error, crate::sandbox::isolates_processes());
+    let _ = neighbour.kill();
+    let _ = neighbour.wait();
+
+    // A persistent shell restarts inside the same confinement.
+    let shell = registry.clone().with_persistent_shell();
+    let outcome = bash(&shell, format!("cd {outside_path} && touch escaped")).await;
+    assert!(outcome.is_error, "{outcome:?}");
+    assert!(!outside.path().join("escaped").exists());
+
+    // The sandbox is part of what the policy sees.
+    let target = registry
+        .canonicalize("bash", &json!({ "command": "make test" }))
+        .expect("canonicalize");
+    let expected = if crate::sandbox::isolates_processes() {
+        crate::PolicyDecision::Allow
+    } else {
+        crate::PolicyDecision::ApprovalRequired
+    };
+    assert_eq!(crate::DefaultPolicy.decide("bash", &target), expected);
+    let networked = ToolRegistry::with_cwd(workspace.path()).with_sandbox(crate::Sandbox {
+        network: true,
+        ..crate::Sandbox::default()
+    });
+    // With the network kept, the command still gets a user namespace
+    // of its own for the process limit to count in.
+    let outcome = bash(&networked, "cat /proc/self/uid_map".to_owned()).await;
+    assert_eq!(
+        outcome.output.split_whitespace().collect::<Vec<_>>(),
+        [uid.to_string(), uid.to_string(), "1".to_owned()]
+    );
+    let target = networked
+        .canonicalize("bash", &json!({ "command": "make test" }))
+        .expect("canonicalize");
+    assert_eq!(
+        crate::DefaultPolicy.decide("bash", &target),
+        crate::PolicyDecision::ApprovalRequired
+    );
+}
+
+#[tokio::test]
+async fn background_processes_stream_into_a_ring_and_stop_with_their_children() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let registry = ToolRegistry::with_cwd(dir.path());
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        let registry = registry.clone();
+        async move {
+            registry
+                .execute(name, &arguments, CancellationToken::new())
+                .await
+        }
+    };
+
+    let outcome = call(
+        "process_start",
+        json!({"command": "echo up; echo oops >&2; sleep 30 & wait"}),
+    )
+    .await;
+    assert_eq!(
+        outcome.output,
+        "started process 1: echo up; echo oops >&2; sleep 30 & wait"
+    );
+    sleep(STEP).await;
+    let outcome = call("process_output", json!({"id": 1})).await;
+    assert_eq!(
+        outcome.output,
+        "process 1 running: echo up; echo oops >&2; sleep 30 & wait\nup\noops\n"
+    );
+    // Each read returns only what is new.
+    let outcome = call("process_output", json!({"id": 1})).await;
+    assert!(outcome.output.ends_with("\n(no new output)"), "{outcome:?}");
+
+    // A chatty process keeps only the tail, and says how much it lost.
+    call("process_start", json!({"command": "seq 1 100000"})).await;
+    let outcome = call("process_output", json!({"id": 2, "wait_secs": 5})).await;
+    assert!(
+        outcome
+            .output
+            .starts_with("process 2 exited with code 0: seq 1 100000\n… "),
+        "{outcome:?}"
+    );
+    assert!(
+        outcome
+            .output
+            .contains(" bytes dropped; only the last 64 KiB are kept …\n")
+    );
+    assert!(outcome.output.ends_with("99999\n100000\n"));
+
+    let outcome = call("process_output", json!({})).await;
+    assert_eq!(
+        outcome.output,
+        "1 running: echo up; echo oops >&2; sleep 30 & wait\n2 exited with code 0: seq 1 100000"
+    );
+
+    let outcome = timeout(
+        Duration::from_secs(3),
+        call("process_stop", json!({"id": 1})),
+    )
+    .await
+    .expect("stop kills the whole group, the backgrounded sleep included");
+    assert_eq!(
+        outcome.output,
+        "process 1 killed: echo up; echo oops >&2; sleep 30 & wait\n(no new output)"
+    );
+    let outcome = call("process_output", json!({"id": 1})).await;
+    assert!(outcome.is_error);
+    assert_eq!(outcome.output, "no background process 1");
+
+    // Reading and stopping carry no approval of their own; starting is
+    // a command like any other.
+    assert_eq!(
+        registry.canonicalize("process_stop", &json!({"id": 2})),
+        Ok(crate::tool::CanonicalTarget::Process { id: Some(2) })
+    );
+    assert_eq!(
+        crate::DefaultPolicy.decide(
+            "process_start",
+            &registry
+                .canonicalize("process_start", &json!({"command": "make serve"}))
+                .expect("canonicalize"),
+        ),
+        crate::PolicyDecision::ApprovalRequired
+    );
+}
+
+#[tokio::test]
+async fn background_processes_die_with_their_operation_or_session() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let marker = dir.path().join("alive");
+    let command = format!(
+        "while true; do touch {}; sleep 0.05; done",
+        marker.display()
+    );
+    let alive = || async {
+        let _ = std::fs::remove_file(&marker);
+        sleep(Duration::from_millis(300)).await;
+        marker.exists()
+    };
+
+    // Cancelling the operation that started it kills it.
+    let registry = crate::ToolCatalog::with_cwd(dir.path()).fork();
+    let cancel = CancellationToken::new();
+    registry
+        .snapshot()
+        .execute(
+            "process_start",
+            &json!({"command": command}),
+            cancel.child_token(),
+        )
+        .await;
+    assert!(alive().await);
+    cancel.cancel();
+    sleep(STEP).await;
+    assert!(!alive().await);
+
+    // A forked session has its own table, and closing it kills what
+    // it started.
+    let session = registry.fork();
+    session
+        .snapshot()
+        .execute(
+            "process_start",
+            &json!({"command": command}),
+            CancellationToken::new(),
+        )
+        .await;
+    let listed = registry
+        .snapshot()
+        .execute("process_output", &json!({}), CancellationToken::new())
+        .await;
+    assert_eq!(listed.output, format!("1 killed: {command}"));
+    assert!(alive().await);
+    drop(session);
+    assert!(!alive().await);
+}
+
+#[tokio::test]
+async fn attachments_inline_files_and_leave_other_tokens_literal() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::create_dir_all(dir.path().join("logs")).expect("mkdir");
+    std::fs::write(dir.path().join("logs/build.log"), "error: boom").expect("write");
+
+    let prompt = "why did @logs/build.log, fail? see @@ -1 +1 @@ and @nobody";
+    let expanded = crate::tool::inline_attachments(dir.path(), prompt)
+        .await
+        .expect("expand");
+    assert!(expanded.starts_with(prompt), "prompt text kept: {expanded}");
+    assert!(
+        expanded.ends_with("<attachment path=\"logs/build.log\">\nerror: boom\n</attachment>"),
+        "{expanded}"
+    );
+    assert_eq!(expanded.matches("<attachment").count(), 1);
+
+    let plain = crate::tool::inline_attachments(dir.path(), "@@ hunk @@\n")
+        .await
+        .expect("plain");
+    assert_eq!(plain, "@@ hunk @@\n");
+}
+
+#[tokio::test]
+async fn attachments_follow_read_path_rules() {
+    let outer = tempfile::tempdir().expect("tempdir");
+    let root = outer.path().join("project");
+    std::fs::create_dir_all(&root).expect("mkdir");
+    std::fs::write(outer.path().join("secret.txt"), "nope").expect("write");
+
+    // Paths outside the project are never probed: they stay text.
+    let absolute = outer.path().join("secret.txt");
+    for prompt in [
+        "read @../secret.txt".to_owned(),
+        format!("read @{}", absolute.display()),
+    ] {
+        let expanded = crate::tool::inline_attachments(&root, &prompt)
+            .await
+            .expect("left literal");
+        assert_eq!(expanded, prompt);
+    }
+
+    // The path attribute cannot close itself or open markup.
+    std::fs::write(root.join("a\"b<&.txt"), "odd").expect("write");
+    let expanded = crate::tool::inline_attachments(&root, "@a\"b<&.txt")
+        .await
+        .expect("expand");
+    assert!(
+        expanded.ends_with("<attachment path=\"a&quot;b&lt;&amp;.txt\">\nodd\n</attachment>"),
+        "{expanded}"
+    );
+}
+
+// ---- Operation-level integration tests ----
+
+#[tokio::test]
+async fn tool_loop_success_admits_tools_and_finishes() {
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::tool("bash", json!({"command":"echo tool-said-hello"})),
+        ScriptedMessage::text("final answer\n"),
+    ]);
+    let runtime = start_runtime(provider, ToolRegistry::default());
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    let operation_id = session.submit("go").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+
+    assert!(matches!(
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { operation_id: id, .. }) if *id == operation_id
+    ));
+    // Tool execution is a live event; tool output is a semantic entry,
+    // never an assistant text delta (DESIGN.md §16.4).
+    assert!(recorded.iter().any(|event| matches!(
+        event,
+        RuntimeEvent::ToolStarted { tool, .. } if tool == "bash"
+    )));
+    // Live output streams between start and settlement.
+    let output = recorded
+        .iter()
+        .position(|event| {
+            matches!(
+                event,
+                RuntimeEvent::ToolOutput { chunk, .. } if chunk == "tool-said-hello\n"
+            )
+        })
+        .expect("live output");
+    let settled = recorded
+        .iter()
+        .position(|event| matches!(event, RuntimeEvent::ToolSettled { .. }))
+        .expect("settled");
+    assert!(output < settled, "{recorded:?}");
+    assert_eq!(texts(&recorded), vec!["final answer\n".to_owned()]);
+    assert!(recorded.iter().all(|e| !matches!(
+        e,
+        RuntimeEvent::OperationFailed { .. } | RuntimeEvent::OperationCancelled { .. }
+    )));
+    let snapshot = session.snapshot().await.expect("snapshot");
+    assert!(snapshot.entries.iter().any(|entry| matches!(
+        entry,
+        SessionEntry::ToolResult {
+            result: ToolResult::Ok { output, .. },
+        } if output.contains("tool-said-hello")
+    )));
+
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
 }
 
 #[tokio::test]
@@ -1405,21 +2813,24 @@ async fn steer_projection_reaches_the_next_model_step() {
     assert_eq!(
         requests[0].plan.messages,
         vec![ContextMessage::User {
-            content: "goal".to_owned()
+            content: "goal".to_owned(),
+            images: Vec::new(),
         }]
     );
     assert_eq!(
         requests[1].plan.messages,
         vec![
             ContextMessage::User {
-                content: "goal".to_owned()
+                content: "goal".to_owned(),
+                images: Vec::new(),
             },
             ContextMessage::Assistant {
                 content: "working".to_owned(),
                 tool_calls: Vec::new(),
             },
             ContextMessage::User {
-                content: "and also check tests".to_owned()
+                content: "and also check tests".to_owned(),
+                images: Vec::new(),
             },
         ],
         "the steer must be projected into the next step's plan"
@@ -1476,53 +2887,199 @@ fn entry_kinds(entries: &[(u64, crate::SessionEntry)]) -> Vec<&'static str> {
             crate::SessionEntry::ToolCall { .. } => "tool_call",
             crate::SessionEntry::ToolResult { .. } => "tool_result",
             crate::SessionEntry::Compaction { .. } => "compaction",
+            crate::SessionEntry::Undone { .. } => "undone",
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn restart_reproduces_the_logical_transcript() {
+    let db = temp_db("restart");
+    let store = SessionStore::open(&db).expect("open store");
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::tool("bash", json!({"command":"echo persisted"})),
+        ScriptedMessage::text("final\n"),
+    ]);
+    let runtime = start_runtime_with_store(provider, ToolRegistry::default(), store);
+    let session_id = runtime.session_id();
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("read it").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    assert!(matches!(
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { .. })
+    ));
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+    drop(session);
+
+    // Reopen the same database: the logical transcript must reproduce.
+    let store = SessionStore::open(&db).expect("reopen store");
+    let loaded = store.load(session_id).await.expect("load");
+    assert_eq!(
+        entry_kinds(&loaded.entries),
+        [
+            "user_message",
+            "assistant_message",
+            "tool_call",
+            "tool_result",
+            "assistant_message",
+        ]
+    );
+    assert_eq!(loaded.operations.len(), 1);
+    let (_, checkpoint) = &loaded.operations[0].latest;
+    assert_eq!(
+        checkpoint.state,
+        OperationState::Finished(OperationOutcome::Completed)
+    );
+    assert!(!checkpoint.cancel_requested);
+    assert!(loaded.pending_inbox.is_empty());
+    let _ = std::fs::remove_dir_all(db.parent().expect("temp parent"));
+}
+
+#[tokio::test]
+async fn image_bytes_persist_once_in_the_blob_table() {
+    let db = temp_db("blobs");
+    let workspace = db.parent().expect("temp parent").join("workspace");
+    std::fs::create_dir_all(&workspace).expect("workspace");
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    std::fs::write(workspace.join("logo.png"), png).expect("write");
+    let store = SessionStore::open(&db).expect("open store");
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::tool("read", json!({"path": "logo.png"})),
+        ScriptedMessage::tool("read", json!({"path": "logo.png"})),
+        ScriptedMessage::text("a tiny png\n"),
+    ]);
+    let runtime = start_runtime_with_store(provider, ToolRegistry::with_cwd(&workspace), store);
+    let session_id = runtime.session_id();
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("look twice").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+    drop(session);
+
+    // Entry payloads name the image by hash; its bytes are stored once.
+    let connection = rusqlite::Connection::open(&db).expect("raw open");
+    let blobs: i64 = connection
+        .query_row("SELECT COUNT(*) FROM blobs", [], |row| row.get(0))
+        .expect("count blobs");
+    assert_eq!(blobs, 1);
+    let payload: String = connection
+        .query_row(
+            "SELECT payload FROM entries WHERE kind = 'tool_result' LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .expect("tool result payload");
+    assert!(payload.contains("\"sha256\""), "{payload}");
+    assert!(!payload.contains("\"data\""), "{payload}");
+    drop(connection);
+
+    let store = SessionStore::open(&db).expect("reopen store");
+    let loaded = store.load(session_id).await.expect("load");
+    let images: Vec<&crate::Image> = loaded
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            crate::SessionEntry::ToolResult {
+                result: ToolResult::Ok { images, .. },
+            } => Some(images),
+            _ => None,
         })
-        .collect()
+        .flatten()
+        .collect();
+    assert_eq!(images.len(), 2);
+    assert!(images.iter().all(|image| &image.data[..] == png));
+    let _ = std::fs::remove_dir_all(db.parent().expect("temp parent"));
+}
+
+#[test]
+fn short_artifacts_are_their_own_excerpt() {
+    for text in ["", "one line\n", &"x".repeat(16 * 1024)] {
+        assert_eq!(crate::Artifact::new(text).excerpt(), text);
+    }
+    let long = "é".repeat(8 * 1024 + 1);
+    let excerpt = crate::Artifact::new(long.as_str()).excerpt();
+    assert!(excerpt.contains("bytes omitted"), "{}", excerpt.len());
 }
 
 #[tokio::test]
-async fn restart_reproduces_the_logical_transcript() {
-    let db = temp_db("restart");
+async fn oversized_outputs_become_artifacts_the_model_can_page() {
+    let db = temp_db("artifacts");
+    let workspace = db.parent().expect("temp parent").join("workspace");
+    std::fs::create_dir_all(&workspace).expect("workspace");
+    let text: String = (1..=30_000).map(|n| format!("{n}\n")).collect();
+    std::fs::write(workspace.join("big.txt"), &text).expect("write");
+    let id = crate::Artifact::new(text.as_str()).id;
     let store = SessionStore::open(&db).expect("open store");
     let provider = ScriptedProvider::new(vec![
-        ScriptedMessage::tool("bash", json!({"command":"echo persisted"})),
-        ScriptedMessage::text("final\n"),
+        ScriptedMessage::tool("bash", json!({"command": "cat big.txt"})),
+        ScriptedMessage::tool(
+            "read_artifact",
+            json!({"id": id, "offset": 29_999, "limit": 5}),
+        ),
+        ScriptedMessage::text("done\n"),
     ]);
-    let runtime = start_runtime_with_store(provider, ToolRegistry::default(), store);
+    let runtime = start_runtime_with_store(provider, ToolRegistry::with_cwd(&workspace), store);
     let session_id = runtime.session_id();
     let session = runtime.session();
     let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
-    session.submit("read it").await.expect("submit");
-    let recorded = collect_until_terminal(&mut events).await.expect("collect");
-    assert!(matches!(
-        recorded.last(),
-        Some(RuntimeEvent::OperationFinished { .. })
-    ));
+    session.submit("page it").await.expect("submit");
+    let events = collect_until_terminal(&mut events).await.expect("collect");
+    assert!(events.iter().any(|event| matches!(
+        event,
+        RuntimeEvent::ToolSettled { artifact: Some(saved), .. } if *saved == id
+    )));
+    // Frontends fetch the full text through the session.
+    let full = session.artifact(&id).await.expect("artifact");
+    assert_eq!(full.as_deref(), Some(text.as_str()));
+    assert_eq!(session.artifact("feed").await.expect("artifact"), None);
     session.close().await.expect("close");
     runtime.join().await.expect("join");
     drop(session);
 
-    // Reopen the same database: the logical transcript must reproduce.
+    // The entry keeps the excerpt; the blob table keeps the text.
+    let connection = rusqlite::Connection::open(&db).expect("raw open");
+    let payload: String = connection
+        .query_row(
+            "SELECT payload FROM entries WHERE kind = 'tool_result' ORDER BY seq LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .expect("tool result payload");
+    assert!(payload.len() < 20 * 1024, "{}", payload.len());
+    let stored: Vec<u8> = connection
+        .query_row("SELECT data FROM blobs WHERE sha256 = ?1", [&id], |row| {
+            row.get(0)
+        })
+        .expect("artifact blob");
+    assert_eq!(stored, text.as_bytes());
+    drop(connection);
+
     let store = SessionStore::open(&db).expect("reopen store");
     let loaded = store.load(session_id).await.expect("load");
-    assert_eq!(
-        entry_kinds(&loaded.entries),
-        [
-            "user_message",
-            "assistant_message",
-            "tool_call",
-            "tool_result",
-            "assistant_message",
-        ]
-    );
-    assert_eq!(loaded.operations.len(), 1);
-    let (_, checkpoint) = &loaded.operations[0].latest;
-    assert_eq!(
-        checkpoint.state,
-        OperationState::Finished(OperationOutcome::Completed)
-    );
-    assert!(!checkpoint.cancel_requested);
-    assert!(loaded.pending_inbox.is_empty());
+    let results: Vec<&ToolResult> = loaded
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            crate::SessionEntry::ToolResult { result } => Some(result),
+            _ => None,
+        })
+        .collect();
+    let artifact = results[0].artifact().expect("artifact survives reload");
+    assert_eq!(&*artifact.text, text.as_str());
+    assert!(
+        results[0]
+            .clone()
+            .into_text()
+            .contains(&format!("is artifact {id}"))
+    );
+    let page = results[1].clone().into_text();
+    assert!(page.contains("29999") && page.contains("30000"), "{page}");
+    assert!(results[1].artifact().is_none());
     let _ = std::fs::remove_dir_all(db.parent().expect("temp parent"));
 }
 
@@ -1683,6 +3240,18 @@ fn recovery_classes_match_the_design() {
     assert_eq!(registry.recovery_class("write"), RecoveryClass::Reconcile);
     assert_eq!(registry.recovery_class("edit"), RecoveryClass::Reconcile);
     assert_eq!(registry.recovery_class("bash"), RecoveryClass::NeverReplay);
+    assert_eq!(
+        registry.recovery_class("process_start"),
+        RecoveryClass::NeverReplay
+    );
+    assert_eq!(
+        registry.recovery_class("process_output"),
+        RecoveryClass::ReplaySafe
+    );
+    assert_eq!(
+        registry.recovery_class("process_stop"),
+        RecoveryClass::NeverReplay
+    );
     assert_eq!(
         registry.recovery_class("unknown-tool"),
         RecoveryClass::NeverReplay
@@ -1895,6 +3464,7 @@ async fn settlement_must_match_a_pending_effect_of_the_operation() {
                     prompt: String::new(),
                     tools: Vec::new(),
                     open_effect: None,
+                    open_batch: Vec::new(),
                 },
             },
             entries: Vec::new(),
@@ -1907,6 +3477,7 @@ async fn settlement_must_match_a_pending_effect_of_the_operation() {
             inbox: Vec::new(),
             inbox_applied: Vec::new(),
             usage: Vec::new(),
+            snapshots: Vec::new(),
         })
         .await
         .expect_err("ghost settlement must fail");
@@ -1973,88 +3544,568 @@ async fn crash_during_model_step_recovers_by_replay() {
         "the replayed model step must stream: {recorded:?}"
     );
     assert!(matches!(
-        recorded.last(),
-        Some(RuntimeEvent::OperationFinished { .. })
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { .. })
+    ));
+
+    let loaded = store.load(session_id).await.expect("load");
+    let (_, checkpoint) = &loaded.operations[0].latest;
+    assert_eq!(
+        checkpoint.state,
+        OperationState::Finished(OperationOutcome::Completed)
+    );
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+}
+
+#[tokio::test]
+async fn crash_during_replayable_tool_recovers_by_reexecution() {
+    let dir = std::env::temp_dir().join(format!("ion-crash-read-{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    std::fs::write(dir.join("note.txt"), "persisted bytes").expect("write");
+
+    let store = SessionStore::open_in_memory().expect("store");
+    let registry = ToolRegistry::with_cwd(&dir);
+    let runtime = start_runtime_with_store(
+        ScriptedProvider::new(vec![ScriptedMessage::tool(
+            "read",
+            json!({"path":"note.txt"}),
+        )]),
+        registry,
+        store.clone(),
+    );
+    let session_id = runtime.session_id();
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("read it").await.expect("submit");
+    loop {
+        let event = timeout(Duration::from_secs(2), events.recv())
+            .await
+            .expect("event")
+            .expect("recv");
+        if matches!(event, RuntimeEvent::ToolStarted { .. }) {
+            break;
+        }
+    }
+
+    // Process loss while the read effect is in flight.
+    runtime.crash();
+    drop(runtime);
+    drop(session);
+
+    // Reopen: read is ReplaySafe, so it re-executes and the operation
+    // continues into the next model step.
+    let runtime = Runtime::open_session(
+        ScriptedProvider::new(vec![ScriptedMessage::text("after recovery\n")]),
+        ToolRegistry::with_cwd(&dir),
+        store.clone(),
+        session_id,
+    )
+    .await
+    .expect("reopen");
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    assert!(matches!(
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { .. })
+    ));
+
+    let loaded = store.load(session_id).await.expect("load");
+    let (_, checkpoint) = &loaded.operations[0].latest;
+    assert_eq!(
+        checkpoint.state,
+        OperationState::Finished(OperationOutcome::Completed)
+    );
+    assert!(loaded.entries.iter().any(|(_, entry)| matches!(
+        entry,
+        SessionEntry::ToolResult {
+            result: ToolResult::Ok { output, .. },
+        } if output.contains("persisted bytes")
+    )));
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// A resumable tool that records whether each call ran as a retry,
+/// and hangs on a first attempt until cancelled.
+struct RetryProbe {
+    seen: Arc<std::sync::Mutex<Vec<bool>>>,
+}
+
+impl crate::tool::Tool for RetryProbe {
+    fn spec(&self) -> crate::ToolSpec {
+        crate::ToolSpec {
+            name: "probe".to_owned(),
+            description: "records retries".to_owned(),
+            input_schema: json!({ "type": "object" }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: serde_json::Value,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> std::pin::Pin<Box<dyn Future<Output = crate::ToolOutcome> + Send + 'a>> {
+        self.call_with_progress(arguments, cancel, crate::ToolProgress::default())
+    }
+
+    fn call_with_progress<'a>(
+        &'a self,
+        _arguments: serde_json::Value,
+        cancel: tokio_util::sync::CancellationToken,
+        progress: crate::ToolProgress,
+    ) -> std::pin::Pin<Box<dyn Future<Output = crate::ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            self.seen.lock().expect("seen").push(progress.is_retry());
+            if !progress.is_retry() {
+                cancel.cancelled().await;
+            }
+            crate::ToolOutcome::text("probed")
+        })
+    }
+
+    fn recovery_class(&self) -> RecoveryClass {
+        RecoveryClass::Resume
+    }
+
+    fn local_target(&self, _arguments: &serde_json::Value) -> Option<crate::tool::CanonicalTarget> {
+        Some(crate::tool::CanonicalTarget::Paths { paths: Vec::new() })
+    }
+}
+
+#[tokio::test]
+async fn only_a_reexecuted_call_runs_as_a_retry() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let probe = || {
+        let catalog = crate::ToolCatalog::default();
+        catalog.register_scope(
+            "probe",
+            vec![Arc::new(RetryProbe {
+                seen: Arc::clone(&seen),
+            })],
+        );
+        catalog
+    };
+    let store = SessionStore::open_in_memory().expect("store");
+    let runtime = Runtime::start_with_store(
+        ScriptedProvider::new(vec![ScriptedMessage::tool("probe", json!({}))]),
+        probe(),
+        store.clone(),
+    );
+    let session_id = runtime.session_id();
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("probe").await.expect("submit");
+    loop {
+        let event = timeout(Duration::from_secs(2), events.recv())
+            .await
+            .expect("event")
+            .expect("recv");
+        if matches!(event, RuntimeEvent::ToolStarted { .. }) {
+            break;
+        }
+    }
+    runtime.crash();
+    drop(runtime);
+    drop(session);
+
+    let runtime = Runtime::open_session(
+        ScriptedProvider::new(vec![ScriptedMessage::text("after recovery\n")]),
+        probe(),
+        store.clone(),
+        session_id,
+    )
+    .await
+    .expect("reopen");
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    assert!(matches!(
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { .. })
+    ));
+    assert_eq!(*seen.lock().expect("seen"), [false, true]);
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+}
+
+#[tokio::test]
+async fn independent_reads_run_concurrently_and_settle_in_call_order() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(dir.path().join(name), format!("contents of {name}")).expect("seed");
+    }
+    let store = SessionStore::open_in_memory().expect("store");
+    let runtime = start_runtime_with_store(
+        ScriptedProvider::new(vec![
+            ScriptedMessage::tools([
+                ("read", json!({"path": "a.txt"})),
+                ("find", json!({"pattern": "*.txt"})),
+                ("read", json!({"path": "b.txt"})),
+                ("write", json!({"path": "d.txt", "contents": "new"})),
+                ("read", json!({"path": "c.txt"})),
+                ("read", json!({"path": "d.txt"})),
+            ]),
+            ScriptedMessage::text("done\n"),
+        ]),
+        ToolRegistry::with_cwd(dir.path()),
+        store.clone(),
+    );
+    let session_id = runtime.session_id();
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("look around").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    // The three leading reads start together and settle in call order;
+    // the write runs alone, and the reads after it batch again.
+    let tool_events: Vec<String> = recorded
+        .iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::ToolStarted { call_id, .. } => Some(format!("start {call_id}")),
+            RuntimeEvent::ToolSettled { call_id, .. } => Some(format!("settle {call_id}")),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        tool_events,
+        [
+            "start 1", "start 2", "start 3", "settle 1", "settle 2", "settle 3", "start 4",
+            "settle 4", "start 5", "start 6", "settle 5", "settle 6",
+        ]
+    );
+    let loaded = store.load(session_id).await.expect("load");
+    let results: Vec<(u64, String)> = loaded
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult { result } => {
+                Some((result.call_id(), result.clone().into_text()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        results.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        [1, 2, 3, 4, 5, 6]
+    );
+    assert!(results[0].1.contains("contents of a.txt"), "{results:?}");
+    assert!(results[4].1.contains("contents of c.txt"), "{results:?}");
+    // The read after the write sees it: batches never cross a mutation.
+    assert!(results[5].1.contains("new"), "{results:?}");
+    assert!(matches!(
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { .. })
+    ));
+}
+
+#[tokio::test]
+async fn undo_restores_an_operations_files_unless_edited_since() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let notes = dir.path().join("notes.txt");
+    let created = dir.path().join("new/created.txt");
+    std::fs::write(&notes, "one\n").expect("seed");
+    let store = SessionStore::open_in_memory().expect("store");
+    let runtime = start_runtime_with_store(
+        ScriptedProvider::new(vec![
+            ScriptedMessage::tools([
+                (
+                    "edit",
+                    json!({"path": "notes.txt", "old_str": "one", "new_str": "two"}),
+                ),
+                (
+                    "write",
+                    json!({"path": "new/created.txt", "contents": "fresh"}),
+                ),
+                (
+                    "edit",
+                    json!({"path": "notes.txt", "old_str": "two", "new_str": "three"}),
+                ),
+            ]),
+            // A step only ends at a tool call or the end of the script;
+            // failing the next one ends the first operation instead.
+            ScriptedMessage::Fail {
+                message: "end of the first operation".to_owned(),
+            },
+            ScriptedMessage::tool(
+                "edit",
+                json!({"path": "notes.txt", "old_str": "three", "new_str": "four"}),
+            ),
+            ScriptedMessage::text("second\n"),
+        ]),
+        ToolRegistry::with_cwd(dir.path()),
+        store,
+    );
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    let first = session.submit("change things").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("first");
+    let second = session.submit("change more").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("second");
+    assert_eq!(std::fs::read_to_string(&notes).expect("notes"), "four\n");
+
+    // The latest operation goes first.
+    assert_eq!(
+        session.undo(None).await.expect("undo second"),
+        crate::UndoOutcome::Restored {
+            operation_id: second,
+            files: vec![notes.clone()],
+        }
+    );
+    assert_eq!(std::fs::read_to_string(&notes).expect("notes"), "three\n");
+    // The transcript tells the model its edits are gone.
+    let (snapshot, _) = session.subscribe().await.expect("subscribe");
+    assert_eq!(
+        snapshot.entries.last(),
+        Some(&SessionEntry::Undone {
+            operation_id: second,
+            files: vec![notes.clone()],
+        })
+    );
+
+    // A file edited since the operation wrote it blocks the whole undo.
+    std::fs::write(&created, "mine").expect("user edit");
+    let outcome = session.undo(Some(first)).await.expect("undo first");
+    assert_eq!(
+        outcome,
+        crate::UndoOutcome::Conflicted {
+            operation_id: first,
+            files: vec![created.clone()],
+        }
+    );
+    assert!(outcome.to_string().contains("created.txt"), "{outcome}");
+    assert_eq!(std::fs::read_to_string(&notes).expect("notes"), "three\n");
+
+    // Back as the operation left it, the file is restored: the edited
+    // one to its state before the first edit, the created one removed.
+    std::fs::write(&created, "fresh").expect("user revert");
+    assert!(matches!(
+        session.undo(None).await.expect("undo first"),
+        crate::UndoOutcome::Restored { operation_id, files }
+            if operation_id == first && files.len() == 2
     ));
-
-    let loaded = store.load(session_id).await.expect("load");
-    let (_, checkpoint) = &loaded.operations[0].latest;
+    assert_eq!(std::fs::read_to_string(&notes).expect("notes"), "one\n");
+    assert!(!created.exists());
     assert_eq!(
-        checkpoint.state,
-        OperationState::Finished(OperationOutcome::Completed)
+        session.undo(None).await.expect("undo again"),
+        crate::UndoOutcome::Nothing
     );
     session.close().await.expect("close");
     runtime.join().await.expect("join");
 }
 
 #[tokio::test]
-async fn crash_during_replayable_tool_recovers_by_reexecution() {
-    let dir = std::env::temp_dir().join(format!("ion-crash-read-{}", std::process::id()));
-    let _ = std::fs::create_dir_all(&dir);
-    std::fs::write(dir.join("note.txt"), "persisted bytes").expect("write");
-
+async fn undo_without_the_runtime_waits_for_the_open_operation() {
+    let dir = tempfile::tempdir().expect("tempdir");
     let store = SessionStore::open_in_memory().expect("store");
-    let registry = ToolRegistry::with_cwd(&dir);
     let runtime = start_runtime_with_store(
-        ScriptedProvider::new(vec![ScriptedMessage::tool(
-            "read",
-            json!({"path":"note.txt"}),
-        )]),
-        registry,
+        ScriptedProvider::new(vec![
+            // Writing to a directory cannot be snapshotted for undo, so
+            // the call is denied before anything runs.
+            ScriptedMessage::tool("write", json!({"path": "sub", "contents": "x"})),
+            ScriptedMessage::delayed(Duration::from_secs(30), "slow"),
+        ]),
+        ToolRegistry::with_cwd(dir.path()),
         store.clone(),
     );
+    std::fs::create_dir(dir.path().join("sub")).expect("mkdir");
     let session_id = runtime.session_id();
     let session = runtime.session();
     let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
-    session.submit("read it").await.expect("submit");
+    let operation_id = session.submit("write").await.expect("submit");
     loop {
         let event = timeout(Duration::from_secs(2), events.recv())
             .await
             .expect("event")
             .expect("recv");
-        if matches!(event, RuntimeEvent::ToolStarted { .. }) {
+        if let RuntimeEvent::ToolSettled {
+            is_error, preview, ..
+        } = event
+        {
+            assert!(is_error);
+            let preview = preview.unwrap_or_default();
+            assert!(preview.contains("cannot snapshot for undo"), "{preview}");
             break;
         }
     }
 
-    // Process loss while the read effect is in flight.
-    runtime.crash();
-    drop(runtime);
-    drop(session);
+    // The operation is open in the store: an undo that bypasses the
+    // runtime is refused rather than racing it for the same files.
+    assert_eq!(
+        crate::undo(&store, session_id, None).await,
+        Err(CommandError::Busy { operation_id })
+    );
+    session.cancel(operation_id).await.expect("cancel");
+    collect_until_terminal(&mut events)
+        .await
+        .expect("cancelled");
+    assert_eq!(
+        crate::undo(&store, session_id, None).await,
+        Ok(crate::UndoOutcome::Nothing)
+    );
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+}
+
+#[test]
+fn operation_ids_parse_with_or_without_their_prefix() {
+    let id = OperationId::generate();
+    assert_eq!(id.to_string().parse::<OperationId>(), Ok(id));
+    let bare = id.to_string().trim_start_matches("op-").to_owned();
+    assert_eq!(bare.parse::<OperationId>(), Ok(id));
+    assert!("op-nope".parse::<OperationId>().is_err());
+}
+
+#[tokio::test]
+async fn a_tool_batch_found_pending_on_reopen_replays_whole() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dir.path().join("a.txt"), "first file").expect("seed");
+    std::fs::write(dir.path().join("b.txt"), "second file").expect("seed");
+    let store = SessionStore::open_in_memory().expect("store");
+    let session_id = crate::SessionId::generate();
+    store
+        .create_session(SessionRecord {
+            id: session_id,
+            cwd: dir.path().to_string_lossy().into_owned(),
+            title: "batch".to_owned(),
+            initial_model_ref: "test-model".to_owned(),
+            parent_session_id: None,
+            parent_call: None,
+            profile: None,
+        })
+        .await
+        .expect("create session");
+
+    // Durable state as process loss leaves it: both intents of a batch
+    // committed, neither result.
+    let operation_id = OperationId::generate();
+    let (mut machine, applied) = OperationMachine::accept(operation_id, "go", Vec::new());
+    let checkpoint = |machine: &OperationMachine, state_seq, open_batch| CheckpointRecord {
+        state_seq,
+        payload: CheckpointPayload {
+            state: machine.state().clone(),
+            cancel_requested: false,
+            prompt: "go".to_owned(),
+            tools: Vec::new(),
+            open_effect: None,
+            open_batch,
+        },
+    };
+    store
+        .begin_operation(
+            session_id,
+            operation_id,
+            InboxRecord {
+                id: InboxId::generate(),
+                kind: InboxKind::Prompt,
+                text: "go".to_owned(),
+                status: crate::InboxStatus::Applied,
+            },
+            checkpoint(&machine, 1, Vec::new()),
+            EntryRecord {
+                seq: 1,
+                entry: applied.entries[0].clone(),
+            },
+        )
+        .await
+        .expect("begin");
+    machine
+        .apply(Transition::StartModelStep {
+            model: step_model(),
+            plan: ContextPlan {
+                system: String::new(),
+                messages: Vec::new(),
+            },
+        })
+        .expect("start model step");
+    let calls: Vec<ToolCall> = ["a.txt", "b.txt"]
+        .into_iter()
+        .zip(1..)
+        .map(|(path, call_id)| ToolCall {
+            operation_id,
+            call_id,
+            name: "read".to_owned(),
+            arguments: json!({ "path": path }),
+        })
+        .collect();
+    machine
+        .apply(Transition::ProviderCompleted {
+            text: String::new(),
+            tool_calls: calls.clone(),
+        })
+        .expect("plan the reads");
+    machine
+        .apply(Transition::AdmitToolBatch { count: 2 })
+        .expect("admit the batch");
+    let effects: Vec<crate::EffectRecord> = calls
+        .iter()
+        .map(|call| crate::EffectRecord {
+            id: EffectId::generate(),
+            kind: "tool:read".to_owned(),
+            recovery_class: RecoveryClass::ReplaySafe,
+            effective_input: json!({
+                "tool": "read",
+                "arguments": call.arguments,
+                "call_id": call.call_id,
+            }),
+            attempt: 1,
+        })
+        .collect();
+    store
+        .commit(CommitRequest {
+            session_id,
+            operation_id,
+            checkpoint: checkpoint(&machine, 2, effects.clone()),
+            entries: Vec::new(),
+            open_effects: effects,
+            settled_effects: Vec::new(),
+            indeterminate_effects: Vec::new(),
+            inbox: Vec::new(),
+            inbox_applied: Vec::new(),
+            usage: Vec::new(),
+            snapshots: Vec::new(),
+        })
+        .await
+        .expect("commit the pending batch");
 
-    // Reopen: read is ReplaySafe, so it re-executes and the operation
-    // continues into the next model step.
     let runtime = Runtime::open_session(
         ScriptedProvider::new(vec![ScriptedMessage::text("after recovery\n")]),
-        ToolRegistry::with_cwd(&dir),
+        ToolRegistry::with_cwd(dir.path()),
         store.clone(),
         session_id,
     )
     .await
     .expect("reopen");
     let session = runtime.session();
-    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
-    let recorded = collect_until_terminal(&mut events).await.expect("collect");
-    assert!(matches!(
-        recorded.last(),
-        Some(RuntimeEvent::OperationFinished { .. })
-    ));
+    for _ in 0..100 {
+        if session.snapshot().await.expect("snapshot").operation == OperationStatus::Idle {
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
 
     let loaded = store.load(session_id).await.expect("load");
-    let (_, checkpoint) = &loaded.operations[0].latest;
+    let (_, latest) = &loaded.operations[0].latest;
     assert_eq!(
-        checkpoint.state,
+        latest.state,
         OperationState::Finished(OperationOutcome::Completed)
     );
-    assert!(loaded.entries.iter().any(|(_, entry)| matches!(
-        entry,
-        SessionEntry::ToolResult {
-            result: ToolResult::Ok { output, .. },
-        } if output.contains("persisted bytes")
-    )));
-    session.close().await.expect("close");
-    runtime.join().await.expect("join");
-    let _ = std::fs::remove_dir_all(&dir);
+    let outputs: Vec<String> = loaded
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult { result } => Some(result.clone().into_text()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(outputs.len(), 2, "{outputs:?}");
+    assert!(outputs[0].contains("first file") && outputs[1].contains("second file"));
 }
 
 #[tokio::test]
@@ -2126,6 +4177,81 @@ async fn crash_during_bash_settles_indeterminate_and_stays_usable() {
     runtime.join().await.expect("join");
 }
 
+#[tokio::test]
+async fn crash_recovery_lists_running_processes_as_orphaned() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = SessionStore::open_in_memory().expect("store");
+    let runtime = Runtime::start_with_policy(
+        ScriptedProvider::new(vec![
+            ScriptedMessage::tool("process_start", json!({"command": "sleep 30"})),
+            ScriptedMessage::tool("process_start", json!({"command": "sleep 31"})),
+            ScriptedMessage::tool("process_stop", json!({"id": 2})),
+            ScriptedMessage::text("started\n"),
+        ]),
+        ToolRegistry::with_cwd(dir.path()),
+        store.clone(),
+        Arc::new(AllowlistPolicy::new(["process_start", "process_stop"])),
+    );
+    let session_id = runtime.session_id();
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("serve").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+
+    // Process loss with one background process still listed.
+    runtime.crash();
+    drop(runtime);
+    drop(session);
+
+    let runtime = Runtime::open_session(
+        ScriptedProvider::new(vec![
+            ScriptedMessage::tool("process_output", json!({})),
+            ScriptedMessage::text("checked\n"),
+        ]),
+        ToolRegistry::with_cwd(dir.path()),
+        store.clone(),
+        session_id,
+    )
+    .await
+    .expect("reopen");
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("check").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+
+    let loaded = store.load(session_id).await.expect("load");
+    let outputs: Vec<&str> = loaded
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult {
+                result: ToolResult::Ok { output, .. },
+            } => Some(output.as_str()),
+            _ => None,
+        })
+        .collect();
+    // The stopped process is gone; the other is listed but not owned.
+    assert_eq!(
+        outputs.last(),
+        Some(&"1 indeterminate (started before the session was restored): sleep 30")
+    );
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    // An orphan's id is not handed out again.
+    let catalog = crate::ToolCatalog::with_cwd(dir.path()).fork();
+    catalog.restore_processes([(1, "sleep 30".to_owned())]);
+    let started = catalog
+        .snapshot()
+        .execute(
+            "process_start",
+            &json!({"command": "true"}),
+            CancellationToken::new(),
+        )
+        .await;
+    assert_eq!(started.output, "started process 2: true");
+}
+
 // ---- Context projection and usage ledger (DESIGN.md §32 Step 4 slice 1) ----
 
 fn plan_of(entries: &[SessionEntry]) -> crate::context::ContextPlan {
@@ -2153,6 +4279,8 @@ fn projector_is_deterministic_and_pairs_tool_calls() {
             result: ToolResult::Ok {
                 call_id: 7,
                 output: "contents".to_owned(),
+                images: Vec::new(),
+                artifact: None,
             },
         },
     ];
@@ -2164,7 +4292,10 @@ fn projector_is_deterministic_and_pairs_tool_calls() {
         panic!("tool call must attach to the assistant message");
     };
     assert_eq!(tool_calls.len(), 1);
-    let crate::context::ContextMessage::Tool { call_id, content } = &first.messages[2] else {
+    let crate::context::ContextMessage::Tool {
+        call_id, content, ..
+    } = &first.messages[2]
+    else {
         panic!("tool result must project as a tool message");
     };
     assert_eq!((*call_id, content.as_str()), (7, "contents"));
@@ -2251,7 +4382,8 @@ fn canonicalization_resolves_and_normalizes_paths() {
     assert_eq!(
         target,
         crate::tool::CanonicalTarget::Path {
-            path: "/tmp/project/src/main.rs".into()
+            path: "/tmp/project/src/main.rs".into(),
+            lines: None,
         }
     );
     let target = registry
@@ -2260,7 +4392,8 @@ fn canonicalization_resolves_and_normalizes_paths() {
     assert_eq!(
         target,
         crate::tool::CanonicalTarget::Path {
-            path: "/etc/hosts".into()
+            path: "/etc/hosts".into(),
+            lines: None,
         }
     );
     let target = registry
@@ -2269,7 +4402,9 @@ fn canonicalization_resolves_and_normalizes_paths() {
     assert_eq!(
         target,
         crate::tool::CanonicalTarget::Command {
-            command: "echo hi".into()
+            command: "echo hi".into(),
+            cwd: "/tmp/project".into(),
+            sandbox: None,
         }
     );
     assert!(registry.canonicalize("read", &json!({})).is_err());
@@ -2433,6 +4568,253 @@ async fn allowlist_policy_admits_bash_and_denial_is_model_visible() {
     assert!(denied, "policy denial must be model-visible: {loaded:?}");
 }
 
+// ---- Multi-file patches (DESIGN.md §12.3, §17.3) ----
+
+const REFACTOR_PATCH: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -2,3 +2,3 @@ fn first() {}
+ fn second() {}
+-fn third() {}
++fn third_renamed() {}
+ fn fourth() {}
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1,2 @@
++pub fn added() {}
++// no trailing newline
+\\ No newline at end of file
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+";
+
+fn seed_patch_tree() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::create_dir_all(dir.path().join("src")).expect("src");
+    // The hunk header says line 2; an inserted line moved it to 3.
+    std::fs::write(
+        dir.path().join("src/lib.rs"),
+        "// header\nfn first() {}\nfn second() {}\nfn third() {}\nfn fourth() {}\n",
+    )
+    .expect("seed");
+    std::fs::write(dir.path().join("old.txt"), "gone\n").expect("seed");
+    dir
+}
+
+#[tokio::test]
+async fn apply_patch_changes_several_files_at_once() {
+    let dir = seed_patch_tree();
+    let registry = ToolRegistry::with_cwd(dir.path());
+    let out = registry
+        .execute(
+            "apply_patch",
+            &json!({ "patch": REFACTOR_PATCH }),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(!out.is_error, "{out:?}");
+    assert_eq!(
+        out.output,
+        "patched 3 files\nM src/lib.rs\nA src/new.rs\nD old.txt"
+    );
+    // Frontends get whole files: none before a creation, none after a
+    // deletion.
+    let side = |name: &str| {
+        let diff = out
+            .diffs
+            .iter()
+            .find(|diff| diff.path.ends_with(name))
+            .expect("diff for file");
+        (diff.old_text.is_some(), diff.new_text.is_some())
+    };
+    assert_eq!(side("src/lib.rs"), (true, true));
+    assert_eq!(side("src/new.rs"), (false, true));
+    assert_eq!(side("old.txt"), (true, false));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/lib.rs")).expect("read"),
+        "// header\nfn first() {}\nfn second() {}\nfn third_renamed() {}\nfn fourth() {}\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/new.rs")).expect("read"),
+        "pub fn added() {}\n// no trailing newline"
+    );
+    assert!(!dir.path().join("old.txt").exists());
+}
+
+#[tokio::test]
+async fn edit_reports_a_bounded_diff_of_the_change() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("a.txt");
+    let original: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+    std::fs::write(&file, &original).expect("seed");
+    let registry = ToolRegistry::with_cwd(dir.path());
+    let out = registry
+        .execute(
+            "edit",
+            &json!({"path": "a.txt", "edits": [
+                { "old_str": "line 2\n", "new_str": "line two\n" },
+                { "old_str": "line 19\n", "new_str": "" },
+            ]}),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(!out.is_error, "{out:?}");
+    let hunks = "@@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n line 3\n line 4\n line 5\n\
+                 @@ -16,5 +16,4 @@\n line 16\n line 17\n line 18\n-line 19\n line 20";
+    assert_eq!(out.output, format!("edited\n{hunks}"));
+    assert_eq!(
+        out.diffs,
+        vec![FileDiff {
+            path: file.canonicalize().expect("canonical"),
+            hunks: hunks.to_owned(),
+            old_text: Some(original.clone()),
+            new_text: Some(
+                original
+                    .replace("line 2\n", "line two\n")
+                    .replace("line 19\n", "")
+            ),
+            whole: true,
+        }]
+    );
+
+    // A rewrite of a large file keeps the head of the diff.
+    let out = registry
+        .execute(
+            "write",
+            &json!({"path": "a.txt", "contents": "x\n".repeat(500)}),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(!out.is_error, "{out:?}");
+    let lines: Vec<&str> = out.diffs[0].hunks.lines().collect();
+    assert_eq!(lines.len(), 41, "{lines:?}");
+    assert_eq!(lines[0], "@@ -1,19 +1,500 @@");
+    assert!(lines[40].starts_with("… ") && lines[40].ends_with(" more lines"));
+    assert!(out.diffs[0].whole);
+
+    // A file too large to carry whole is described by its hunks alone.
+    let out = registry
+        .execute(
+            "write",
+            &json!({"path": "a.txt", "contents": "y\n".repeat(40_000)}),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(!out.is_error, "{out:?}");
+    let diff = &out.diffs[0];
+    assert!(!diff.whole);
+    assert_eq!((&diff.old_text, &diff.new_text), (&None, &None));
+    assert!(
+        diff.hunks.starts_with("@@ -1,500 +1,40000 @@"),
+        "{}",
+        diff.hunks
+    );
+}
+
+#[tokio::test]
+async fn apply_patch_validates_every_hunk_before_writing() {
+    let dir = seed_patch_tree();
+    let registry = ToolRegistry::with_cwd(dir.path());
+    let stale = REFACTOR_PATCH.replace("-gone", "-not what the file says");
+    let out = registry
+        .execute(
+            "apply_patch",
+            &json!({ "patch": stale }),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(out.is_error);
+    assert!(out.output.contains("does not match old.txt"), "{out:?}");
+    // Nothing was written: the valid first file is untouched too.
+    assert!(
+        std::fs::read_to_string(dir.path().join("src/lib.rs"))
+            .expect("read")
+            .contains("fn third() {}")
+    );
+    assert!(!dir.path().join("src/new.rs").exists());
+    assert!(dir.path().join("old.txt").exists());
+    assert_eq!(
+        std::fs::read_dir(dir.path().join("src"))
+            .expect("dir")
+            .count(),
+        1,
+        "no staging files left behind"
+    );
+
+    for bad in [
+        "",
+        "--- a/x\n+++ b/x\n",
+        "--- a/../x\n+++ b/../x\n@@ -1 +1 @@\n-a\n+b\n",
+    ] {
+        let out = registry
+            .execute(
+                "apply_patch",
+                &json!({ "patch": bad }),
+                CancellationToken::new(),
+            )
+            .await;
+        assert!(out.is_error, "{bad:?} should fail");
+    }
+}
+
+#[test]
+fn apply_patch_canonicalizes_to_every_path() {
+    let registry = ToolRegistry::with_cwd("/tmp/project");
+    let target = registry
+        .canonicalize("apply_patch", &json!({ "patch": REFACTOR_PATCH }))
+        .expect("canonicalize");
+    assert_eq!(
+        target,
+        crate::tool::CanonicalTarget::Paths {
+            paths: vec![
+                "/tmp/project/src/lib.rs".into(),
+                "/tmp/project/src/new.rs".into(),
+                "/tmp/project/old.txt".into(),
+            ]
+        }
+    );
+    assert_eq!(
+        crate::tool::target_from_arguments("apply_patch", &json!({ "patch": REFACTOR_PATCH })),
+        Some("lib.rs, new.rs, old.txt".to_owned())
+    );
+    assert!(
+        registry
+            .canonicalize("apply_patch", &json!({ "patch": "not a diff" }))
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn patch_evidence_is_classified_per_file() {
+    use crate::tool::{ReconcileVerdict, reconcile, reconciliation_evidence};
+    let dir = seed_patch_tree();
+    let arguments = json!({ "patch": REFACTOR_PATCH });
+    let evidence = reconciliation_evidence(dir.path(), "apply_patch", &arguments)
+        .await
+        .expect("evidence");
+    let files = evidence["files"].as_array().expect("per-file evidence");
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[1]["preimage"]["exists"], false);
+    assert_eq!(files[2]["postimage"]["exists"], false);
+    assert_eq!(reconcile(&evidence).await, ReconcileVerdict::SafeToExecute);
+
+    // Cut off after the first file: neither replay nor settle.
+    let lib = dir.path().join("src/lib.rs");
+    let original = std::fs::read_to_string(&lib).expect("read");
+    std::fs::write(&lib, original.replace("third", "third_renamed")).expect("write");
+    assert_eq!(reconcile(&evidence).await, ReconcileVerdict::Conflict);
+
+    std::fs::write(&lib, &original).expect("restore");
+    let out = ToolRegistry::with_cwd(dir.path())
+        .execute("apply_patch", &arguments, CancellationToken::new())
+        .await;
+    assert!(!out.is_error, "{out:?}");
+    assert_eq!(reconcile(&evidence).await, ReconcileVerdict::AlreadyApplied);
+}
+
 // ---- File-write reconciliation (DESIGN.md §12.3, §32 Step 4 slice 3) ----
 
 mod reconcile {
@@ -2503,6 +4885,41 @@ mod reconcile {
         );
     }
 
+    #[tokio::test]
+    async fn edit_evidence_covers_replace_all_and_batches() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("c.txt"), "a a b").expect("seed");
+        let evidence = reconciliation_evidence(
+            dir.path(),
+            "edit",
+            &json!({ "path": "c.txt", "old_str": "a", "new_str": "z", "replace_all": true }),
+        )
+        .await
+        .expect("evidence");
+        assert_eq!(evidence["postimage_hash"], sha_hex(b"z z b"));
+        let evidence = reconciliation_evidence(
+            dir.path(),
+            "edit",
+            &json!({ "path": "c.txt", "edits": [
+                { "old_str": "b", "new_str": "c" },
+                { "old_str": "a a", "new_str": "d" },
+            ]}),
+        )
+        .await
+        .expect("evidence");
+        assert_eq!(evidence["postimage_hash"], sha_hex(b"d c"));
+        // An ambiguous match is not classifiable either.
+        assert!(
+            reconciliation_evidence(
+                dir.path(),
+                "edit",
+                &json!({ "path": "c.txt", "old_str": "a", "new_str": "x" }),
+            )
+            .await
+            .is_err()
+        );
+    }
+
     #[test]
     fn classification_covers_all_verdicts() {
         let preimage_hash = sha_hex(b"preimage");
@@ -2562,6 +4979,8 @@ mod reconcile {
                 title: "reconcile".to_owned(),
                 initial_model_ref: "test-model".to_owned(),
                 parent_session_id: None,
+                parent_call: None,
+                profile: None,
             })
             .await
             .expect("create session");
@@ -2587,6 +5006,7 @@ mod reconcile {
                 prompt: "go".to_owned(),
                 tools: Vec::new(),
                 open_effect: None,
+                open_batch: Vec::new(),
             },
         };
         store
@@ -2647,6 +5067,7 @@ mod reconcile {
                 prompt: "go".to_owned(),
                 tools: Vec::new(),
                 open_effect: Some(effect.clone()),
+                open_batch: Vec::new(),
             },
         };
         store
@@ -2661,6 +5082,7 @@ mod reconcile {
                 inbox: Vec::new(),
                 inbox_applied: Vec::new(),
                 usage: Vec::new(),
+                snapshots: Vec::new(),
             })
             .await
             .expect("commit pending write");
@@ -2709,7 +5131,7 @@ mod reconcile {
         let recovered = loaded.entries.iter().any(|(_, entry)| {
             matches!(entry, SessionEntry::ToolResult {
                 result: ToolResult::Ok { output, .. },
-            } if output == "written")
+            } if output.starts_with("written\n"))
         });
         assert!(recovered, "{loaded:?}");
     }
@@ -2941,7 +5363,7 @@ impl Provider for CompactionProbe {
         let operation_id = request.operation_id;
         let step = request.step;
         let is_compaction = request.plan.messages.iter().any(|message| {
-            matches!(message, crate::context::ContextMessage::User { content }
+            matches!(message, crate::context::ContextMessage::User { content, .. }
                 if content.contains("Summarize the conversation"))
         });
         self.log.lock().expect("log poisoned").push(request);
@@ -3062,6 +5484,7 @@ fn entry_kind_name(entry: &SessionEntry) -> &'static str {
         SessionEntry::ToolCall { .. } => "tool_call",
         SessionEntry::ToolResult { .. } => "tool_result",
         SessionEntry::Compaction { .. } => "compaction",
+        SessionEntry::Undone { .. } => "undone",
     }
 }
 
@@ -3210,7 +5633,7 @@ impl Provider for CompactToolProbe {
         let operation_id = request.operation_id;
         let step = request.step;
         let is_compaction = request.plan.messages.iter().any(|message| {
-            matches!(message, crate::context::ContextMessage::User { content }
+            matches!(message, crate::context::ContextMessage::User { content, .. }
                 if content.contains("Summarize the conversation"))
         });
         self.log.lock().expect("log poisoned").push(request);
@@ -3382,7 +5805,7 @@ impl Provider for OverflowProbe {
         let operation_id = request.operation_id;
         let step = request.step;
         let is_compaction = request.plan.messages.iter().any(|message| {
-            matches!(message, crate::context::ContextMessage::User { content }
+            matches!(message, crate::context::ContextMessage::User { content, .. }
                 if content.contains("Summarize the conversation"))
         });
         self.log.lock().expect("log poisoned").push(request);
@@ -3771,8 +6194,11 @@ fn delegate_tool(
 ) -> Arc<dyn crate::tool::Tool> {
     Arc::new(crate::DelegateTool::new(
         crate::DelegateConfig {
+            cwd: std::env::current_dir().expect("cwd"),
             store,
             make_provider: Arc::new(move || ScriptedProvider::new(child_script.clone())),
+            make_model_provider: None,
+            profiles: Vec::new(),
             max_active_children: 4,
             child_budget: budget,
         },
@@ -3780,80 +6206,544 @@ fn delegate_tool(
     ))
 }
 
-/// Extract `session-<uuid>` references from a delegate result.
-fn child_ids(output: &str) -> Vec<crate::SessionId> {
-    output
-        .split("[child session: ")
-        .skip(1)
-        .filter_map(|part| {
-            let end = part.find(']')?;
-            crate::ids::SessionId::parse(part[..end].trim_start_matches("session-"))
-        })
-        .collect()
+/// Extract `session-<uuid>` references from a delegate result.
+fn child_ids(output: &str) -> Vec<crate::SessionId> {
+    output
+        .split("[child session: ")
+        .skip(1)
+        .filter_map(|part| {
+            let end = part.find(']')?;
+            crate::ids::SessionId::parse(part[..end].trim_start_matches("session-"))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn two_read_only_children_run_and_report_lineage() {
+    let child_script = vec![ScriptedMessage::text("child answer")];
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::ToolCall {
+            name: "delegate".to_owned(),
+            arguments: json!({
+                "children": [
+                    { "objective": "investigate a" },
+                    { "objective": "investigate b", "context": "seed text" }
+                ]
+            }),
+        },
+        ScriptedMessage::text("done"),
+    ]);
+    let store = SessionStore::open_in_memory().expect("store");
+    let catalog = crate::ToolCatalog::default();
+    let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
+    let parent_id = runtime.session_id();
+    catalog.register_scope(
+        "delegate",
+        vec![delegate_tool(
+            store.clone(),
+            child_script,
+            parent_id,
+            crate::RuntimeBudget::unbounded(),
+        )],
+    );
+
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("fan out").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    assert!(
+        recorded
+            .iter()
+            .any(|e| matches!(e, RuntimeEvent::OperationFinished { .. })),
+        "{recorded:?}"
+    );
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    // The tool result reached the parent transcript with both children
+    // referenced.
+    let loaded = store.load(parent_id).await.expect("load");
+    let tool_output = loaded
+        .entries
+        .iter()
+        .find_map(|(_, entry)| {
+            serde_json::to_string(entry)
+                .ok()
+                .filter(|text| text.contains("child answer"))
+        })
+        .expect("child results in parent transcript");
+
+    // Both children are durable sessions with lineage to the parent.
+    let ids = child_ids(&tool_output);
+    assert_eq!(ids.len(), 2, "{tool_output}");
+    for child in ids {
+        let child_loaded = store.load(child).await.expect("child session");
+        assert_eq!(child_loaded.session.parent_session_id, Some(parent_id));
+    }
+}
+
+#[tokio::test]
+async fn children_report_live_progress_on_the_delegate_call() {
+    let child_script = vec![
+        ScriptedMessage::tool("read", json!({ "path": "Cargo.toml" })),
+        ScriptedMessage::text("child answer\n"),
+    ];
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::tool(
+            "delegate",
+            json!({ "children": [{ "objective": "read the manifest" }] }),
+        ),
+        ScriptedMessage::text("done"),
+    ]);
+    let store = SessionStore::open_in_memory().expect("store");
+    let catalog = crate::ToolCatalog::default();
+    let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
+    let parent_id = runtime.session_id();
+    catalog.register_scope(
+        "delegate",
+        vec![delegate_tool(
+            store.clone(),
+            child_script,
+            parent_id,
+            crate::RuntimeBudget::unbounded(),
+        )],
+    );
+
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("delegate it").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    let delegate_call = recorded
+        .iter()
+        .find_map(|event| match event {
+            RuntimeEvent::ToolStarted { call_id, tool, .. } if tool == "delegate" => Some(*call_id),
+            _ => None,
+        })
+        .expect("delegate started");
+    let progress: Vec<(crate::SessionId, crate::ChildUpdate)> = recorded
+        .iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::ChildProgress {
+                call_id,
+                child_session_id,
+                update,
+                ..
+            } => {
+                assert_eq!(*call_id, delegate_call);
+                Some((*child_session_id, update.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    let child = progress.first().expect("progress").0;
+    assert!(progress.iter().all(|(id, _)| *id == child));
+    let updates: Vec<crate::ChildUpdate> = progress.into_iter().map(|(_, u)| u).collect();
+    assert_eq!(
+        updates,
+        [
+            crate::ChildUpdate::Started {
+                objective: "read the manifest".to_owned(),
+                profile: None,
+            },
+            crate::ChildUpdate::ToolStarted {
+                tool: "read".to_owned(),
+                target: Some("Cargo.toml".to_owned()),
+            },
+            crate::ChildUpdate::ToolSettled {
+                tool: "read".to_owned(),
+                is_error: false,
+            },
+            crate::ChildUpdate::Text {
+                line: "child answer".to_owned(),
+            },
+            crate::ChildUpdate::Finished,
+        ]
+    );
+    // Progress precedes the call's settlement, which names the child.
+    let settled = recorded
+        .iter()
+        .position(|event| matches!(event, RuntimeEvent::ToolSettled { call_id, .. } if *call_id == delegate_call))
+        .expect("delegate settled");
+    let last_progress = recorded
+        .iter()
+        .rposition(|event| matches!(event, RuntimeEvent::ChildProgress { .. }))
+        .expect("progress");
+    assert!(last_progress < settled);
+    let loaded = store.load(parent_id).await.expect("load");
+    let output = loaded
+        .entries
+        .iter()
+        .find_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult { result } => Some(result.clone().into_text()),
+            _ => None,
+        })
+        .expect("delegate result");
+    assert_eq!(child_ids(&output), [child]);
+}
+
+#[tokio::test]
+async fn a_parent_lists_its_children_and_reads_their_transcripts() {
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::tool(
+            "delegate",
+            json!({ "children": [{ "objective": "look around" }] }),
+        ),
+        ScriptedMessage::text("done"),
+    ]);
+    let store = SessionStore::open_in_memory().expect("store");
+    let catalog = crate::ToolCatalog::default();
+    let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
+    let parent_id = runtime.session_id();
+    catalog.register_scope(
+        "delegate",
+        vec![delegate_tool(
+            store.clone(),
+            vec![ScriptedMessage::text("child answer")],
+            parent_id,
+            crate::RuntimeBudget::unbounded(),
+        )],
+    );
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("delegate it").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+
+    let children = session.children().await.expect("children");
+    assert_eq!(children.len(), 1, "{children:?}");
+    let child = &children[0];
+    assert_eq!(child.objective, "look around");
+    assert_eq!(child.outcome, Some(OperationOutcome::Completed));
+    let parent_call = store
+        .load(child.session_id)
+        .await
+        .expect("child")
+        .session
+        .parent_call
+        .expect("the child records its delegate call");
+    assert_eq!(parent_call.call_id, 1);
+
+    let transcript = session
+        .child_transcript(child.session_id)
+        .await
+        .expect("transcript");
+    assert!(matches!(
+        transcript.first(),
+        Some(SessionEntry::UserMessage { text }) if text == "look around"
+    ));
+    assert!(
+        transcript.iter().any(
+            |entry| matches!(entry, SessionEntry::AssistantMessage { text, .. } if text == "child answer")
+        ),
+        "{transcript:?}"
+    );
+
+    // Only this session's children are readable through it.
+    assert!(matches!(
+        session.child_transcript(parent_id).await,
+        Err(CommandError::NotAChild(id)) if id == parent_id
+    ));
+    // Children never stand in for the latest top-level session.
+    assert_eq!(
+        store.latest_session().await.expect("latest"),
+        Some(parent_id)
+    );
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+}
+
+#[tokio::test]
+async fn a_recovered_delegate_call_resumes_its_earlier_children() {
+    let store = SessionStore::open_in_memory().expect("store");
+    let parent = Runtime::start_with_store(
+        ScriptedProvider::echo(),
+        ToolRegistry::default(),
+        store.clone(),
+    );
+    let parent_id = parent.session_id();
+    parent.session().close().await.expect("close");
+    parent.join().await.expect("join");
+    let call = crate::ParentCall {
+        operation_id: OperationId::generate(),
+        call_id: 3,
+    };
+    let lineage = || crate::ChildLineage {
+        parent: parent_id,
+        call: Some(call),
+        profile: None,
+    };
+
+    // The lost attempt finished one child and left another mid-step.
+    let finished = Runtime::start_child(
+        ScriptedProvider::new(vec![ScriptedMessage::text("earlier answer")]),
+        ToolRegistry::default(),
+        store.clone(),
+        permissive_policy(),
+        crate::RuntimeBudget::unbounded(),
+        lineage(),
+    );
+    let finished_id = finished.session_id();
+    let session = finished.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("first").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+    session.close().await.expect("close");
+    finished.join().await.expect("join");
+
+    let interrupted = Runtime::start_child(
+        ScriptedProvider::new(vec![ScriptedMessage::delayed(
+            Duration::from_secs(30),
+            "never arrives",
+        )]),
+        ToolRegistry::default(),
+        store.clone(),
+        permissive_policy(),
+        crate::RuntimeBudget::unbounded(),
+        lineage(),
+    );
+    let interrupted_id = interrupted.session_id();
+    let session = interrupted.session();
+    session.submit("second").await.expect("submit");
+    wait_for_state(&session, |state| {
+        matches!(state, OperationState::AssistantEffectPending)
+    })
+    .await;
+    interrupted.crash();
+    drop(interrupted);
+    drop(session);
+
+    // Re-execution under the same call identity adopts both instead of
+    // starting new children; only the interrupted one runs again.
+    let delegate = delegate_tool(
+        store.clone(),
+        vec![ScriptedMessage::text("resumed answer")],
+        parent_id,
+        crate::RuntimeBudget::unbounded(),
+    );
+    assert_eq!(delegate.recovery_class(), RecoveryClass::Resume);
+    // Only this call's children are its earlier ones.
+    let unrelated = Runtime::start_child(
+        ScriptedProvider::echo(),
+        ToolRegistry::default(),
+        store.clone(),
+        permissive_policy(),
+        crate::RuntimeBudget::unbounded(),
+        crate::ChildLineage {
+            parent: parent_id,
+            call: Some(crate::ParentCall { call_id: 4, ..call }),
+            profile: None,
+        },
+    );
+    let unrelated_id = unrelated.session_id();
+    unrelated.session().close().await.expect("close");
+    unrelated.join().await.expect("join");
+    assert_eq!(
+        store
+            .call_children(parent_id, call)
+            .await
+            .expect("call children"),
+        [finished_id, interrupted_id]
+    );
+    let outcome = delegate
+        .call_with_progress(
+            json!({ "children": [{ "objective": "first" }, { "objective": "second" }] }),
+            CancellationToken::new(),
+            crate::ToolProgress::default()
+                .for_call(call.operation_id, call.call_id)
+                .retried(),
+        )
+        .await;
+    assert!(!outcome.is_error, "{}", outcome.output);
+    let output = outcome.output;
+    assert_eq!(
+        child_ids(&output),
+        [finished_id, interrupted_id],
+        "{output}"
+    );
+    assert!(output.contains("earlier answer"), "{output}");
+    assert!(output.contains("resumed answer"), "{output}");
+    assert_eq!(
+        store.children(parent_id).await.expect("children"),
+        [finished_id, interrupted_id, unrelated_id]
+    );
+}
+
+#[tokio::test]
+async fn a_child_past_its_timeout_is_cancelled_with_its_partial_result() {
+    let store = SessionStore::open_in_memory().expect("store");
+    let parent = Runtime::start_with_store(
+        ScriptedProvider::echo(),
+        ToolRegistry::default(),
+        store.clone(),
+    );
+    let parent_id = parent.session_id();
+    parent.session().close().await.expect("close");
+    parent.join().await.expect("join");
+
+    let delegate = delegate_tool(
+        store.clone(),
+        vec![
+            ScriptedMessage::text("partial line\n"),
+            ScriptedMessage::delayed(Duration::from_secs(30), "never arrives"),
+        ],
+        parent_id,
+        crate::RuntimeBudget::unbounded(),
+    );
+    let outcome = delegate
+        .call(
+            json!({ "children": [{ "objective": "slow", "timeout_secs": 1 }] }),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(!outcome.is_error, "{}", outcome.output);
+    let output = outcome.output;
+    assert!(output.starts_with("1 child: 1 timed out\n"), "{output}");
+    assert!(output.contains("child timed out after 1s"), "{output}");
+    assert!(output.contains("partial result:\npartial line"), "{output}");
+    let [child] = child_ids(&output)[..] else {
+        panic!("one child reference: {output}");
+    };
+    let loaded = store.load(child).await.expect("child");
+    assert_eq!(
+        loaded.operations[0].latest.1.state,
+        OperationState::Finished(OperationOutcome::Cancelled)
+    );
+
+    // Zero is not a limit.
+    let outcome = delegate
+        .call(
+            json!({ "children": [{ "objective": "slow", "timeout_secs": 0 }] }),
+            CancellationToken::new(),
+        )
+        .await;
+    assert!(outcome.is_error, "{}", outcome.output);
 }
 
 #[tokio::test]
-async fn two_read_only_children_run_and_report_lineage() {
-    let child_script = vec![ScriptedMessage::text("child answer")];
+async fn one_child_is_cancelled_without_its_sibling_or_parent() {
+    let slow = vec![
+        ScriptedMessage::text("halfway\n"),
+        ScriptedMessage::delayed(Duration::from_secs(30), "never arrives"),
+    ];
+    let started = Arc::new(std::sync::atomic::AtomicUsize::new(0));
     let provider = ScriptedProvider::new(vec![
-        ScriptedMessage::ToolCall {
-            name: "delegate".to_owned(),
-            arguments: json!({
-                "children": [
-                    { "objective": "investigate a" },
-                    { "objective": "investigate b", "context": "seed text" }
-                ]
-            }),
-        },
+        ScriptedMessage::tool(
+            "delegate",
+            json!({ "children": [{ "objective": "one" }, { "objective": "two" }] }),
+        ),
         ScriptedMessage::text("done"),
     ]);
     let store = SessionStore::open_in_memory().expect("store");
     let catalog = crate::ToolCatalog::default();
     let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
     let parent_id = runtime.session_id();
-    catalog.register_scope(
-        "delegate",
-        vec![delegate_tool(
-            store.clone(),
-            child_script,
-            parent_id,
-            crate::RuntimeBudget::unbounded(),
-        )],
+    // Whichever child starts first answers; the other hangs.
+    let delegate = crate::DelegateTool::new(
+        crate::DelegateConfig {
+            cwd: std::env::current_dir().expect("cwd"),
+            store: store.clone(),
+            make_provider: Arc::new(move || {
+                if started.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    ScriptedProvider::new(vec![ScriptedMessage::text("quick answer")])
+                } else {
+                    ScriptedProvider::new(slow.clone())
+                }
+            }),
+            make_model_provider: None,
+            profiles: Vec::new(),
+            max_active_children: 4,
+            child_budget: crate::RuntimeBudget::unbounded(),
+        },
+        parent_id,
     );
+    catalog.register_scope("delegate", vec![Arc::new(delegate)]);
 
     let session = runtime.session();
     let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
     session.submit("fan out").await.expect("submit");
+    assert!(matches!(
+        session.cancel_child(parent_id).await,
+        Err(CommandError::ChildNotRunning(id)) if id == parent_id
+    ));
+
+    // Once one child finished and the other streamed text, cancel the
+    // one still running.
+    let mut finished = None;
+    let mut hanging = None;
+    while finished.is_none() || hanging.is_none() {
+        let event = timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("child progress")
+            .expect("event");
+        if let RuntimeEvent::ChildProgress {
+            child_session_id,
+            update,
+            ..
+        } = event
+        {
+            match update {
+                crate::ChildUpdate::Finished => finished = Some(child_session_id),
+                crate::ChildUpdate::Text { line } if line == "halfway" => {
+                    hanging = Some(child_session_id);
+                }
+                _ => {}
+            }
+        }
+    }
+    let (finished, hanging) = (finished.expect("finished"), hanging.expect("hanging"));
+    assert_ne!(finished, hanging);
+    session
+        .cancel_child(hanging)
+        .await
+        .expect("cancel one child");
+
     let recorded = collect_until_terminal(&mut events).await.expect("collect");
     assert!(
-        recorded
-            .iter()
-            .any(|e| matches!(e, RuntimeEvent::OperationFinished { .. })),
+        recorded.iter().any(|event| matches!(
+            event,
+            RuntimeEvent::ChildProgress { child_session_id, update: crate::ChildUpdate::Cancelled, .. }
+                if *child_session_id == hanging
+        )),
         "{recorded:?}"
     );
+    // The parent operation went on to its own answer.
+    assert!(
+        matches!(
+            recorded.last(),
+            Some(RuntimeEvent::OperationFinished { .. })
+        ),
+        "{recorded:?}"
+    );
+    // A finished child is no longer cancellable.
+    assert!(matches!(
+        session.cancel_child(finished).await,
+        Err(CommandError::ChildNotRunning(_))
+    ));
     session.close().await.expect("close");
     runtime.join().await.expect("join");
 
-    // The tool result reached the parent transcript with both children
-    // referenced.
     let loaded = store.load(parent_id).await.expect("load");
-    let tool_output = loaded
+    let output = loaded
         .entries
         .iter()
-        .find_map(|(_, entry)| {
-            serde_json::to_string(entry)
-                .ok()
-                .filter(|text| text.contains("child answer"))
+        .find_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult { result } => Some(result.clone().into_text()),
+            _ => None,
         })
-        .expect("child results in parent transcript");
-
-    // Both children are durable sessions with lineage to the parent.
-    let ids = child_ids(&tool_output);
-    assert_eq!(ids.len(), 2, "{tool_output}");
-    for child in ids {
-        let child_loaded = store.load(child).await.expect("child session");
-        assert_eq!(child_loaded.session.parent_session_id, Some(parent_id));
-    }
+        .expect("delegate result");
+    assert!(
+        output.starts_with("2 children: 1 completed, 1 cancelled\n"),
+        "{output}"
+    );
+    assert!(output.contains("quick answer"), "{output}");
+    assert!(
+        output.contains(&format!(
+            "child cancelled [child session: {hanging}]\npartial result:\nhalfway"
+        )),
+        "{output}"
+    );
 }
 
 #[tokio::test]
@@ -3911,6 +6801,107 @@ async fn child_cannot_widen_capabilities() {
     );
 }
 
+#[tokio::test]
+async fn profiled_children_run_as_configured_and_record_it() {
+    let profile = crate::AgentProfile {
+        name: "reviewer".to_owned(),
+        description: Some("reviews a change".to_owned()),
+        system_prompt: Some("Review only; report findings.".to_owned()),
+        model: Some("review-model".to_owned()),
+        // `bash` is outside the child's base set: named, never granted.
+        tools: Some(vec!["read".to_owned(), "bash".to_owned()]),
+        max_model_steps: Some(3),
+        max_tool_calls: None,
+        timeout_secs: None,
+    };
+    let provider = ScriptedProvider::new(vec![
+        ScriptedMessage::ToolCall {
+            name: "delegate".to_owned(),
+            arguments: json!({
+                "children": [{ "objective": "review it", "profile": "reviewer" }]
+            }),
+        },
+        ScriptedMessage::ToolCall {
+            name: "delegate".to_owned(),
+            arguments: json!({
+                "children": [{ "objective": "anything", "profile": "admin" }]
+            }),
+        },
+    ]);
+    let child = SharedLogProvider::default();
+    let store = SessionStore::open_in_memory().expect("store");
+    let catalog = crate::ToolCatalog::default();
+    let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
+    let parent_id = runtime.session_id();
+    let (plain, profiled) = (child.clone(), child.clone());
+    let delegate = crate::DelegateTool::new(
+        crate::DelegateConfig {
+            cwd: std::env::current_dir().expect("cwd"),
+            store: store.clone(),
+            make_provider: Arc::new(move || plain.clone()),
+            make_model_provider: Some(Arc::new(move |_model| profiled.clone())),
+            max_active_children: 4,
+            child_budget: crate::child_budget_default(),
+            profiles: vec![profile.clone()],
+        },
+        parent_id,
+    );
+    let schema = crate::tool::Tool::spec(&delegate).input_schema;
+    assert_eq!(
+        schema["properties"]["children"]["items"]["properties"]["profile"]["enum"],
+        json!(["reviewer"])
+    );
+    catalog.register_scope("delegate", vec![Arc::new(delegate)]);
+
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("review").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    // The child ran on the profile's model, with its addendum and only
+    // the allowed tool.
+    let requests = child.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].model.model_ref, "review-model");
+    assert!(
+        requests[0]
+            .plan
+            .system
+            .ends_with("\n\nReview only; report findings."),
+        "{}",
+        requests[0].plan.system
+    );
+    let tools: Vec<&str> = requests[0].tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(tools, ["read"]);
+
+    // The profile is part of the child's durable lineage.
+    let loaded = store.load(parent_id).await.expect("load");
+    let results: Vec<String> = loaded
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult { result } => Some(result.clone().into_text()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(results.len(), 2, "{results:?}");
+    let ids = child_ids(&results[0]);
+    assert_eq!(ids.len(), 1, "{}", results[0]);
+    let child_loaded = store.load(ids[0]).await.expect("child session");
+    assert_eq!(child_loaded.session.profile, Some(profile));
+    assert_eq!(child_loaded.session.initial_model_ref, "review-model");
+
+    // An unknown profile never passes admission.
+    assert!(
+        results[1].contains("invalid arguments for `delegate`")
+            && results[1].contains("$.children[0].profile"),
+        "{}",
+        results[1]
+    );
+}
+
 #[tokio::test]
 async fn budget_stops_a_runaway_child() {
     // The child would loop forever; its budget stops it after one
@@ -3935,49 +6926,347 @@ async fn budget_stops_a_runaway_child() {
         ScriptedMessage::text("gave up on the child"),
     ]);
     let store = SessionStore::open_in_memory().expect("store");
-    let catalog = crate::ToolCatalog::default();
+    let catalog = crate::ToolCatalog::default();
+    let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
+    let parent_id = runtime.session_id();
+    catalog.register_scope(
+        "delegate",
+        vec![Arc::new(crate::DelegateTool::new(
+            crate::DelegateConfig {
+                cwd: std::env::current_dir().expect("cwd"),
+                store: store.clone(),
+                make_provider: Arc::new(looping_child),
+                make_model_provider: None,
+                profiles: Vec::new(),
+                max_active_children: 4,
+                child_budget: crate::RuntimeBudget {
+                    max_model_steps: Some(1),
+                    max_tool_calls: None,
+                },
+            },
+            parent_id,
+        ))],
+    );
+
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("run away").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("collect");
+    assert!(
+        recorded
+            .iter()
+            .any(|e| matches!(e, RuntimeEvent::OperationFinished { .. })),
+        "parent survives a failed child: {recorded:?}"
+    );
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    let loaded = store.load(parent_id).await.expect("load");
+    let tool_output = loaded
+        .entries
+        .iter()
+        .find_map(|(_, entry)| {
+            serde_json::to_string(entry)
+                .ok()
+                .filter(|text| text.contains("budget exceeded"))
+        })
+        .expect("budget failure surfaces to the parent");
+    assert!(tool_output.contains("child session"));
+}
+
+#[tokio::test]
+async fn worktree_children_edit_a_private_checkout_the_parent_can_merge() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let repo = dir.path();
+    let at = "2026-01-02T10:00:00Z";
+    git_in(repo, &["init", "-q", "-b", "main"], "Alice", at);
+    std::fs::create_dir(repo.join("src")).expect("src");
+    std::fs::write(repo.join("src/notes.txt"), "original\n").expect("seed");
+    git_in(repo, &["add", "."], "Alice", at);
+    git_in(repo, &["commit", "-q", "-m", "seed"], "Alice", at);
+    // Config that would bend the diff out of shape.
+    for (key, value) in [
+        ("diff.noprefix", "true"),
+        ("diff.mnemonicPrefix", "true"),
+        ("diff.external", "false"),
+        ("color.ui", "always"),
+    ] {
+        git_in(repo, &["config", key, value], "Alice", at);
+    }
+
+    let child_script = vec![
+        ScriptedMessage::tool(
+            "edit",
+            json!({"path": "notes.txt", "old_str": "original", "new_str": "child edit"}),
+        ),
+        ScriptedMessage::tool("write", json!({"path": "added.txt", "contents": "new\n"})),
+        ScriptedMessage::text("edited"),
+    ];
+    let provider = ScriptedProvider::new(vec![ScriptedMessage::tool(
+        "delegate",
+        json!({ "children": [{ "objective": "edit notes", "workspace": "worktree" }] }),
+    )]);
+    let store = SessionStore::open_in_memory().expect("store");
+    let catalog = crate::ToolCatalog::with_cwd(repo.join("src"));
+    let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
+    let parent_id = runtime.session_id();
+    let delegate = crate::DelegateTool::new(
+        crate::DelegateConfig {
+            cwd: repo.join("src"),
+            store: store.clone(),
+            make_provider: Arc::new(move || ScriptedProvider::new(child_script.clone())),
+            make_model_provider: None,
+            profiles: Vec::new(),
+            max_active_children: 4,
+            child_budget: crate::RuntimeBudget::unbounded(),
+        },
+        parent_id,
+    );
+    let merge = delegate.merge_tool();
+    catalog.register_scope("delegate", vec![Arc::new(delegate), Arc::new(merge)]);
+    let session = runtime.session();
+    let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("delegate an edit").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+
+    // The child worked in its own checkout, rooted at the counterpart
+    // of the parent's subdirectory, and the worktree is gone with it.
+    let loaded = store.load(parent_id).await.expect("load");
+    let output = loaded
+        .entries
+        .iter()
+        .find_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult {
+                result: ToolResult::Ok { output, .. },
+            } => Some(output.clone()),
+            _ => None,
+        })
+        .expect("delegate result");
+    assert!(
+        output.contains("changed src/added.txt, src/notes.txt"),
+        "{output}"
+    );
+    assert!(output.contains("+child edit"), "{output}");
+    assert_eq!(
+        std::fs::read_to_string(repo.join("src/notes.txt")).expect("notes"),
+        "original\n"
+    );
+    let worktrees = git_in(repo, &["worktree", "list"], "Alice", at);
+    assert_eq!(worktrees.lines().count(), 1, "{worktrees}");
+
+    // Merging applies the diff to the parent's working tree, once.
+    let child = child_ids(&output)[0];
+    let target = catalog
+        .canonicalize("merge_child", &json!({ "session": child.to_string() }))
+        .expect("canonical");
+    assert!(
+        matches!(&target, crate::tool::CanonicalTarget::Paths { paths }
+            if paths.len() == 2 && paths.iter().all(|path| path.starts_with(repo))),
+        "{target:?}"
+    );
+    // It recovers like a patch: evidence names each file's preimage and
+    // the postimage the diff leaves, previewed without touching them.
+    assert_eq!(
+        catalog.recovery_class("merge_child"),
+        crate::tool::RecoveryClass::Reconcile
+    );
+    let evidence = catalog
+        .reconciliation_evidence("merge_child", &json!({ "session": child.to_string() }))
+        .await
+        .expect("evidence");
+    assert_eq!(
+        evidence["files"].as_array().map(Vec::len),
+        Some(2),
+        "{evidence}"
+    );
+    assert_eq!(
+        crate::tool::reconcile(&evidence).await,
+        crate::tool::ReconcileVerdict::SafeToExecute
+    );
+    assert_eq!(
+        std::fs::read_to_string(repo.join("src/notes.txt")).expect("notes"),
+        "original\n"
+    );
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let merged = catalog
+        .execute(
+            "merge_child",
+            &json!({ "session": child.to_string() }),
+            cancel.clone(),
+        )
+        .await;
+    assert!(!merged.is_error, "{merged:?}");
+    assert_eq!(
+        crate::tool::reconcile(&evidence).await,
+        crate::tool::ReconcileVerdict::AlreadyApplied
+    );
+    assert_eq!(
+        std::fs::read_to_string(repo.join("src/notes.txt")).expect("notes"),
+        "child edit\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(repo.join("src/added.txt")).expect("added"),
+        "new\n"
+    );
+    let again = catalog
+        .execute(
+            "merge_child",
+            &json!({ "session": child.to_string() }),
+            cancel,
+        )
+        .await;
+    assert!(again.is_error, "{again:?}");
+}
+
+#[tokio::test]
+async fn pending_merges_outlive_the_process_and_orphaned_worktrees_are_pruned() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let repo = dir.path();
+    let at = "2026-01-02T10:00:00Z";
+    git_in(repo, &["init", "-q", "-b", "main"], "Alice", at);
+    std::fs::write(repo.join("notes.txt"), "original\n").expect("seed");
+    git_in(repo, &["add", "."], "Alice", at);
+    git_in(repo, &["commit", "-q", "-m", "seed"], "Alice", at);
+
+    let child_script = vec![
+        ScriptedMessage::tool(
+            "edit",
+            json!({"path": "notes.txt", "old_str": "original", "new_str": "child edit"}),
+        ),
+        ScriptedMessage::text("edited"),
+    ];
+    let config = |store: &SessionStore| crate::DelegateConfig {
+        cwd: repo.to_path_buf(),
+        store: store.clone(),
+        make_provider: Arc::new({
+            let child_script = child_script.clone();
+            move || ScriptedProvider::new(child_script.clone())
+        }),
+        make_model_provider: None,
+        profiles: Vec::new(),
+        max_active_children: 1,
+        child_budget: crate::RuntimeBudget::unbounded(),
+    };
+    let store = SessionStore::open_in_memory().expect("store");
+    let provider = ScriptedProvider::new(vec![ScriptedMessage::tool(
+        "delegate",
+        json!({ "children": [{ "objective": "edit notes", "workspace": "worktree" }] }),
+    )]);
+    let catalog = crate::ToolCatalog::with_cwd(repo);
     let runtime = Runtime::start_with_store(provider, catalog.clone(), store.clone());
     let parent_id = runtime.session_id();
-    catalog.register_scope(
-        "delegate",
-        vec![Arc::new(crate::DelegateTool::new(
-            crate::DelegateConfig {
-                store: store.clone(),
-                make_provider: Arc::new(looping_child),
-                max_active_children: 4,
-                child_budget: crate::RuntimeBudget {
-                    max_model_steps: Some(1),
-                    max_tool_calls: None,
-                },
-            },
-            parent_id,
-        ))],
-    );
-
+    let delegate = crate::DelegateTool::new(config(&store), parent_id);
+    let merge = delegate.merge_tool();
+    catalog.register_scope("delegate", vec![Arc::new(delegate), Arc::new(merge)]);
     let session = runtime.session();
     let (_snapshot, mut events) = session.subscribe().await.expect("subscribe");
-    session.submit("run away").await.expect("submit");
-    let recorded = collect_until_terminal(&mut events).await.expect("collect");
-    assert!(
-        recorded
-            .iter()
-            .any(|e| matches!(e, RuntimeEvent::OperationFinished { .. })),
-        "parent survives a failed child: {recorded:?}"
-    );
+    session.submit("delegate an edit").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("collect");
     session.close().await.expect("close");
     runtime.join().await.expect("join");
-
     let loaded = store.load(parent_id).await.expect("load");
-    let tool_output = loaded
+    let output = loaded
         .entries
         .iter()
-        .find_map(|(_, entry)| {
-            serde_json::to_string(entry)
-                .ok()
-                .filter(|text| text.contains("budget exceeded"))
+        .find_map(|(_, entry)| match entry {
+            SessionEntry::ToolResult {
+                result: ToolResult::Ok { output, .. },
+            } => Some(output.clone()),
+            _ => None,
         })
-        .expect("budget failure surfaces to the parent");
-    assert!(tool_output.contains("child session"));
+        .expect("delegate result");
+    let child = child_ids(&output)[0];
+
+    // A later process knows nothing in memory; the store still has the
+    // changes, so the merge applies them, once. Until it has seen them
+    // it cannot name the paths, so the gate treats them as remote.
+    let restarted = crate::DelegateTool::new(config(&store), parent_id);
+    let catalog = crate::ToolCatalog::with_cwd(repo);
+    catalog.register_scope("delegate", vec![Arc::new(restarted.merge_tool())]);
+    let target = catalog
+        .canonicalize("merge_child", &json!({ "session": child.to_string() }))
+        .expect("canonical");
+    assert!(
+        matches!(target, crate::tool::CanonicalTarget::Remote { .. }),
+        "{target:?}"
+    );
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let merged = catalog
+        .execute(
+            "merge_child",
+            &json!({ "session": child.to_string() }),
+            cancel.clone(),
+        )
+        .await;
+    assert!(!merged.is_error, "{merged:?}");
+    assert_eq!(
+        std::fs::read_to_string(repo.join("notes.txt")).expect("notes"),
+        "child edit\n"
+    );
+    let again = catalog
+        .execute(
+            "merge_child",
+            &json!({ "session": child.to_string() }),
+            cancel,
+        )
+        .await;
+    assert!(again.is_error, "{again:?}");
+
+    // A checkout whose process died is pruned; a live one is kept.
+    let live = crate::worktree::Worktree::create(repo, "live").expect("live worktree");
+    let orphan = repo.join(".git/ion-worktrees/orphan");
+    git_in(
+        repo,
+        &[
+            "worktree",
+            "add",
+            "--detach",
+            "--quiet",
+            &orphan.to_string_lossy(),
+            "HEAD",
+        ],
+        "Alice",
+        at,
+    );
+    assert_eq!(crate::worktree::prune_orphans(repo), Ok(1));
+    assert!(!orphan.exists());
+    assert!(live.cwd().exists());
+    let worktrees = git_in(repo, &["worktree", "list"], "Alice", at);
+    assert_eq!(worktrees.lines().count(), 2, "{worktrees}");
+    drop(live);
+    let worktrees = git_in(repo, &["worktree", "list"], "Alice", at);
+    assert_eq!(worktrees.lines().count(), 1, "{worktrees}");
+}
+
+#[tokio::test]
+async fn worktree_children_need_a_git_repository() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let delegate = crate::DelegateTool::new(
+        crate::DelegateConfig {
+            cwd: dir.path().to_path_buf(),
+            store: SessionStore::open_in_memory().expect("store"),
+            make_provider: Arc::new(|| ScriptedProvider::new(vec![ScriptedMessage::text("x")])),
+            make_model_provider: None,
+            profiles: Vec::new(),
+            max_active_children: 1,
+            child_budget: crate::RuntimeBudget::unbounded(),
+        },
+        crate::SessionId::generate(),
+    );
+    let outcome = crate::tool::Tool::call(
+        &delegate,
+        json!({ "children": [{ "objective": "edit", "workspace": "worktree" }] }),
+        tokio_util::sync::CancellationToken::new(),
+    )
+    .await;
+    assert!(
+        outcome
+            .output
+            .contains("child failed: worktree children need a git repository"),
+        "{outcome:?}"
+    );
 }
 
 /// A provider that records the cancellation tokens it is given and
@@ -4025,10 +7314,13 @@ async fn parent_cancel_cancels_running_children() {
         "delegate",
         vec![Arc::new(crate::DelegateTool::new(
             crate::DelegateConfig {
+                cwd: std::env::current_dir().expect("cwd"),
                 store: store.clone(),
                 make_provider: Arc::new(move || HangingProvider {
                     tokens: Arc::clone(&spy_tokens),
                 }),
+                make_model_provider: None,
+                profiles: Vec::new(),
                 max_active_children: 4,
                 child_budget: crate::RuntimeBudget::unbounded(),
             },
@@ -4473,3 +7765,476 @@ async fn event_lag_is_signaled_reliably_and_snapshot_carries_live_draft() {
     session.close().await.expect("close");
     runtime.join().await.expect("join");
 }
+
+// ---- Remote session handles (DESIGN.md §21.1, Step 10) ----
+
+/// An in-process stand-in for a wire adapter: forwards every command
+/// to a local session and re-feeds its events through an EventFeed.
+struct Loopback(SessionHandle);
+
+impl crate::SessionBackend for Loopback {
+    fn submit(&self, prompt: String) -> crate::BackendFuture<'_, OperationId> {
+        Box::pin(self.0.submit(prompt))
+    }
+    fn steer(&self, text: String) -> crate::BackendFuture<'_, ()> {
+        Box::pin(self.0.steer(text))
+    }
+    fn follow_up(&self, text: String) -> crate::BackendFuture<'_, ()> {
+        Box::pin(self.0.follow_up(text))
+    }
+    fn compact(&self, instructions: Option<String>) -> crate::BackendFuture<'_, bool> {
+        Box::pin(self.0.compact(instructions))
+    }
+    fn switch_model(&self, model_ref: String) -> crate::BackendFuture<'_, String> {
+        Box::pin(self.0.switch_model(model_ref))
+    }
+    fn cancel(&self, operation_id: OperationId) -> crate::BackendFuture<'_, ()> {
+        Box::pin(self.0.cancel(operation_id))
+    }
+    fn cancel_child(&self, child: crate::SessionId) -> crate::BackendFuture<'_, ()> {
+        Box::pin(self.0.cancel_child(child))
+    }
+    fn undo(&self, operation: Option<OperationId>) -> crate::BackendFuture<'_, crate::UndoOutcome> {
+        Box::pin(self.0.undo(operation))
+    }
+    fn snapshot(&self) -> crate::BackendFuture<'_, crate::SessionSnapshot> {
+        Box::pin(self.0.snapshot())
+    }
+    fn artifact(&self, id: String) -> crate::BackendFuture<'_, Option<String>> {
+        Box::pin(self.0.artifact(id))
+    }
+    fn children(&self) -> crate::BackendFuture<'_, Vec<crate::ChildSummary>> {
+        Box::pin(self.0.children())
+    }
+    fn child_transcript(
+        &self,
+        child: crate::SessionId,
+    ) -> crate::BackendFuture<'_, Vec<crate::SessionEntry>> {
+        Box::pin(self.0.child_transcript(child))
+    }
+    fn subscribe(
+        &self,
+    ) -> crate::BackendFuture<'_, (crate::SessionSnapshot, crate::EventSubscription)> {
+        Box::pin(async move {
+            let (snapshot, mut upstream) = self.0.subscribe().await?;
+            let (mut feed, events) = crate::EventFeed::channel();
+            tokio::spawn(async move {
+                while let Ok(event) = upstream.recv().await {
+                    if !feed.push(event) {
+                        break;
+                    }
+                }
+            });
+            Ok((snapshot, events))
+        })
+    }
+    fn resume(
+        &self,
+        instance: crate::RuntimeInstanceId,
+        after: crate::RuntimeCursor,
+    ) -> crate::BackendFuture<'_, crate::EventSubscription> {
+        Box::pin(async move {
+            let mut upstream = self.0.resume(instance, after).await?;
+            let (mut feed, events) = crate::EventFeed::channel();
+            tokio::spawn(async move {
+                while let Ok(event) = upstream.recv().await {
+                    if !feed.push(event) {
+                        break;
+                    }
+                }
+            });
+            Ok(events)
+        })
+    }
+    fn claim_control(&self) -> crate::BackendFuture<'_, ()> {
+        Box::pin(async move { self.0.claim_control().await.map(drop) })
+    }
+    fn release_control(&self) -> crate::BackendFuture<'_, ()> {
+        Box::pin(self.0.release_control())
+    }
+    fn close(&self) -> crate::BackendFuture<'_, ()> {
+        Box::pin(self.0.close())
+    }
+}
+
+#[tokio::test]
+async fn remote_handle_forwards_commands_and_events() {
+    let runtime = start_runtime(
+        ScriptedProvider::new(vec![
+            ScriptedMessage::text("hi "),
+            ScriptedMessage::text("there"),
+        ]),
+        ToolRegistry::default(),
+    );
+    let remote = SessionHandle::remote(Loopback(runtime.session()));
+
+    let (_snapshot, mut events) = remote.subscribe().await.expect("subscribe");
+    remote.submit("hello").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("events");
+    assert_eq!(texts(&recorded).concat(), "hi there");
+
+    let snapshot = remote.snapshot().await.expect("snapshot");
+    assert_eq!(snapshot, runtime.session().snapshot().await.expect("local"));
+    assert!(matches!(
+        remote.cancel(OperationId::generate()).await,
+        Err(CommandError::NoActiveOperation)
+    ));
+
+    remote.close().await.expect("close");
+    assert!(matches!(remote.snapshot().await, Err(CommandError::Closed)));
+    runtime.join().await.expect("join");
+}
+
+#[tokio::test]
+async fn event_feed_reports_lag_instead_of_dropping_deltas() {
+    let (mut feed, mut events) = crate::EventFeed::channel();
+    let operation_id = OperationId::generate();
+    let mut delivered = 0;
+    for i in 0..80 {
+        if !feed.push(RuntimeEvent::AssistantTextDelta {
+            cursor: crate::RuntimeCursor::default(),
+            operation_id,
+            text: format!("d{i}"),
+        }) {
+            break;
+        }
+        delivered += 1;
+    }
+    assert!(feed.is_ended());
+    for _ in 0..delivered {
+        assert!(events.recv().await.is_ok());
+    }
+    assert!(matches!(
+        events.recv().await,
+        Err(RuntimeError::SubscriptionLagged)
+    ));
+    drop(feed);
+    assert!(matches!(
+        events.recv().await,
+        Err(RuntimeError::SubscriptionClosed)
+    ));
+}
+
+// ---- Multi-client attach (DESIGN.md §21.2) ----
+
+#[tokio::test]
+async fn one_controller_submits_while_observers_only_watch() {
+    let runtime = start_runtime(
+        ScriptedProvider::new(vec![ScriptedMessage::text("ok")]),
+        ToolRegistry::default(),
+    );
+    let host = runtime.session();
+    let observer = host.observer();
+    assert!(observer.is_observer());
+    assert!(matches!(
+        observer.submit("nope").await,
+        Err(CommandError::ReadOnly)
+    ));
+    assert!(matches!(
+        observer.close().await,
+        Err(CommandError::ReadOnly)
+    ));
+    // Reads are consistent across handles.
+    assert_eq!(
+        observer.snapshot().await.expect("observer snapshot"),
+        host.snapshot().await.expect("host snapshot")
+    );
+
+    let controller = host.claim_control().await.expect("claim");
+    assert!(matches!(
+        observer.claim_control().await,
+        Err(CommandError::ControllerHeld)
+    ));
+    let (_, mut events) = observer.subscribe().await.expect("subscribe");
+    controller.submit("go").await.expect("controller submits");
+    let recorded = collect_until_terminal(&mut events).await.expect("events");
+    assert_eq!(texts(&recorded).concat(), "ok");
+
+    // Released: the stale lease is read-only and the seat is free.
+    controller.release_control().await.expect("release");
+    assert!(matches!(
+        controller.steer("late").await,
+        Err(CommandError::ReadOnly)
+    ));
+    let next = observer.claim_control().await.expect("claim after release");
+    assert!(!next.is_observer());
+    // The host revokes whichever lease is held.
+    host.release_control().await.expect("revoke");
+    assert!(matches!(
+        next.follow_up("revoked").await,
+        Err(CommandError::ReadOnly)
+    ));
+
+    host.close().await.expect("close");
+    runtime.join().await.expect("join");
+}
+
+#[tokio::test]
+async fn resume_replays_events_after_a_cursor() {
+    let runtime = start_runtime(
+        ScriptedProvider::new(vec![
+            ScriptedMessage::text("one "),
+            ScriptedMessage::text("two"),
+        ]),
+        ToolRegistry::default(),
+    );
+    let session = runtime.session();
+    let (snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("count").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("events");
+    assert!(recorded.len() > 2);
+
+    // A client that saw only the first event picks up exactly where it
+    // left off, without a snapshot.
+    let mut resumed = session
+        .resume(snapshot.instance, recorded[0].cursor())
+        .await
+        .expect("resume");
+    for expected in &recorded[1..] {
+        assert_eq!(&resumed.recv().await.expect("replayed"), expected);
+    }
+    // Caught up: the stream continues live.
+    let last = recorded.last().expect("terminal").cursor();
+    session
+        .resume(snapshot.instance, last)
+        .await
+        .expect("at tip");
+
+    // Cursors from the future or another incarnation are refused.
+    assert!(matches!(
+        session.resume(snapshot.instance, last.next()).await,
+        Err(CommandError::CursorUnavailable { .. })
+    ));
+    assert!(matches!(
+        session
+            .resume(crate::RuntimeInstanceId::generate(), snapshot.cursor)
+            .await,
+        Err(CommandError::CursorUnavailable { .. })
+    ));
+
+    // Remote handles resume the same way; observers stay read-only.
+    let remote = SessionHandle::remote(Loopback(session.clone()));
+    let mut replayed = remote
+        .resume(snapshot.instance, snapshot.cursor)
+        .await
+        .expect("remote resume");
+    assert_eq!(&replayed.recv().await.expect("first"), &recorded[0]);
+    assert!(matches!(
+        remote.observer().submit("no").await,
+        Err(CommandError::ReadOnly)
+    ));
+
+    session.close().await.expect("close");
+    runtime.join().await.expect("join");
+}
+
+// ---- Session registry (DESIGN.md §4.2) ----
+
+#[tokio::test]
+async fn registry_hosts_concurrent_sessions_with_their_own_writers() {
+    let store = SessionStore::open_in_memory().expect("store");
+    let runtime = crate::RuntimeHandle::new(
+        ScriptedProvider::echo(),
+        ToolRegistry::default(),
+        store,
+        permissive_policy(),
+    );
+    let (first_id, first) = runtime.create_session().expect("create");
+    let (second_id, second) = runtime.create_session().expect("create");
+    assert_ne!(first_id, second_id);
+    assert_eq!(runtime.list_sessions(), vec![first_id, second_id]);
+
+    // Both writers accept work at once: neither serializes the other.
+    let (_, mut first_events) = first.subscribe().await.expect("subscribe");
+    let (_, mut second_events) = second.subscribe().await.expect("subscribe");
+    first.submit("one").await.expect("submit");
+    second.submit("two").await.expect("submit");
+    for events in [&mut first_events, &mut second_events] {
+        let recorded = collect_until_terminal(events).await.expect("events");
+        assert!(matches!(
+            recorded.last(),
+            Some(RuntimeEvent::OperationFinished { .. })
+        ));
+    }
+
+    // Opening a loaded session returns its one writer.
+    let again = runtime.open_session(first_id).await.expect("open loaded");
+    assert_eq!(
+        again.snapshot().await.expect("snapshot"),
+        first.snapshot().await.expect("snapshot")
+    );
+
+    // Closing one leaves the other running; reopening reloads it.
+    runtime.close_session(first_id).await.expect("close");
+    assert_eq!(runtime.list_sessions(), vec![second_id]);
+    assert!(matches!(first.snapshot().await, Err(CommandError::Closed)));
+    second.snapshot().await.expect("sibling still loaded");
+    let reopened = runtime.open_session(first_id).await.expect("reload");
+    assert!(
+        reopened
+            .snapshot()
+            .await
+            .expect("snapshot")
+            .entries
+            .contains(&SessionEntry::UserMessage {
+                text: "one".to_owned()
+            })
+    );
+    assert_eq!(runtime.list_sessions(), vec![second_id, first_id]);
+    assert!(matches!(
+        runtime.close_session(crate::SessionId::generate()).await,
+        Err(CommandError::Closed)
+    ));
+
+    // Shutdown drains everything and refuses new sessions.
+    runtime.shutdown().await.expect("shutdown");
+    assert!(runtime.list_sessions().is_empty());
+    assert!(matches!(second.snapshot().await, Err(CommandError::Closed)));
+    assert!(matches!(
+        reopened.snapshot().await,
+        Err(CommandError::Closed)
+    ));
+    assert!(matches!(
+        runtime.create_session(),
+        Err(CommandError::Closed)
+    ));
+}
+
+#[tokio::test]
+async fn a_crashed_session_does_not_take_down_its_siblings() {
+    /// Panics inside the session task when admitting `bash`.
+    struct Explodes;
+    impl PolicyEngine for Explodes {
+        fn decide(
+            &self,
+            tool: &str,
+            _target: &crate::tool::CanonicalTarget,
+        ) -> crate::policy::PolicyDecision {
+            assert_ne!(tool, "bash", "test policy explodes on bash");
+            crate::policy::PolicyDecision::Allow
+        }
+    }
+
+    let runtime = crate::RuntimeHandle::new(
+        // One shared provider: the first step takes the tool call, the
+        // next one the text.
+        ScriptedProvider::new(vec![
+            ScriptedMessage::ToolCall {
+                name: "bash".to_owned(),
+                arguments: json!({ "command": "true" }),
+            },
+            ScriptedMessage::text("still here"),
+        ]),
+        ToolRegistry::default(),
+        SessionStore::open_in_memory().expect("store"),
+        Arc::new(Explodes),
+    );
+    let (doomed_id, doomed) = runtime.create_session().expect("create");
+    let (survivor_id, survivor) = runtime.create_session().expect("create");
+
+    doomed.submit("crash").await.expect("submit");
+    timeout(Duration::from_secs(2), async {
+        while runtime.list_sessions().contains(&doomed_id) {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("crashed session is unloaded");
+    assert!(doomed.snapshot().await.is_err());
+    assert_eq!(runtime.list_sessions(), vec![survivor_id]);
+
+    let (_, mut events) = survivor.subscribe().await.expect("subscribe");
+    survivor.submit("hello").await.expect("submit");
+    let recorded = collect_until_terminal(&mut events).await.expect("events");
+    assert_eq!(texts(&recorded).concat(), "still here");
+
+    runtime.shutdown().await.expect("shutdown");
+}
+
+// ---- Session hibernation (DESIGN.md §4.2) ----
+
+async fn wait_for_hibernation(runtime: &crate::RuntimeHandle, session_id: crate::SessionId) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !runtime.is_hibernated(session_id) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("session hibernates");
+}
+
+#[tokio::test]
+async fn idle_sessions_hibernate_and_rehydrate_with_identical_snapshots() {
+    let runtime = crate::RuntimeHandle::new(
+        ScriptedProvider::echo(),
+        ToolRegistry::default(),
+        SessionStore::open_in_memory().expect("store"),
+        permissive_policy(),
+    );
+    runtime.set_idle_timeout(Some(Duration::from_millis(50)));
+    let (session_id, session) = runtime.create_session().expect("create");
+    let (_, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("before").await.expect("submit");
+    collect_until_terminal(&mut events).await.expect("events");
+    drop(events);
+    let before = session.snapshot().await.expect("snapshot");
+
+    wait_for_hibernation(&runtime, session_id).await;
+    assert_eq!(runtime.list_sessions(), vec![session_id]);
+
+    // The next command rehydrates transparently from the store.
+    let after = session.snapshot().await.expect("snapshot");
+    assert!(!runtime.is_hibernated(session_id));
+    assert_eq!(before, after);
+
+    let (_, mut events) = session.subscribe().await.expect("subscribe");
+    session
+        .submit("after")
+        .await
+        .expect("submit after rehydration");
+    let recorded = collect_until_terminal(&mut events).await.expect("events");
+    assert!(matches!(
+        recorded.last(),
+        Some(RuntimeEvent::OperationFinished { .. })
+    ));
+    assert!(recorded.iter().all(|event| event.cursor() > after.cursor));
+    drop(events);
+
+    // A hibernated session closes without waking.
+    wait_for_hibernation(&runtime, session_id).await;
+    runtime.close_session(session_id).await.expect("close");
+    assert!(runtime.list_sessions().is_empty());
+}
+
+#[tokio::test]
+async fn watched_sessions_do_not_hibernate() {
+    let runtime = crate::RuntimeHandle::new(
+        ScriptedProvider::echo(),
+        ToolRegistry::default(),
+        SessionStore::open_in_memory().expect("store"),
+        permissive_policy(),
+    );
+    runtime.set_idle_timeout(Some(Duration::from_millis(20)));
+    let (session_id, session) = runtime.create_session().expect("create");
+    let (_, events) = session.subscribe().await.expect("subscribe");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!runtime.is_hibernated(session_id));
+
+    // A controller lease also keeps it awake.
+    let controller = session.claim_control().await.expect("claim");
+    drop(events);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!runtime.is_hibernated(session_id));
+
+    controller.release_control().await.expect("release");
+    wait_for_hibernation(&runtime, session_id).await;
+
+    // Leases issued after rehydration never match a stale one.
+    let fresh = session.claim_control().await.expect("claim after waking");
+    assert_eq!(
+        controller.submit("stale").await,
+        Err(CommandError::ReadOnly)
+    );
+    fresh.release_control().await.expect("release");
+    runtime.shutdown().await.expect("shutdown");
+}
@@ -10,10 +10,12 @@
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::future::Future;
+use std::io::BufRead;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::process::Stdio;
 use std::sync::Arc;
+use std::time::Duration;
 
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use regex::Regex;
@@ -23,7 +25,15 @@ use tokio::io::AsyncReadExt;
 use tokio::process::Command;
 use tokio_util::sync::CancellationToken;
 
-use crate::ids::OperationId;
+use crate::artifact::{Artifact, ArtifactTable, ReadArtifactTool};
+use crate::context::Image;
+use crate::delegate::{ChildUpdate, RunningChildren};
+use crate::git::git_tools;
+use crate::ids::{OperationId, SessionId};
+use crate::process::{ProcessTable, process_tools};
+use crate::sandbox::Sandbox;
+use crate::shell::PersistentShell;
+use crate::store::ParentCall;
 
 /// Identifier for an in-flight tool call. Monotonic per provider.
 pub type ToolCallId = u64;
@@ -52,8 +62,23 @@ pub struct ToolCall {
 /// (DESIGN.md §16.4): exactly what the model will see.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ToolResult {
-    Ok { call_id: ToolCallId, output: String },
-    Err { call_id: ToolCallId, error: String },
+    Ok {
+        call_id: ToolCallId,
+        output: String,
+        /// Images the model sees with the output (an image `read`).
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        images: Vec<Image>,
+        /// The full output, when `output` is only its excerpt.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        artifact: Option<Artifact>,
+    },
+    Err {
+        call_id: ToolCallId,
+        error: String,
+        /// The full output, when `error` is only its excerpt.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        artifact: Option<Artifact>,
+    },
 }
 
 impl ToolResult {
@@ -70,6 +95,14 @@ impl ToolResult {
         matches!(self, Self::Ok { .. })
     }
 
+    /// The saved full output this result excerpts, if any.
+    #[must_use]
+    pub const fn artifact(&self) -> Option<&Artifact> {
+        match self {
+            Self::Ok { artifact, .. } | Self::Err { artifact, .. } => artifact.as_ref(),
+        }
+    }
+
     /// Render the result to a single string (success output or error text).
     #[must_use]
     pub fn into_text(self) -> String {
@@ -81,7 +114,7 @@ impl ToolResult {
 
     /// Bounded display text for frontend rendering: the tail of the
     /// result, never the full body. Purely presentational - the model
-    /// always sees the complete settled output from the session entry.
+    /// sees the settled output from the session entry.
     #[must_use]
     pub fn display_preview(&self) -> Option<String> {
         let text = match self {
@@ -146,11 +179,110 @@ fn truncate_tail(text: &str, max_lines: usize, max_bytes: usize) -> Option<Strin
     }
 }
 
+/// Diff bounds: head-truncated, since the first hunks locate the change.
+const DIFF_MAX_LINES: usize = 40;
+const DIFF_MAX_BYTES: usize = 4 * 1024;
+
+/// Largest file whose whole text rides along with its diff; events
+/// carry every diff, so bigger files are described by the hunks alone.
+const DIFF_TEXT_MAX_BYTES: usize = 64 * 1024;
+
+/// Keep the head of `text` within `max_lines` and `max_bytes`, the
+/// marker for the dropped tail included.
+fn truncate_head(text: &str, max_lines: usize, max_bytes: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let marker = |dropped: usize| format!("\n… {dropped} more lines");
+    let mut kept = lines.len().min(max_lines);
+    loop {
+        let dropped = lines.len() - kept;
+        let marker_len = if dropped > 0 {
+            marker(dropped).len()
+        } else {
+            0
+        };
+        let body_len: usize = lines[..kept]
+            .iter()
+            .map(|l| l.len() + 1)
+            .sum::<usize>()
+            .saturating_sub(1);
+        if kept <= 1 || marker_len + body_len <= max_bytes {
+            break;
+        }
+        kept -= 1;
+    }
+    let dropped = lines.len() - kept;
+    let mut out = lines[..kept].join("\n");
+    let budget = max_bytes.saturating_sub(if dropped > 0 {
+        marker(dropped).len()
+    } else {
+        0
+    });
+    if out.len() > budget {
+        // A lone oversized line is cut on a char boundary.
+        let mut end = budget;
+        while !out.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.truncate(end);
+    }
+    if dropped > 0 {
+        out.push_str(&marker(dropped));
+    }
+    out
+}
+
+/// One file a tool changed, for frontends to render (ACP `diff`
+/// content, colored TUI rows). The model sees the same hunks in the
+/// tool output.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FileDiff {
+    /// Absolute path of the changed file.
+    pub path: PathBuf,
+    /// `@@` hunks of the change without file headers, head-truncated
+    /// to a bounded size.
+    pub hunks: String,
+    /// The whole file before the change; `None` when it was created.
+    #[serde(default)]
+    pub old_text: Option<String>,
+    /// The whole file after the change; `None` when it was deleted.
+    #[serde(default)]
+    pub new_text: Option<String>,
+    /// Whether `old_text` and `new_text` are set. A side over the size
+    /// bound leaves both `None`, and frontends fall back to `hunks`.
+    #[serde(default)]
+    pub whole: bool,
+}
+
+impl FileDiff {
+    /// Diff `before` (`None`: the file did not exist) against `after`
+    /// (`None`: the file was deleted).
+    fn between(path: &Path, before: Option<&str>, after: Option<&str>) -> Self {
+        let hunks = crate::patch::diff(before.unwrap_or_default(), after.unwrap_or_default());
+        let whole = [before, after]
+            .iter()
+            .all(|text| text.map_or(0, str::len) <= DIFF_TEXT_MAX_BYTES);
+        let text = |side: Option<&str>| side.filter(|_| whole).map(str::to_owned);
+        Self {
+            path: path.to_path_buf(),
+            hunks: truncate_head(&hunks, DIFF_MAX_LINES, DIFF_MAX_BYTES),
+            old_text: text(before),
+            new_text: text(after),
+            whole,
+        }
+    }
+}
+
 /// Outcome of tool execution, before it is classified into a [`ToolResult`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ToolOutcome {
     pub output: String,
     pub is_error: bool,
+    /// Files the call changed, for display; empty for most tools.
+    pub diffs: Vec<FileDiff>,
+    /// Images for the model alongside `output`.
+    pub images: Vec<Image>,
+    /// The full output when `output` was cut to an excerpt.
+    pub artifact: Option<Artifact>,
 }
 
 impl ToolOutcome {
@@ -159,6 +291,9 @@ impl ToolOutcome {
         Self {
             output: output.into(),
             is_error: false,
+            diffs: Vec::new(),
+            images: Vec::new(),
+            artifact: None,
         }
     }
 
@@ -167,6 +302,124 @@ impl ToolOutcome {
         Self {
             output: message.into(),
             is_error: true,
+            diffs: Vec::new(),
+            images: Vec::new(),
+            artifact: None,
+        }
+    }
+
+    /// A successful change to one file: `summary`, then the diff so the
+    /// model sees exactly what changed and where.
+    fn changed(summary: &str, diff: FileDiff) -> Self {
+        let output = if diff.hunks.is_empty() {
+            format!("{summary} (no changes)")
+        } else {
+            format!("{summary}\n{}", diff.hunks)
+        };
+        Self {
+            output,
+            is_error: false,
+            diffs: vec![diff],
+            images: Vec::new(),
+            artifact: None,
+        }
+    }
+}
+
+/// Live output of a running tool call. Display-only: chunks never
+/// reach the model or the store, and a slow consumer loses chunks
+/// rather than stalling the tool.
+#[derive(Clone, Default)]
+pub struct ToolProgress {
+    sink: Option<Arc<dyn Fn(String) + Send + Sync>>,
+    children: Option<Arc<dyn Fn(SessionId, ChildUpdate) + Send + Sync>>,
+    call: Option<ParentCall>,
+    retry: bool,
+    running: Option<RunningChildren>,
+}
+
+impl ToolProgress {
+    /// Report chunks to `sink`, which must not block.
+    pub fn new(sink: impl Fn(String) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Some(Arc::new(sink)),
+            children: None,
+            call: None,
+            retry: false,
+            running: None,
+        }
+    }
+
+    /// Name the call being run, for tools that record durable work
+    /// under it.
+    #[must_use]
+    pub fn for_call(mut self, operation_id: OperationId, call_id: u64) -> Self {
+        self.call = Some(ParentCall {
+            operation_id,
+            call_id,
+        });
+        self
+    }
+
+    /// The call being run, when the runtime named it.
+    #[must_use]
+    pub fn call(&self) -> Option<ParentCall> {
+        self.call
+    }
+
+    /// Mark the call as re-executed after process loss: an earlier
+    /// attempt may have recorded work under it.
+    #[must_use]
+    pub fn retried(mut self) -> Self {
+        self.retry = true;
+        self
+    }
+
+    /// Whether the runtime is re-executing the call after process loss.
+    #[must_use]
+    pub fn is_retry(&self) -> bool {
+        self.retry
+    }
+
+    /// Also report what the children a `delegate` call started are
+    /// doing (§20.7) to `sink`, which must not block.
+    #[must_use]
+    pub fn with_children(
+        mut self,
+        sink: impl Fn(SessionId, ChildUpdate) + Send + Sync + 'static,
+    ) -> Self {
+        self.children = Some(Arc::new(sink));
+        self
+    }
+
+    pub(crate) fn emit(&self, chunk: String) {
+        if let Some(sink) = &self.sink {
+            sink(chunk);
+        }
+    }
+
+    pub(crate) fn child(&self, child: SessionId, update: ChildUpdate) {
+        if let Some(sink) = &self.children {
+            sink(child, update);
+        }
+    }
+
+    /// Make the children this call runs cancellable through `running`.
+    pub(crate) fn with_running(mut self, running: RunningChildren) -> Self {
+        self.running = Some(running);
+        self
+    }
+
+    /// A child started; `cancel` stops it alone until it is done.
+    pub(crate) fn child_running(&self, child: SessionId, cancel: &CancellationToken) {
+        if let Some(running) = &self.running {
+            running.insert(child, cancel.clone());
+        }
+    }
+
+    pub(crate) fn child_done(&self, child: SessionId) {
+        if let Some(running) = &self.running {
+            running.remove(child);
         }
     }
 }
@@ -185,6 +438,46 @@ pub trait Tool: Send + Sync {
         arguments: Value,
         cancel: CancellationToken,
     ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>>;
+
+    /// Execute the tool, reporting output to `progress` as it is
+    /// produced. Tools without incremental output keep the default,
+    /// which ignores `progress`.
+    fn call_with_progress<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+        progress: ToolProgress,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        let _ = progress;
+        self.call(arguments, cancel)
+    }
+
+    /// How an unresolved effect of this tool recovers, for tools
+    /// registered under a scope; core tools are classified where they
+    /// are registered.
+    fn recovery_class(&self) -> RecoveryClass {
+        RecoveryClass::NeverReplay
+    }
+
+    /// What an invocation acts on, for tools outside the core set that
+    /// change local files and should be gated like them. `None` keeps
+    /// the default for registered non-native tools: a remote target.
+    fn local_target(&self, arguments: &Value) -> Option<CanonicalTarget> {
+        let _ = arguments;
+        None
+    }
+
+    /// Reconciliation evidence for an invocation, for scope tools that
+    /// recover as [`RecoveryClass::Reconcile`]: the `{"files": [...]}`
+    /// shape `apply_patch` records, which also names the preimages
+    /// `undo` restores.
+    fn reconciliation_evidence<'a>(
+        &'a self,
+        arguments: &'a Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, String>> + Send + 'a>> {
+        let _ = arguments;
+        Box::pin(async { Err("this tool takes no reconciliation evidence".to_owned()) })
+    }
 }
 
 /// A callable tool wrapped as a trait object with its spec cached.
@@ -207,6 +500,10 @@ pub enum RecoveryClass {
     /// Repeating may duplicate an external mutation; unresolved means
     /// indeterminate, never automatic replay.
     NeverReplay,
+    /// The tool records the durable work it starts under its call, and
+    /// re-executing the call resumes that work instead of repeating it
+    /// (`delegate`, §20.8).
+    Resume,
 }
 
 /// The effective target of one tool invocation, canonicalized before
@@ -215,13 +512,81 @@ pub enum RecoveryClass {
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum CanonicalTarget {
     /// Absolute, lexically normalized path (cwd-relative arguments are
-    /// resolved against the tool registry's working directory).
-    Path { path: std::path::PathBuf },
-    /// The exact shell command the executor will run.
-    Command { command: String },
+    /// resolved against the tool registry's working directory), and
+    /// for a ranged read the lines it covers.
+    Path {
+        path: std::path::PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lines: Option<LineRange>,
+    },
+    /// Every file one invocation may change (a multi-file patch), in
+    /// patch order.
+    Paths { paths: Vec<std::path::PathBuf> },
+    /// The exact shell command the executor will run, the directory it
+    /// runs in (a persistent shell's last reported one), and the
+    /// sandbox confining it, if any.
+    Command {
+        command: String,
+        #[serde(default)]
+        cwd: std::path::PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sandbox: Option<Sandbox>,
+    },
     /// A registered non-native tool (MCP/extension): the invocation
     /// goes through its owning transport, not local I/O (§19.2).
     Remote { tool: String },
+    /// A background process this session started, or all of them when
+    /// `id` is absent. Its start was the approved command.
+    Process { id: Option<u64> },
+    /// A saved output of this session's own tool calls.
+    Artifact { id: String },
+}
+
+/// The lines a ranged `read` covers: `limit` lines from the 1-based
+/// `offset`, or through the end of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LineRange {
+    pub offset: u64,
+    pub limit: Option<u64>,
+}
+
+impl LineRange {
+    /// The range named by `offset`/`limit` arguments; `None` when the
+    /// call names neither.
+    pub fn from_arguments(arguments: &Value) -> Result<Option<Self>, String> {
+        let positive = |key: &str| -> Result<Option<u64>, String> {
+            match arguments.get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(value) => match value.as_u64() {
+                    Some(n) if n > 0 => Ok(Some(n)),
+                    _ => Err(format!("{key} must be a positive integer")),
+                },
+            }
+        };
+        let offset = positive("offset")?;
+        let limit = positive("limit")?;
+        if offset.is_none() && limit.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            offset: offset.unwrap_or(1),
+            limit,
+        }))
+    }
+}
+
+impl std::fmt::Display for LineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            Some(limit) => write!(
+                f,
+                "{}-{}",
+                self.offset,
+                self.offset.saturating_add(limit.saturating_sub(1))
+            ),
+            None => write!(f, "{}-", self.offset),
+        }
+    }
 }
 
 /// Lexically normalize a path without touching the filesystem:
@@ -268,6 +633,9 @@ pub async fn reconciliation_evidence(
     name: &str,
     arguments: &Value,
 ) -> Result<Value, String> {
+    if name == "apply_patch" {
+        return patch_evidence(cwd, arguments).await;
+    }
     let path_arg = arguments
         .get("path")
         .and_then(|v| v.as_str())
@@ -285,21 +653,11 @@ pub async fn reconciliation_evidence(
             .as_bytes()
             .to_vec(),
         "edit" => {
-            let old_str = arguments
-                .get("old_str")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| "missing string argument: old_str".to_owned())?;
-            let new_str = arguments
-                .get("new_str")
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
+            let edits = parse_edits(arguments)?;
             let original = fs::read_to_string(&full)
                 .await
                 .map_err(|err| format!("read failed: {err}"))?;
-            if !original.contains(old_str) {
-                return Err("old_str not found in file".to_owned());
-            }
-            original.replacen(old_str, new_str, 1).into_bytes()
+            apply_edits(&original, &edits)?.into_bytes()
         }
         other => return Err(format!("tool {other} takes no reconciliation evidence")),
     };
@@ -312,6 +670,47 @@ pub async fn reconciliation_evidence(
     }))
 }
 
+/// Per-file evidence for a patch: each file's preimage and intended
+/// postimage, so recovery classifies every file on its own.
+async fn patch_evidence(cwd: &Path, arguments: &Value) -> Result<Value, String> {
+    let planned = plan_patch(cwd, arguments).await?;
+    let files: Vec<Value> = planned
+        .iter()
+        .map(|file| {
+            file_evidence(
+                &file.path,
+                file.before.as_deref().map(str::as_bytes),
+                file.after.as_deref().map(str::as_bytes),
+            )
+        })
+        .collect();
+    Ok(serde_json::json!({ "files": files }))
+}
+
+/// One file's entry in multi-file evidence: its preimage, and either
+/// the intended postimage hash or that the file will not exist.
+pub(crate) fn file_evidence(path: &Path, before: Option<&[u8]>, after: Option<&[u8]>) -> Value {
+    use sha2::{Digest, Sha256};
+    let preimage = match before {
+        Some(before) => {
+            serde_json::json!({ "exists": true, "hash": hex(Sha256::digest(before).as_slice()) })
+        }
+        None => serde_json::json!({ "exists": false }),
+    };
+    match after {
+        Some(after) => serde_json::json!({
+            "path": path,
+            "preimage": preimage,
+            "postimage_hash": hex(Sha256::digest(after).as_slice()),
+        }),
+        None => serde_json::json!({
+            "path": path,
+            "preimage": preimage,
+            "postimage": { "exists": false },
+        }),
+    }
+}
+
 /// What recovery may do with a pending Reconcile effect, given the
 /// recorded evidence and the file state found after process loss
 /// (DESIGN.md §12.3).
@@ -337,6 +736,15 @@ pub fn classify_reconciliation(evidence: &Value, current: Option<[u8; 32]>) -> R
     if postimage == current_hex.as_deref() && postimage.is_some() {
         return ReconcileVerdict::AlreadyApplied;
     }
+    // A deletion is applied once the file is gone.
+    let deletes = evidence
+        .get("postimage")
+        .and_then(|v| v.get("exists"))
+        .and_then(|v| v.as_bool())
+        == Some(false);
+    if deletes && current.is_none() {
+        return ReconcileVerdict::AlreadyApplied;
+    }
     let Some(preimage) = evidence.get("preimage") else {
         return ReconcileVerdict::Unknown;
     };
@@ -352,12 +760,49 @@ pub fn classify_reconciliation(evidence: &Value, current: Option<[u8; 32]>) -> R
     }
 }
 
+/// Classify a pending Reconcile effect against the files on disk now.
+/// Patch evidence is classified per file: the effect is applied or
+/// safe to execute only when every file agrees; a patch that was cut
+/// off between files is a conflict, never re-executed over the files
+/// it already wrote.
+pub(crate) async fn reconcile(evidence: &Value) -> ReconcileVerdict {
+    let Some(files) = evidence.get("files").and_then(|v| v.as_array()) else {
+        let current = match evidence.get("path").and_then(|v| v.as_str()) {
+            Some(path) => file_hash(Path::new(path)).await,
+            None => None,
+        };
+        return classify_reconciliation(evidence, current);
+    };
+    let mut verdicts = Vec::with_capacity(files.len());
+    for file in files {
+        let Some(path) = file.get("path").and_then(|v| v.as_str()) else {
+            return ReconcileVerdict::Unknown;
+        };
+        let verdict = classify_reconciliation(file, file_hash(Path::new(path)).await);
+        tracing::debug!(path, ?verdict, "patch file reconciled");
+        verdicts.push(verdict);
+    }
+    match verdicts.first() {
+        None => ReconcileVerdict::Unknown,
+        Some(first) if verdicts.iter().all(|verdict| verdict == first) => *first,
+        Some(_) if verdicts.contains(&ReconcileVerdict::Unknown) => ReconcileVerdict::Unknown,
+        Some(_) => ReconcileVerdict::Conflict,
+    }
+}
+
 /// Registry and executor for tools. Holds an `Arc<Path>` so a tool task
 /// can clone the working directory cheaply before invoking a tool.
 #[derive(Clone)]
 pub struct ToolRegistry {
     cwd: Arc<Path>,
     entries: Arc<HashMap<String, ToolEntry>>,
+    /// The registered `bash` tool's configuration; `None` when the
+    /// registry has no `bash`.
+    bash: Option<BashTool>,
+    /// Full outputs of this session's oversized results.
+    artifacts: Arc<ArtifactTable>,
+    /// This session's background processes.
+    processes: Arc<ProcessTable>,
 }
 
 impl Default for ToolRegistry {
@@ -372,10 +817,15 @@ impl ToolRegistry {
     #[must_use]
     pub fn with_cwd(cwd: impl AsRef<Path>) -> Self {
         let cwd: Arc<Path> = Arc::from(cwd.as_ref());
-        let entries = core_tools(&cwd);
+        let artifacts = Arc::default();
+        let processes = Arc::new(ProcessTable::new(Arc::clone(&cwd)));
+        let entries = core_tools(&cwd, &artifacts, &processes);
         Self {
+            bash: Some(BashTool::new(Arc::clone(&cwd))),
             cwd,
             entries: Arc::new(entries),
+            artifacts,
+            processes,
         }
     }
 
@@ -384,21 +834,140 @@ impl ToolRegistry {
     /// absent, not denied at the gate.
     #[must_use]
     pub fn read_only(cwd: impl AsRef<Path>) -> Self {
-        let cwd: Arc<Path> = Arc::from(cwd.as_ref());
-        let mut all = core_tools(&cwd);
-        all.retain(|name, _| matches!(name.as_str(), "read" | "search" | "find"));
+        Self::narrowed(cwd.as_ref(), false)
+    }
+
+    /// The read-only set plus `write`, `edit`, and `apply_patch`: the
+    /// worktree-child capability set (§20.4). Still no shell or
+    /// processes, so every change stays a file under `cwd`.
+    #[must_use]
+    pub fn file_editing(cwd: impl AsRef<Path>) -> Self {
+        Self::narrowed(cwd.as_ref(), true)
+    }
+
+    fn narrowed(cwd: &Path, edits: bool) -> Self {
+        let cwd: Arc<Path> = Arc::from(cwd);
+        let artifacts = Arc::default();
+        let processes = Arc::new(ProcessTable::new(Arc::clone(&cwd)));
+        let mut all = core_tools(&cwd, &artifacts, &processes);
+        all.retain(|name, _| match name.as_str() {
+            "read" | "search" | "find" | "read_artifact" | "git_status" | "git_diff"
+            | "git_log" | "git_blame" => true,
+            "write" | "edit" | "apply_patch" => edits,
+            _ => false,
+        });
         Self {
             cwd,
             entries: Arc::new(all),
+            bash: None,
+            artifacts,
+            processes,
         }
     }
 
+    /// Keep only the tools named in `names`: an agent profile's
+    /// allowlist (§20.2). Names this registry lacks are ignored, so the
+    /// result is never wider than the registry.
+    #[must_use]
+    pub fn only(mut self, names: &[String]) -> Self {
+        Arc::make_mut(&mut self.entries).retain(|name, _| names.contains(name));
+        if !self.entries.contains_key("bash") {
+            self.bash = None;
+        }
+        self
+    }
+
     /// The directory tool paths are resolved against.
     #[must_use]
     pub fn cwd(&self) -> &Path {
         &self.cwd
     }
 
+    /// Replace the `bash` tool's timeout bounds. A registry without
+    /// `bash` is returned unchanged.
+    #[must_use]
+    pub fn with_bash_limits(mut self, limits: BashLimits) -> Self {
+        if let Some(bash) = self.bash.clone() {
+            self.install_bash(BashTool { limits, ..bash });
+        }
+        self
+    }
+
+    /// Run `bash` commands in one persistent shell owned by this
+    /// registry (and its clones and snapshots) instead of a fresh
+    /// `sh -c` per call. A registry without `bash` is returned
+    /// unchanged.
+    #[must_use]
+    pub fn with_persistent_shell(mut self) -> Self {
+        if let Some(bash) = self.bash.clone() {
+            let shell = Arc::new(PersistentShell::new(&self.cwd, bash.sandbox));
+            self.install_bash(BashTool {
+                shell: Some(shell),
+                ..bash
+            });
+        }
+        self
+    }
+
+    /// Run `bash` commands under the OS-level [`Sandbox`]; a persistent
+    /// shell restarts inside it. A registry without `bash` is returned
+    /// unchanged.
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        if let Some(bash) = self.bash.clone() {
+            let persistent = bash.shell.is_some();
+            self.install_bash(BashTool {
+                sandbox: Some(sandbox),
+                ..bash
+            });
+            if persistent {
+                self = self.with_persistent_shell();
+            }
+        }
+        self
+    }
+
+    /// This registry with its own per-session state - a not yet
+    /// started persistent shell when it has one, and empty process and
+    /// artifact tables - so a forked session never shares another's.
+    fn for_session(&self) -> Self {
+        let persistent = self.bash.as_ref().is_some_and(|bash| bash.shell.is_some());
+        let mut registry = if persistent {
+            self.clone().with_persistent_shell()
+        } else {
+            self.clone()
+        };
+        registry.processes = Arc::new(ProcessTable::new(Arc::clone(&self.cwd)));
+        registry.artifacts = Arc::default();
+        let read_artifact: Arc<dyn Tool> = Arc::new(ReadArtifactTool {
+            artifacts: Arc::clone(&registry.artifacts),
+        });
+        let entries = Arc::make_mut(&mut registry.entries);
+        let session_tools = process_tools(&registry.processes)
+            .into_iter()
+            .chain([(read_artifact, RecoveryClass::ReplaySafe)]);
+        for (tool, recovery_class) in session_tools {
+            let spec = tool.spec();
+            if let Some(entry) = entries.get_mut(&spec.name) {
+                *entry = ToolEntry {
+                    tool,
+                    spec,
+                    recovery_class,
+                };
+            }
+        }
+        registry
+    }
+
+    fn install_bash(&mut self, tool: BashTool) {
+        let entries = Arc::make_mut(&mut self.entries);
+        if let Some(entry) = entries.get_mut("bash") {
+            entry.spec = tool.spec();
+            entry.tool = Arc::new(tool.clone());
+            self.bash = Some(tool);
+        }
+    }
+
     /// All registered tool specs, ordered by name. The order is part of
     /// the capability snapshot, so it must be deterministic across
     /// processes for prompt-prefix stability (DESIGN.md P9, §14.4).
@@ -424,6 +993,25 @@ impl ToolRegistry {
             .map_or(RecoveryClass::NeverReplay, |e| e.recovery_class)
     }
 
+    /// Reconciliation evidence for one invocation of a Reconcile tool
+    /// (§12.3): the core file tools compute it here, scope tools
+    /// through [`Tool::reconciliation_evidence`].
+    pub async fn reconciliation_evidence(
+        &self,
+        name: &str,
+        arguments: &Value,
+    ) -> Result<Value, String> {
+        match name {
+            "write" | "edit" | "apply_patch" => {
+                reconciliation_evidence(&self.cwd, name, arguments).await
+            }
+            _ => match self.entries.get(name) {
+                Some(entry) => entry.tool.reconciliation_evidence(arguments).await,
+                None => Err(format!("tool {name} takes no reconciliation evidence")),
+            },
+        }
+    }
+
     /// Validate `arguments` against a tool's schema: the value must be an
     /// object containing every name in the schema's `"required"` array.
     /// Canonicalize one invocation's effective target (§17.3). Pure:
@@ -443,17 +1031,45 @@ impl ToolRegistry {
             Ok(normalize(&joined))
         };
         match name {
-            "read" | "write" | "edit" => Ok(CanonicalTarget::Path {
+            "read" | "git_blame" => Ok(CanonicalTarget::Path {
+                path: resolve("path")?,
+                lines: LineRange::from_arguments(arguments)?,
+            }),
+            "write" | "edit" => Ok(CanonicalTarget::Path {
                 path: resolve("path")?,
+                lines: None,
             }),
-            "search" | "find" => {
+            "apply_patch" => {
+                let text = arguments
+                    .get("patch")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| "missing string argument: patch".to_owned())?;
+                let mut paths = Vec::new();
+                for file in crate::patch::parse(text)? {
+                    for raw in [&file.old_path, &file.new_path].into_iter().flatten() {
+                        let joined = if Path::new(raw).is_absolute() {
+                            PathBuf::from(raw)
+                        } else {
+                            self.cwd.join(raw)
+                        };
+                        let path = normalize(&joined);
+                        if !paths.contains(&path) {
+                            paths.push(path);
+                        }
+                    }
+                }
+                Ok(CanonicalTarget::Paths { paths })
+            }
+            "search" | "find" | "git_status" | "git_diff" | "git_log" => {
                 if arguments.get("path").is_some() {
                     Ok(CanonicalTarget::Path {
                         path: resolve("path")?,
+                        lines: None,
                     })
                 } else {
                     Ok(CanonicalTarget::Path {
                         path: normalize(&self.cwd),
+                        lines: None,
                     })
                 }
             }
@@ -464,46 +1080,68 @@ impl ToolRegistry {
                     .ok_or_else(|| "missing string argument: command".to_owned())?;
                 Ok(CanonicalTarget::Command {
                     command: command.to_owned(),
+                    cwd: self
+                        .bash
+                        .as_ref()
+                        .map_or_else(|| normalize(&self.cwd), BashTool::working_dir),
+                    sandbox: self.bash.as_ref().and_then(|bash| bash.sandbox),
+                })
+            }
+            "process_start" => {
+                let command = arguments
+                    .get("command")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| "missing string argument: command".to_owned())?;
+                Ok(CanonicalTarget::Command {
+                    command: command.to_owned(),
+                    cwd: normalize(&self.cwd),
+                    sandbox: None,
                 })
             }
+            "process_output" | "process_stop" => Ok(CanonicalTarget::Process {
+                id: arguments.get("id").and_then(Value::as_u64),
+            }),
+            "read_artifact" => Ok(CanonicalTarget::Artifact {
+                id: arguments
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| "missing string argument: id".to_owned())?
+                    .to_owned(),
+            }),
             other => {
                 // Registered non-native tools (MCP/extension scopes)
-                // canonicalize to a remote target; truly unknown names
-                // still deny model-visibly.
-                if self.entries.contains_key(other) {
-                    Ok(CanonicalTarget::Remote {
-                        tool: other.to_owned(),
-                    })
-                } else {
-                    Err(format!("unknown tool: {other}"))
+                // canonicalize to a remote target unless they name the
+                // local files they change; truly unknown names still
+                // deny model-visibly.
+                match self.entries.get(other) {
+                    Some(entry) => Ok(entry.tool.local_target(arguments).unwrap_or_else(|| {
+                        CanonicalTarget::Remote {
+                            tool: other.to_owned(),
+                        }
+                    })),
+                    None => Err(format!("unknown tool: {other}")),
                 }
             }
         }
     }
 
+    /// Check `arguments` against the tool's published input schema:
+    /// native, MCP, and extension tools alike. The error names every
+    /// offending argument by path.
     pub fn validate(&self, name: &str, arguments: &Value) -> Result<(), String> {
         let entry = self
             .entries
             .get(name)
             .ok_or_else(|| format!("unknown tool: {name}"))?;
-        let required = entry
-            .spec
-            .input_schema
-            .get("required")
-            .and_then(|r| r.as_array())
-            .ok_or_else(|| "missing required args in tool spec".to_owned())?
-            .iter()
-            .map_while(|v| v.as_str().map(str::to_owned))
-            .collect::<Vec<_>>();
-        let object = arguments
-            .as_object()
-            .ok_or_else(|| "tool arguments must be a JSON object".to_owned())?;
-        for key in required {
-            if !object.contains_key(&key) {
-                return Err(format!("missing required argument for `{name}`: {key}"));
-            }
+        if !arguments.is_object() {
+            return Err("tool arguments must be a JSON object".to_owned());
         }
-        Ok(())
+        crate::schema::validate(&entry.spec.input_schema, arguments).map_err(|errors| {
+            format!(
+                "invalid arguments for `{name}`: {}",
+                crate::schema::describe(&errors)
+            )
+        })
     }
 
     /// Resolve a tool by name, validate its arguments, and execute it.
@@ -513,6 +1151,18 @@ impl ToolRegistry {
         name: &str,
         arguments: &Value,
         cancel: CancellationToken,
+    ) -> ToolOutcome {
+        self.execute_with_progress(name, arguments, cancel, ToolProgress::default())
+            .await
+    }
+
+    /// [`Self::execute`], reporting live output to `progress`.
+    pub async fn execute_with_progress(
+        &self,
+        name: &str,
+        arguments: &Value,
+        cancel: CancellationToken,
+        progress: ToolProgress,
     ) -> ToolOutcome {
         let entry = match self.entries.get(name) {
             Some(e) => e,
@@ -521,7 +1171,34 @@ impl ToolRegistry {
         if let Err(msg) = self.validate(name, arguments) {
             return ToolOutcome::error(msg);
         }
-        entry.tool.as_ref().call(arguments.clone(), cancel).await
+        let mut outcome = entry
+            .tool
+            .as_ref()
+            .call_with_progress(arguments.clone(), cancel, progress)
+            .await;
+        if name != "read_artifact" {
+            self.artifacts.offload(&mut outcome);
+        }
+        outcome
+    }
+
+    /// Make the artifacts a loaded transcript names readable again.
+    pub(crate) fn restore_artifacts<'a>(&self, artifacts: impl IntoIterator<Item = &'a Artifact>) {
+        for artifact in artifacts {
+            self.artifacts.insert(artifact);
+        }
+    }
+
+    /// The full text of one of this session's artifacts.
+    #[must_use]
+    pub fn artifact(&self, id: &str) -> Option<Arc<str>> {
+        self.artifacts.get(id)
+    }
+
+    /// List again the background processes a loaded transcript shows
+    /// as started and never stopped; see [`ProcessTable::reconcile`].
+    pub(crate) fn restore_processes(&self, started: impl IntoIterator<Item = (u64, String)>) {
+        self.processes.reconcile(started);
     }
 }
 
@@ -591,11 +1268,60 @@ impl ToolCatalog {
         Self::from(ToolRegistry::read_only(cwd))
     }
 
+    /// A file-editing catalog over `cwd` (§20.4): the worktree child
+    /// capability set.
+    #[must_use]
+    pub fn file_editing(cwd: impl AsRef<Path>) -> Self {
+        Self::from(ToolRegistry::file_editing(cwd))
+    }
+
     #[must_use]
     pub fn cwd(&self) -> &Path {
         self.core.cwd()
     }
 
+    /// Replace the core `bash` tool's timeout bounds.
+    #[must_use]
+    pub fn with_bash_limits(self, limits: BashLimits) -> Self {
+        Self {
+            core: self.core.with_bash_limits(limits),
+            ..self
+        }
+    }
+
+    /// Run core `bash` commands under the OS-level sandbox.
+    #[must_use]
+    pub fn with_sandbox(self, sandbox: Sandbox) -> Self {
+        Self {
+            core: self.core.with_sandbox(sandbox),
+            ..self
+        }
+    }
+
+    /// Run core `bash` commands in a persistent shell. Each
+    /// [`Self::fork`] gets its own, so every session owns one.
+    #[must_use]
+    pub fn with_persistent_shell(self) -> Self {
+        Self {
+            core: self.core.with_persistent_shell(),
+            ..self
+        }
+    }
+
+    /// A catalog with the same core tools and a copy of the current
+    /// scopes, but its own scope table: later registrations on either
+    /// side stay private. Hosts serving several sessions fork one base
+    /// catalog per session so session-bound scopes (`delegate`) never
+    /// collide.
+    #[must_use]
+    pub fn fork(&self) -> Self {
+        let scopes = self.dynamic.read().expect("tool catalog poisoned").clone();
+        Self {
+            core: self.core.for_session(),
+            dynamic: Arc::new(std::sync::RwLock::new(scopes)),
+        }
+    }
+
     /// Register tools under `scope`, replacing that scope's previous
     /// registration. Publishing at a safe context boundary is the
     /// caller's contract (§19.2).
@@ -604,7 +1330,7 @@ impl ToolCatalog {
             .into_iter()
             .map(|tool| {
                 let spec = tool.spec();
-                let recovery_class = RecoveryClass::NeverReplay;
+                let recovery_class = tool.recovery_class();
                 ToolEntry {
                     tool,
                     spec,
@@ -643,6 +1369,9 @@ impl ToolCatalog {
         ToolRegistry {
             cwd: Arc::from(self.core.cwd()),
             entries: Arc::new(entries),
+            bash: self.core.bash.clone(),
+            artifacts: Arc::clone(&self.core.artifacts),
+            processes: Arc::clone(&self.core.processes),
         }
     }
 
@@ -670,6 +1399,34 @@ impl ToolCatalog {
         self.snapshot().canonicalize(name, arguments)
     }
 
+    /// Reconciliation evidence for one invocation of a Reconcile tool.
+    pub async fn reconciliation_evidence(
+        &self,
+        name: &str,
+        arguments: &Value,
+    ) -> Result<Value, String> {
+        self.snapshot()
+            .reconciliation_evidence(name, arguments)
+            .await
+    }
+
+    /// Make the artifacts a loaded transcript names readable again.
+    pub(crate) fn restore_artifacts<'a>(&self, artifacts: impl IntoIterator<Item = &'a Artifact>) {
+        self.core.restore_artifacts(artifacts);
+    }
+
+    /// The full text of one of this session's artifacts.
+    #[must_use]
+    pub fn artifact(&self, id: &str) -> Option<Arc<str>> {
+        self.core.artifact(id)
+    }
+
+    /// List again the background processes a loaded transcript shows
+    /// as started and never stopped.
+    pub(crate) fn restore_processes(&self, started: impl IntoIterator<Item = (u64, String)>) {
+        self.core.restore_processes(started);
+    }
+
     /// Validate `arguments` against a tool's schema in the current
     /// snapshot.
     pub fn validate(&self, name: &str, arguments: &Value) -> Result<(), String> {
@@ -687,6 +1444,19 @@ impl ToolCatalog {
     ) -> ToolOutcome {
         self.snapshot().execute(name, arguments, cancel).await
     }
+
+    /// [`Self::execute`], reporting live output to `progress`.
+    pub async fn execute_with_progress(
+        &self,
+        name: &str,
+        arguments: &Value,
+        cancel: CancellationToken,
+        progress: ToolProgress,
+    ) -> ToolOutcome {
+        self.snapshot()
+            .execute_with_progress(name, arguments, cancel, progress)
+            .await
+    }
 }
 
 impl From<ToolRegistry> for ToolCatalog {
@@ -704,32 +1474,74 @@ impl Default for ToolCatalog {
     }
 }
 
+/// An artifact id cut to a readable prefix for display.
+#[must_use]
+pub fn short_artifact_id(id: &str) -> &str {
+    id.get(..12).unwrap_or(id)
+}
+
 /// A short display summary of a tool call's target, derived from the
 /// raw arguments. Used where durable entries are rendered without a
 /// registry (recovered transcripts); matches what live emission shows
 /// because canonicalization preserves the file name and command text.
 #[must_use]
 pub fn target_from_arguments(name: &str, arguments: &Value) -> Option<String> {
-    if name == "bash" {
+    if name == "process_output" || name == "process_stop" {
+        return arguments
+            .get("id")
+            .and_then(Value::as_u64)
+            .map(|id| format!("process {id}"));
+    }
+    if name == "read_artifact" {
+        return arguments
+            .get("id")
+            .and_then(Value::as_str)
+            .map(|id| format!("artifact {}", short_artifact_id(id)));
+    }
+    if name == "apply_patch" {
+        let files = crate::patch::parse(arguments.get("patch")?.as_str()?).ok()?;
+        return Some(
+            files
+                .iter()
+                .map(|file| {
+                    let path = file.display_path();
+                    Path::new(path)
+                        .file_name()
+                        .map_or_else(|| path.to_owned(), |n| n.to_string_lossy().into_owned())
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+    if name == "bash" || name == "process_start" {
         return arguments
             .get("command")
             .and_then(|v| v.as_str())
             .map(str::to_owned);
     }
-    arguments.get("path").and_then(|v| v.as_str()).map(|path| {
+    let file = arguments.get("path").and_then(|v| v.as_str()).map(|path| {
         std::path::Path::new(path)
             .file_name()
             .map_or_else(|| path.to_owned(), |n| n.to_string_lossy().into_owned())
-    })
+    })?;
+    match LineRange::from_arguments(arguments) {
+        Ok(Some(lines)) if name == "read" || name == "git_blame" => Some(format!("{file}:{lines}")),
+        _ => Some(file),
+    }
 }
 
-fn core_tools(cwd: &Path) -> HashMap<String, ToolEntry> {
+fn core_tools(
+    cwd: &Path,
+    artifacts: &Arc<ArtifactTable>,
+    processes: &Arc<ProcessTable>,
+) -> HashMap<String, ToolEntry> {
     let cwd_path: Arc<Path> = Arc::from(cwd);
     // Recovery classes per DESIGN.md §12.2/§12.3: reads are
     // replay-safe; bash never replays automatically (§12.4); write/edit
     // reconcile because admission persists preimage/postimage evidence
     // with the intent, so recovery can classify the file state it
-    // finds.
+    // finds. Background processes and git inspection bring their own
+    // (process.rs, git.rs).
     let tools: Vec<(Arc<dyn Tool>, RecoveryClass)> = vec![
         (
             Arc::new(ReadTool {
@@ -750,9 +1562,13 @@ fn core_tools(cwd: &Path) -> HashMap<String, ToolEntry> {
             RecoveryClass::Reconcile,
         ),
         (
-            Arc::new(BashTool {
+            Arc::new(ApplyPatchTool {
                 cwd: cwd_path.clone(),
             }),
+            RecoveryClass::Reconcile,
+        ),
+        (
+            Arc::new(BashTool::new(cwd_path.clone())),
             RecoveryClass::NeverReplay,
         ),
         (
@@ -771,9 +1587,18 @@ fn core_tools(cwd: &Path) -> HashMap<String, ToolEntry> {
         // the call at admission; execution itself is a no-op so the
         // normal tool-result path settles it durably.
         (Arc::new(CompactTool), RecoveryClass::ReplaySafe),
+        (
+            Arc::new(ReadArtifactTool {
+                artifacts: Arc::clone(artifacts),
+            }),
+            RecoveryClass::ReplaySafe,
+        ),
     ];
     let mut map = HashMap::new();
-    for (tool, recovery_class) in tools {
+    let session_tools = process_tools(processes)
+        .into_iter()
+        .chain(git_tools(&cwd_path));
+    for (tool, recovery_class) in tools.into_iter().chain(session_tools) {
         let spec = tool.spec();
         map.insert(
             spec.name.clone(),
@@ -789,7 +1614,7 @@ fn core_tools(cwd: &Path) -> HashMap<String, ToolEntry> {
 
 /// Resolve a user-supplied relative path under `cwd`, lexically normalizing
 /// `.` and `..` and rejecting escapes above the project root.
-fn resolve_under(cwd: &Path, raw: &str) -> Result<PathBuf, ToolOutcome> {
+pub(crate) fn resolve_under(cwd: &Path, raw: &str) -> Result<PathBuf, ToolOutcome> {
     let relative = Path::new(raw);
     if relative.is_absolute() {
         return Err(ToolOutcome::error(format!(
@@ -816,6 +1641,22 @@ fn resolve_under(cwd: &Path, raw: &str) -> Result<PathBuf, ToolOutcome> {
     Ok(normalized)
 }
 
+/// [`resolve_under`] for a path a file tool will change. Git metadata
+/// is refused: hooks and config there run code the next time git does.
+pub(crate) fn resolve_writable(cwd: &Path, raw: &str) -> Result<PathBuf, ToolOutcome> {
+    let path = resolve_under(cwd, raw)?;
+    let relative = path.strip_prefix(lexically_normalize(cwd)).unwrap_or(&path);
+    if relative
+        .components()
+        .any(|part| part.as_os_str().eq_ignore_ascii_case(".git"))
+    {
+        return Err(ToolOutcome::error(format!(
+            "refusing to modify git metadata: {raw}"
+        )));
+    }
+    Ok(path)
+}
+
 /// Lexical normalization: collapse `.` and `..` without touching the
 /// filesystem, so writes to not-yet-created files stay contained.
 fn lexically_normalize(path: &Path) -> PathBuf {
@@ -855,6 +1696,14 @@ fn lexically_normalize(path: &Path) -> PathBuf {
 
 // ---- read ----
 
+/// Lines one read returns when the call sets no smaller limit.
+const READ_MAX_LINES: usize = 2000;
+/// Hard cap on one read's output, truncation notice excluded.
+const READ_MAX_BYTES: usize = 50 * 1024;
+/// Largest image a read attaches for the model; bigger ones are
+/// reported like other binary files.
+const READ_MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
 pub struct ReadTool {
     cwd: Arc<Path>,
 }
@@ -864,18 +1713,269 @@ impl ReadTool {
         json!({
             "type": "object",
             "properties": {
-                "path": { "type": "string", "description": "Path relative to the project root." }
+                "path": { "type": "string", "description": "Path relative to the project root." },
+                "offset": { "type": "integer", "minimum": 1, "description": "First line to read (1-based)." },
+                "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of lines to read." }
             },
             "required": ["path"]
         })
     }
 }
 
+/// The media type of an image format models accept, by file
+/// signature.
+fn image_media_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Bytes of a file's head sniffed for its type.
+const READ_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Recognizable non-text content in a file's `head`: a known file
+/// signature or a NUL byte. Text in another encoding is not binary;
+/// it reads with invalid UTF-8 replaced.
+fn binary_kind(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"\xff\xd8\xff", "JPEG image"),
+        (b"GIF8", "GIF image"),
+        (b"RIFF", "RIFF media"),
+        (b"%PDF-", "PDF document"),
+        (b"PK\x03\x04", "zip archive"),
+        (b"\x1f\x8b", "gzip archive"),
+        (b"\x7fELF", "ELF executable"),
+        (b"\0asm", "WebAssembly module"),
+    ];
+    if let Some((_, kind)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(kind);
+    }
+    head.contains(&0).then_some("binary data")
+}
+
+/// Number `text`'s lines in `range` like `cat -n`, within
+/// [`READ_MAX_LINES`] and [`READ_MAX_BYTES`]. When lines remain, the
+/// output ends with a notice naming the offset to continue from.
+pub(crate) fn render_lines(text: &str, range: Option<LineRange>) -> Result<String, String> {
+    let mut page = Page::new(range);
+    for (index, line) in text.lines().enumerate() {
+        if page.done(index + 1) {
+            break;
+        }
+        page.push(index + 1, line);
+    }
+    page.finish(text.lines().count())
+}
+
+/// [`render_lines`] over a file streamed from `reader`: only the lines
+/// of the page are kept, each at most [`READ_MAX_BYTES`] of it, and
+/// the rest are only counted.
+fn page_lines(
+    mut reader: impl BufRead,
+    range: Option<LineRange>,
+) -> std::io::Result<Result<String, String>> {
+    let mut page = Page::new(range);
+    let mut line = Vec::new();
+    let mut total = 0;
+    loop {
+        let number = total + 1;
+        let cap = if page.before(number) || page.done(number) {
+            0
+        } else {
+            READ_MAX_BYTES
+        };
+        if !next_line(&mut reader, cap, &mut line)? {
+            break;
+        }
+        total = number;
+        if cap > 0 {
+            page.push(number, &String::from_utf8_lossy(&line));
+        }
+    }
+    Ok(page.finish(total))
+}
+
+/// Read the next line of `reader` into `line` without its terminator,
+/// keeping at most `cap` bytes of it; `false` at the end of input.
+/// Lines split like [`str::lines`].
+fn next_line(reader: &mut impl BufRead, cap: usize, line: &mut Vec<u8>) -> std::io::Result<bool> {
+    line.clear();
+    let mut read_any = false;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(read_any);
+        }
+        read_any = true;
+        let (chunk, consumed, ended) = match buf.iter().position(|&byte| byte == b'\n') {
+            Some(at) => (&buf[..at], at + 1, true),
+            None => (buf, buf.len(), false),
+        };
+        let room = cap.saturating_sub(line.len());
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        reader.consume(consumed);
+        if ended {
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            return Ok(true);
+        }
+    }
+}
+
+/// One page of numbered lines being filled.
+struct Page {
+    offset: usize,
+    limit: usize,
+    out: String,
+    /// Last line shown so far.
+    last: usize,
+    /// A line over the byte cap, shown cut.
+    cut_line: Option<usize>,
+    full: bool,
+}
+
+impl Page {
+    fn new(range: Option<LineRange>) -> Self {
+        let offset = range.map_or(1, |range| {
+            usize::try_from(range.offset).unwrap_or(usize::MAX)
+        });
+        let limit = range
+            .and_then(|range| range.limit)
+            .map_or(READ_MAX_LINES, |limit| {
+                usize::try_from(limit)
+                    .unwrap_or(usize::MAX)
+                    .min(READ_MAX_LINES)
+            });
+        Self {
+            offset,
+            limit,
+            out: String::new(),
+            last: offset - 1,
+            cut_line: None,
+            full: false,
+        }
+    }
+
+    /// Whether line `number` (1-based) comes before the page.
+    fn before(&self, number: usize) -> bool {
+        number < self.offset
+    }
+
+    /// Whether the page takes no more lines from `number` on.
+    fn done(&self, number: usize) -> bool {
+        self.full || number >= self.offset.saturating_add(self.limit)
+    }
+
+    fn push(&mut self, number: usize, line: &str) {
+        if self.before(number) || self.done(number) {
+            return;
+        }
+        let numbered = format!("{number:>6}\t{line}");
+        let separator = usize::from(!self.out.is_empty());
+        if self.out.len() + separator + numbered.len() > READ_MAX_BYTES {
+            if self.out.is_empty() {
+                // One line over the cap: show its head rather than
+                // nothing, so paging always makes progress.
+                let mut end = READ_MAX_BYTES;
+                while !numbered.is_char_boundary(end) {
+                    end -= 1;
+                }
+                self.out.push_str(&numbered[..end]);
+                self.last = number;
+                self.cut_line = Some(number);
+            }
+            self.full = true;
+            return;
+        }
+        if separator == 1 {
+            self.out.push('\n');
+        }
+        self.out.push_str(&numbered);
+        self.last = number;
+    }
+
+    /// The page, given the file's `total` line count.
+    fn finish(self, total: usize) -> Result<String, String> {
+        let Self {
+            offset,
+            mut out,
+            last,
+            cut_line,
+            ..
+        } = self;
+        if offset > total.max(1) {
+            return Err(format!(
+                "offset {offset} is past the end of the file ({total} lines)"
+            ));
+        }
+        if let Some(line) = cut_line {
+            out.push_str(&format!(
+                "\n[line {line} is longer than {READ_MAX_BYTES} bytes and was cut]"
+            ));
+        }
+        if last < total {
+            out.push_str(&format!(
+                "\n[truncated: showing lines {offset}-{last} of {total}; continue with offset={}]",
+                last + 1
+            ));
+        }
+        Ok(out)
+    }
+}
+
+/// What a read found in a file.
+enum FileRead {
+    Image(&'static str, Vec<u8>),
+    Binary(&'static str, u64),
+    Text(Result<String, String>),
+}
+
+/// Sniff the file at `path` from its head, then read all of an image
+/// or only the requested page of text.
+fn read_file(path: &Path, range: Option<LineRange>) -> std::io::Result<FileRead> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut head = Vec::new();
+    (&mut file)
+        .take(READ_SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    if let Some(media_type) = image_media_type(&head)
+        && len <= READ_MAX_IMAGE_BYTES as u64
+    {
+        let mut bytes = head;
+        file.read_to_end(&mut bytes)?;
+        return Ok(FileRead::Image(media_type, bytes));
+    }
+    if let Some(kind) = binary_kind(&head) {
+        return Ok(FileRead::Binary(kind, len));
+    }
+    let reader = std::io::BufReader::new(Read::chain(std::io::Cursor::new(head), file));
+    Ok(FileRead::Text(page_lines(reader, range)?))
+}
+
 impl Tool for ReadTool {
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: "read".to_owned(),
-            description: "Read a file's contents".to_owned(),
+            description: format!(
+                "Read a file with numbered lines. Output stops after {READ_MAX_LINES} lines or \
+                 {} KiB; page through larger files with offset and limit. PNG, JPEG, GIF, \
+                 and WebP images up to {} MiB are shown to you as images; other binary files \
+                 are reported by type and size.",
+                READ_MAX_BYTES / 1024,
+                READ_MAX_IMAGE_BYTES / (1024 * 1024)
+            ),
             input_schema: Self::input_schema(),
         }
     }
@@ -890,21 +1990,104 @@ impl Tool for ReadTool {
                 Some(p) => p.to_owned(),
                 None => return ToolOutcome::error("missing argument: path"),
             };
+            let range = match LineRange::from_arguments(&arguments) {
+                Ok(range) => range,
+                Err(err) => return ToolOutcome::error(err),
+            };
             let full = match resolve_under(&self.cwd, &path) {
                 Ok(p) => p,
                 Err(e) => return e,
             };
-            match fs::read(&full).await {
-                Ok(bytes) => match String::from_utf8(bytes) {
-                    Ok(text) => ToolOutcome::text(text),
-                    Err(err) => ToolOutcome::error(format!("file is not valid UTF-8: {err}")),
-                },
+            let read = tokio::task::spawn_blocking(move || read_file(&full, range)).await;
+            match read {
+                Ok(Ok(FileRead::Image(media_type, bytes))) => {
+                    let summary = format!("image: {path} ({media_type}, {} bytes)", bytes.len());
+                    ToolOutcome {
+                        images: vec![Image::new(media_type, bytes)],
+                        ..ToolOutcome::text(summary)
+                    }
+                }
+                Ok(Ok(FileRead::Binary(kind, len))) => ToolOutcome::text(format!(
+                    "binary file not shown: {path} ({kind}, {len} bytes)"
+                )),
+                Ok(Ok(FileRead::Text(Ok(out)))) => ToolOutcome::text(out),
+                Ok(Ok(FileRead::Text(Err(err)))) => ToolOutcome::error(err),
+                Ok(Err(err)) => ToolOutcome::error(format!("read failed: {err}")),
                 Err(err) => ToolOutcome::error(format!("read failed: {err}")),
             }
         })
     }
 }
 
+// ---- attachments ----
+
+/// Expand `@path` tokens in a user prompt into inline file blocks.
+///
+/// Paths resolve under `cwd` with exactly the rules [`ReadTool`] uses,
+/// so an attachment can never reach a file the model could not read
+/// itself. The expanded text is what gets submitted: the file contents
+/// become part of the durable `UserMessage`, and replaying the session
+/// never depends on the file still existing (DESIGN.md §16).
+///
+/// A token is an attachment only when it resolves under the path rules
+/// to an existing regular file (trailing sentence punctuation is
+/// tolerated); anything else - `@@` hunk headers, decorators, mentions,
+/// paths outside the project - stays literal, which keeps piped diffs
+/// and logs intact and never probes the filesystem outside `cwd`. A
+/// file that cannot be read or is not UTF-8 is an error, never a
+/// silent omission.
+pub async fn inline_attachments(cwd: &Path, prompt: &str) -> Result<String, String> {
+    let mut blocks: Vec<(String, String)> = Vec::new();
+    for token in prompt.split_whitespace() {
+        let Some(raw) = token.strip_prefix('@') else {
+            continue;
+        };
+        let trimmed = raw.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']);
+        for candidate in [raw, trimmed] {
+            if candidate.is_empty() || blocks.iter().any(|(path, _)| path == candidate) {
+                continue;
+            }
+            let Ok(full) = resolve_under(cwd, candidate) else {
+                continue;
+            };
+            if !fs::metadata(&full).await.is_ok_and(|meta| meta.is_file()) {
+                continue;
+            }
+            let bytes = fs::read(&full)
+                .await
+                .map_err(|err| format!("attachment @{candidate}: read failed: {err}"))?;
+            let text = String::from_utf8(bytes).map_err(|err| {
+                format!("attachment @{candidate}: file is not valid UTF-8: {err}")
+            })?;
+            blocks.push((candidate.to_owned(), text));
+            break;
+        }
+    }
+    if blocks.is_empty() {
+        return Ok(prompt.to_owned());
+    }
+    let mut out = prompt.trim_end().to_owned();
+    for (path, text) in blocks {
+        out.push_str(&format!(
+            "\n\n<attachment path=\"{}\">\n{text}",
+            escape_attribute(&path)
+        ));
+        if !text.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str("</attachment>");
+    }
+    Ok(out)
+}
+
+/// `value` safe inside a double-quoted attribute.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
 // ---- write ----
 
 pub struct WriteTool {
@@ -947,10 +2130,14 @@ impl Tool for WriteTool {
                 Some(c) => c.to_owned(),
                 None => return ToolOutcome::error("missing argument: contents"),
             };
-            let full = match resolve_under(&self.cwd, &path) {
+            let full = match resolve_writable(&self.cwd, &path) {
                 Ok(p) => p,
                 Err(e) => return e,
             };
+            let before = fs::read(&full)
+                .await
+                .ok()
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
             if let Some(parent) = full.parent() {
                 match fs::create_dir_all(parent).await {
                     Ok(()) => {}
@@ -959,8 +2146,11 @@ impl Tool for WriteTool {
                     }
                 }
             }
-            match fs::write(&full, contents).await {
-                Ok(()) => ToolOutcome::text("written"),
+            match fs::write(&full, &contents).await {
+                Ok(()) => ToolOutcome::changed(
+                    "written",
+                    FileDiff::between(&full, before.as_deref(), Some(&contents)),
+                ),
                 Err(err) => ToolOutcome::error(format!("write failed: {err}")),
             }
         })
@@ -979,19 +2169,126 @@ impl EditTool {
             "type": "object",
             "properties": {
                 "path": { "type": "string" },
-                "old_str": { "type": "string" },
-                "new_str": { "type": "string" }
+                "old_str": { "type": "string", "description": "Exact text to replace; must match once unless replace_all is set." },
+                "new_str": { "type": "string" },
+                "replace_all": { "type": "boolean", "description": "Replace every occurrence of old_str." },
+                "edits": {
+                    "type": "array",
+                    "description": "Several replacements, applied in order; all succeed or none is written.",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "old_str": { "type": "string" },
+                            "new_str": { "type": "string" },
+                            "replace_all": { "type": "boolean" }
+                        },
+                        "required": ["old_str", "new_str"]
+                    }
+                }
             },
-            "required": ["path", "old_str", "new_str"]
+            "required": ["path"],
+            "dependentRequired": { "old_str": ["new_str"], "new_str": ["old_str"] }
+        })
+    }
+}
+
+/// One exact-match replacement of an `edit` call.
+struct Replacement {
+    old_str: String,
+    new_str: String,
+    replace_all: bool,
+}
+
+/// The replacements of an `edit` call: the `edits` batch, or the single
+/// top-level `old_str`/`new_str` pair.
+fn parse_edits(arguments: &Value) -> Result<Vec<Replacement>, String> {
+    let parse_one = |value: &Value| -> Result<Replacement, String> {
+        let old_str = value
+            .get("old_str")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "missing string argument: old_str".to_owned())?;
+        if old_str.is_empty() {
+            return Err("old_str must not be empty".to_owned());
+        }
+        let new_str = value
+            .get("new_str")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "missing string argument: new_str".to_owned())?;
+        Ok(Replacement {
+            old_str: old_str.to_owned(),
+            new_str: new_str.to_owned(),
+            replace_all: value
+                .get("replace_all")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
         })
+    };
+    match arguments.get("edits") {
+        None | Some(Value::Null) => Ok(vec![parse_one(arguments)?]),
+        Some(Value::Array(edits)) if !edits.is_empty() => {
+            if arguments.get("old_str").is_some() {
+                return Err("pass either old_str or edits, not both".to_owned());
+            }
+            edits
+                .iter()
+                .enumerate()
+                .map(|(index, edit)| {
+                    parse_one(edit).map_err(|err| format!("edit {}: {err}", index + 1))
+                })
+                .collect()
+        }
+        Some(_) => Err("edits must be a non-empty array".to_owned()),
+    }
+}
+
+/// Apply `edits` in order to `original`. Pure, so admission computes
+/// the same postimage the executor writes (§12.3). An `old_str` that is
+/// missing, or ambiguous without `replace_all`, fails the whole call.
+fn apply_edits(original: &str, edits: &[Replacement]) -> Result<String, String> {
+    let mut text = original.to_owned();
+    for (index, edit) in edits.iter().enumerate() {
+        let label = if edits.len() > 1 {
+            format!("edit {}: ", index + 1)
+        } else {
+            String::new()
+        };
+        match occurrences(&text, &edit.old_str) {
+            0 => return Err(format!("{label}old_str not found in file")),
+            1 => text = text.replacen(&edit.old_str, &edit.new_str, 1),
+            _ if edit.replace_all => text = text.replace(&edit.old_str, &edit.new_str),
+            matches => {
+                return Err(format!(
+                    "{label}old_str matches {matches} times; add surrounding context to make it unique, or set replace_all"
+                ));
+            }
+        }
     }
+    Ok(text)
+}
+
+/// How often `pattern` occurs in `text`, overlaps included: `"aa"`
+/// occurs twice in `"aaa"`, so it does not pick one place to edit.
+fn occurrences(text: &str, pattern: &str) -> usize {
+    let mut count = 0;
+    let mut start = 0;
+    while let Some(found) = text[start..].find(pattern) {
+        count += 1;
+        start += found
+            + text[start + found..]
+                .chars()
+                .next()
+                .map_or(1, char::len_utf8);
+    }
+    count
 }
 
 impl Tool for EditTool {
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: "edit".to_owned(),
-            description: "Replace the first occurrence of a string in a file".to_owned(),
+            description: "Replace exact text in a file. old_str must match exactly once unless \
+                          replace_all is set; pass edits to apply several replacements atomically"
+                .to_owned(),
             input_schema: Self::input_schema(),
         }
     }
@@ -1006,16 +2303,11 @@ impl Tool for EditTool {
                 Some(p) => p.to_owned(),
                 None => return ToolOutcome::error("missing argument: path"),
             };
-            let old_str = match arguments.get("old_str").and_then(|v| v.as_str()) {
-                Some(s) => s.to_owned(),
-                None => return ToolOutcome::error("missing argument: old_str"),
+            let edits = match parse_edits(&arguments) {
+                Ok(edits) => edits,
+                Err(err) => return ToolOutcome::error(err),
             };
-            let new_str = arguments
-                .get("new_str")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_owned();
-            let full = match resolve_under(&self.cwd, &path) {
+            let full = match resolve_writable(&self.cwd, &path) {
                 Ok(p) => p,
                 Err(e) => return e,
             };
@@ -1023,42 +2315,339 @@ impl Tool for EditTool {
                 Ok(text) => text,
                 Err(err) => return ToolOutcome::error(format!("read failed: {err}")),
             };
-            if !original.contains(&old_str) {
-                return ToolOutcome::error("old_str not found in file");
-            }
-            let updated = original.replacen(&old_str, &new_str, 1);
-            match fs::write(&full, updated).await {
-                Ok(()) => ToolOutcome::text("edited"),
+            let updated = match apply_edits(&original, &edits) {
+                Ok(updated) => updated,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            match fs::write(&full, &updated).await {
+                Ok(()) => ToolOutcome::changed(
+                    "edited",
+                    FileDiff::between(&full, Some(&original), Some(&updated)),
+                ),
                 Err(err) => ToolOutcome::error(format!("write failed: {err}")),
             }
         })
     }
 }
 
+// ---- apply_patch ----
+
+pub struct ApplyPatchTool {
+    cwd: Arc<Path>,
+}
+
+impl ApplyPatchTool {
+    fn input_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff; paths relative to the project root. Use /dev/null to create or delete a file."
+                }
+            },
+            "required": ["patch"]
+        })
+    }
+}
+
+/// One file of a validated patch: its contents now and after.
+/// `None` means absent (created, or deleted by the patch).
+struct PlannedFile {
+    path: PathBuf,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// Parse the patch and apply every hunk in memory against the current
+/// files. Nothing is written; any mismatch fails the whole patch.
+async fn plan_patch(cwd: &Path, arguments: &Value) -> Result<Vec<PlannedFile>, String> {
+    let text = arguments
+        .get("patch")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "missing string argument: patch".to_owned())?;
+    let read = async |path: &Path, raw: &str| -> Result<Option<String>, String> {
+        match fs::read(path).await {
+            Ok(bytes) => String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|_| format!("{raw} is not valid UTF-8")),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(format!("{raw}: read failed: {err}")),
+        }
+    };
+    let mut planned: Vec<PlannedFile> = Vec::new();
+    for file in crate::patch::parse(text)? {
+        let old = match &file.old_path {
+            Some(raw) => {
+                let path = resolve_writable(cwd, raw).map_err(|e| e.output)?;
+                let before = read(&path, raw)
+                    .await?
+                    .ok_or_else(|| format!("{raw} does not exist"))?;
+                Some((path, before))
+            }
+            None => None,
+        };
+        let new = match &file.new_path {
+            Some(raw) => Some(resolve_writable(cwd, raw).map_err(|e| e.output)?),
+            None => None,
+        };
+        let after = crate::patch::apply(old.as_ref().map_or("", |(_, before)| before), &file)?;
+        match (old, new) {
+            (Some((old_path, before)), Some(new_path)) if old_path == new_path => {
+                planned.push(PlannedFile {
+                    path: new_path,
+                    before: Some(before),
+                    after: Some(after),
+                });
+            }
+            (old, new) => {
+                if let Some((old_path, before)) = old {
+                    planned.push(PlannedFile {
+                        path: old_path,
+                        before: Some(before),
+                        after: None,
+                    });
+                }
+                if let Some(new_path) = new {
+                    if read(&new_path, file.display_path()).await?.is_some() {
+                        return Err(format!("{} already exists", file.display_path()));
+                    }
+                    planned.push(PlannedFile {
+                        path: new_path,
+                        before: None,
+                        after: Some(after),
+                    });
+                } else if !after.is_empty() {
+                    return Err(format!(
+                        "deleting {} needs hunks that remove all of it",
+                        file.display_path()
+                    ));
+                }
+            }
+        }
+    }
+    for (index, file) in planned.iter().enumerate() {
+        if planned[..index].iter().any(|other| other.path == file.path) {
+            return Err(format!("{} is changed twice", file.path.display()));
+        }
+    }
+    Ok(planned)
+}
+
+/// Write a validated patch all-or-nothing: stage every new file next
+/// to its target, then swap them in by rename. A failure part-way
+/// restores the files already swapped.
+async fn commit_patch(planned: &[PlannedFile]) -> Result<(), String> {
+    let staging = |path: &Path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!(".{name}.ion-patch-{}", std::process::id()))
+    };
+    let mut staged: Vec<PathBuf> = Vec::new();
+    for file in planned {
+        let Some(after) = &file.after else { continue };
+        let temp = staging(&file.path);
+        let written = async {
+            if let Some(parent) = file.path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::write(&temp, after).await
+        }
+        .await;
+        if let Err(err) = written {
+            for temp in staged {
+                let _ = fs::remove_file(temp).await;
+            }
+            return Err(format!("{}: write failed: {err}", file.path.display()));
+        }
+        staged.push(temp);
+    }
+    for (index, file) in planned.iter().enumerate() {
+        let swapped = match &file.after {
+            Some(_) => fs::rename(staging(&file.path), &file.path).await,
+            None => fs::remove_file(&file.path).await,
+        };
+        if let Err(err) = swapped {
+            for done in planned[..index].iter().rev() {
+                let _ = match &done.before {
+                    Some(before) => fs::write(&done.path, before).await,
+                    None => fs::remove_file(&done.path).await,
+                };
+            }
+            for pending in &planned[index..] {
+                if pending.after.is_some() {
+                    let _ = fs::remove_file(staging(&pending.path)).await;
+                }
+            }
+            return Err(format!("{}: {err}; patch rolled back", file.path.display()));
+        }
+    }
+    Ok(())
+}
+
+impl Tool for ApplyPatchTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "apply_patch".to_owned(),
+            description:
+                "Apply a unified diff to one or more files. Every hunk is checked against \
+                          the current contents first; the patch applies completely or not at all"
+                    .to_owned(),
+            input_schema: Self::input_schema(),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        arguments: Value,
+        _cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        Box::pin(async move {
+            let planned = match plan_patch(&self.cwd, &arguments).await {
+                Ok(planned) => planned,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            if let Err(err) = commit_patch(&planned).await {
+                return ToolOutcome::error(err);
+            }
+            let mut out = format!("patched {} files", planned.len());
+            for file in &planned {
+                let mark = match (&file.before, &file.after) {
+                    (None, _) => 'A',
+                    (_, None) => 'D',
+                    _ => 'M',
+                };
+                let shown = file.path.strip_prefix(&*self.cwd).unwrap_or(&file.path);
+                out.push_str(&format!("\n{mark} {}", shown.display()));
+            }
+            // The model wrote the hunks itself; only frontends need them.
+            ToolOutcome {
+                diffs: planned
+                    .iter()
+                    .map(|file| {
+                        FileDiff::between(&file.path, file.before.as_deref(), file.after.as_deref())
+                    })
+                    .collect(),
+                ..ToolOutcome::text(out)
+            }
+        })
+    }
+}
+
 // ---- bash ----
 
+/// Timeout bounds for `bash`: `default_timeout` applies when a call
+/// names no `timeout_secs`, and no call runs longer than `max_timeout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BashLimits {
+    pub default_timeout: Duration,
+    pub max_timeout: Duration,
+}
+
+impl Default for BashLimits {
+    fn default() -> Self {
+        Self {
+            default_timeout: Duration::from_secs(120),
+            max_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Output `bash` keeps at most. Past it the middle of the stream is
+/// dropped: the head shows what the command set out to do, the tail
+/// how it ended. Anything over the artifact threshold reaches the
+/// model as an excerpt, so this bounds memory, not context.
+const BASH_MAX_OUTPUT_BYTES: usize = 8 * 1024 * 1024;
+/// Pipe read size; also the largest live output chunk.
+pub(crate) const BASH_CHUNK_BYTES: usize = 8 * 1024;
+/// How long output is still collected after the command exits, from
+/// background processes it left holding its stdout or stderr.
+const BASH_DRAIN_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
 pub struct BashTool {
     cwd: Arc<Path>,
+    limits: BashLimits,
+    /// The session's persistent shell, when opted in; otherwise every
+    /// call spawns a fresh `sh -c`.
+    shell: Option<Arc<PersistentShell>>,
+    /// OS-level confinement, when opted in.
+    sandbox: Option<Sandbox>,
 }
 
 impl BashTool {
-    fn input_schema() -> Value {
+    fn new(cwd: Arc<Path>) -> Self {
+        Self {
+            cwd,
+            limits: BashLimits::default(),
+            shell: None,
+            sandbox: None,
+        }
+    }
+
+    /// The directory the next command runs in.
+    fn working_dir(&self) -> PathBuf {
+        self.shell
+            .as_ref()
+            .map_or_else(|| normalize(&self.cwd), |shell| shell.cwd())
+    }
+
+    fn input_schema(&self) -> Value {
         json!({
             "type": "object",
             "properties": {
-                "command": { "type": "string", "description": "Shell command to run with sh -c." }
+                "command": { "type": "string", "description": "Shell command to run with sh -c." },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    // Not a schema `maximum`: longer requests are capped,
+                    // not refused.
+                    "description": format!(
+                        "Kill the command after this many seconds; defaults to {}, capped at {}.",
+                        self.limits.default_timeout.as_secs(),
+                        self.limits.max_timeout.as_secs()
+                    )
+                }
             },
             "required": ["command"]
         })
     }
+
+    /// The call's timeout: its `timeout_secs`, capped at the maximum.
+    fn timeout(&self, arguments: &Value) -> Result<Duration, String> {
+        match arguments.get("timeout_secs") {
+            None | Some(Value::Null) => Ok(self.limits.default_timeout),
+            Some(value) => match value.as_u64() {
+                Some(secs) if secs > 0 => {
+                    Ok(Duration::from_secs(secs).min(self.limits.max_timeout))
+                }
+                _ => Err("timeout_secs must be a positive integer".to_owned()),
+            },
+        }
+    }
 }
 
 impl Tool for BashTool {
     fn spec(&self) -> ToolSpec {
+        let session = if self.shell.is_some() {
+            " Commands share one shell session: the working directory and exported \
+             variables persist between calls; a killed command restarts the shell."
+        } else {
+            ""
+        };
+        let sandbox = self
+            .sandbox
+            .map_or_else(String::new, |sandbox| format!(" {}", sandbox.describe()));
         ToolSpec {
             name: "bash".to_owned(),
-            description: "Run a shell command and return its combined output".to_owned(),
-            input_schema: Self::input_schema(),
+            description: format!(
+                "Run a shell command and return its combined output. The command is killed \
+                 after timeout_secs (default {}s, at most {}s); output past {} MiB keeps \
+                 only its head and tail.{session}{sandbox}",
+                self.limits.default_timeout.as_secs(),
+                self.limits.max_timeout.as_secs(),
+                BASH_MAX_OUTPUT_BYTES / (1024 * 1024)
+            ),
+            input_schema: self.input_schema(),
         }
     }
 
@@ -1066,20 +2655,87 @@ impl Tool for BashTool {
         &'a self,
         arguments: Value,
         cancel: CancellationToken,
+    ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+        self.call_with_progress(arguments, cancel, ToolProgress::default())
+    }
+
+    fn call_with_progress<'a>(
+        &'a self,
+        arguments: Value,
+        cancel: CancellationToken,
+        progress: ToolProgress,
     ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
         Box::pin(async move {
             let command = match arguments.get("command").and_then(|v| v.as_str()) {
                 Some(c) => c.to_owned(),
                 None => return ToolOutcome::error("missing argument: command"),
             };
-            run_shell(&self.cwd, &command, cancel).await
+            let timeout = match self.timeout(&arguments) {
+                Ok(timeout) => timeout,
+                Err(err) => return ToolOutcome::error(err),
+            };
+            match &self.shell {
+                Some(shell) => shell.run(&command, timeout, cancel, progress).await,
+                None => {
+                    run_shell(&self.cwd, self.sandbox, &command, timeout, cancel, progress).await
+                }
+            }
         })
     }
 }
 
-/// Spawn `sh -c command` under `cwd`, killing the child on cancel.
-/// Returns combined stdout/stderr as text.
-async fn run_shell(cwd: &Path, command: &str, cancel: CancellationToken) -> ToolOutcome {
+/// Command output bounded to [`BASH_MAX_OUTPUT_BYTES`]: the first half
+/// is kept as it arrives, then a sliding tail of the rest.
+#[derive(Default)]
+pub(crate) struct HeadTail {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    omitted: usize,
+}
+
+impl HeadTail {
+    const HALF: usize = BASH_MAX_OUTPUT_BYTES / 2;
+
+    pub(crate) fn push(&mut self, mut bytes: &[u8]) {
+        let room = Self::HALF - self.head.len();
+        let (head, rest) = bytes.split_at(room.min(bytes.len()));
+        self.head.extend_from_slice(head);
+        bytes = rest;
+        self.tail.extend(bytes);
+        let excess = self.tail.len().saturating_sub(Self::HALF);
+        self.tail.drain(..excess);
+        self.omitted += excess;
+    }
+
+    pub(crate) fn finish(self) -> String {
+        let head = String::from_utf8_lossy(&self.head);
+        let tail = Vec::from(self.tail);
+        let tail = String::from_utf8_lossy(&tail);
+        if self.omitted == 0 {
+            format!("{head}{tail}")
+        } else {
+            format!(
+                "{head}\n… {} bytes of output omitted …\n{tail}",
+                self.omitted
+            )
+        }
+    }
+}
+
+/// Spawn `sh -c command` under `cwd`, killing its process group on
+/// cancel or once `timeout` passes. Stdout and stderr are interleaved
+/// in arrival order and streamed to `progress` as they are read. The
+/// call ends when the command exits: a background process it started
+/// may keep the pipes open, so they are drained only for a short grace
+/// period after that.
+async fn run_shell(
+    cwd: &Path,
+    sandbox: Option<Sandbox>,
+    command: &str,
+    timeout: Duration,
+    cancel: CancellationToken,
+    progress: ToolProgress,
+) -> ToolOutcome {
     let mut cmd = Command::new("sh");
     cmd.arg("-c")
         .arg(command)
@@ -1092,52 +2748,92 @@ async fn run_shell(cwd: &Path, command: &str, cancel: CancellationToken) -> Tool
     // rather than only the direct `sh`.
     #[cfg(unix)]
     cmd.process_group(0);
+    if let Some(sandbox) = sandbox
+        && let Err(err) = sandbox.confine(&mut cmd, cwd)
+    {
+        return ToolOutcome::error(err);
+    }
     let mut child = match cmd.spawn() {
         Ok(child) => child,
         Err(err) => return ToolOutcome::error(format!("spawn failed: {err}")),
     };
     let pid = child.id();
 
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-
-    // Drain both pipes concurrently. Waiting for EOF first would deadlock on
-    // a silent long-running command and make cancellation uninterruptible.
-    let read_task = tokio::spawn(async move {
-        let mut buf = String::new();
-        if let Some(mut s) = stdout {
-            let _ = s.read_to_string(&mut buf).await;
-        }
-        if let Some(mut e) = stderr {
-            let mut err_text = String::new();
-            let _ = e.read_to_string(&mut err_text).await;
-            buf.push_str(&err_text);
+    // Drain both pipes concurrently into one channel, so chunks arrive
+    // in the order the command wrote them. Waiting for EOF first would
+    // deadlock on a silent long-running command and make cancellation
+    // uninterruptible.
+    let (chunk_tx, mut chunks) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+    let pipes: [Option<Pin<Box<dyn tokio::io::AsyncRead + Send>>>; 2] = [
+        child.stdout.take().map(|s| Box::pin(s) as _),
+        child.stderr.take().map(|s| Box::pin(s) as _),
+    ];
+    for mut pipe in pipes.into_iter().flatten() {
+        let chunk_tx = chunk_tx.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; BASH_CHUNK_BYTES];
+            while let Ok(n) = pipe.read(&mut buf).await {
+                if n == 0 || chunk_tx.send(buf[..n].to_vec()).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(chunk_tx);
+
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
+    let mut output = HeadTail::default();
+    let mut pipes_open = true;
+    let mut status = None;
+    let mut drained_by = None;
+    let stopped = loop {
+        if !pipes_open && let Some(status) = status {
+            break Ok(status);
         }
-        buf
-    });
-
-    tokio::select! {
-        status = child.wait() => {
-            let out = read_task.await.unwrap_or_default();
-            let code = match status {
-                Ok(s) => s.code().unwrap_or(-1),
-                Err(_) => -1,
-            };
-            if code == 0 {
-                ToolOutcome::text(out)
-            } else {
-                ToolOutcome::error(format!("command exited with code {code}\n{out}"))
+        let grace = async {
+            match drained_by {
+                Some(at) => tokio::time::sleep_until(at).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            chunk = chunks.recv(), if pipes_open => match chunk {
+                Some(chunk) => {
+                    progress.emit(String::from_utf8_lossy(&chunk).into_owned());
+                    output.push(&chunk);
+                }
+                None => pipes_open = false,
+            },
+            exited = child.wait(), if status.is_none() => {
+                status = Some(exited.ok().and_then(|s| s.code()).unwrap_or(-1));
+                drained_by = Some(tokio::time::Instant::now() + BASH_DRAIN_GRACE);
             }
+            () = grace => break Ok(status.unwrap_or(-1)),
+            () = &mut deadline => break Err(format!(
+                "command timed out after {}s and was killed",
+                timeout.as_secs()
+            )),
+            () = cancel.cancelled() => break Err("cancelled".to_owned()),
         }
-        _ = cancel.cancelled() => {
+    };
+    match stopped {
+        Ok(0) => ToolOutcome::text(output.finish()),
+        Ok(code) => ToolOutcome::error(format!(
+            "command exited with code {code}\n{}",
+            output.finish()
+        )),
+        Err(reason) => {
             if let Some(pid) = pid {
                 kill_process_group(pid as i32);
             }
             // Reap the killed child so it does not linger as a zombie.
             let _ = child.wait().await;
-            // Killing the group closes the pipes, so the read task finishes.
-            let _ = read_task.await;
-            ToolOutcome::error("cancelled".to_owned())
+            if cancel.is_cancelled() {
+                return ToolOutcome::error(reason);
+            }
+            // A timeout shows what the command printed before it hung.
+            ToolOutcome::error(format!("{reason}\n{}", output.finish()))
         }
     }
 }
@@ -1147,7 +2843,7 @@ async fn run_shell(cwd: &Path, command: &str, cancel: CancellationToken) -> Tool
 /// too. A race with a naturally-finishing command is harmless (ESRCH is
 /// ignored). Non-Unix falls back to killing nothing here; the caller also
 /// reaps the direct child.
-fn kill_process_group(pgid: i32) {
+pub(crate) fn kill_process_group(pgid: i32) {
     #[cfg(unix)]
     #[allow(unsafe_code)] // libc::kill on our own child's process group
     unsafe {
@@ -1159,6 +2855,215 @@ fn kill_process_group(pgid: i32) {
     }
 }
 
+// ---- search and find walks ----
+
+/// Most matches `search` and paths `find` list; the rest are counted.
+const WALK_MAX_RESULTS: usize = 256;
+/// Most context lines `search` shows on each side of a match.
+const SEARCH_MAX_CONTEXT: u64 = 10;
+/// Larger files are skipped by `search`: generated or data files whose
+/// matches would only crowd out the rest.
+const SEARCH_MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Which entries a search or find walk visits. Ignore files
+/// (`.gitignore`, `.ignore`, git's excludes) and hidden entries are
+/// always honored; `include` narrows the files visited and `exclude`
+/// prunes files and whole directories. Globs match the root-relative
+/// path or the bare name.
+#[derive(Clone, Default)]
+struct WalkFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl WalkFilter {
+    fn from_arguments(arguments: &Value) -> Result<Self, String> {
+        let globs = |key: &str| -> Result<Option<GlobSet>, String> {
+            let patterns = match arguments.get(key) {
+                None | Some(Value::Null) => return Ok(None),
+                Some(Value::Array(patterns)) => patterns,
+                Some(_) => return Err(format!("{key} must be an array of globs")),
+            };
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                let Some(pattern) = pattern.as_str() else {
+                    return Err(format!("{key} must be an array of globs"));
+                };
+                let glob = Glob::new(pattern).map_err(|_| format!("invalid glob: {pattern}"))?;
+                builder.add(glob);
+            }
+            builder
+                .build()
+                .map(Some)
+                .map_err(|_| format!("cannot build {key} globs"))
+        };
+        Ok(Self {
+            include: globs("include")?,
+            exclude: globs("exclude")?,
+        })
+    }
+
+    fn matches(set: &GlobSet, rel: &str) -> bool {
+        let name = rel.rsplit('/').next().unwrap_or(rel);
+        set.is_match(rel) || set.is_match(name)
+    }
+}
+
+/// The optional `max_results` argument: at most [`WALK_MAX_RESULTS`].
+fn max_results(arguments: &Value) -> Result<usize, String> {
+    match arguments.get("max_results") {
+        None | Some(Value::Null) => Ok(WALK_MAX_RESULTS),
+        Some(value) => match value.as_u64() {
+            Some(n) if n > 0 => {
+                Ok(usize::try_from(n).map_or(WALK_MAX_RESULTS, |n| n.min(WALK_MAX_RESULTS)))
+            }
+            _ => Err("max_results must be a positive integer".to_owned()),
+        },
+    }
+}
+
+/// `root`-relative display path; a file root names itself.
+fn relative_display(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => rel.to_string_lossy().replace('\\', "/"),
+        _ => path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+            .into_owned(),
+    }
+}
+
+/// What a walk kept, and how much it found in all.
+struct Walked<T> {
+    /// The first admitted files by relative path whose weights reach
+    /// the walk's limit, sorted.
+    kept: Vec<(String, T)>,
+    /// The summed weight of every admitted file, kept or not.
+    total: usize,
+}
+
+/// Walk the files under `root` on a parallel, ignore-aware walker off
+/// the async runtime. `visit` returns what to keep for an admitted file
+/// and its weight (matches, or one per path); once the kept files weigh
+/// `limit`, later ones by path are only counted, so memory stays bounded
+/// while the result does not depend on the walk order. `None` when
+/// `cancel` stopped the walk; every walker thread checks it before each
+/// entry.
+async fn walk_files<T, F>(
+    root: PathBuf,
+    filter: WalkFilter,
+    cancel: CancellationToken,
+    limit: usize,
+    visit: F,
+) -> Option<Walked<T>>
+where
+    T: Send + 'static,
+    F: Fn(&Path) -> Option<(T, usize)> + Send + Sync + 'static,
+{
+    let walk_cancel = cancel.clone();
+    let walked = tokio::task::spawn_blocking(move || {
+        let found = std::sync::Mutex::new(Kept::new(limit));
+        let mut builder = ignore::WalkBuilder::new(&root);
+        // Honor .gitignore in trees that are not (yet) git repos too.
+        builder.require_git(false);
+        if let Some(exclude) = filter.exclude.clone() {
+            let prune_root = root.clone();
+            builder.filter_entry(move |entry| {
+                entry.depth() == 0
+                    || !WalkFilter::matches(&exclude, &relative_display(&prune_root, entry.path()))
+            });
+        }
+        builder.build_parallel().run(|| {
+            Box::new(|entry| {
+                if walk_cancel.is_cancelled() {
+                    return ignore::WalkState::Quit;
+                }
+                let Ok(entry) = entry else {
+                    return ignore::WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                    return ignore::WalkState::Continue;
+                }
+                let rel = relative_display(&root, entry.path());
+                if filter
+                    .include
+                    .as_ref()
+                    .is_some_and(|include| !WalkFilter::matches(include, &rel))
+                {
+                    return ignore::WalkState::Continue;
+                }
+                if let Some((kept, weight)) = visit(entry.path()) {
+                    found
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner)
+                        .insert(rel, kept, weight);
+                }
+                ignore::WalkState::Continue
+            })
+        });
+        found
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .finish()
+    })
+    .await
+    .ok()?;
+    (!cancel.is_cancelled()).then_some(walked)
+}
+
+/// The bounded set a walk keeps: files in path order, trimmed from the
+/// end while the rest still weigh at least `limit`.
+struct Kept<T> {
+    limit: usize,
+    files: std::collections::BTreeMap<String, (T, usize)>,
+    kept_weight: usize,
+    total: usize,
+}
+
+impl<T> Kept<T> {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            files: std::collections::BTreeMap::new(),
+            kept_weight: 0,
+            total: 0,
+        }
+    }
+
+    fn insert(&mut self, rel: String, kept: T, weight: usize) {
+        self.total += weight;
+        if self.kept_weight >= self.limit
+            && self
+                .files
+                .last_key_value()
+                .is_some_and(|(last, _)| rel > *last)
+        {
+            return;
+        }
+        self.kept_weight += weight;
+        self.files.insert(rel, (kept, weight));
+        while let Some(entry) = self.files.last_entry() {
+            let last = entry.get().1;
+            if self.kept_weight - last < self.limit {
+                break;
+            }
+            self.kept_weight -= last;
+            entry.remove();
+        }
+    }
+
+    fn finish(self) -> Walked<T> {
+        Walked {
+            kept: self
+                .files
+                .into_iter()
+                .map(|(rel, (kept, _))| (rel, kept))
+                .collect(),
+            total: self.total,
+        }
+    }
+}
+
 // ---- search ----
 
 pub struct SearchTool {
@@ -1171,7 +3076,13 @@ impl SearchTool {
             "type": "object",
             "properties": {
                 "pattern": { "type": "string" },
-                "path": { "type": "string", "description": "Directory to search under; defaults to the project root." }
+                "path": { "type": "string", "description": "Directory or file to search under; defaults to the project root." },
+                "include": { "type": "array", "items": { "type": "string" }, "description": "Only search files matching one of these globs." },
+                "exclude": { "type": "array", "items": { "type": "string" }, "description": "Skip files and directories matching any of these globs." },
+                "case_insensitive": { "type": "boolean" },
+                "fixed_strings": { "type": "boolean", "description": "Treat pattern as a literal string, not a regex." },
+                "context": { "type": "integer", "minimum": 0, "description": format!("Lines of context around each match, at most {SEARCH_MAX_CONTEXT}.") },
+                "max_results": { "type": "integer", "minimum": 1, "description": format!("At most {WALK_MAX_RESULTS}.") }
             },
             "required": ["pattern"]
         })
@@ -1182,7 +3093,12 @@ impl Tool for SearchTool {
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: "search".to_owned(),
-            description: "Search file contents for a regex pattern".to_owned(),
+            description: format!(
+                "Search file contents for a regex pattern, skipping gitignored and hidden \
+                 files and files over {} MiB. Lists at most {WALK_MAX_RESULTS} matches and \
+                 counts the rest.",
+                SEARCH_MAX_FILE_BYTES / (1024 * 1024)
+            ),
             input_schema: Self::input_schema(),
         }
     }
@@ -1196,9 +3112,32 @@ impl Tool for SearchTool {
             let Some(pattern) = arguments.get("pattern").and_then(|v| v.as_str()) else {
                 return ToolOutcome::error("missing argument: pattern");
             };
-            let Ok(regex) = Regex::new(pattern) else {
+            let flag = |key: &str| arguments.get(key).and_then(Value::as_bool) == Some(true);
+            let source = if flag("fixed_strings") {
+                regex::escape(pattern)
+            } else {
+                pattern.to_owned()
+            };
+            let Ok(regex) = regex::RegexBuilder::new(&source)
+                .case_insensitive(flag("case_insensitive"))
+                .build()
+            else {
                 return ToolOutcome::error(format!("invalid regex: {pattern}"));
             };
+            let context = match arguments.get("context") {
+                None | Some(Value::Null) => 0,
+                Some(value) => match value.as_u64() {
+                    Some(n) => n.min(SEARCH_MAX_CONTEXT) as usize,
+                    None => return ToolOutcome::error("context must be a non-negative integer"),
+                },
+            };
+            let (filter, limit) = match (
+                WalkFilter::from_arguments(&arguments),
+                max_results(&arguments),
+            ) {
+                (Ok(filter), Ok(limit)) => (filter, limit),
+                (Err(err), _) | (_, Err(err)) => return ToolOutcome::error(err),
+            };
             let root = match arguments.get("path").and_then(|v| v.as_str()) {
                 Some(p) => match resolve_under(&self.cwd, p) {
                     Ok(r) => r,
@@ -1206,67 +3145,121 @@ impl Tool for SearchTool {
                 },
                 None => self.cwd.as_ref().to_path_buf(),
             };
-            search_files(&root, &regex, &cancel).await
+            let Some(walked) = walk_files(root, filter, cancel, limit, move |path| {
+                search_file(path, &regex, context, limit)
+            })
+            .await
+            else {
+                return ToolOutcome::error("cancelled");
+            };
+            render_search(&walked, context, limit)
         })
     }
 }
 
-/// Walk `root` collecting `path:line_number:line` for every non-binary
-/// file containing a regex match. Skips hidden entries and `target`.
-async fn search_files(root: &Path, regex: &Regex, cancel: &CancellationToken) -> ToolOutcome {
-    let mut results: Vec<String> = Vec::new();
-    let mut files: VecDeque<PathBuf> = VecDeque::new();
-    collect_files(root, &mut files);
+/// One line `search` shows: a match or context around one.
+struct SearchLine {
+    /// 1-based line number.
+    number: usize,
+    text: String,
+    is_match: bool,
+}
 
-    for file in files {
-        if cancel.is_cancelled() {
-            return ToolOutcome::error("cancelled".to_owned());
-        }
-        let Ok(contents) = fs::read_to_string(&file).await else {
+/// The matching lines of one text file, each with up to `context`
+/// lines around it, and how many lines match. Only the first `limit`
+/// matches are kept; the rest are counted. `None` for unreadable,
+/// binary, oversized, or matchless files.
+fn search_file(
+    path: &Path,
+    regex: &Regex,
+    context: usize,
+    limit: usize,
+) -> Option<(Vec<SearchLine>, usize)> {
+    use std::io::Read;
+    let file = std::fs::File::open(path).ok()?;
+    if file.metadata().ok()?.len() > SEARCH_MAX_FILE_BYTES {
+        return None;
+    }
+    let mut contents = String::new();
+    file.take(SEARCH_MAX_FILE_BYTES)
+        .read_to_string(&mut contents)
+        .ok()?;
+    if contents.as_bytes().contains(&0u8) {
+        return None;
+    }
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut shown = Vec::new();
+    // Next line index not yet shown, so overlapping context is not
+    // repeated.
+    let mut next = 0;
+    let mut matches = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if !regex.is_match(line) {
             continue;
-        };
-        if contents.as_bytes().contains(&0u8) {
+        }
+        matches += 1;
+        if matches > limit {
             continue;
         }
-        let rel = file.strip_prefix(root).unwrap_or(&file);
-        let rel_str = rel.to_string_lossy().replace('\\', "/");
-        for (lineno, line) in contents.lines().enumerate() {
-            if regex.is_match(line) {
-                results.push(format!("{rel_str}:{}:{line}", lineno + 1));
-            }
-            if results.len() >= 256 {
-                return ToolOutcome::text(results.join("\n"));
-            }
+        let start = index.saturating_sub(context).max(next);
+        let end = (index + context + 1).min(lines.len());
+        for (at, text) in lines.iter().enumerate().take(end).skip(start) {
+            shown.push(SearchLine {
+                number: at + 1,
+                text: (*text).to_owned(),
+                is_match: at == index || regex.is_match(text),
+            });
         }
+        next = next.max(end);
     }
-
-    if results.is_empty() {
-        ToolOutcome::text("no matches")
-    } else {
-        ToolOutcome::text(results.join("\n"))
-    }
+    // A match inside earlier context was already shown as a match.
+    shown.dedup_by_key(|line| line.number);
+    (matches > 0).then_some((shown, matches))
 }
 
-/// Recursively collect regular files under `root`, skipping hidden entries
-/// and the `target` directory. Synchronous filesystem traversal is acceptable
-/// here because the tool future is spawned on its own task.
-fn collect_files(root: &Path, out: &mut VecDeque<PathBuf>) {
-    let entries = match std::fs::read_dir(root) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let name = entry.file_name().to_string_lossy().into_owned();
-        if name.starts_with('.') || name == "target" {
-            continue;
+/// `path:line:text` for matches and `path-line-text` for context,
+/// groups separated by `--` when context is shown. Past `limit`
+/// matches the rest are counted in a closing notice.
+fn render_search(walked: &Walked<Vec<SearchLine>>, context: usize, limit: usize) -> ToolOutcome {
+    let total = walked.total;
+    if total == 0 {
+        return ToolOutcome::text("no matches");
+    }
+    let mut out: Vec<String> = Vec::new();
+    let mut shown = 0;
+    'files: for (rel, lines) in &walked.kept {
+        let mut previous: Option<usize> = None;
+        let mut last_match = 0;
+        for line in lines {
+            if shown == limit && line.number > last_match + context {
+                break 'files;
+            }
+            if context > 0 && !out.is_empty() && previous.is_none_or(|at| line.number != at + 1) {
+                out.push("--".to_owned());
+            }
+            if line.is_match {
+                if shown == limit {
+                    break 'files;
+                }
+                shown += 1;
+                last_match = line.number;
+                out.push(format!("{rel}:{}:{}", line.number, line.text));
+            } else {
+                out.push(format!("{rel}-{}-{}", line.number, line.text));
+            }
+            previous = Some(line.number);
         }
-        if path.is_dir() {
-            collect_files(&path, out);
-        } else if path.is_file() {
-            out.push_back(path);
+        if shown == limit {
+            break;
         }
     }
+    if total > shown {
+        out.push(format!(
+            "… {} more matches not shown (limit {limit}); narrow the pattern, path, or include globs",
+            total - shown
+        ));
+    }
+    ToolOutcome::text(out.join("\n"))
 }
 
 // ---- find ----
@@ -1281,7 +3274,9 @@ impl FindTool {
             "type": "object",
             "properties": {
                 "pattern": { "type": "string", "description": "Glob pattern, e.g. *.rs or src/**/*.rs" },
-                "path": { "type": "string", "description": "Directory to search under; defaults to the project root." }
+                "path": { "type": "string", "description": "Directory to search under; defaults to the project root." },
+                "exclude": { "type": "array", "items": { "type": "string" }, "description": "Skip files and directories matching any of these globs." },
+                "max_results": { "type": "integer", "minimum": 1, "description": format!("At most {WALK_MAX_RESULTS}.") }
             },
             "required": ["pattern"]
         })
@@ -1292,7 +3287,10 @@ impl Tool for FindTool {
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: "find".to_owned(),
-            description: "Find files matching a glob pattern".to_owned(),
+            description: format!(
+                "Find files matching a glob pattern, skipping gitignored and hidden files. \
+                 Lists at most {WALK_MAX_RESULTS} paths and counts the rest."
+            ),
             input_schema: Self::input_schema(),
         }
     }
@@ -1314,6 +3312,20 @@ impl Tool for FindTool {
             let Ok(set) = builder.build() else {
                 return ToolOutcome::error("cannot build glob set");
             };
+            // The pattern is the include set; find takes no other.
+            let (filter, limit) = match (
+                WalkFilter::from_arguments(&arguments),
+                max_results(&arguments),
+            ) {
+                (Ok(filter), Ok(limit)) => (
+                    WalkFilter {
+                        include: Some(set),
+                        ..filter
+                    },
+                    limit,
+                ),
+                (Err(err), _) | (_, Err(err)) => return ToolOutcome::error(err),
+            };
             let root = match arguments.get("path").and_then(|v| v.as_str()) {
                 Some(p) => match resolve_under(&self.cwd, p) {
                     Ok(r) => r,
@@ -1321,48 +3333,23 @@ impl Tool for FindTool {
                 },
                 None => self.cwd.as_ref().to_path_buf(),
             };
-            let mut outputs: Vec<String> = Vec::new();
-            collect_matches(&root, &set, &mut outputs);
-            let _ = cancel;
-            if outputs.is_empty() {
-                ToolOutcome::text("no matches")
-            } else {
-                ToolOutcome::text(outputs.join("\n"))
+            let Some(found) = walk_files(root, filter, cancel, limit, |_| Some(((), 1))).await
+            else {
+                return ToolOutcome::error("cancelled");
+            };
+            if found.total == 0 {
+                return ToolOutcome::text("no matches");
             }
-        })
-    }
-}
-
-/// Walk `root` recursively and collect paths matching `set`, relative
-/// to `root` itself - not to each visited directory, which would test
-/// nested files as bare names. Skips hidden entries and `target`.
-fn collect_matches(root: &Path, set: &GlobSet, out: &mut Vec<String>) {
-    collect_matches_under(root, root, set, out);
-}
-
-fn collect_matches_under(original_root: &Path, dir: &Path, set: &GlobSet, out: &mut Vec<String>) {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let name = entry.file_name().to_string_lossy().into_owned();
-        if name.starts_with('.') || name == "target" {
-            continue;
-        }
-        if path.is_dir() {
-            collect_matches_under(original_root, &path, set, out);
-        } else {
-            let rel = path
-                .strip_prefix(original_root)
-                .unwrap_or(&path)
-                .to_string_lossy()
-                .replace('\\', "/");
-            if set.is_match(&rel) || set.is_match(&name) {
-                out.push(rel);
+            let total = found.total;
+            let mut out: Vec<String> = found.kept.into_iter().map(|(rel, ())| rel).collect();
+            if total > limit {
+                out.push(format!(
+                    "… {} more paths not shown (limit {limit}); narrow the pattern or path",
+                    total - limit
+                ));
             }
-        }
+            ToolOutcome::text(out.join("\n"))
+        })
     }
 }
 
@@ -1427,7 +3414,60 @@ mod catalog_tests {
             .into_iter()
             .find(|s| s.name == "read")
             .expect("read exists");
-        assert_eq!(read.description, "Read a file's contents");
+        assert!(
+            read.description
+                .starts_with("Read a file with numbered lines. Output stops after "),
+            "{}",
+            read.description
+        );
+    }
+
+    #[tokio::test]
+    async fn dynamic_tools_are_held_to_their_published_schema() {
+        struct CreateIssue;
+        impl Tool for CreateIssue {
+            fn spec(&self) -> ToolSpec {
+                ToolSpec {
+                    name: "mcp_create_issue".to_owned(),
+                    description: "create an issue".to_owned(),
+                    input_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string", "minLength": 3 },
+                            "priority": { "enum": ["low", "high"] },
+                            "labels": { "type": "array", "items": { "type": "string" } }
+                        },
+                        "required": ["title"],
+                        "additionalProperties": false
+                    }),
+                }
+            }
+            fn call<'a>(
+                &'a self,
+                _arguments: Value,
+                _cancel: CancellationToken,
+            ) -> Pin<Box<dyn Future<Output = ToolOutcome> + Send + 'a>> {
+                unreachable!("invalid arguments never reach the server")
+            }
+        }
+        let catalog = ToolCatalog::with_cwd("/tmp");
+        catalog.register_scope("mcp:tracker", vec![Arc::new(CreateIssue)]);
+        let outcome = catalog
+            .execute(
+                "mcp_create_issue",
+                &json!({"title": "", "priority": "urgent", "labels": ["ok", 7], "owner": "me"}),
+                CancellationToken::default(),
+            )
+            .await;
+        assert!(outcome.is_error);
+        assert_eq!(
+            outcome.output,
+            "invalid arguments for `mcp_create_issue`: \
+             $.labels[1]: expected string, found integer; \
+             $.owner: unexpected argument; \
+             $.priority: must be one of \"low\", \"high\"; \
+             $.title: must be at least 3 characters"
+        );
     }
 
     #[tokio::test]
@@ -1459,6 +3499,8 @@ mod preview_bound_tests {
         ToolResult::Ok {
             call_id: 1,
             output: output.to_owned(),
+            images: Vec::new(),
+            artifact: None,
         }
     }
 
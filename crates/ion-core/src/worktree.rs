@@ -0,0 +1,335 @@
+//! Isolated workspaces for write-enabled children (DESIGN.md §20.4).
+//!
+//! A worktree child edits its own `git worktree`, checked out detached
+//! at the parent repository's `HEAD`, so concurrent writers never share
+//! files. Uncommitted parent changes are not part of that base. What
+//! the child changed comes back as a binary-safe diff against it, and
+//! `merge_child` applies that diff to the parent's working tree when
+//! the parent asks. The worktree lives exactly as long as the child
+//! session: dropping it removes the checkout. A process that dies
+//! first leaves the checkout behind; each live one holds a lock beside
+//! it, so recovery can tell the orphans apart and prune them.
+//!
+//! Git runs synchronously here; async callers go through
+//! `spawn_blocking`, and `Drop` can clean up on any exit path. Every run
+//! pins the config that shapes a patch: user or repository settings for
+//! prefixes, colour, or external diff drivers would otherwise produce a
+//! diff that does not apply, or applies to other paths than the ones
+//! the merge was approved for.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Config every run overrides, ahead of its own arguments.
+const PINNED: [&str; 14] = [
+    "-c",
+    "core.fsmonitor=false",
+    "-c",
+    "core.hooksPath=/dev/null",
+    "-c",
+    "core.quotepath=false",
+    "-c",
+    "color.ui=false",
+    "-c",
+    "diff.noprefix=false",
+    "-c",
+    "diff.mnemonicPrefix=false",
+    "-c",
+    "diff.relative=false",
+];
+
+/// One child's checkout, removed on drop.
+pub(crate) struct Worktree {
+    /// Top level of the parent's working tree.
+    repo: PathBuf,
+    /// The checkout itself, under the repository's git directory.
+    root: PathBuf,
+    /// Where the child's tools resolve: the checkout's counterpart of
+    /// the parent's working directory.
+    cwd: PathBuf,
+    /// Held for the checkout's lifetime; an unlocked checkout is an
+    /// orphan.
+    lock: File,
+}
+
+/// What a child changed, ready for `merge_child`.
+#[derive(Debug, Clone)]
+pub(crate) struct Changes {
+    /// Top level of the parent's working tree the diff applies to.
+    pub(crate) repo: PathBuf,
+    /// Changed paths, relative to `repo`.
+    pub(crate) files: Vec<String>,
+    pub(crate) diff: String,
+}
+
+impl Worktree {
+    /// Check out `HEAD` of the repository containing `parent_cwd` into
+    /// a fresh worktree named `name`.
+    pub(crate) fn create(parent_cwd: &Path, name: &str) -> Result<Self, String> {
+        let repo = git(parent_cwd, &["rev-parse", "--show-toplevel"]).map_err(|_| {
+            format!(
+                "worktree children need a git repository; {} is not in one",
+                parent_cwd.display()
+            )
+        })?;
+        let repo = PathBuf::from(repo.trim_end());
+        let root = worktrees_dir(parent_cwd)?.join(name);
+        // Locked before the checkout exists, so a concurrent prune
+        // never sees it unlocked.
+        let lock = lock_file(&root)?;
+        lock.lock()
+            .map_err(|err| format!("could not lock {}: {err}", root.display()))?;
+        let root_arg = root.to_string_lossy();
+        git(
+            &repo,
+            &["worktree", "add", "--detach", "--quiet", &root_arg, "HEAD"],
+        )?;
+        // Both sides canonical: the top level git reports has symlinks
+        // resolved, the working directory may not.
+        let relative = parent_cwd
+            .canonicalize()
+            .ok()
+            .and_then(|cwd| {
+                let repo = repo.canonicalize().ok()?;
+                cwd.strip_prefix(repo).ok().map(Path::to_path_buf)
+            })
+            .unwrap_or_default();
+        let cwd = root.join(relative);
+        Ok(Self {
+            repo,
+            root,
+            cwd,
+            lock,
+        })
+    }
+
+    pub(crate) fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    /// Everything the child changed, against the commit it started
+    /// from; `None` when it changed nothing.
+    pub(crate) fn changes(&self) -> Result<Option<Changes>, String> {
+        git(&self.root, &["add", "--all"])?;
+        let names = git(
+            &self.root,
+            &["diff", "--cached", "--no-relative", "--name-only", "HEAD"],
+        )?;
+        let files: Vec<String> = names.lines().map(str::to_owned).collect();
+        if files.is_empty() {
+            return Ok(None);
+        }
+        let diff = git(
+            &self.root,
+            &[
+                "diff",
+                "--cached",
+                "--binary",
+                "--no-ext-diff",
+                "--no-color",
+                "--no-textconv",
+                "--no-relative",
+                "--src-prefix=a/",
+                "--dst-prefix=b/",
+                "HEAD",
+            ],
+        )?;
+        Ok(Some(Changes {
+            repo: self.repo.clone(),
+            files,
+            diff,
+        }))
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let root = self.root.to_string_lossy().into_owned();
+        if let Err(err) = git(&self.repo, &["worktree", "remove", "--force", &root]) {
+            tracing::warn!(worktree = %root, "worktree cleanup failed: {err}");
+        }
+        let _ = self.lock.unlock();
+        let _ = std::fs::remove_file(lock_path(&self.root));
+    }
+}
+
+/// Remove the checkouts of the repository containing `cwd` that no
+/// live process holds: children of a process that died before it could
+/// clean up. Returns how many were removed.
+pub(crate) fn prune_orphans(cwd: &Path) -> Result<usize, String> {
+    let dir = worktrees_dir(cwd)?;
+    let Ok(listing) = std::fs::read_dir(&dir) else {
+        return Ok(0);
+    };
+    let mut pruned = 0;
+    for entry in listing.flatten() {
+        let root = entry.path();
+        if !root.is_dir() {
+            continue;
+        }
+        let Ok(lock) = lock_file(&root) else {
+            continue;
+        };
+        if lock.try_lock().is_err() {
+            continue;
+        }
+        let root_arg = root.to_string_lossy();
+        if git(cwd, &["worktree", "remove", "--force", &root_arg]).is_err() {
+            std::fs::remove_dir_all(&root)
+                .map_err(|err| format!("could not remove {}: {err}", root.display()))?;
+        }
+        let _ = std::fs::remove_file(lock_path(&root));
+        pruned += 1;
+    }
+    // Drop the administrative entries of checkouts removed by hand.
+    git(cwd, &["worktree", "prune"])?;
+    Ok(pruned)
+}
+
+/// Where a repository's child checkouts live: `ion-worktrees` in its
+/// common git directory.
+fn worktrees_dir(cwd: &Path) -> Result<PathBuf, String> {
+    let common = git(cwd, &["rev-parse", "--git-common-dir"])?;
+    Ok(cwd.join(common.trim_end()).join("ion-worktrees"))
+}
+
+fn lock_path(root: &Path) -> PathBuf {
+    root.with_extension("lock")
+}
+
+fn lock_file(root: &Path) -> Result<File, String> {
+    if let Some(dir) = root.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|err| format!("could not create {}: {err}", dir.display()))?;
+    }
+    let path = lock_path(root);
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|err| format!("could not open {}: {err}", path.display()))
+}
+
+/// Apply a child's diff to the parent's working tree. All or nothing:
+/// when any hunk no longer applies, no file is touched.
+pub(crate) fn apply(changes: &Changes) -> Result<(), String> {
+    git_apply(
+        Command::new("git").arg("-C").arg(&changes.repo),
+        &changes.diff,
+    )
+}
+
+/// One file a merge changes, as it is now and as the merge would leave
+/// it; `None` where the file does not exist.
+pub(crate) struct Preview {
+    pub(crate) path: PathBuf,
+    pub(crate) before: Option<Vec<u8>>,
+    pub(crate) after: Option<Vec<u8>>,
+}
+
+/// What `apply` would leave in each changed file, without touching the
+/// working tree: the diff is applied to copies in a scratch directory.
+pub(crate) fn preview(changes: &Changes) -> Result<Vec<Preview>, String> {
+    let scratch = std::env::temp_dir().join(format!("ion-merge-{}", uuid::Uuid::now_v7()));
+    let previews = preview_in(&scratch, changes);
+    let _ = std::fs::remove_dir_all(&scratch);
+    previews
+}
+
+fn preview_in(scratch: &Path, changes: &Changes) -> Result<Vec<Preview>, String> {
+    std::fs::create_dir_all(scratch)
+        .map_err(|err| format!("could not create {}: {err}", scratch.display()))?;
+    let mut previews = Vec::new();
+    for file in &changes.files {
+        let path = changes.repo.join(file);
+        let before = read_if_exists(&path)?;
+        if before.is_some() {
+            let copy = scratch.join(file);
+            if let Some(parent) = copy.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|err| format!("could not create {}: {err}", parent.display()))?;
+            }
+            // `copy` keeps the mode, which the diff may check.
+            std::fs::copy(&path, &copy)
+                .map_err(|err| format!("could not copy {}: {err}", path.display()))?;
+        }
+        previews.push(Preview {
+            path,
+            before,
+            after: None,
+        });
+    }
+    // Outside any repository `git apply` patches plain files; the
+    // ceiling keeps it from finding one above the scratch directory.
+    git_apply(
+        Command::new("git")
+            .arg("-C")
+            .arg(scratch)
+            .env("GIT_CEILING_DIRECTORIES", std::env::temp_dir()),
+        &changes.diff,
+    )?;
+    for (preview, file) in previews.iter_mut().zip(&changes.files) {
+        preview.after = read_if_exists(&scratch.join(file))?;
+    }
+    Ok(previews)
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("could not read {}: {err}", path.display())),
+    }
+}
+
+fn git_apply(command: &mut Command, diff: &str) -> Result<(), String> {
+    let mut child = command
+        .args(PINNED)
+        .args(["apply", "--whitespace=nowarn", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("could not run git: {err}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(diff.as_bytes())
+            .map_err(|err| format!("git apply: {err}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("git apply: {err}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "git apply: {}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ))
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(PINNED)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("could not run git: {err}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "git {}: {}",
+            args.iter()
+                .find(|arg| !arg.starts_with('-') && !arg.contains('='))
+                .unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ))
+    }
+}
@@ -10,6 +10,9 @@
 //! resume over ACP is deferred: ion persists sessions in its own
 //! store; replaying them as ACP updates is additional frontend
 //! surface, not new runtime capability.
+//!
+//! [`serve_connected`] speaks the same surface with sessions hosted by
+//! a running `ion daemon` instead of runtimes embedded in this process.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -19,8 +22,8 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::sync::Mutex;
 
 use ion_core::{
-    EventSubscription, OperationId, Provider, Runtime, RuntimeError, RuntimeEvent, SessionStore,
-    ToolCatalog,
+    EventSubscription, FileDiff, OperationId, Provider, Runtime, RuntimeError, RuntimeEvent,
+    SessionStore, ToolCatalog,
 };
 
 /// The ACP major version this adapter speaks (v1 is the stable spec;
@@ -32,22 +35,63 @@ const PROTOCOL_VERSION: u64 = 1;
 /// factory (providers own step cursors, so they are not shared).
 pub struct AcpConfig<P> {
     pub make_provider: Arc<dyn Fn() -> P + Send + Sync>,
+    /// Agent profiles the sessions' children may run under.
+    pub child_profiles: crate::ChildProfiles<P>,
     pub store: Arc<SessionStore>,
     pub policy: Arc<dyn PolicyEngine>,
 }
 
 use ion_core::PolicyEngine;
 
+use crate::children::ChildTree;
+use crate::daemon::DaemonClient;
+
 struct AcpSession {
     handle: ion_core::SessionHandle,
-    #[allow(dead_code)] // owns the runtime task until process exit
-    runtime: Runtime,
+    /// Owns an embedded runtime task until process exit; None when the
+    /// session lives in a daemon.
+    #[allow(dead_code)]
+    runtime: Option<Runtime>,
     /// The in-flight prompt turn, if any: (JSON-RPC id, operation id).
     active_prompt: Option<(Value, OperationId)>,
 }
 
 /// Serve ACP over `input`/`output` until the peer disconnects.
 pub async fn serve<P, R, W>(input: R, output: W, config: AcpConfig<P>) -> std::io::Result<()>
+where
+    P: Provider,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    serve_with(input, output, Sessions::Embedded(config)).await
+}
+
+/// Serve ACP with every session created in the daemon behind `client`.
+pub async fn serve_connected<R, W>(input: R, output: W, client: DaemonClient) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    // The provider type is moot: daemon sessions never compose one here.
+    serve_with::<crate::CliProvider, _, _>(input, output, Sessions::Daemon(client)).await
+}
+
+/// Where `session/new` gets its runtime.
+enum Sessions<P> {
+    Embedded(AcpConfig<P>),
+    Daemon(DaemonClient),
+}
+
+impl<P: Provider> Sessions<P> {
+    async fn create(&self, params: &Value) -> Result<(String, AcpSession), String> {
+        match self {
+            Self::Embedded(config) => session_new(config, params).await,
+            Self::Daemon(client) => session_attach(client, params).await,
+        }
+    }
+}
+
+async fn serve_with<P, R, W>(input: R, output: W, source: Sessions<P>) -> std::io::Result<()>
 where
     P: Provider,
     R: AsyncRead + Unpin,
@@ -99,7 +143,7 @@ where
                 )
                 .await;
             }
-            Some("session/new") => match session_new(&config, &params).await {
+            Some("session/new") => match source.create(&params).await {
                 Ok((session_id_string, session)) => {
                     sessions.insert(session_id_string.clone(), session);
                     write(
@@ -206,6 +250,7 @@ async fn pump_turn<W>(
 where
     W: AsyncWrite + Unpin + Send + 'static,
 {
+    let mut children: HashMap<u64, ChildTree> = HashMap::new();
     loop {
         let event = match events.recv().await {
             Ok(event) => event,
@@ -254,20 +299,48 @@ where
                 )
                 .await;
             }
-            RuntimeEvent::ToolSettled {
-                call_id, is_error, ..
+            // Child progress re-renders the delegate call's content:
+            // one line per child, replacing the previous rendering.
+            RuntimeEvent::ChildProgress {
+                call_id,
+                child_session_id,
+                update: child_update,
+                ..
             } => {
+                let tree = children.entry(call_id).or_default();
+                tree.apply(child_session_id, child_update);
                 update(
                     output,
                     session_id,
                     json!({
                         "sessionUpdate": "tool_call_update",
                         "toolCallId": call_id.to_string(),
-                        "status": if is_error { "failed" } else { "completed" },
+                        "status": "in_progress",
+                        "content": [{
+                            "type": "content",
+                            "content": { "type": "text", "text": tree.lines().join("\n") },
+                        }],
                     }),
                 )
                 .await;
             }
+            RuntimeEvent::ToolSettled {
+                call_id,
+                is_error,
+                diffs,
+                ..
+            } => {
+                children.remove(&call_id);
+                let mut fields = json!({
+                    "sessionUpdate": "tool_call_update",
+                    "toolCallId": call_id.to_string(),
+                    "status": if is_error { "failed" } else { "completed" },
+                });
+                if !diffs.is_empty() {
+                    fields["content"] = diffs.iter().map(diff_content).collect();
+                }
+                update(output, session_id, fields).await;
+            }
             RuntimeEvent::OperationFinished { .. } => return TurnStop::EndTurn,
             RuntimeEvent::OperationCancelled { .. } => return TurnStop::Cancelled,
             RuntimeEvent::OperationFailed { message, .. } => {
@@ -276,7 +349,11 @@ where
             RuntimeEvent::OperationApprovalRequired { tool, .. } => {
                 return TurnStop::ApprovalRequired(tool);
             }
-            RuntimeEvent::OperationStarted { .. } | RuntimeEvent::SessionClosed { .. } => {}
+            // Live output has no incremental ACP form: tool call
+            // content replaces rather than appends.
+            RuntimeEvent::ToolOutput { .. }
+            | RuntimeEvent::OperationStarted { .. }
+            | RuntimeEvent::SessionClosed { .. } => {}
         }
     }
 }
@@ -381,12 +458,13 @@ where
         Arc::clone(&config.policy),
     );
     let session_id = runtime.session_id();
-    // ACP sessions can delegate to bounded read-only children (§20).
+    // ACP sessions can delegate to bounded children (§20).
     let factory = Arc::clone(&config.make_provider);
     crate::enable_children(
         &catalog,
         &config.store,
         Arc::new(move || factory()),
+        &config.child_profiles,
         session_id,
     );
     let session_id_string = session_id.to_string();
@@ -395,7 +473,39 @@ where
         session_id_string,
         AcpSession {
             handle,
-            runtime,
+            runtime: Some(runtime),
+            active_prompt: None,
+        },
+    ))
+}
+
+/// Create an ACP session inside the daemon. Tools and MCP servers are
+/// the daemon's own composition, so client-supplied servers are
+/// refused rather than silently dropped.
+async fn session_attach(
+    client: &DaemonClient,
+    params: &Value,
+) -> Result<(String, AcpSession), String> {
+    let cwd = params
+        .get("cwd")
+        .and_then(|v| v.as_str())
+        .ok_or("missing cwd")?;
+    if params
+        .get("mcpServers")
+        .and_then(|v| v.as_array())
+        .is_some_and(|servers| !servers.is_empty())
+    {
+        return Err("mcpServers are configured on the daemon, not per session".to_owned());
+    }
+    let (session_id, handle) = client
+        .create_session(Some(std::path::Path::new(cwd)))
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok((
+        session_id.to_string(),
+        AcpSession {
+            handle,
+            runtime: None,
             active_prompt: None,
         },
     ))
@@ -420,13 +530,50 @@ fn prompt_text(params: &Value) -> Option<String> {
 
 fn tool_kind(tool: &str) -> &'static str {
     match tool {
-        "bash" => "execute",
-        "read" | "search" | "find" => "read",
-        "write" | "edit" => "edit",
+        "bash" | "process_start" | "process_stop" => "execute",
+        "read" | "search" | "find" | "process_output" | "git_status" | "git_diff" | "git_log"
+        | "git_blame" => "read",
+        "write" | "edit" | "apply_patch" | "merge_child" => "edit",
         _ => "other",
     }
 }
 
+/// ACP `diff` content for one changed file: the whole file on both
+/// sides, which the client diffs itself. A created file has no old
+/// side; a deleted one has an empty new side. A file too large to ride
+/// along whole is sent as the hunks' two sides instead: an excerpt the
+/// client diffs back into the same change.
+fn diff_content(diff: &FileDiff) -> Value {
+    if diff.whole {
+        return json!({
+            "type": "diff",
+            "path": diff.path,
+            "oldText": diff.old_text,
+            "newText": diff.new_text.as_deref().unwrap_or_default(),
+        });
+    }
+    let mut old_text = Vec::new();
+    let mut new_text = Vec::new();
+    for line in diff.hunks.lines() {
+        if let Some(rest) = line.strip_prefix(' ') {
+            old_text.push(rest);
+            new_text.push(rest);
+        } else if let Some(rest) = line.strip_prefix('-') {
+            old_text.push(rest);
+        } else if let Some(rest) = line.strip_prefix('+') {
+            new_text.push(rest);
+        }
+    }
+    // A created file has no old side at all.
+    let created = diff.hunks.starts_with("@@ -0,0 ");
+    json!({
+        "type": "diff",
+        "path": diff.path,
+        "oldText": (!created).then(|| old_text.join("\n")),
+        "newText": new_text.join("\n"),
+    })
+}
+
 async fn write<W: AsyncWrite + Unpin>(output: &Arc<Mutex<W>>, message: Value) {
     let mut out = output.lock().await;
     let line = message.to_string();
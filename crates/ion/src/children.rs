@@ -0,0 +1,136 @@
+//! Live view of a running `delegate` call's children (DESIGN.md
+//! §20.7), folded from the call's `ChildProgress` events. Shared by the
+//! TUI tree and the ACP progress updates; display-only, like the events
+//! it is built from.
+
+use ion_core::{ChildUpdate, SessionId};
+
+/// The children of one `delegate` call, in the order they started.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ChildTree {
+    children: Vec<ChildRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChildRow {
+    session_id: SessionId,
+    objective: String,
+    profile: Option<String>,
+    state: ChildState,
+    /// What the child did last: a tool call or a line of its text.
+    activity: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChildState {
+    Running,
+    Finished,
+    Failed(String),
+    TimedOut,
+    Cancelled,
+}
+
+impl ChildTree {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub(crate) fn apply(&mut self, session_id: SessionId, update: ChildUpdate) {
+        if let ChildUpdate::Started { objective, profile } = update {
+            self.children.push(ChildRow {
+                session_id,
+                objective,
+                profile,
+                state: ChildState::Running,
+                activity: None,
+            });
+            return;
+        }
+        // Updates for a child whose start was dropped have no row.
+        let Some(child) = self
+            .children
+            .iter_mut()
+            .find(|child| child.session_id == session_id)
+        else {
+            return;
+        };
+        match update {
+            ChildUpdate::Started { .. } => {}
+            ChildUpdate::ToolStarted { tool, target } => {
+                child.activity = Some(match target {
+                    Some(target) => format!("{tool} {target}…"),
+                    None => format!("{tool}…"),
+                });
+            }
+            ChildUpdate::ToolSettled { tool, is_error } => {
+                if is_error {
+                    child.activity = Some(format!("{tool} ✗"));
+                }
+            }
+            ChildUpdate::Text { line } => child.activity = Some(format!("« {line}")),
+            ChildUpdate::Finished => child.state = ChildState::Finished,
+            ChildUpdate::Failed { message } => child.state = ChildState::Failed(message),
+            ChildUpdate::TimedOut => child.state = ChildState::TimedOut,
+            ChildUpdate::Cancelled => child.state = ChildState::Cancelled,
+        }
+    }
+
+    /// The collapsed form: how many children are in which state.
+    pub(crate) fn summary(&self) -> String {
+        let count = |wanted: fn(&ChildState) -> bool| {
+            self.children
+                .iter()
+                .filter(|child| wanted(&child.state))
+                .count()
+        };
+        let parts: Vec<String> = [
+            (count(|s| *s == ChildState::Running), "running"),
+            (count(|s| *s == ChildState::Finished), "done"),
+            (count(|s| matches!(s, ChildState::Failed(_))), "failed"),
+            (count(|s| *s == ChildState::TimedOut), "timed out"),
+            (count(|s| *s == ChildState::Cancelled), "cancelled"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, state)| format!("{n} {state}"))
+        .collect();
+        let noun = if self.children.len() == 1 {
+            "child"
+        } else {
+            "children"
+        };
+        format!("{} {noun}: {}", self.children.len(), parts.join(", "))
+    }
+
+    /// The expanded form: one line per child with its state, objective,
+    /// and latest activity.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.children
+            .iter()
+            .map(|child| {
+                let marker = match child.state {
+                    ChildState::Running => "●",
+                    ChildState::Finished => "✓",
+                    ChildState::Failed(_) => "✗",
+                    ChildState::TimedOut => "⏱",
+                    ChildState::Cancelled => "⊘",
+                };
+                let mut line = format!("{marker} {}", child.objective);
+                if let Some(profile) = &child.profile {
+                    line.push_str(&format!(" [{profile}]"));
+                }
+                let detail = match &child.state {
+                    ChildState::Failed(message) => Some(message.as_str()),
+                    ChildState::Running => child.activity.as_deref(),
+                    ChildState::TimedOut => Some("timed out"),
+                    ChildState::Finished | ChildState::Cancelled => None,
+                };
+                if let Some(detail) = detail {
+                    line.push_str(" — ");
+                    line.push_str(detail);
+                }
+                line
+            })
+            .collect()
+    }
+}
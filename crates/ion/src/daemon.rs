@@ -0,0 +1,1023 @@
+//! Headless daemon (DESIGN.md Step 10): one long-lived process hosts
+//! many `SessionRuntime`s and serves them over a Unix socket, so the
+//! TUI, print mode, and ACP can attach as clients instead of embedding
+//! the runtime.
+//!
+//! The wire is newline-delimited JSON-RPC 2.0, one method per
+//! `SessionHandle` command (`session/submit`, `session/steer`, ...),
+//! plus `session/create` and `session/open` to load sessions. Events
+//! flow as `session/event` notifications for each subscription; a
+//! subscription that lags ends with `session/subscription_end` and the
+//! client resumes from its last cursor or resynchronizes from a fresh
+//! snapshot (§21.4). This is an adapter: the daemon is the sole store
+//! owner (§11.5) and every command lands on the same single-writer
+//! session task an embedded host would use.
+//!
+//! Each connection attaches to a session as its controller or as a
+//! read-only observer. One connection controls a session at a time;
+//! its lease is released when it disconnects.
+//!
+//! [`DaemonClient`] is the client half. Its sessions are ordinary
+//! [`SessionHandle`]s built with [`SessionHandle::remote`], so
+//! frontends need no second code path.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+
+use ion_core::{
+    BackendFuture, ChildSummary, CommandError, EventFeed, EventSubscription, OperationId,
+    PolicyEngine, Provider, RuntimeCursor, RuntimeError, RuntimeEvent, RuntimeHandle,
+    RuntimeInstanceId, SessionBackend, SessionEntry, SessionHandle, SessionId, SessionSnapshot,
+    SessionStore, ToolCatalog, UndoOutcome,
+};
+
+/// Outbound lines buffered per connection. A client that stops reading
+/// backs up into its subscriptions, which then lag (§21.4) instead of
+/// growing the daemon.
+const OUTBOUND_CAPACITY: usize = 64;
+
+/// JSON-RPC error codes. Command failures carry the typed
+/// [`CommandError`] as `data` so clients reconstruct it exactly.
+const COMMAND_FAILED: i64 = -32000;
+const UNKNOWN_SESSION: i64 = -32001;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// How a client attaches to a daemon-hosted session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachRole {
+    /// Holds the session's single controller lease.
+    Controller,
+    /// Watches snapshots and events; commands are refused.
+    Observer,
+}
+
+impl AttachRole {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Controller => "controller",
+            Self::Observer => "observer",
+        }
+    }
+}
+
+/// The default socket: `$XDG_RUNTIME_DIR/ion/daemon.sock`, falling back
+/// to the directory that holds the session database.
+#[must_use]
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("ion").join("daemon.sock"),
+        None => ion_core::default_db_path().with_file_name("daemon.sock"),
+    }
+}
+
+/// Bind the daemon's socket so only its user can reach it: a missing
+/// parent directory is created private, and the socket itself is
+/// owner-only. [`serve`] still checks each peer's uid, since an
+/// existing parent may be shared.
+pub fn bind(socket: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    if let Some(parent) = socket.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Composition shared by every session the daemon hosts.
+pub struct DaemonConfig<P> {
+    /// Builds the provider the sessions share, and one per delegated
+    /// child.
+    pub make_provider: Arc<dyn Fn() -> P + Send + Sync>,
+    /// Agent profiles every session's children may run under.
+    pub child_profiles: crate::ChildProfiles<P>,
+    pub store: SessionStore,
+    pub policy: Arc<dyn PolicyEngine>,
+    /// Base tool surface (core, MCP, extensions). Each session runs on
+    /// its own fork so session-bound scopes stay private.
+    pub tools: ToolCatalog,
+    /// Hibernate sessions nobody watches after this long idle; the
+    /// next command rehydrates them.
+    pub idle_timeout: Option<Duration>,
+}
+
+/// The daemon's session registry plus the directory it serves.
+struct Host {
+    runtime: RuntimeHandle,
+    root: PathBuf,
+    store: SessionStore,
+}
+
+impl Host {
+    fn new<P: Provider + 'static>(config: DaemonConfig<P>) -> Self {
+        let root = config.tools.cwd().to_path_buf();
+        let runtime = RuntimeHandle::new(
+            (config.make_provider)(),
+            config.tools,
+            config.store.clone(),
+            config.policy,
+        );
+        runtime.set_idle_timeout(config.idle_timeout);
+        let store = config.store.clone();
+        let make_provider = config.make_provider;
+        let profiles = config.child_profiles;
+        runtime.set_session_setup(Arc::new(move |tools, session_id| {
+            crate::enable_children(
+                tools,
+                &store,
+                Arc::clone(&make_provider),
+                &profiles,
+                session_id,
+            );
+        }));
+        Self {
+            runtime,
+            root,
+            store: config.store,
+        }
+    }
+
+    /// Start a new session. A client working elsewhere is refused: the
+    /// daemon's tools are rooted at its own working directory.
+    fn create(&self, cwd: Option<PathBuf>) -> Result<SessionId, String> {
+        if let Some(cwd) = cwd
+            && std::fs::canonicalize(&cwd).ok() != std::fs::canonicalize(&self.root).ok()
+        {
+            return Err(format!(
+                "daemon serves {}, not {}",
+                self.root.display(),
+                cwd.display()
+            ));
+        }
+        let (session_id, _) = self
+            .runtime
+            .create_session()
+            .map_err(|err| err.to_string())?;
+        Ok(session_id)
+    }
+
+    /// Load a persisted session (the most recent one by default), or
+    /// find it already loaded.
+    async fn open(&self, requested: Option<SessionId>) -> Result<SessionId, String> {
+        let session_id = match requested {
+            Some(id) => id,
+            None => self
+                .store
+                .latest_session()
+                .await
+                .map_err(|err| err.to_string())?
+                .ok_or("no persisted session to open")?,
+        };
+        self.runtime
+            .open_session(session_id)
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(session_id)
+    }
+}
+
+/// Serve `listener` until `shutdown` resolves, then close every hosted
+/// session before returning. Only peers running as the user that owns
+/// the socket are served; anyone else is disconnected unanswered.
+pub async fn serve<P>(
+    listener: UnixListener,
+    config: DaemonConfig<P>,
+    shutdown: impl Future<Output = ()>,
+) -> std::io::Result<()>
+where
+    P: Provider + 'static,
+{
+    let owner = socket_owner(&listener)?;
+    let host = Arc::new(Host::new(config));
+    let mut connections = tokio::task::JoinSet::new();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                match stream.peer_cred() {
+                    Ok(peer) if peer.uid() == owner => {
+                        connections.spawn(connection(stream, Arc::clone(&host)));
+                    }
+                    Ok(peer) => tracing::warn!(uid = peer.uid(), "refused a connection from another user"),
+                    Err(err) => tracing::warn!("refused a connection without credentials: {err}"),
+                }
+            }
+            () = &mut shutdown => break,
+        }
+    }
+    connections.shutdown().await;
+    // Open operations suspend durably and recover on the next open
+    // (§9.5).
+    let _ = host.runtime.shutdown().await;
+    Ok(())
+}
+
+/// The uid the listening socket belongs to: the daemon's own, since it
+/// bound the socket.
+fn socket_owner(listener: &UnixListener) -> std::io::Result<u32> {
+    use std::os::unix::fs::MetadataExt;
+    let address = listener.local_addr()?;
+    let path = address.as_pathname().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the daemon serves a socket bound to a path",
+        )
+    })?;
+    Ok(std::fs::metadata(path)?.uid())
+}
+
+/// One connection's view: the sessions it attached to, under the role
+/// it attached with, and its live subscriptions.
+#[derive(Default)]
+struct Attachments {
+    sessions: HashMap<SessionId, SessionHandle>,
+    subscriptions: HashMap<u64, tokio::task::JoinHandle<()>>,
+}
+
+impl Attachments {
+    fn session(&self, session_id: SessionId) -> Result<&SessionHandle, Value> {
+        self.sessions.get(&session_id).ok_or_else(|| {
+            rpc_error(
+                UNKNOWN_SESSION,
+                "session is not attached",
+                Some(CommandError::Closed),
+            )
+        })
+    }
+
+    fn subscribe(&mut self, subscription: u64, pump: tokio::task::JoinHandle<()>) {
+        if let Some(previous) = self.subscriptions.insert(subscription, pump) {
+            previous.abort();
+        }
+    }
+
+    /// Attach under `role`, replacing (and releasing) an earlier
+    /// attachment to the same session.
+    async fn attach(
+        &mut self,
+        session_id: SessionId,
+        host_handle: SessionHandle,
+        role: AttachRole,
+    ) -> Result<(), CommandError> {
+        let handle = match role {
+            AttachRole::Controller => match self.sessions.get(&session_id) {
+                Some(current) if !current.is_observer() => current.clone(),
+                _ => host_handle.claim_control().await?,
+            },
+            AttachRole::Observer => host_handle.observer(),
+        };
+        if let Some(previous) = self.sessions.insert(session_id, handle.clone())
+            && !previous.is_observer()
+            && handle.is_observer()
+        {
+            let _ = previous.release_control().await;
+        }
+        Ok(())
+    }
+
+    /// Disconnect: stop streaming and give back every lease. Sessions
+    /// outlive the clients watching them.
+    async fn detach(self) {
+        for (_, pump) in self.subscriptions {
+            pump.abort();
+        }
+        for (_, handle) in self.sessions {
+            if !handle.is_observer() {
+                let _ = handle.release_control().await;
+            }
+        }
+    }
+}
+
+/// Serve one client until it disconnects. Requests are handled in
+/// arrival order, so one client's `submit` then `steer` reach the
+/// session in that order; subscriptions stream from their own tasks.
+async fn connection(stream: UnixStream, host: Arc<Host>) {
+    let (read, mut write) = stream.into_split();
+    let (out, mut out_rx) = mpsc::channel::<String>(OUTBOUND_CAPACITY);
+    let writer = tokio::spawn(async move {
+        while let Some(line) = out_rx.recv().await {
+            if write.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+            let _ = write.flush().await;
+        }
+    });
+    let mut attachments = Attachments::default();
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+        let reply = dispatch(&host, method, &params, &out, &mut attachments).await;
+        if let Some(id) = id {
+            let message = match reply {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            };
+            if out.send(format!("{message}\n")).await.is_err() {
+                break;
+            }
+        }
+    }
+    attachments.detach().await;
+    drop(out);
+    let _ = writer.await;
+}
+
+async fn dispatch(
+    host: &Host,
+    method: &str,
+    params: &Value,
+    out: &mpsc::Sender<String>,
+    attachments: &mut Attachments,
+) -> Result<Value, Value> {
+    match method {
+        // The creating client controls its new session.
+        "session/create" => {
+            let cwd = params.get("cwd").and_then(Value::as_str).map(PathBuf::from);
+            let session_id = host
+                .create(cwd)
+                .map_err(|message| rpc_error(COMMAND_FAILED, &message, None))?;
+            attach(host, attachments, session_id, AttachRole::Controller).await?;
+            return Ok(json!({ "sessionId": session_id }));
+        }
+        "session/open" => {
+            let requested = match params.get("sessionId") {
+                None | Some(Value::Null) => None,
+                Some(value) => Some(session_id_param(value)?),
+            };
+            let role = match params.get("role").and_then(Value::as_str) {
+                None | Some("controller") => AttachRole::Controller,
+                Some("observer") => AttachRole::Observer,
+                Some(other) => {
+                    return Err(rpc_error(
+                        INVALID_PARAMS,
+                        &format!("unknown role: {other}"),
+                        None,
+                    ));
+                }
+            };
+            let session_id = host
+                .open(requested)
+                .await
+                .map_err(|message| rpc_error(COMMAND_FAILED, &message, None))?;
+            attach(host, attachments, session_id, role).await?;
+            return Ok(json!({ "sessionId": session_id }));
+        }
+        "session/unsubscribe" => {
+            if let Some(pump) = params
+                .get("subscription")
+                .and_then(Value::as_u64)
+                .and_then(|subscription| attachments.subscriptions.remove(&subscription))
+            {
+                pump.abort();
+            }
+            return Ok(Value::Null);
+        }
+        _ => {}
+    }
+
+    let session_id = session_id_param(params.get("sessionId").unwrap_or(&Value::Null))?;
+    let session = attachments.session(session_id)?.clone();
+    match method {
+        // Only the controller closes; the host drains the task.
+        "session/close" => {
+            if session.is_observer() {
+                return Err(command_error(CommandError::ReadOnly));
+            }
+            attachments.sessions.remove(&session_id);
+            return host
+                .runtime
+                .close_session(session_id)
+                .await
+                .map(|()| Value::Null)
+                .map_err(command_error);
+        }
+        "session/claim_control" => {
+            attach(host, attachments, session_id, AttachRole::Controller).await?;
+            return Ok(Value::Null);
+        }
+        "session/release_control" => {
+            attach(host, attachments, session_id, AttachRole::Observer).await?;
+            return Ok(Value::Null);
+        }
+        _ => {}
+    }
+    match method {
+        "session/submit" => {
+            let prompt = string_param(params, "prompt")?;
+            let operation_id = session.submit(prompt).await.map_err(command_error)?;
+            Ok(json!({ "operationId": operation_id }))
+        }
+        "session/steer" => {
+            let text = string_param(params, "text")?;
+            session.steer(text).await.map_err(command_error)?;
+            Ok(Value::Null)
+        }
+        "session/follow_up" => {
+            let text = string_param(params, "text")?;
+            session.follow_up(text).await.map_err(command_error)?;
+            Ok(Value::Null)
+        }
+        "session/compact" => {
+            let instructions = params
+                .get("instructions")
+                .and_then(Value::as_str)
+                .map(str::to_owned);
+            let requested = session.compact(instructions).await.map_err(command_error)?;
+            Ok(json!({ "requested": requested }))
+        }
+        "session/switch_model" => {
+            let model_ref = string_param(params, "modelRef")?;
+            let previous = session
+                .switch_model(model_ref)
+                .await
+                .map_err(command_error)?;
+            Ok(json!({ "previous": previous }))
+        }
+        "session/cancel" => {
+            let operation_id: OperationId =
+                serde_json::from_value(params.get("operationId").cloned().unwrap_or_default())
+                    .map_err(|_| rpc_error(INVALID_PARAMS, "missing operationId", None))?;
+            session.cancel(operation_id).await.map_err(command_error)?;
+            Ok(Value::Null)
+        }
+        "session/cancel_child" => {
+            let child: SessionId =
+                serde_json::from_value(params.get("child").cloned().unwrap_or_default())
+                    .map_err(|_| rpc_error(INVALID_PARAMS, "missing child", None))?;
+            session.cancel_child(child).await.map_err(command_error)?;
+            Ok(Value::Null)
+        }
+        "session/undo" => {
+            let operation: Option<OperationId> =
+                serde_json::from_value(params.get("operationId").cloned().unwrap_or_default())
+                    .map_err(|_| rpc_error(INVALID_PARAMS, "invalid operationId", None))?;
+            let outcome = session.undo(operation).await.map_err(command_error)?;
+            Ok(json!({ "outcome": outcome }))
+        }
+        "session/snapshot" => {
+            let snapshot = session.snapshot().await.map_err(command_error)?;
+            Ok(json!({ "snapshot": snapshot }))
+        }
+        "session/artifact" => {
+            let id = string_param(params, "id")?;
+            let text = session.artifact(id).await.map_err(command_error)?;
+            Ok(json!({ "text": text }))
+        }
+        "session/children" => {
+            let children = session.children().await.map_err(command_error)?;
+            Ok(json!({ "children": children }))
+        }
+        "session/child_transcript" => {
+            let child: SessionId =
+                serde_json::from_value(params.get("child").cloned().unwrap_or_default())
+                    .map_err(|_| rpc_error(INVALID_PARAMS, "missing child", None))?;
+            let entries = session
+                .child_transcript(child)
+                .await
+                .map_err(command_error)?;
+            Ok(json!({ "entries": entries }))
+        }
+        "session/subscribe" => {
+            let subscription = params
+                .get("subscription")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| rpc_error(INVALID_PARAMS, "missing subscription", None))?;
+            // Events may reach the client before this response does;
+            // the client registers its feed before asking, and every
+            // event postdates the snapshot either way.
+            let (snapshot, events) = session.subscribe().await.map_err(command_error)?;
+            attachments.subscribe(
+                subscription,
+                tokio::spawn(pump(subscription, events, out.clone())),
+            );
+            Ok(json!({ "snapshot": snapshot }))
+        }
+        "session/resume" => {
+            let subscription = params
+                .get("subscription")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| rpc_error(INVALID_PARAMS, "missing subscription", None))?;
+            let instance: RuntimeInstanceId =
+                serde_json::from_value(params.get("instance").cloned().unwrap_or_default())
+                    .map_err(|_| rpc_error(INVALID_PARAMS, "missing instance", None))?;
+            let after: RuntimeCursor =
+                serde_json::from_value(params.get("cursor").cloned().unwrap_or_default())
+                    .map_err(|_| rpc_error(INVALID_PARAMS, "missing cursor", None))?;
+            let events = session
+                .resume(instance, after)
+                .await
+                .map_err(command_error)?;
+            attachments.subscribe(
+                subscription,
+                tokio::spawn(pump(subscription, events, out.clone())),
+            );
+            Ok(Value::Null)
+        }
+        other => Err(rpc_error(
+            METHOD_NOT_FOUND,
+            &format!("method not supported: {other}"),
+            None,
+        )),
+    }
+}
+
+/// Attach this connection to a loaded session under `role`.
+async fn attach(
+    host: &Host,
+    attachments: &mut Attachments,
+    session_id: SessionId,
+    role: AttachRole,
+) -> Result<(), Value> {
+    let Some(handle) = host.runtime.session(session_id) else {
+        return Err(rpc_error(
+            UNKNOWN_SESSION,
+            "session is not loaded",
+            Some(CommandError::Closed),
+        ));
+    };
+    attachments
+        .attach(session_id, handle, role)
+        .await
+        .map_err(command_error)
+}
+
+/// Forward one subscription's events as notifications until it ends.
+async fn pump(subscription: u64, mut events: EventSubscription, out: mpsc::Sender<String>) {
+    let reason = loop {
+        match events.recv().await {
+            Ok(event) => {
+                let message = json!({
+                    "jsonrpc": "2.0",
+                    "method": "session/event",
+                    "params": { "subscription": subscription, "event": event },
+                });
+                if out.send(format!("{message}\n")).await.is_err() {
+                    return;
+                }
+            }
+            Err(RuntimeError::SubscriptionLagged) => break "lagged",
+            Err(_) => break "closed",
+        }
+    };
+    let message = json!({
+        "jsonrpc": "2.0",
+        "method": "session/subscription_end",
+        "params": { "subscription": subscription, "reason": reason },
+    });
+    let _ = out.send(format!("{message}\n")).await;
+}
+
+fn session_id_param(value: &Value) -> Result<SessionId, Value> {
+    serde_json::from_value(value.clone())
+        .map_err(|_| rpc_error(INVALID_PARAMS, "missing or malformed sessionId", None))
+}
+
+fn string_param(params: &Value, key: &str) -> Result<String, Value> {
+    params
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| rpc_error(INVALID_PARAMS, &format!("missing {key}"), None))
+}
+
+fn command_error(err: CommandError) -> Value {
+    rpc_error(COMMAND_FAILED, &err.to_string(), Some(err))
+}
+
+fn rpc_error(code: i64, message: &str, data: Option<CommandError>) -> Value {
+    match data {
+        Some(data) => json!({ "code": code, "message": message, "data": data }),
+        None => json!({ "code": code, "message": message }),
+    }
+}
+
+// ---- client ----
+
+/// In-flight requests keyed by id.
+/// Errors stay raw JSON-RPC error objects until the caller decides how
+/// to read them. `None` once the daemon hung up, so a request issued
+/// after the reader exited fails instead of waiting forever.
+type Pending = std::sync::Mutex<Option<HashMap<u64, oneshot::Sender<Result<Value, Value>>>>>;
+
+/// A connection to a running daemon.
+#[derive(Clone)]
+pub struct DaemonClient {
+    inner: Arc<ClientInner>,
+}
+
+struct ClientInner {
+    out: mpsc::Sender<String>,
+    next_id: AtomicU64,
+    pending: Pending,
+    /// Live subscriptions, fed by the reader task.
+    feeds: std::sync::Mutex<HashMap<u64, EventFeed>>,
+}
+
+impl DaemonClient {
+    /// Connect to the daemon listening on `path`.
+    pub async fn connect(path: &Path) -> std::io::Result<Self> {
+        let stream = UnixStream::connect(path).await?;
+        let (read, mut write) = stream.into_split();
+        let (out, mut out_rx) = mpsc::channel::<String>(OUTBOUND_CAPACITY);
+        tokio::spawn(async move {
+            while let Some(line) = out_rx.recv().await {
+                if write.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+                let _ = write.flush().await;
+            }
+        });
+        let inner = Arc::new(ClientInner {
+            out,
+            next_id: AtomicU64::new(1),
+            pending: std::sync::Mutex::new(Some(HashMap::new())),
+            feeds: std::sync::Mutex::new(HashMap::new()),
+        });
+        // Weak: once every handle and the client are gone, the writer
+        // closes and the daemon sees this client leave.
+        let weak = Arc::downgrade(&inner);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Some(reader) = weak.upgrade() else {
+                    return;
+                };
+                if let Ok(message) = serde_json::from_str::<Value>(&line) {
+                    reader.dispatch(&message);
+                }
+            }
+            let Some(reader) = weak.upgrade() else {
+                return;
+            };
+            // Daemon gone: fail in-flight requests and end every feed
+            // so callers observe the loss instead of waiting forever.
+            let pending = reader.pending.lock().expect("pending poisoned").take();
+            for (_, sender) in pending.into_iter().flatten() {
+                let _ = sender.send(Err(command_error(CommandError::Closed)));
+            }
+            reader.feeds.lock().expect("feeds poisoned").clear();
+        });
+        Ok(Self { inner })
+    }
+
+    /// Start a new session in the daemon. `cwd` is checked against the
+    /// directory the daemon serves.
+    pub async fn create_session(
+        &self,
+        cwd: Option<&Path>,
+    ) -> Result<(SessionId, SessionHandle), String> {
+        let result = self
+            .inner
+            .request_raw("session/create", json!({ "cwd": cwd }))
+            .await
+            .map_err(error_message)?;
+        Ok(self.attach(session_id_result(&result)?))
+    }
+
+    /// Load a persisted session (the most recent one when `session_id`
+    /// is None), or attach to it if the daemon already hosts it. A
+    /// controller attach fails while another client holds control; an
+    /// observer's handle refuses commands until it claims control.
+    pub async fn open_session(
+        &self,
+        session_id: Option<SessionId>,
+        role: AttachRole,
+    ) -> Result<(SessionId, SessionHandle), String> {
+        let result = self
+            .inner
+            .request_raw(
+                "session/open",
+                json!({ "sessionId": session_id, "role": role.as_str() }),
+            )
+            .await
+            .map_err(error_message)?;
+        let (session_id, handle) = self.attach(session_id_result(&result)?);
+        Ok(match role {
+            AttachRole::Controller => (session_id, handle),
+            AttachRole::Observer => (session_id, handle.observer()),
+        })
+    }
+
+    fn attach(&self, session_id: SessionId) -> (SessionId, SessionHandle) {
+        let backend = RemoteSession {
+            inner: Arc::clone(&self.inner),
+            session_id,
+        };
+        (session_id, SessionHandle::remote(backend))
+    }
+}
+
+fn session_id_result(result: &Value) -> Result<SessionId, String> {
+    serde_json::from_value(result.get("sessionId").cloned().unwrap_or_default())
+        .map_err(|_| "daemon answered without a session id".to_owned())
+}
+
+fn error_message(error: Value) -> String {
+    error
+        .get("message")
+        .and_then(Value::as_str)
+        .map_or_else(|| error.to_string(), str::to_owned)
+}
+
+impl ClientInner {
+    /// A session command: typed failures round-trip; anything else is
+    /// a protocol mismatch the caller cannot act on.
+    async fn request(&self, method: &str, params: Value) -> Result<Value, CommandError> {
+        self.request_raw(method, params).await.map_err(|error| {
+            error
+                .get("data")
+                .and_then(|data| serde_json::from_value(data.clone()).ok())
+                .unwrap_or(CommandError::RuntimeDropped)
+        })
+    }
+
+    async fn request_raw(&self, method: &str, params: Value) -> Result<Value, Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let (tx, rx) = oneshot::channel();
+        match self.pending.lock().expect("pending poisoned").as_mut() {
+            Some(pending) => pending.insert(id, tx),
+            None => return Err(command_error(CommandError::Closed)),
+        };
+        if self.out.send(format!("{message}\n")).await.is_err() {
+            if let Some(pending) = self.pending.lock().expect("pending poisoned").as_mut() {
+                pending.remove(&id);
+            }
+            return Err(command_error(CommandError::Closed));
+        }
+        rx.await
+            .unwrap_or_else(|_| Err(command_error(CommandError::Closed)))
+    }
+
+    fn dispatch(&self, message: &Value) {
+        if let Some(id) = message.get("id").and_then(Value::as_u64) {
+            let sender = self
+                .pending
+                .lock()
+                .expect("pending poisoned")
+                .as_mut()
+                .and_then(|pending| pending.remove(&id));
+            let Some(sender) = sender else {
+                return;
+            };
+            let result = match message.get("error") {
+                Some(error) => Err(error.clone()),
+                None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            let _ = sender.send(result);
+            return;
+        }
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let Some(subscription) = params.get("subscription").and_then(Value::as_u64) else {
+            return;
+        };
+        let mut feeds = self.feeds.lock().expect("feeds poisoned");
+        match message.get("method").and_then(Value::as_str) {
+            Some("session/event") => {
+                let Some(feed) = feeds.get_mut(&subscription) else {
+                    return;
+                };
+                let delivered = params
+                    .get("event")
+                    .and_then(|event| serde_json::from_value::<RuntimeEvent>(event.clone()).ok())
+                    .is_some_and(|event| feed.push(event));
+                if !delivered && feed.is_ended() {
+                    feeds.remove(&subscription);
+                    let message = json!({
+                        "jsonrpc": "2.0",
+                        "method": "session/unsubscribe",
+                        "params": { "subscription": subscription },
+                    });
+                    let _ = self.out.try_send(format!("{message}\n"));
+                }
+            }
+            Some("session/subscription_end") => {
+                if let Some(mut feed) = feeds.remove(&subscription)
+                    && params.get("reason").and_then(Value::as_str) == Some("lagged")
+                {
+                    feed.lagged();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One daemon-hosted session seen through a [`DaemonClient`].
+struct RemoteSession {
+    inner: Arc<ClientInner>,
+    session_id: SessionId,
+}
+
+impl RemoteSession {
+    async fn call(&self, method: &str, mut params: Value) -> Result<Value, CommandError> {
+        params["sessionId"] = json!(self.session_id);
+        self.inner.request(method, params).await
+    }
+
+    /// Open a subscription-shaped stream. The feed is registered before
+    /// asking: events may follow the response on the wire before this
+    /// future resumes.
+    async fn stream(
+        &self,
+        method: &str,
+        mut params: Value,
+    ) -> (Result<Value, CommandError>, EventSubscription) {
+        let subscription = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let (feed, events) = EventFeed::channel();
+        self.inner
+            .feeds
+            .lock()
+            .expect("feeds poisoned")
+            .insert(subscription, feed);
+        params["subscription"] = json!(subscription);
+        let result = self.call(method, params).await;
+        if result.is_err() {
+            self.inner
+                .feeds
+                .lock()
+                .expect("feeds poisoned")
+                .remove(&subscription);
+        }
+        (result, events)
+    }
+}
+
+impl SessionBackend for RemoteSession {
+    fn submit(&self, prompt: String) -> BackendFuture<'_, OperationId> {
+        Box::pin(async move {
+            let result = self
+                .call("session/submit", json!({ "prompt": prompt }))
+                .await?;
+            serde_json::from_value(result.get("operationId").cloned().unwrap_or_default())
+                .map_err(|_| CommandError::RuntimeDropped)
+        })
+    }
+
+    fn steer(&self, text: String) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/steer", json!({ "text": text })).await?;
+            Ok(())
+        })
+    }
+
+    fn follow_up(&self, text: String) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/follow_up", json!({ "text": text }))
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn compact(&self, instructions: Option<String>) -> BackendFuture<'_, bool> {
+        Box::pin(async move {
+            let result = self
+                .call("session/compact", json!({ "instructions": instructions }))
+                .await?;
+            Ok(result.get("requested").and_then(Value::as_bool) == Some(true))
+        })
+    }
+
+    fn switch_model(&self, model_ref: String) -> BackendFuture<'_, String> {
+        Box::pin(async move {
+            let result = self
+                .call("session/switch_model", json!({ "modelRef": model_ref }))
+                .await?;
+            Ok(result
+                .get("previous")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned())
+        })
+    }
+
+    fn cancel(&self, operation_id: OperationId) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/cancel", json!({ "operationId": operation_id }))
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn cancel_child(&self, child: SessionId) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/cancel_child", json!({ "child": child }))
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn undo(&self, operation: Option<OperationId>) -> BackendFuture<'_, UndoOutcome> {
+        Box::pin(async move {
+            let result = self
+                .call("session/undo", json!({ "operationId": operation }))
+                .await?;
+            serde_json::from_value(result.get("outcome").cloned().unwrap_or_default())
+                .map_err(|_| CommandError::RuntimeDropped)
+        })
+    }
+
+    fn snapshot(&self) -> BackendFuture<'_, SessionSnapshot> {
+        Box::pin(async move {
+            let result = self.call("session/snapshot", json!({})).await?;
+            snapshot_result(&result)
+        })
+    }
+
+    fn artifact(&self, id: String) -> BackendFuture<'_, Option<String>> {
+        Box::pin(async move {
+            let result = self.call("session/artifact", json!({ "id": id })).await?;
+            Ok(result
+                .get("text")
+                .and_then(Value::as_str)
+                .map(str::to_owned))
+        })
+    }
+
+    fn children(&self) -> BackendFuture<'_, Vec<ChildSummary>> {
+        Box::pin(async move {
+            let result = self.call("session/children", json!({})).await?;
+            serde_json::from_value(result.get("children").cloned().unwrap_or_default())
+                .map_err(|_| CommandError::RuntimeDropped)
+        })
+    }
+
+    fn child_transcript(&self, child: SessionId) -> BackendFuture<'_, Vec<SessionEntry>> {
+        Box::pin(async move {
+            let result = self
+                .call("session/child_transcript", json!({ "child": child }))
+                .await?;
+            serde_json::from_value(result.get("entries").cloned().unwrap_or_default())
+                .map_err(|_| CommandError::RuntimeDropped)
+        })
+    }
+
+    fn subscribe(&self) -> BackendFuture<'_, (SessionSnapshot, EventSubscription)> {
+        Box::pin(async move {
+            let (result, events) = self.stream("session/subscribe", json!({})).await;
+            Ok((snapshot_result(&result?)?, events))
+        })
+    }
+
+    fn resume(
+        &self,
+        instance: RuntimeInstanceId,
+        after: RuntimeCursor,
+    ) -> BackendFuture<'_, EventSubscription> {
+        Box::pin(async move {
+            let (result, events) = self
+                .stream(
+                    "session/resume",
+                    json!({ "instance": instance, "cursor": after }),
+                )
+                .await;
+            result.map(|_| events)
+        })
+    }
+
+    fn claim_control(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/claim_control", json!({})).await?;
+            Ok(())
+        })
+    }
+
+    fn release_control(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/release_control", json!({})).await?;
+            Ok(())
+        })
+    }
+
+    fn close(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.call("session/close", json!({})).await?;
+            Ok(())
+        })
+    }
+}
+
+fn snapshot_result(result: &Value) -> Result<SessionSnapshot, CommandError> {
+    serde_json::from_value(result.get("snapshot").cloned().unwrap_or_default())
+        .map_err(|_| CommandError::RuntimeDropped)
+}
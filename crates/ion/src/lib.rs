@@ -2,6 +2,8 @@
 //! shell over this library; integration tests drive the same surface.
 
 pub mod acp;
+mod children;
+pub mod daemon;
 pub mod openrouter;
 pub mod print;
 pub mod settings;
@@ -16,6 +18,7 @@ use ion_core::{EngineSignal, Provider, ProviderRequest, ScriptedProvider};
 use openrouter::OpenRouterProvider;
 
 pub use acp::{AcpConfig, serve as acp_serve};
+pub use daemon::{AttachRole, DaemonClient, DaemonConfig};
 pub use settings::Settings;
 
 /// The host's provider choice for one invocation. `Provider` is not
@@ -54,6 +57,33 @@ impl Provider for CliProvider {
     }
 }
 
+/// The agent profiles delegated children may run under (§20.2), and
+/// the provider factory for the models they name.
+pub struct ChildProfiles<P> {
+    pub profiles: Vec<ion_core::AgentProfile>,
+    /// `None` when the host cannot select models; a profile naming one
+    /// then fails its child.
+    pub make_model_provider: Option<Arc<dyn Fn(String) -> P + Send + Sync>>,
+}
+
+impl<P> Default for ChildProfiles<P> {
+    fn default() -> Self {
+        Self {
+            profiles: Vec::new(),
+            make_model_provider: None,
+        }
+    }
+}
+
+impl<P> Clone for ChildProfiles<P> {
+    fn clone(&self) -> Self {
+        Self {
+            profiles: self.profiles.clone(),
+            make_model_provider: self.make_model_provider.clone(),
+        }
+    }
+}
+
 /// Register the bounded-child delegation surface (§20) on a started
 /// runtime: the delegate tool needs the parent session id, which only
 /// exists once the runtime is composed. Call before the first submit.
@@ -61,22 +91,25 @@ pub fn enable_children<P>(
     tools: &ion_core::ToolCatalog,
     store: &ion_core::SessionStore,
     make_provider: Arc<dyn Fn() -> P + Send + Sync>,
+    profiles: &ChildProfiles<P>,
     parent_id: ion_core::SessionId,
 ) where
     P: ion_core::Provider,
 {
-    tools.register_scope(
-        "delegate",
-        vec![Arc::new(ion_core::DelegateTool::new(
-            ion_core::DelegateConfig {
-                store: store.clone(),
-                make_provider,
-                max_active_children: 4,
-                child_budget: ion_core::child_budget_default(),
-            },
-            parent_id,
-        ))],
+    let delegate = ion_core::DelegateTool::new(
+        ion_core::DelegateConfig {
+            cwd: tools.cwd().to_path_buf(),
+            store: store.clone(),
+            make_provider,
+            make_model_provider: profiles.make_model_provider.clone(),
+            max_active_children: 4,
+            child_budget: ion_core::child_budget_default(),
+            profiles: profiles.profiles.clone(),
+        },
+        parent_id,
     );
+    let merge = delegate.merge_tool();
+    tools.register_scope("delegate", vec![Arc::new(delegate), Arc::new(merge)]);
 }
 
 /// Build the scripted-provider factory used when no real model is
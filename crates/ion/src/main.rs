@@ -1,13 +1,16 @@
 //! Ion CLI host. This binary owns process lifetime and frontend selection.
 
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use ion::daemon::{self, AttachRole, DaemonClient};
 use ion::enable_children;
 use ion::openrouter::OpenRouterProvider;
-use ion::print::PrintFrontend;
+use ion::print::{PrintFrontend, PromptSource, load_prompt};
 use ion::settings::Settings;
 use ion::tui;
 use ion::{CliProvider, acp};
@@ -23,9 +26,23 @@ use ion_core::{
     disable_help_subcommand = true
 )]
 struct Cli {
-    /// Run one prompt through print mode and exit.
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Attach to a running `ion daemon` instead of embedding the
+    /// runtime. Without a value, uses the daemon's default socket.
+    #[arg(long = "connect", value_name = "SOCKET")]
+    connect: Option<Option<PathBuf>>,
+    /// With --connect: watch the daemon's most recent session read-only
+    /// while another client controls it.
+    #[arg(long = "observe", requires = "connect")]
+    observe: bool,
+    /// Run one prompt through print mode and exit. `-` reads the
+    /// prompt from stdin; `@path` tokens attach project files inline.
     #[arg(short = 'p', long = "print", value_name = "PROMPT")]
     print: Option<String>,
+    /// Run print mode with the prompt read from a file.
+    #[arg(long = "prompt-file", value_name = "PATH", conflicts_with = "print")]
+    prompt_file: Option<PathBuf>,
     /// Run against a real OpenRouter model (e.g. stealth/ox-alpha)
     /// instead of the scripted provider. Requires OPENROUTER_API_KEY.
     #[arg(long = "model", value_name = "MODEL")]
@@ -49,6 +66,43 @@ struct Cli {
     allow: Vec<String>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Host sessions for other ion clients over a Unix socket.
+    Daemon {
+        /// Socket path (default: $XDG_RUNTIME_DIR/ion/daemon.sock).
+        #[arg(long = "socket", value_name = "PATH")]
+        socket: Option<PathBuf>,
+        /// Hibernate sessions idle for this many seconds; 0 keeps
+        /// them loaded.
+        #[arg(long = "idle-timeout", value_name = "SECS", default_value_t = 300)]
+        idle_timeout: u64,
+    },
+    /// Restore the files an operation changed, unless any was edited
+    /// since. Without an operation, undoes the latest one with changes
+    /// left. With --connect, the daemon's session does it.
+    Undo {
+        /// Operation id (`op-…`), as shown in the transcript.
+        #[arg(value_name = "OPERATION")]
+        operation: Option<ion_core::OperationId>,
+        /// Session the operation belongs to (default: the most recent).
+        #[arg(long = "session", value_name = "ID", value_parser = parse_session_id)]
+        session: Option<ion_core::SessionId>,
+    },
+    /// List the children a session delegated to, with their objective,
+    /// outcome, and token usage.
+    Children {
+        /// Parent session (default: the most recent).
+        #[arg(long = "session", value_name = "ID", value_parser = parse_session_id)]
+        session: Option<ion_core::SessionId>,
+    },
+}
+
+fn parse_session_id(text: &str) -> Result<ion_core::SessionId, String> {
+    ion_core::SessionId::parse(text.strip_prefix("session-").unwrap_or(text))
+        .ok_or_else(|| format!("not a session id: {text}"))
+}
+
 #[tokio::main]
 async fn main() -> ExitCode {
     tracing_subscriber::fmt()
@@ -64,15 +118,62 @@ async fn main() -> ExitCode {
             return ExitCode::from(2);
         }
     };
-    if cli.acp {
-        return run_acp(&cli, &settings).await;
+    if let Some(Command::Daemon {
+        socket,
+        idle_timeout,
+    }) = &cli.command
+    {
+        let socket = socket.clone().unwrap_or_else(daemon::default_socket_path);
+        let idle_timeout = (*idle_timeout > 0).then(|| Duration::from_secs(*idle_timeout));
+        return run_daemon(socket, idle_timeout, &cli, &settings).await;
+    }
+    if let Some(Command::Children { session }) = &cli.command {
+        return run_children(*session).await;
+    }
+    let client = match &cli.connect {
+        None => None,
+        Some(socket) => {
+            let socket = socket.clone().unwrap_or_else(daemon::default_socket_path);
+            match DaemonClient::connect(&socket).await {
+                Ok(client) => Some(client),
+                Err(err) => {
+                    let _ = writeln!(io::stderr(), "connect {}: {err}", socket.display());
+                    return ExitCode::from(2);
+                }
+            }
+        }
+    };
+    if let Some(Command::Undo { operation, session }) = &cli.command {
+        return run_undo(*operation, *session, client).await;
     }
-    if cli.print.is_none() {
-        return run_tui(&cli, &settings).await;
+    if cli.acp {
+        return run_acp(&cli, &settings, client).await;
     }
-    let prompt = cli.print.clone().expect("checked above");
+    let source = match (cli.print.clone(), cli.prompt_file.clone()) {
+        (Some(arg), _) => PromptSource::from_print_arg(arg),
+        (None, Some(path)) => PromptSource::File(path),
+        (None, None) => {
+            return match client {
+                Some(client) => run_tui_connected(&cli, &settings, client).await,
+                None => run_tui(&cli, &settings).await,
+            };
+        }
+    };
+    // Attachments resolve under the same root the tools use.
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let prompt = match load_prompt(source, tokio::io::stdin(), &cwd).await {
+        Ok(prompt) => prompt,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "{err}");
+            return ExitCode::from(2);
+        }
+    };
 
-    match run_print(prompt, &cli, &settings).await {
+    let result = match client {
+        Some(client) => run_print_connected(prompt, client).await,
+        None => run_print(prompt, &cli, &settings).await,
+    };
+    match result {
         Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
             let _ = writeln!(io::stderr(), "{err}");
@@ -81,7 +182,116 @@ async fn main() -> ExitCode {
     }
 }
 
-async fn run_acp(cli: &Cli, settings: &Settings) -> ExitCode {
+/// `ion undo`: with `--connect`, through the daemon's session as its
+/// controller, so the runtime refuses while an operation runs. Without,
+/// straight against the store, refused while the store shows an
+/// operation open. Exits 1 when a conflict left everything as is.
+async fn run_undo(
+    operation: Option<ion_core::OperationId>,
+    session: Option<ion_core::SessionId>,
+    client: Option<DaemonClient>,
+) -> ExitCode {
+    let result = match client {
+        Some(client) => match client.open_session(session, AttachRole::Controller).await {
+            Ok((_, handle)) => handle.undo(operation).await,
+            Err(err) => {
+                let _ = writeln!(io::stderr(), "daemon: {err}");
+                return ExitCode::from(2);
+            }
+        },
+        None => {
+            let store = match SessionStore::open(default_db_path()) {
+                Ok(store) => store,
+                Err(err) => {
+                    let _ = writeln!(io::stderr(), "store: {err}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let session_id = match session {
+                Some(id) => id,
+                None => match store.latest_session().await {
+                    Ok(Some(id)) => id,
+                    Ok(None) => {
+                        let _ = writeln!(io::stderr(), "no persisted session to undo in");
+                        return ExitCode::from(2);
+                    }
+                    Err(err) => {
+                        let _ = writeln!(io::stderr(), "store: {err}");
+                        return ExitCode::FAILURE;
+                    }
+                },
+            };
+            ion_core::undo(&store, session_id, operation).await
+        }
+    };
+    match result {
+        Ok(outcome @ ion_core::UndoOutcome::Conflicted { .. }) => {
+            let _ = writeln!(io::stderr(), "{outcome}");
+            ExitCode::FAILURE
+        }
+        Ok(outcome) => {
+            let _ = writeln!(io::stdout(), "{outcome}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `ion children`: read straight from the store.
+async fn run_children(session: Option<ion_core::SessionId>) -> ExitCode {
+    let store = match SessionStore::open(default_db_path()) {
+        Ok(store) => store,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "store: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let session_id = match session {
+        Some(id) => id,
+        None => match store.latest_session().await {
+            Ok(Some(id)) => id,
+            Ok(None) => {
+                let _ = writeln!(io::stderr(), "no persisted session");
+                return ExitCode::from(2);
+            }
+            Err(err) => {
+                let _ = writeln!(io::stderr(), "store: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    match ion_core::children(&store, session_id).await {
+        Ok(children) if children.is_empty() => {
+            let _ = writeln!(io::stdout(), "{session_id} has no delegated children");
+            ExitCode::SUCCESS
+        }
+        Ok(children) => {
+            let mut stdout = io::stdout().lock();
+            for child in children {
+                let _ = writeln!(stdout, "{child}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run_acp(cli: &Cli, settings: &Settings, client: Option<DaemonClient>) -> ExitCode {
+    if let Some(client) = client {
+        return match acp::serve_connected(tokio::io::stdin(), tokio::io::stdout(), client).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                let _ = writeln!(io::stderr(), "acp: {err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
     let make_provider = match provider_factory(cli, settings) {
         Ok(factory) => factory,
         Err(err) => {
@@ -103,6 +313,7 @@ async fn run_acp(cli: &Cli, settings: &Settings) -> ExitCode {
     };
     let config = acp::AcpConfig {
         make_provider,
+        child_profiles: child_profiles(settings),
         store,
         policy,
     };
@@ -119,7 +330,13 @@ async fn run_acp(cli: &Cli, settings: &Settings) -> ExitCode {
 /// server's published tools. A failing server logs and is skipped -
 /// one broken server never blocks startup (DESIGN.md §19.1).
 async fn build_catalog(settings: &Settings, cli: &Cli) -> ion_core::ToolCatalog {
-    let tools = ion_core::ToolCatalog::default();
+    let mut tools = ion_core::ToolCatalog::default().with_bash_limits(settings.bash_limits());
+    if let Some(sandbox) = settings.bash_sandbox() {
+        tools = tools.with_sandbox(sandbox);
+    }
+    if settings.persistent_shell {
+        tools = tools.with_persistent_shell();
+    }
     if !settings.mcp_servers.is_empty() {
         let defs: Vec<ion_core::ServerDef> = settings
             .mcp_servers
@@ -229,7 +446,19 @@ async fn run_tui(cli: &Cli, settings: &Settings) -> ExitCode {
     } else {
         Arc::new(ion_core::AllowlistPolicy::new(cli.allow.clone()))
     };
+    let enable = |parent_id| {
+        enable_children(
+            &tools,
+            &store,
+            Arc::clone(&make_provider),
+            &child_profiles(settings),
+            parent_id,
+        );
+    };
     let runtime = if let Some(session_id) = resume_session {
+        // Before the session task starts: recovering an interrupted
+        // `delegate` call re-executes it to reattach its children.
+        enable(session_id);
         match Runtime::open_session(root_provider, tools.clone(), (*store).clone(), session_id)
             .await
         {
@@ -240,14 +469,11 @@ async fn run_tui(cli: &Cli, settings: &Settings) -> ExitCode {
             }
         }
     } else {
-        Runtime::start_with_policy(root_provider, tools.clone(), (*store).clone(), policy)
+        let runtime =
+            Runtime::start_with_policy(root_provider, tools.clone(), (*store).clone(), policy);
+        enable(runtime.session_id());
+        runtime
     };
-    enable_children(
-        &tools,
-        &store,
-        Arc::clone(&make_provider),
-        runtime.session_id(),
-    );
     let keymap = match tui::KeyMap::from_settings(&settings.keybindings) {
         Ok(keymap) => keymap,
         Err(err) => {
@@ -258,7 +484,6 @@ async fn run_tui(cli: &Cli, settings: &Settings) -> ExitCode {
     let session = runtime.session();
     let result = tui::run(
         session.clone(),
-        store,
         resume_session,
         settings.theme(),
         keymap,
@@ -287,6 +512,133 @@ async fn run_tui(cli: &Cli, settings: &Settings) -> ExitCode {
     }
 }
 
+/// The TUI over a daemon-hosted session. Model switching and the
+/// provider live in the daemon; `--resume` reopens the most recent
+/// session there, and `--observe` watches it without control.
+async fn run_tui_connected(cli: &Cli, settings: &Settings, client: DaemonClient) -> ExitCode {
+    let attached = cli.resume || cli.observe;
+    let opened = if cli.observe {
+        client.open_session(None, AttachRole::Observer).await
+    } else if cli.resume {
+        client.open_session(None, AttachRole::Controller).await
+    } else {
+        let cwd = std::env::current_dir().ok();
+        client.create_session(cwd.as_deref()).await
+    };
+    let (session_id, session) = match opened {
+        Ok(opened) => opened,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "daemon: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    let keymap = match tui::KeyMap::from_settings(&settings.keybindings) {
+        Ok(keymap) => keymap,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "settings: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    let guard = match tui::setup_terminal() {
+        Ok(guard) => guard,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "{err}");
+            return ExitCode::from(2);
+        }
+    };
+    let model_name = resolve_model(cli.model.clone(), settings).ok().flatten();
+    let result = tui::run(
+        session.clone(),
+        attached.then_some(session_id),
+        settings.theme(),
+        keymap,
+        tui::HostConfig {
+            model_name,
+            hide_thinking_block: settings.hide_thinking_block,
+        },
+        guard,
+    )
+    .await;
+    if result.is_err() {
+        let _ = session.close().await;
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Host sessions over `socket` until interrupted. The daemon owns the
+/// store and the tool composition; clients only forward commands.
+async fn run_daemon(
+    socket: PathBuf,
+    idle_timeout: Option<Duration>,
+    cli: &Cli,
+    settings: &Settings,
+) -> ExitCode {
+    let make_provider = match provider_factory(cli, settings) {
+        Ok(factory) => factory,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "{err}");
+            return ExitCode::from(2);
+        }
+    };
+    let store = match SessionStore::open(default_db_path()) {
+        Ok(store) => store,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "store: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // A live daemon answers; a stale socket file from a crashed one
+    // does not and is replaced.
+    if tokio::net::UnixStream::connect(&socket).await.is_ok() {
+        let _ = writeln!(
+            io::stderr(),
+            "a daemon is already listening on {}",
+            socket.display()
+        );
+        return ExitCode::from(2);
+    }
+    let _ = std::fs::remove_file(&socket);
+    let listener = match daemon::bind(&socket) {
+        Ok(listener) => listener,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "bind {}: {err}", socket.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let policy: Arc<dyn ion_core::PolicyEngine> = if cli.allow.is_empty() {
+        Arc::new(ion_core::DefaultPolicy)
+    } else {
+        Arc::new(ion_core::AllowlistPolicy::new(cli.allow.clone()))
+    };
+    let config = daemon::DaemonConfig {
+        make_provider,
+        child_profiles: child_profiles(settings),
+        store,
+        policy,
+        tools: build_catalog(settings, cli).await,
+        idle_timeout,
+    };
+    let _ = writeln!(io::stderr(), "ion daemon listening on {}", socket.display());
+    let shutdown = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    let result = daemon::serve(listener, config, shutdown).await;
+    let _ = std::fs::remove_file(&socket);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "daemon: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
 /// `--model` wins; otherwise the settings default (pi-style: the
 /// compiled-in defaults mirror the maintainer's pi settings).
 fn resolve_model(cli_model: Option<String>, settings: &Settings) -> Result<Option<String>, String> {
@@ -296,6 +648,20 @@ fn resolve_model(cli_model: Option<String>, settings: &Settings) -> Result<Optio
     settings.openrouter_model()
 }
 
+/// The agent profiles from settings, and a provider factory for the
+/// models they name when OpenRouter is usable.
+fn child_profiles(settings: &Settings) -> ion::ChildProfiles<CliProvider> {
+    let make_model_provider = std::env::var("OPENROUTER_API_KEY").ok().map(|key| {
+        Arc::new(move |model: String| {
+            CliProvider::OpenRouter(OpenRouterProvider::new(model, key.clone()))
+        }) as Arc<dyn Fn(String) -> CliProvider + Send + Sync>
+    });
+    ion::ChildProfiles {
+        profiles: settings.agent_profiles(),
+        make_model_provider,
+    }
+}
+
 /// The provider factory shared by the root session and any children it
 /// delegates to (§20): every child gets a fresh adapter instance.
 fn provider_factory(
@@ -319,6 +685,20 @@ fn provider_factory(
     })
 }
 
+/// Print mode against a daemon: a fresh session there, closed once the
+/// prompt settles.
+async fn run_print_connected(prompt: String, client: DaemonClient) -> Result<(), RuntimeError> {
+    let cwd = std::env::current_dir().ok();
+    let (_session_id, session) = client
+        .create_session(cwd.as_deref())
+        .await
+        .map_err(RuntimeError::OperationFailed)?;
+    let result = PrintFrontend::new(io::stdout()).run(&session, prompt).await;
+    let shutdown = session.close().await;
+    result?;
+    shutdown.map_err(RuntimeError::from)
+}
+
 async fn run_print(prompt: String, cli: &Cli, settings: &Settings) -> Result<(), RuntimeError> {
     let make_provider = provider_factory(cli, settings).map_err(RuntimeError::OperationFailed)?;
     let tools = build_catalog(settings, cli).await;
@@ -335,6 +715,7 @@ async fn run_print(prompt: String, cli: &Cli, settings: &Settings) -> Result<(),
         &tools,
         &store,
         Arc::clone(&make_provider),
+        &child_profiles(settings),
         runtime.session_id(),
     );
     let session = runtime.session();
@@ -19,8 +19,8 @@ use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
 use ion_core::{
-    ContextMessage, ContextPlan, EngineSignal, Provider, ProviderRequest, TokenUsage, ToolCall,
-    ToolSpec,
+    ContextMessage, ContextPlan, EngineSignal, Image, Provider, ProviderRequest, TokenUsage,
+    ToolCall, ToolSpec,
 };
 
 /// A step in the OpenAI-compatible SSE stream, decoded.
@@ -342,10 +342,17 @@ fn message_payloads(plan: &ContextPlan) -> Vec<serde_json::Value> {
         "role": "system",
         "content": plan.system,
     })];
-    for message in &plan.messages {
+    let mut tool_images = Vec::new();
+    for (index, message) in plan.messages.iter().enumerate() {
         match message {
-            ContextMessage::User { content } => {
-                out.push(serde_json::json!({ "role": "user", "content": content }));
+            ContextMessage::User { content, images } => {
+                if images.is_empty() {
+                    out.push(serde_json::json!({ "role": "user", "content": content }));
+                } else {
+                    let mut parts = vec![serde_json::json!({ "type": "text", "text": content })];
+                    parts.extend(images.iter().map(image_part));
+                    out.push(serde_json::json!({ "role": "user", "content": parts }));
+                }
             }
             ContextMessage::Assistant {
                 content,
@@ -376,18 +383,47 @@ fn message_payloads(plan: &ContextPlan) -> Vec<serde_json::Value> {
                 }
                 out.push(message);
             }
-            ContextMessage::Tool { call_id, content } => {
+            ContextMessage::Tool {
+                call_id,
+                content,
+                images,
+            } => {
                 out.push(serde_json::json!({
                     "role": "tool",
                     "tool_call_id": format!("call_{}", call_id),
                     "content": content,
                 }));
+                tool_images.extend(images.iter().map(image_part));
             }
         }
+        // Tool messages carry text only; their images follow the run
+        // of tool results as one user message.
+        let run_continues = matches!(
+            plan.messages.get(index + 1),
+            Some(ContextMessage::Tool { .. })
+        );
+        if !run_continues && !tool_images.is_empty() {
+            let mut parts = vec![serde_json::json!({
+                "type": "text",
+                "text": "Images returned by the tool calls above:",
+            })];
+            parts.append(&mut tool_images);
+            out.push(serde_json::json!({ "role": "user", "content": parts }));
+        }
     }
     out
 }
 
+/// An `image_url` content part carrying the image inline as a data URL.
+fn image_part(image: &Image) -> serde_json::Value {
+    use base64::Engine as _;
+    let data = base64::engine::general_purpose::STANDARD.encode(&image.data);
+    serde_json::json!({
+        "type": "image_url",
+        "image_url": { "url": format!("data:{};base64,{data}", image.media_type) },
+    })
+}
+
 fn find_line_end(buffer: &[u8]) -> Option<usize> {
     buffer.iter().position(|&b| b == b'\n')
 }
@@ -535,9 +571,7 @@ mod tests {
             },
             plan: ContextPlan {
                 system: "sys".to_owned(),
-                messages: vec![ContextMessage::User {
-                    content: "hello".to_owned(),
-                }],
+                messages: vec![ContextMessage::user("hello")],
             },
             tools: Vec::new(),
         };
@@ -761,4 +795,50 @@ mod tests {
         let provider = OpenRouterProvider::new("test/model", "key").with_base_url(base_url);
         assert_eq!(provider.context_window().await, None);
     }
+
+    #[test]
+    fn images_become_data_url_parts_after_the_tool_run() {
+        let png = ion_core::Image::new("image/png", b"\x89PNG\r\n\x1a\nfake".to_vec());
+        let operation_id = ion_core::OperationId::generate();
+        let call = |call_id| ion_core::ToolCall {
+            operation_id,
+            call_id,
+            name: "read".to_owned(),
+            arguments: serde_json::json!({}),
+        };
+        let plan = ContextPlan {
+            system: "sys".to_owned(),
+            messages: vec![
+                ContextMessage::User {
+                    content: "look".to_owned(),
+                    images: vec![png.clone()],
+                },
+                ContextMessage::Assistant {
+                    content: String::new(),
+                    tool_calls: vec![call(1), call(2)],
+                },
+                ContextMessage::Tool {
+                    call_id: 1,
+                    content: "image: a.png".to_owned(),
+                    images: vec![png.clone()],
+                },
+                ContextMessage::Tool {
+                    call_id: 2,
+                    content: "text".to_owned(),
+                    images: Vec::new(),
+                },
+            ],
+        };
+        let payloads = message_payloads(&plan);
+        let url = "data:image/png;base64,iVBORw0KGgpmYWtl";
+        assert_eq!(payloads[1]["content"][0]["text"], "look");
+        assert_eq!(payloads[1]["content"][1]["image_url"]["url"], url);
+        // Tool messages stay text; the image follows both results.
+        assert_eq!(payloads[3]["role"], "tool");
+        assert_eq!(payloads[3]["content"], "image: a.png");
+        assert_eq!(payloads[4]["role"], "tool");
+        assert_eq!(payloads[5]["role"], "user");
+        assert_eq!(payloads[5]["content"][1]["image_url"]["url"], url);
+        assert_eq!(payloads.len(), 6);
+    }
 }
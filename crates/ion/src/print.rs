@@ -2,8 +2,59 @@
 //! One frontend over the runtime contract; it owns no agent truth.
 
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use ion_core::{CommandError, RuntimeError, RuntimeEvent, SessionHandle};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Where a print-mode prompt comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptSource {
+    Text(String),
+    Stdin,
+    File(PathBuf),
+}
+
+impl PromptSource {
+    /// `-p -` reads the prompt from stdin, like most Unix filters; any
+    /// other value is the prompt itself.
+    #[must_use]
+    pub fn from_print_arg(arg: String) -> Self {
+        if arg == "-" {
+            Self::Stdin
+        } else {
+            Self::Text(arg)
+        }
+    }
+}
+
+/// Read the prompt body and inline its `@path` attachments under
+/// `cwd`. The result is the exact text submitted, so the durable
+/// `UserMessage` carries the attached contents.
+pub async fn load_prompt(
+    source: PromptSource,
+    mut stdin: impl AsyncRead + Unpin,
+    cwd: &Path,
+) -> Result<String, String> {
+    let text = match source {
+        PromptSource::Text(text) => text,
+        PromptSource::Stdin => {
+            let mut text = String::new();
+            stdin
+                .read_to_string(&mut text)
+                .await
+                .map_err(|err| format!("prompt: reading stdin: {err}"))?;
+            text
+        }
+        PromptSource::File(path) => tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|err| format!("prompt: reading {}: {err}", path.display()))?,
+    };
+    if text.trim().is_empty() {
+        return Err("prompt: empty prompt".to_owned());
+    }
+    ion_core::inline_attachments(cwd, &text).await
+}
 
 pub struct PrintFrontend<W> {
     writer: W,
@@ -32,7 +83,10 @@ impl<W: Write> PrintFrontend<W> {
                         .map_err(|err| RuntimeError::OperationFailed(err.to_string()))?;
                 }
                 // Tool settlement is durable-state news, not output.
-                RuntimeEvent::ToolStarted { .. } | RuntimeEvent::ToolSettled { .. } => {}
+                RuntimeEvent::ToolStarted { .. }
+                | RuntimeEvent::ToolOutput { .. }
+                | RuntimeEvent::ChildProgress { .. }
+                | RuntimeEvent::ToolSettled { .. } => {}
                 // Print mode is quiet output only.
                 RuntimeEvent::ThinkingDelta { .. } => {}
                 RuntimeEvent::OperationFinished { .. } => return Ok(()),
@@ -80,4 +134,39 @@ mod tests {
         session.close().await.expect("close");
         runtime.join().await.expect("join");
     }
+
+    #[tokio::test]
+    async fn prompt_reads_stdin_and_inlines_attachments() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("notes.md"), "remember this\n").expect("write");
+        let stdin: &[u8] = b"summarize @notes.md\n";
+        let prompt = load_prompt(
+            PromptSource::from_print_arg("-".to_owned()),
+            stdin,
+            dir.path(),
+        )
+        .await
+        .expect("prompt");
+        assert_eq!(
+            prompt,
+            "summarize @notes.md\n\n<attachment path=\"notes.md\">\nremember this\n</attachment>"
+        );
+
+        let empty: &[u8] = b" \n";
+        let err = load_prompt(PromptSource::Stdin, empty, dir.path())
+            .await
+            .expect_err("empty");
+        assert!(err.contains("empty prompt"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn prompt_file_is_read_verbatim() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("prompt.txt");
+        std::fs::write(&path, "@@ -1 +1 @@\n-old\n+new\n").expect("write");
+        let prompt = load_prompt(PromptSource::File(path), tokio::io::empty(), dir.path())
+            .await
+            .expect("prompt");
+        assert_eq!(prompt, "@@ -1 +1 @@\n-old\n+new\n");
+    }
 }
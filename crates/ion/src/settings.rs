@@ -2,6 +2,7 @@
 //! keys. The compiled-in defaults mirror the maintainer's pi settings
 //! (`stealth/ox-alpha` via openrouter); a settings file overrides them.
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use serde::Deserialize;
@@ -55,6 +56,44 @@ pub struct Settings {
     /// Hide reasoning output in the TUI (pi-parity hideThinkingBlock).
     #[serde(default)]
     pub hide_thinking_block: bool,
+    /// Seconds a `bash` call runs when it names no timeout.
+    bash_timeout_secs: Option<u64>,
+    /// Longest timeout a `bash` call may ask for, in seconds.
+    bash_max_timeout_secs: Option<u64>,
+    /// Run every `bash` call of a session in one long-lived shell, so
+    /// `cd` and exported variables persist between calls.
+    #[serde(default)]
+    pub persistent_shell: bool,
+    /// Confine `bash` with the Linux sandbox; absent means unconfined.
+    bash_sandbox: Option<SandboxConfig>,
+    /// Named configurations delegated children may run under.
+    #[serde(default)]
+    agent_profiles: BTreeMap<String, AgentProfileConfig>,
+}
+
+/// The `[bashSandbox]` table: the workspace stays the only writable
+/// tree; `network` keeps the host network; `maxProcesses` bounds the
+/// processes a command may run.
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfig {
+    #[serde(default)]
+    network: bool,
+    max_processes: Option<u64>,
+}
+
+/// One `[agentProfiles.<name>]` table (DESIGN.md §20.2). `tools` only
+/// narrows the child's tool set; unset keys keep the child defaults.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentProfileConfig {
+    description: Option<String>,
+    system_prompt: Option<String>,
+    model: Option<String>,
+    tools: Option<Vec<String>>,
+    max_model_steps: Option<u32>,
+    max_tool_calls: Option<u32>,
+    timeout_secs: Option<u64>,
 }
 
 /// One `[[extensions]]` entry: a subprocess extension publishing tools
@@ -101,6 +140,11 @@ impl Settings {
             mcp_servers: Vec::new(),
             extensions: Vec::new(),
             hide_thinking_block: true,
+            bash_timeout_secs: None,
+            bash_max_timeout_secs: None,
+            persistent_shell: false,
+            bash_sandbox: None,
+            agent_profiles: BTreeMap::new(),
         }
     }
     pub fn path() -> Option<PathBuf> {
@@ -125,6 +169,11 @@ impl Settings {
             mcp_servers: Vec::new(),
             extensions: Vec::new(),
             hide_thinking_block: false,
+            bash_timeout_secs: None,
+            bash_max_timeout_secs: None,
+            persistent_shell: false,
+            bash_sandbox: None,
+            agent_profiles: BTreeMap::new(),
         }
     }
 
@@ -168,6 +217,57 @@ impl Settings {
     pub fn theme(&self) -> Theme {
         self.theme.unwrap_or(Theme::Auto)
     }
+
+    /// The `bash` sandbox, when configured; unset keys keep the
+    /// built-in values.
+    pub fn bash_sandbox(&self) -> Option<ion_core::Sandbox> {
+        let config = self.bash_sandbox?;
+        let builtin = ion_core::Sandbox::default();
+        Some(ion_core::Sandbox {
+            network: config.network,
+            max_processes: config.max_processes.unwrap_or(builtin.max_processes),
+        })
+    }
+
+    /// The configured agent profiles, by name. Model ids take the same
+    /// optional `openrouter/` prefix as `defaultModel`.
+    pub fn agent_profiles(&self) -> Vec<ion_core::AgentProfile> {
+        self.agent_profiles
+            .iter()
+            .map(|(name, config)| ion_core::AgentProfile {
+                name: name.clone(),
+                description: config.description.clone(),
+                system_prompt: config.system_prompt.clone(),
+                model: config.model.as_deref().map(|model| {
+                    model
+                        .strip_prefix("openrouter/")
+                        .unwrap_or(model)
+                        .to_owned()
+                }),
+                tools: config.tools.clone(),
+                max_model_steps: config.max_model_steps,
+                max_tool_calls: config.max_tool_calls,
+                timeout_secs: config.timeout_secs,
+            })
+            .collect()
+    }
+
+    /// `bash` timeout bounds; unset keys keep the built-in values. The
+    /// default never exceeds the maximum.
+    pub fn bash_limits(&self) -> ion_core::BashLimits {
+        let builtin = ion_core::BashLimits::default();
+        let max_timeout = self
+            .bash_max_timeout_secs
+            .map_or(builtin.max_timeout, std::time::Duration::from_secs);
+        let default_timeout = self
+            .bash_timeout_secs
+            .map_or(builtin.default_timeout, std::time::Duration::from_secs)
+            .min(max_timeout);
+        ion_core::BashLimits {
+            default_timeout,
+            max_timeout,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -214,6 +314,66 @@ mod tests {
         assert!(settings.openrouter_model().is_err());
     }
 
+    #[test]
+    fn bash_timeouts_default_and_clamp() {
+        let limits = Settings::empty().bash_limits();
+        assert_eq!(limits, ion_core::BashLimits::default());
+        let settings: Settings =
+            toml::from_str("bashTimeoutSecs = 900\nbashMaxTimeoutSecs = 300").unwrap();
+        let limits = settings.bash_limits();
+        assert_eq!(limits.max_timeout.as_secs(), 300);
+        assert_eq!(limits.default_timeout.as_secs(), 300);
+        assert!(!settings.persistent_shell);
+        let settings: Settings = toml::from_str("persistentShell = true").unwrap();
+        assert!(settings.persistent_shell);
+    }
+
+    #[test]
+    fn bash_sandbox_is_opt_in() {
+        assert_eq!(Settings::empty().bash_sandbox(), None);
+        let settings: Settings = toml::from_str("[bashSandbox]").unwrap();
+        assert_eq!(settings.bash_sandbox(), Some(ion_core::Sandbox::default()));
+        let settings: Settings =
+            toml::from_str("[bashSandbox]\nnetwork = true\nmaxProcesses = 32").unwrap();
+        assert_eq!(
+            settings.bash_sandbox(),
+            Some(ion_core::Sandbox {
+                network: true,
+                max_processes: 32,
+            })
+        );
+    }
+
+    #[test]
+    fn agent_profiles_parse_by_name() {
+        assert!(Settings::empty().agent_profiles().is_empty());
+        let settings: Settings = toml::from_str(
+            r#"
+            [agentProfiles.reviewer]
+            description = "reviews a diff"
+            systemPrompt = "Review only; never speculate."
+            model = "openrouter/stealth/ox-beta"
+            tools = ["read", "git_diff"]
+            maxToolCalls = 8
+            timeoutSecs = 300
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.agent_profiles(),
+            vec![ion_core::AgentProfile {
+                name: "reviewer".to_owned(),
+                description: Some("reviews a diff".to_owned()),
+                system_prompt: Some("Review only; never speculate.".to_owned()),
+                model: Some("stealth/ox-beta".to_owned()),
+                tools: Some(vec!["read".to_owned(), "git_diff".to_owned()]),
+                max_model_steps: None,
+                max_tool_calls: Some(8),
+                timeout_secs: Some(300),
+            }]
+        );
+    }
+
     #[test]
     fn malformed_file_is_an_error() {
         let result: Result<Settings, _> = toml::from_str("defaultModel = 42");
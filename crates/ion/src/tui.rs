@@ -8,7 +8,6 @@
 //! RAII owner, never scattered across widgets.
 
 use std::io::{self, Write};
-use std::sync::Arc;
 
 use futures_util::StreamExt;
 use ratatui::backend::CrosstermBackend;
@@ -23,10 +22,11 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Clear, Paragraph, Widget, Wrap};
 use ratatui::{Frame, Terminal, TerminalOptions, Viewport};
 
+use crate::children::ChildTree;
 use crate::settings::Theme;
 use ion_core::{
-    CommandError, OperationStatus, RuntimeError, RuntimeEvent, SessionHandle, SessionSnapshot,
-    SessionStore,
+    CommandError, FileDiff, OperationStatus, RuntimeError, RuntimeEvent, SessionHandle,
+    SessionSnapshot,
 };
 
 /// Host-provided configuration for one launch. Cloneable handles;
@@ -55,10 +55,36 @@ impl HostConfig {
 /// path back into the runtime (§22.2).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UiEffect {
-    Submit { text: String },
-    Steer { text: String },
-    Compact { instructions: Option<String> },
-    SwitchModel { model: String },
+    Submit {
+        text: String,
+    },
+    Steer {
+        text: String,
+    },
+    Compact {
+        instructions: Option<String>,
+    },
+    SwitchModel {
+        model: String,
+    },
+    /// Page a saved tool output outside the inline viewport.
+    OpenArtifact {
+        id: String,
+    },
+    /// Restore the files an operation changed; `None` = the latest.
+    Undo {
+        operation: Option<ion_core::OperationId>,
+    },
+    /// List the session's delegated children.
+    ListChildren,
+    /// Show a child's transcript, read-only, by session id prefix.
+    OpenChild {
+        id: String,
+    },
+    /// Cancel one running child, by session id prefix.
+    CancelChild {
+        id: String,
+    },
     Cancel,
     Quit,
 }
@@ -314,11 +340,69 @@ fn parse_key(spec: &str) -> Result<(KeyCode, KeyModifiers), String> {
 }
 
 /// One started tool effect: its display label plus the bounded output
-/// preview from settlement (rendered only while expanded).
+/// preview from settlement (rendered only while expanded), the diffs
+/// of any files it changed (always rendered), the artifact holding
+/// its full output when the model saw an excerpt, and for `delegate`
+/// the children it started (a summary line, or one line per child
+/// while expanded).
 #[derive(Debug, Clone)]
 struct ToolRow {
+    call_id: u64,
     label: String,
     preview: Option<String>,
+    diffs: Vec<FileDiff>,
+    artifact: Option<String>,
+    children: ChildTree,
+}
+
+/// A delegate row's children: collapsed to one summary line, or a
+/// tree with one line per child.
+fn child_lines(children: &ChildTree, expanded: bool) -> Vec<Line<'static>> {
+    if children.is_empty() {
+        return Vec::new();
+    }
+    if !expanded {
+        return vec![Line::from(format!("  └ {}", children.summary())).dim()];
+    }
+    let lines = children.lines();
+    let last = lines.len() - 1;
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let branch = if i == last { "└" } else { "├" };
+            Line::from(format!("  {branch} {line}")).dim()
+        })
+        .collect()
+}
+
+/// Lines of a running tool's output kept for display.
+const LIVE_OUTPUT_LINES: usize = 20;
+
+/// Drop all but the last `max` lines of `text`.
+fn keep_last_lines(text: &mut String, max: usize) {
+    if let Some((cut, _)) = text.rmatch_indices('\n').nth(max) {
+        text.drain(..=cut);
+    }
+}
+
+/// Colored rows for a tool's file diffs: hunk headers dim, added lines
+/// green, removed lines red, context plain.
+fn diff_lines(diffs: &[FileDiff]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for diff in diffs {
+        lines.push(Line::from(format!("  {}", diff.path.display())).dim());
+        for hunk_line in diff.hunks.lines() {
+            let line = Line::from(format!("  {hunk_line}"));
+            lines.push(match hunk_line.chars().next() {
+                Some('+') => line.green(),
+                Some('-') => line.red(),
+                Some('@') | Some('…') => line.dim(),
+                _ => line,
+            });
+        }
+    }
+    lines
 }
 
 /// One UI state owner (§22.1). Plain data; no handles, no hidden state.
@@ -356,6 +440,9 @@ pub struct UiState {
     draft_degraded: bool,
     /// Completed tool rows for the live operation, newest last.
     tool_rows: Vec<ToolRow>,
+    /// Artifact ids this session's tool rows named, oldest first;
+    /// /artifact pages the newest or one picked by id prefix.
+    artifacts: Vec<String>,
     status: UiStatus,
     /// Model id for /model display (host-provided, not runtime state).
     model_name: Option<String>,
@@ -457,8 +544,9 @@ fn notice(state: &mut UiState, text: &str) {
         .push(Line::from(text.to_owned()).dim());
 }
 
-/// Slash-command surface: /help, /compact, /model. Anything else is a
-/// visible unknown-command error, never a silent no-op.
+/// Slash-command surface: /help, /compact, /model, /artifact, /undo,
+/// /children, /child. Anything
+/// else is a visible unknown-command error, never a silent no-op.
 fn handle_command(state: &mut UiState, command: &str) -> (UiState, Option<UiEffect>) {
     let (name, rest) = match command.split_once(' ') {
         Some((name, rest)) => (name, rest.trim()),
@@ -469,6 +557,11 @@ fn handle_command(state: &mut UiState, command: &str) -> (UiState, Option<UiEffe
             for line in [
                 "/compact [instructions] - summarize the active operation's context",
                 "/model [id]             - show or switch the model",
+                "/artifact [id]          - page a tool call's full output",
+                "/undo [operation]       - restore the files an operation changed",
+                "/children               - list delegated children",
+                "/child <id>             - show a child's transcript (read-only)",
+                "/child cancel <id>      - cancel one running child",
                 "ctrl+o                  - toggle tool output previews",
                 "ctrl+t                  - toggle thinking blocks",
                 "/help                   - this list",
@@ -501,6 +594,62 @@ fn handle_command(state: &mut UiState, command: &str) -> (UiState, Option<UiEffe
                 }),
             )
         }
+        "artifact" => {
+            let found = state
+                .artifacts
+                .iter()
+                .rev()
+                .find(|id| id.starts_with(rest))
+                .cloned();
+            match found {
+                Some(id) => (std::mem::take(state), Some(UiEffect::OpenArtifact { id })),
+                None if rest.is_empty() => {
+                    notice(
+                        state,
+                        "no artifacts: every tool output so far was shown whole",
+                    );
+                    (std::mem::take(state), None)
+                }
+                None => {
+                    notice(state, &format!("no artifact {rest}"));
+                    (std::mem::take(state), None)
+                }
+            }
+        }
+        "undo" => {
+            if rest.is_empty() {
+                return (
+                    std::mem::take(state),
+                    Some(UiEffect::Undo { operation: None }),
+                );
+            }
+            match rest.parse() {
+                Ok(operation) => (
+                    std::mem::take(state),
+                    Some(UiEffect::Undo {
+                        operation: Some(operation),
+                    }),
+                ),
+                Err(err) => {
+                    notice(state, &err);
+                    (std::mem::take(state), None)
+                }
+            }
+        }
+        "children" => (std::mem::take(state), Some(UiEffect::ListChildren)),
+        "child" if rest.is_empty() => (std::mem::take(state), Some(UiEffect::ListChildren)),
+        "child" if rest.starts_with("cancel ") => (
+            std::mem::take(state),
+            Some(UiEffect::CancelChild {
+                id: rest["cancel ".len()..].trim().to_owned(),
+            }),
+        ),
+        "child" => (
+            std::mem::take(state),
+            Some(UiEffect::OpenChild {
+                id: rest.to_owned(),
+            }),
+        ),
         other => {
             notice(state, &format!("unknown command: /{other} (try /help)"));
             (std::mem::take(state), None)
@@ -704,28 +853,65 @@ fn apply_runtime_event(mut state: UiState, event: RuntimeEvent) -> UiState {
         RuntimeEvent::ThinkingDelta { text, .. } => {
             state.draft_thinking.push_str(&text);
         }
-        RuntimeEvent::ToolStarted { tool, target, .. } => {
+        RuntimeEvent::ToolStarted {
+            call_id,
+            tool,
+            target,
+            ..
+        } => {
             flush_thinking(&mut state);
             state.tool_rows.push(ToolRow {
+                call_id,
                 label: match target {
                     Some(target) => format!("· {tool} {target}…"),
                     None => format!("· {tool}…"),
                 },
                 preview: None,
+                diffs: Vec::new(),
+                artifact: None,
+                children: ChildTree::default(),
             });
             state.status = UiStatus::Working {
                 operation: format!("running {tool}"),
             };
         }
+        RuntimeEvent::ChildProgress {
+            call_id,
+            child_session_id,
+            update,
+            ..
+        } => {
+            if let Some(row) = state.tool_row_mut(call_id) {
+                row.children.apply(child_session_id, update);
+            }
+        }
+        RuntimeEvent::ToolOutput { call_id, chunk, .. } => {
+            if let Some(row) = state.tool_row_mut(call_id) {
+                // Live output stands in for the preview until
+                // settlement replaces it with the bounded one.
+                let live = row.preview.get_or_insert_with(String::new);
+                live.push_str(&chunk);
+                keep_last_lines(live, LIVE_OUTPUT_LINES);
+            }
+        }
         RuntimeEvent::ToolSettled {
-            is_error, preview, ..
+            call_id,
+            is_error,
+            preview,
+            diffs,
+            artifact,
+            ..
         } => {
-            if let Some(row) = state.tool_rows.last_mut() {
-                // The running row is the one this settlement answers.
+            if let Some(id) = &artifact {
+                state.artifacts.push(id.clone());
+            }
+            if let Some(row) = state.tool_row_mut(call_id) {
                 if is_error && !row.label.ends_with("✗") {
                     row.label.push_str(" ✗");
                 }
                 row.preview = preview;
+                row.diffs = diffs;
+                row.artifact = artifact;
             }
         }
         RuntimeEvent::OperationFinished { .. } => {
@@ -819,16 +1005,41 @@ fn flush_thinking(state: &mut UiState) {
 }
 
 impl UiState {
+    /// The row a call's output and settlement belong to. Concurrent
+    /// calls start together, so it is not necessarily the newest row;
+    /// that one stands in when no row carries the call id.
+    fn tool_row_mut(&mut self, call_id: u64) -> Option<&mut ToolRow> {
+        let index = self
+            .tool_rows
+            .iter()
+            .rposition(|row| row.call_id == call_id)
+            .or(self.tool_rows.len().checked_sub(1))?;
+        self.tool_rows.get_mut(index)
+    }
+
     /// Move the live draft into scrollback as a completed assistant
     /// turn (inline scrollback pattern: completed content leaves the
     /// live viewport). Assistant lines get markdown-lite styling.
     fn flush_draft(&mut self) {
         flush_thinking(self);
-        // Tool rows precede the text they enabled. Expanded rendering
-        // includes each settled output preview (pi-parity ctrl+o).
+        // Tool rows precede the text they enabled. Diffs always show;
+        // expanded rendering also includes each settled output preview
+        // (pi-parity ctrl+o), which for file changes repeats the diff.
         for row in self.tool_rows.drain(..) {
             self.pending_scrollback.push(Line::from(row.label).dim());
-            if self.tool_output_expanded {
+            if let Some(id) = &row.artifact {
+                self.pending_scrollback.push(
+                    Line::from(format!(
+                        "  full output saved: /artifact {}",
+                        ion_core::short_artifact_id(id)
+                    ))
+                    .dim(),
+                );
+            }
+            self.pending_scrollback
+                .extend(child_lines(&row.children, self.tool_output_expanded));
+            self.pending_scrollback.extend(diff_lines(&row.diffs));
+            if self.tool_output_expanded && row.diffs.is_empty() {
                 for line in row.preview.iter().flat_map(|p| p.lines()) {
                     self.pending_scrollback
                         .push(Line::from(format!("  {line}")).dark_gray());
@@ -875,8 +1086,12 @@ impl UiState {
                         None => format!("· {}…", tool.tool),
                     };
                     self.tool_rows.push(ToolRow {
+                        call_id: tool.call_id,
                         label,
                         preview: None,
+                        diffs: Vec::new(),
+                        artifact: None,
+                        children: ChildTree::default(),
                     });
                 }
                 self.draft_degraded = false;
@@ -904,6 +1119,19 @@ impl TerminalGuard {
         Ok(Self { restored: false })
     }
 
+    /// Hand the terminal to a child program (a pager) until
+    /// [`Self::resume`].
+    fn suspend(&mut self) {
+        self.restore();
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnableBracketedPaste)?;
+        self.restored = false;
+        Ok(())
+    }
+
     fn restore(&mut self) {
         if self.restored {
             return;
@@ -980,7 +1208,9 @@ pub fn render(state: &UiState, frame: &mut Frame, palette: &Palette) {
     let mut tail: Vec<Line> = Vec::new();
     if let Some(latest) = state.tool_rows.last() {
         tail.push(Line::from(latest.label.clone()).style(palette.tool_row));
-        if state.tool_output_expanded {
+        tail.extend(child_lines(&latest.children, state.tool_output_expanded));
+        tail.extend(diff_lines(&latest.diffs));
+        if state.tool_output_expanded && latest.diffs.is_empty() {
             for line in latest.preview.iter().flat_map(|p| p.lines()) {
                 tail.push(
                     Line::from(format!("  {line}"))
@@ -1043,7 +1273,6 @@ pub fn setup_terminal() -> Result<TerminalGuard, RuntimeError> {
 /// blocks rendering on provider/tool I/O (§22.2).
 pub async fn run(
     session: SessionHandle,
-    store: Arc<SessionStore>,
     resume_session: Option<ion_core::SessionId>,
     theme: Theme,
     keymap: KeyMap,
@@ -1071,15 +1300,15 @@ pub async fn run(
 
     print_banner(&mut terminal, resume_session.is_some())?;
 
-    // Resume: project the persisted transcript into scrollback.
+    let (snapshot, mut events) = session.subscribe().await?;
+
+    // Resume: project the durable transcript into scrollback. The
+    // snapshot carries it, so a daemon-hosted session replays the same
+    // way an embedded one does.
     if let Some(session_id) = resume_session {
-        let loaded = store
-            .load(session_id)
-            .await
-            .map_err(|err| RuntimeError::OperationFailed(err.to_string()))?;
         let mut restored: Vec<Line> = Vec::new();
-        for (_, entry) in loaded.entries {
-            push_entry_lines(&entry, &mut restored);
+        for entry in &snapshot.entries {
+            push_entry_lines(entry, &mut restored);
         }
         if !restored.is_empty() {
             let count = restored.len() as u16;
@@ -1105,17 +1334,30 @@ pub async fn run(
     state.set_model_name(host.model_name.clone());
     state.thinking_visible = !host.hide_thinking_block;
     state.model_switching_available = switching_available;
-    let (snapshot, mut events) = session.subscribe().await?;
     // The session's durable selection is authoritative once subscribed;
     // a resumed session may have switched models in an earlier run.
     // Scripted launches keep the host's display fallback.
     if host.model_name.is_some() {
         state.set_model_name(Some(snapshot.model_ref.clone()));
     }
+    // Outputs saved before a resume stay pageable.
+    state.artifacts = snapshot
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            ion_core::SessionEntry::ToolResult { result } => result.artifact(),
+            _ => None,
+        })
+        .map(|artifact| artifact.id.clone())
+        .collect();
     let mut active_operation: Option<ion_core::OperationId> = match snapshot.operation {
         OperationStatus::Active { operation_id, .. } => Some(operation_id),
         OperationStatus::Idle => None,
     };
+    // Last event applied; a lag resumes from here before falling back
+    // to a fresh snapshot.
+    let mut instance = snapshot.instance;
+    let mut last_cursor = snapshot.cursor;
     let mut result: Result<(), RuntimeError> = Ok(());
 
     loop {
@@ -1160,7 +1402,13 @@ pub async fn run(
                     Some(Ok(TermEvent::Key(key))) => {
                         let (next, effect) = update(state, UiMessage::Key(key));
                         state = next;
-                        if let Some(effect) = effect {
+                        if let Some(UiEffect::OpenArtifact { id }) = effect {
+                            // Stop the key reader first: it would race
+                            // the pager for the terminal's input.
+                            drop(std::mem::replace(&mut key_stream, EventStream::new()));
+                            page_artifact(&session, &mut state, &mut guard, &id).await;
+                            terminal.clear().ok();
+                        } else if let Some(effect) = effect {
                             dispatch(&session, &mut state, active_operation, effect).await;
                         }
                     }
@@ -1179,6 +1427,7 @@ pub async fn run(
             event = events.recv() => {
                 match event {
                     Ok(event) => {
+                        last_cursor = event.cursor();
                         if let RuntimeEvent::OperationStarted { operation_id, .. } = &event {
                             active_operation = Some(*operation_id);
                         }
@@ -1198,11 +1447,19 @@ pub async fn run(
                         }
                     }
                     Err(RuntimeError::SubscriptionLagged) => {
+                        // Missed events still in the runtime's replay
+                        // buffer arrive in order; nothing to rebuild.
+                        if let Ok(resumed) = session.resume(instance, last_cursor).await {
+                            events = resumed;
+                            continue;
+                        }
                         // Bounded loss (§21.4): re-subscribe; the fresh
                         // snapshot is authoritative for live state.
                         match session.subscribe().await {
                             Ok((snapshot, fresh)) => {
                                 events = fresh;
+                                instance = snapshot.instance;
+                                last_cursor = snapshot.cursor;
                                 active_operation = match &snapshot.operation {
                                     OperationStatus::Active { operation_id, .. } => {
                                         Some(*operation_id)
@@ -1231,7 +1488,8 @@ pub async fn run(
     terminal.clear().ok();
     result?;
     match session.close().await {
-        Ok(()) | Err(CommandError::Closed) => Ok(()),
+        // An observer detaches; the controller owns the session.
+        Ok(()) | Err(CommandError::Closed | CommandError::ReadOnly) => Ok(()),
         Err(err) => Err(err.into()),
     }
 }
@@ -1281,6 +1539,28 @@ async fn dispatch(
             }
             Err(err) => notice(state, &format!("model switch failed: {err}")),
         },
+        UiEffect::Undo { operation } => match session.undo(operation).await {
+            Ok(outcome) => notice(state, &outcome.to_string()),
+            Err(err) => notice(state, &format!("undo failed: {err}")),
+        },
+        UiEffect::ListChildren => match session.children().await {
+            Ok(children) if children.is_empty() => notice(state, "no delegated children"),
+            Ok(children) => {
+                for child in children {
+                    notice(state, &child.to_string());
+                }
+            }
+            Err(err) => notice(state, &format!("children unavailable: {err}")),
+        },
+        UiEffect::OpenChild { id } => open_child(session, state, &id).await,
+        UiEffect::CancelChild { id } => {
+            if let Some(child_id) = find_child(session, state, &id).await {
+                match session.cancel_child(child_id).await {
+                    Ok(()) => notice(state, &format!("cancelling child {child_id}")),
+                    Err(err) => notice(state, &format!("cancel failed: {err}")),
+                }
+            }
+        }
         UiEffect::Steer { text } => match session.steer(text).await {
             Ok(()) => {
                 let (next, _) = update(std::mem::take(state), UiMessage::SteerAccepted);
@@ -1299,7 +1579,110 @@ async fn dispatch(
                 let _ = session.cancel(operation_id).await;
             }
         }
+        UiEffect::OpenArtifact { id } => {
+            // The event loop pages artifacts itself: it owns the
+            // terminal and the key reader.
+            notice(state, &format!("cannot page artifact {id} here"));
+        }
+    }
+}
+
+/// Print one child's transcript into the scrollback between markers.
+/// Nothing is loaded: the child stays a stored session.
+async fn open_child(session: &SessionHandle, state: &mut UiState, id: &str) {
+    let Some(child_id) = find_child(session, state, id).await else {
+        return;
+    };
+    let entries = match session.child_transcript(child_id).await {
+        Ok(entries) => entries,
+        Err(err) => return notice(state, &format!("child transcript unavailable: {err}")),
+    };
+    notice(state, &format!("— child {child_id} (read-only) —"));
+    for entry in &entries {
+        push_entry_lines(entry, &mut state.pending_scrollback);
+    }
+    notice(state, &format!("— end of child {child_id} —"));
+}
+
+/// The child whose session id starts with `id`; a notice says why
+/// there is none.
+async fn find_child(
+    session: &SessionHandle,
+    state: &mut UiState,
+    id: &str,
+) -> Option<ion_core::SessionId> {
+    let children = match session.children().await {
+        Ok(children) => children,
+        Err(err) => {
+            notice(state, &format!("children unavailable: {err}"));
+            return None;
+        }
+    };
+    let wanted = id.strip_prefix("session-").unwrap_or(id);
+    let matches: Vec<_> = children
+        .iter()
+        .map(|child| child.session_id)
+        .filter(|child_id| {
+            child_id
+                .to_string()
+                .trim_start_matches("session-")
+                .starts_with(wanted)
+        })
+        .collect();
+    match matches.as_slice() {
+        [child_id] => Some(*child_id),
+        [] => {
+            notice(state, &format!("no child {id} (try /children)"));
+            None
+        }
+        _ => {
+            notice(state, &format!("child {id} is ambiguous"));
+            None
+        }
+    }
+}
+
+/// Show an artifact's full text in `$PAGER` (default `less -R`), with
+/// the terminal out of raw mode until the pager exits.
+async fn page_artifact(
+    session: &SessionHandle,
+    state: &mut UiState,
+    guard: &mut TerminalGuard,
+    id: &str,
+) {
+    let text = match session.artifact(id).await {
+        Ok(Some(text)) => text,
+        Ok(None) => return notice(state, &format!("no artifact {id} in this session")),
+        Err(err) => return notice(state, &format!("artifact unavailable: {err}")),
+    };
+    guard.suspend();
+    let paged = run_pager(&text).await;
+    if let Err(err) = guard.resume() {
+        notice(state, &format!("terminal restore failed: {err}"));
     }
+    if let Err(err) = paged {
+        notice(state, &format!("pager failed: {err}"));
+    }
+}
+
+async fn run_pager(text: &str) -> io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_owned());
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&pager)
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A pager quit before the end closes its input early; that is
+        // not a failure.
+        let _ = stdin.write_all(text.as_bytes()).await;
+    }
+    child.wait().await?;
+    Ok(())
 }
 
 fn print_banner(
@@ -1339,6 +1722,16 @@ fn push_entry_lines(entry: &ion_core::SessionEntry, out: &mut Vec<Line<'static>>
         ion_core::SessionEntry::Compaction { summary, .. } => {
             Some(format!("≡ compacted: {summary}"))
         }
+        ion_core::SessionEntry::Undone {
+            operation_id,
+            files,
+        } => {
+            let files: Vec<String> = files
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            Some(format!("↶ undid {operation_id}: {}", files.join(", ")))
+        }
     };
     if let Some(line) = line {
         for chunk in line.chars().collect::<Vec<_>>().chunks(80) {
@@ -1484,6 +1877,7 @@ pub(crate) mod tests {
         );
         let snapshot = SessionSnapshot {
             cursor: RuntimeCursor::default(),
+            instance: ion_core::RuntimeInstanceId::generate(),
             operation: OperationStatus::Active {
                 operation_id: OperationId::generate(),
                 prompt: "do things".to_owned(),
@@ -1515,6 +1909,7 @@ pub(crate) mod tests {
         state.draft = "partial".to_owned();
         let snapshot = SessionSnapshot {
             cursor: RuntimeCursor::default(),
+            instance: ion_core::RuntimeInstanceId::generate(),
             operation: OperationStatus::Idle,
             entries: Vec::new(),
             model_ref: "test-model".to_owned(),
@@ -1547,6 +1942,7 @@ pub(crate) mod tests {
         let operation_id = OperationId::generate();
         let snapshot = SessionSnapshot {
             cursor: RuntimeCursor::default(),
+            instance: ion_core::RuntimeInstanceId::generate(),
             operation: OperationStatus::Active {
                 operation_id,
                 prompt: "do things".to_owned(),
@@ -1749,7 +2145,7 @@ pub(crate) mod tests {
 
 #[cfg(test)]
 mod display_surface_tests {
-    use super::tests::ctrl;
+    use super::tests::{ctrl, key, type_text};
     use super::*;
     use ion_core::{OperationId, RuntimeCursor};
 
@@ -1760,6 +2156,8 @@ mod display_surface_tests {
             call_id: 1,
             is_error: false,
             preview,
+            diffs: Vec::new(),
+            artifact: None,
         })
     }
 
@@ -1784,6 +2182,25 @@ mod display_surface_tests {
         assert_eq!(row.preview.as_deref(), Some("hello\nworld"));
     }
 
+    #[test]
+    fn concurrent_calls_settle_onto_their_own_rows() {
+        let state = started(UiState::new());
+        let state = apply_runtime_event(
+            state,
+            RuntimeEvent::ToolStarted {
+                cursor: RuntimeCursor::default(),
+                operation_id: OperationId::generate(),
+                call_id: 2,
+                tool: "read".to_owned(),
+                target: Some("a.txt".to_owned()),
+            },
+        );
+        // Call 1 settles while call 2 is the newest row.
+        let state = update(state, settled(Some("hi".to_owned()))).0;
+        assert_eq!(state.tool_rows[0].preview.as_deref(), Some("hi"));
+        assert_eq!(state.tool_rows[1].preview, None);
+    }
+
     #[test]
     fn ctrl_o_toggles_whether_flushed_rows_carry_previews() {
         let state = started(UiState::new());
@@ -1811,6 +2228,217 @@ mod display_surface_tests {
         );
     }
 
+    #[test]
+    fn delegate_rows_fold_child_progress_into_a_collapsible_tree() {
+        let progress = |child, update| {
+            UiMessage::Runtime(RuntimeEvent::ChildProgress {
+                cursor: RuntimeCursor::default(),
+                operation_id: OperationId::generate(),
+                call_id: 1,
+                child_session_id: child,
+                update,
+            })
+        };
+        let (a, b) = (
+            ion_core::SessionId::generate(),
+            ion_core::SessionId::generate(),
+        );
+        let mut state = started(UiState::new());
+        for (child, objective) in [(a, "survey the parser"), (b, "survey the store")] {
+            let started = ion_core::ChildUpdate::Started {
+                objective: objective.to_owned(),
+                profile: None,
+            };
+            state = update(state, progress(child, started)).0;
+        }
+        let reading = ion_core::ChildUpdate::ToolStarted {
+            tool: "read".to_owned(),
+            target: Some("parser.rs".to_owned()),
+        };
+        state = update(state, progress(a, reading)).0;
+        state = update(state, progress(b, ion_core::ChildUpdate::Finished)).0;
+
+        let render = |state: &UiState| {
+            let mut flushed = state.clone();
+            flushed.flush_draft();
+            flushed
+                .pending_scrollback
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        // Collapsed: one summary line under the row.
+        assert_eq!(
+            render(&state),
+            ["· bash echo hi…", "  └ 2 children: 1 running, 1 done"]
+        );
+        // Expanded: one line per child with its latest activity.
+        let expanded = update(state, ctrl('o')).0;
+        assert_eq!(
+            render(&expanded),
+            [
+                "· bash echo hi…",
+                "  ├ ● survey the parser — read parser.rs…",
+                "  └ ✓ survey the store",
+            ]
+        );
+    }
+
+    #[test]
+    fn live_output_previews_the_running_row_until_settlement() {
+        let mut state = started(UiState::new());
+        for n in 0..30 {
+            state = apply_runtime_event(
+                state,
+                RuntimeEvent::ToolOutput {
+                    cursor: RuntimeCursor::default(),
+                    operation_id: OperationId::generate(),
+                    call_id: 1,
+                    chunk: format!("line {n}\n"),
+                },
+            );
+        }
+        let live = state.tool_rows.last().and_then(|row| row.preview.clone());
+        let live = live.expect("live preview");
+        assert_eq!(live.lines().count(), LIVE_OUTPUT_LINES);
+        assert!(live.ends_with("line 29\n"), "{live:?}");
+
+        let state = update(state, settled(Some("done".to_owned()))).0;
+        let row = state.tool_rows.last().expect("row");
+        assert_eq!(row.preview.as_deref(), Some("done"));
+    }
+
+    #[test]
+    fn flushed_rows_render_diffs_colored_by_side() {
+        let state = started(UiState::new());
+        let mut state = update(
+            state,
+            UiMessage::Runtime(RuntimeEvent::ToolSettled {
+                cursor: RuntimeCursor::default(),
+                operation_id: OperationId::generate(),
+                call_id: 1,
+                is_error: false,
+                preview: Some("edited".to_owned()),
+                diffs: vec![FileDiff {
+                    path: "/w/a.txt".into(),
+                    hunks: "@@ -1,2 +1,2 @@\n keep\n-old\n+new".to_owned(),
+                    old_text: Some("keep\nold\n".to_owned()),
+                    new_text: Some("keep\nnew\n".to_owned()),
+                    whole: true,
+                }],
+                artifact: None,
+            }),
+        )
+        .0;
+        state.flush_draft();
+        let line = |text: &str| {
+            state
+                .pending_scrollback
+                .iter()
+                .find(|line| line.to_string() == format!("  {text}"))
+                .unwrap_or_else(|| panic!("no line {text:?}"))
+                .style
+        };
+        assert_eq!(line("-old").fg, Some(ratatui::style::Color::Red));
+        assert_eq!(line("+new").fg, Some(ratatui::style::Color::Green));
+        assert_eq!(line(" keep").fg, None);
+    }
+
+    #[test]
+    fn artifact_command_pages_saved_outputs_by_prefix() {
+        let (state, effect) = update(type_text(UiState::new(), "/artifact"), key(KeyCode::Enter));
+        assert!(effect.is_none());
+        let text: String = state.pending_scrollback[0]
+            .spans
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect();
+        assert!(text.starts_with("no artifacts"), "{text}");
+
+        let mut state = state;
+        for id in ["abc123", "def456"] {
+            state = started(state);
+            state = update(
+                state,
+                UiMessage::Runtime(RuntimeEvent::ToolSettled {
+                    cursor: RuntimeCursor::default(),
+                    operation_id: OperationId::generate(),
+                    call_id: 1,
+                    is_error: false,
+                    preview: None,
+                    diffs: Vec::new(),
+                    artifact: Some(id.to_owned()),
+                }),
+            )
+            .0;
+        }
+        state.flush_draft();
+        assert!(state.pending_scrollback.iter().any(|line| {
+            line.spans
+                .iter()
+                .any(|span| span.content.contains("/artifact abc123"))
+        }));
+        let (state, effect) = update(type_text(state, "/artifact"), key(KeyCode::Enter));
+        assert!(matches!(effect, Some(UiEffect::OpenArtifact { id }) if id == "def456"));
+        let (state, effect) = update(type_text(state, "/artifact abc"), key(KeyCode::Enter));
+        assert!(matches!(effect, Some(UiEffect::OpenArtifact { id }) if id == "abc123"));
+        let (_, effect) = update(type_text(state, "/artifact 999"), key(KeyCode::Enter));
+        assert!(effect.is_none());
+    }
+
+    #[test]
+    fn child_commands_list_open_or_cancel_a_child() {
+        let (state, effect) = update(type_text(UiState::new(), "/children"), key(KeyCode::Enter));
+        assert_eq!(effect, Some(UiEffect::ListChildren));
+        let (state, effect) = update(type_text(state, "/child"), key(KeyCode::Enter));
+        assert_eq!(effect, Some(UiEffect::ListChildren));
+        let (state, effect) = update(type_text(state, "/child 0199ab"), key(KeyCode::Enter));
+        assert_eq!(
+            effect,
+            Some(UiEffect::OpenChild {
+                id: "0199ab".to_owned()
+            })
+        );
+        let (_, effect) = update(
+            type_text(state, "/child cancel 0199ab"),
+            key(KeyCode::Enter),
+        );
+        assert_eq!(
+            effect,
+            Some(UiEffect::CancelChild {
+                id: "0199ab".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn undo_command_names_the_latest_or_a_given_operation() {
+        let (state, effect) = update(type_text(UiState::new(), "/undo"), key(KeyCode::Enter));
+        assert_eq!(effect, Some(UiEffect::Undo { operation: None }));
+        let id = OperationId::generate();
+        let (state, effect) = update(
+            type_text(state, &format!("/undo {id}")),
+            key(KeyCode::Enter),
+        );
+        assert_eq!(
+            effect,
+            Some(UiEffect::Undo {
+                operation: Some(id)
+            })
+        );
+        let (state, effect) = update(type_text(state, "/undo yesterday"), key(KeyCode::Enter));
+        assert!(effect.is_none());
+        let text: String = state
+            .pending_scrollback
+            .last()
+            .expect("notice")
+            .spans
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect();
+        assert!(text.contains("not an operation id"), "{text}");
+    }
+
     #[test]
     fn thinking_flushes_before_text_and_respects_visibility() {
         let mut state = UiState::new();
@@ -81,6 +81,7 @@ async fn start_agent() -> TestClient {
             ion_core::ScriptedMessage::text("hello "),
             ion_core::ScriptedMessage::text("world"),
         ]),
+        child_profiles: ion::ChildProfiles::default(),
         store: Arc::new(SessionStore::open_in_memory().expect("store")),
         policy: Arc::new(AllowlistPolicy::new(["read"])),
     };
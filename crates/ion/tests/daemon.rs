@@ -0,0 +1,325 @@
+//! Daemon integration tests: a real Unix socket between `daemon::serve`
+//! and `DaemonClient`, with a scripted provider. Clients drive ordinary
+//! `SessionHandle`s; these tests assert that the wire preserves the
+//! embedded semantics (events, snapshots, typed errors, durability).
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+
+use ion::daemon::{self, AttachRole, DaemonClient, DaemonConfig};
+use ion::print::PrintFrontend;
+use ion::scripted_provider_factory;
+use ion_core::{
+    AllowlistPolicy, CommandError, EventSubscription, OperationStatus, RuntimeEvent,
+    ScriptedMessage, SessionEntry, SessionStore, ToolCatalog,
+};
+
+struct Daemon {
+    socket: PathBuf,
+    stop: oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Daemon {
+    async fn shutdown(self) {
+        let _ = self.stop.send(());
+        timeout(Duration::from_secs(5), self.task)
+            .await
+            .expect("daemon drains")
+            .expect("daemon task");
+    }
+}
+
+fn start_daemon(dir: &Path, store: SessionStore) -> Daemon {
+    let socket = dir.join("daemon.sock");
+    let _ = std::fs::remove_file(&socket);
+    let listener = daemon::bind(&socket).expect("bind");
+    let config = DaemonConfig {
+        make_provider: scripted_provider_factory(vec![
+            ScriptedMessage::text("hello "),
+            ScriptedMessage::text("world"),
+        ]),
+        child_profiles: ion::ChildProfiles::default(),
+        store,
+        policy: Arc::new(AllowlistPolicy::new(["read"])),
+        tools: ToolCatalog::with_cwd(dir),
+        idle_timeout: None,
+    };
+    let (stop, stopped) = oneshot::channel::<()>();
+    let task = tokio::spawn(async move {
+        daemon::serve(listener, config, async {
+            let _ = stopped.await;
+        })
+        .await
+        .expect("serve");
+    });
+    Daemon { socket, stop, task }
+}
+
+async fn until_terminal(events: &mut EventSubscription) -> Vec<RuntimeEvent> {
+    let mut out = Vec::new();
+    loop {
+        let event = timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("event timeout")
+            .expect("event");
+        let done = matches!(
+            event,
+            RuntimeEvent::OperationFinished { .. }
+                | RuntimeEvent::OperationFailed { .. }
+                | RuntimeEvent::OperationCancelled { .. }
+        );
+        out.push(event);
+        if done {
+            return out;
+        }
+    }
+}
+
+fn text_of(events: &[RuntimeEvent]) -> String {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::AssistantTextDelta { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn clients_share_one_daemon_session() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let daemon = start_daemon(dir.path(), SessionStore::open_in_memory().expect("store"));
+
+    let first = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (session_id, session) = first
+        .create_session(Some(dir.path()))
+        .await
+        .expect("create");
+    let (snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    assert_eq!(snapshot.operation, OperationStatus::Idle);
+    session.submit("say hello").await.expect("submit");
+    let recorded = until_terminal(&mut events).await;
+    assert_eq!(text_of(&recorded), "hello world");
+
+    // A second client attaches to the same loaded session: same writer,
+    // same durable view. The creator controls it, so the second client
+    // can only observe.
+    let second = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let refused = second
+        .open_session(Some(session_id), AttachRole::Controller)
+        .await
+        .expect_err("control is held");
+    assert!(refused.contains("controls"), "{refused}");
+    let (attached_id, attached) = second
+        .open_session(Some(session_id), AttachRole::Observer)
+        .await
+        .expect("open");
+    assert_eq!(attached_id, session_id);
+    let seen = attached.snapshot().await.expect("snapshot");
+    assert_eq!(seen, session.snapshot().await.expect("snapshot"));
+    assert!(seen.entries.contains(&SessionEntry::AssistantMessage {
+        text: "hello world".to_owned()
+    }));
+    assert!(matches!(
+        attached.submit("not mine").await,
+        Err(CommandError::ReadOnly)
+    ));
+
+    // Typed command errors survive the wire.
+    assert!(matches!(
+        session.cancel(ion_core::OperationId::generate()).await,
+        Err(CommandError::NoActiveOperation)
+    ));
+
+    session.close().await.expect("close");
+    assert!(matches!(
+        attached.snapshot().await,
+        Err(CommandError::Closed)
+    ));
+    daemon.shutdown().await;
+}
+
+#[tokio::test]
+async fn print_mode_runs_against_the_daemon() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let daemon = start_daemon(dir.path(), SessionStore::open_in_memory().expect("store"));
+    let client = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (_, session) = client.create_session(None).await.expect("create");
+    let mut out = Vec::new();
+    PrintFrontend::new(&mut out)
+        .run(&session, "hi")
+        .await
+        .expect("print");
+    assert_eq!(String::from_utf8(out).expect("utf8"), "hello world");
+    session.close().await.expect("close");
+    daemon.shutdown().await;
+}
+
+#[tokio::test]
+async fn the_socket_is_private_to_its_user() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().expect("tempdir");
+    let socket = dir.path().join("run/ion/daemon.sock");
+    let _listener = daemon::bind(&socket).expect("bind");
+    let mode = |path: &Path| {
+        std::fs::metadata(path)
+            .expect("metadata")
+            .permissions()
+            .mode()
+            & 0o777
+    };
+    assert_eq!(mode(&socket), 0o600);
+    assert_eq!(mode(socket.parent().expect("parent")), 0o700);
+}
+
+#[tokio::test]
+async fn daemon_refuses_sessions_for_another_directory() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let elsewhere = tempfile::tempdir().expect("tempdir");
+    let daemon = start_daemon(dir.path(), SessionStore::open_in_memory().expect("store"));
+    let client = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let err = client
+        .create_session(Some(elsewhere.path()))
+        .await
+        .expect_err("refused");
+    assert!(err.contains("daemon serves"), "{err}");
+    daemon.shutdown().await;
+}
+
+#[tokio::test]
+async fn sessions_survive_a_daemon_restart() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let db = dir.path().join("sessions.db");
+
+    let daemon = start_daemon(dir.path(), SessionStore::open(&db).expect("store"));
+    let client = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (session_id, session) = client.create_session(None).await.expect("create");
+    let (_, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("remember me").await.expect("submit");
+    until_terminal(&mut events).await;
+    daemon.shutdown().await;
+    // The daemon closed its sessions on the way out.
+    assert!(session.snapshot().await.is_err());
+
+    let daemon = start_daemon(dir.path(), SessionStore::open(&db).expect("store"));
+    let client = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (reopened_id, reopened) = client
+        .open_session(None, AttachRole::Controller)
+        .await
+        .expect("open latest");
+    assert_eq!(reopened_id, session_id);
+    let snapshot = reopened.snapshot().await.expect("snapshot");
+    assert!(snapshot.entries.contains(&SessionEntry::UserMessage {
+        text: "remember me".to_owned()
+    }));
+    daemon.shutdown().await;
+}
+
+#[tokio::test]
+async fn control_passes_when_the_controller_leaves() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let daemon = start_daemon(dir.path(), SessionStore::open_in_memory().expect("store"));
+    let first = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (session_id, controller) = first.create_session(None).await.expect("create");
+    let second = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (_, observer) = second
+        .open_session(Some(session_id), AttachRole::Observer)
+        .await
+        .expect("observe");
+    assert!(matches!(
+        observer.claim_control().await,
+        Err(CommandError::ControllerHeld)
+    ));
+
+    // An explicit release hands the seat over.
+    controller.release_control().await.expect("release");
+    assert!(matches!(
+        controller.submit("stale").await,
+        Err(CommandError::ReadOnly)
+    ));
+    let promoted = observer.claim_control().await.expect("claim");
+    let (_, mut events) = promoted.subscribe().await.expect("subscribe");
+    promoted.submit("mine now").await.expect("submit");
+    assert_eq!(text_of(&until_terminal(&mut events).await), "hello world");
+
+    // A disconnect releases the lease too.
+    drop((promoted, observer, events, second));
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    let reclaimed = loop {
+        match first
+            .open_session(Some(session_id), AttachRole::Controller)
+            .await
+        {
+            Ok((_, handle)) => break handle,
+            Err(_) if tokio::time::Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            Err(err) => panic!("lease never released: {err}"),
+        }
+    };
+    reclaimed.close().await.expect("controller closes");
+    daemon.shutdown().await;
+}
+
+#[tokio::test]
+async fn observers_resume_from_their_last_cursor() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let daemon = start_daemon(dir.path(), SessionStore::open_in_memory().expect("store"));
+    let client = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (session_id, session) = client.create_session(None).await.expect("create");
+    let (snapshot, mut events) = session.subscribe().await.expect("subscribe");
+    session.submit("stream").await.expect("submit");
+    let recorded = until_terminal(&mut events).await;
+
+    // A watcher that reconnects after the first event replays the rest
+    // without taking a new snapshot.
+    let watcher = DaemonClient::connect(&daemon.socket)
+        .await
+        .expect("connect");
+    let (_, observer) = watcher
+        .open_session(Some(session_id), AttachRole::Observer)
+        .await
+        .expect("observe");
+    let mut resumed = observer
+        .resume(snapshot.instance, recorded[0].cursor())
+        .await
+        .expect("resume");
+    for expected in &recorded[1..] {
+        let event = timeout(Duration::from_secs(5), resumed.recv())
+            .await
+            .expect("replay timeout")
+            .expect("replayed");
+        assert_eq!(&event, expected);
+    }
+    assert!(matches!(
+        observer
+            .resume(ion_core::RuntimeInstanceId::generate(), snapshot.cursor)
+            .await,
+        Err(CommandError::CursorUnavailable { .. })
+    ));
+    session.close().await.expect("close");
+    daemon.shutdown().await;
+}